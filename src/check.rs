@@ -1,7 +1,91 @@
+use crate::issue::Issue;
+use crate::state::StateUpdate;
 use anyhow::Result;
 use async_trait::async_trait;
+use std::time::Duration;
+
+pub const DEFAULT_CHECK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// What a checker found, and what it wants remembered for its next run.
+pub struct CheckOutput {
+    pub issues: Vec<Issue>,
+    /// Checkers never save the state file themselves, so dry runs leave it untouched.
+    pub state_update: Option<StateUpdate>,
+}
+
+impl std::fmt::Debug for CheckOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CheckOutput")
+            .field("issues", &self.issues)
+            .field("state_update", &self.state_update.is_some())
+            .finish()
+    }
+}
+
+impl From<Vec<Issue>> for CheckOutput {
+    fn from(issues: Vec<Issue>) -> Self {
+        Self {
+            issues,
+            state_update: None,
+        }
+    }
+}
 
 #[async_trait]
-pub trait Check {
-    async fn check(&self) -> Result<Vec<String>>;
+pub trait Check: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// How long a single `check()` may run before it's abandoned and reported as failed.
+    fn timeout(&self) -> Duration {
+        DEFAULT_CHECK_TIMEOUT
+    }
+
+    /// How often watch mode runs this checker; `None` uses the watch `--interval`.
+    fn interval(&self) -> Option<Duration> {
+        None
+    }
+
+    async fn check(&self) -> Result<CheckOutput>;
+}
+
+/// Runs `checker.check()`, turning a run that exceeds `checker.timeout()` into an error.
+pub async fn check_with_timeout(checker: &dyn Check) -> Result<CheckOutput> {
+    let timeout = checker.timeout();
+    match tokio::time::timeout(timeout, checker.check()).await {
+        Ok(result) => result,
+        Err(_) => Err(anyhow::anyhow!(
+            "{} check timed out after {}s",
+            checker.name(),
+            timeout.as_secs_f64()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct SlowChecker;
+
+    #[async_trait]
+    impl Check for SlowChecker {
+        fn name(&self) -> &str {
+            "Slow"
+        }
+
+        fn timeout(&self) -> Duration {
+            Duration::from_millis(20)
+        }
+
+        async fn check(&self) -> Result<CheckOutput> {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(Vec::new().into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_with_timeout_reports_slow_checker() {
+        let err = check_with_timeout(&SlowChecker).await.unwrap_err();
+        assert_eq!(err.to_string(), "Slow check timed out after 0.02s");
+    }
 }
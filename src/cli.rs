@@ -0,0 +1,214 @@
+use anyhow::{Context, Result, bail};
+use work_driver::config::{CHECKER_NAMES, Config, Renotify};
+use work_driver::runner::RunOptions;
+
+pub const USAGE: &str = "\
+Usage: work-driver [COMMAND]
+
+Commands:
+  check    Run all checks once (the default when no command is given)
+  serve    Run the dashboard server
+  state    Print the saved state as JSON
+  stats    Print how long resolved issues were open, by source and kind
+
+Check options:
+  --watch              Keep running checks on an interval until Ctrl-C
+  --interval <MIN>     Minutes between runs in watch mode (default 5)
+  --only <CHECKER>     Only run the named checker (github, launchdarkly); repeatable
+  --no-notify          Update the dashboard without sending a desktop notification
+  --once-per-issue     Notify about each issue once, never re-notifying
+  --dry-run            Print issues and what would be notified without writing anything
+  --format <FORMAT>    Output format: human (default) or json
+
+Exit codes for a single check run:
+  0  no issues found
+  1  error
+  2  invalid arguments
+  3  no usable checkers (each one's reason is printed)
+  4  issues found
+";
+
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_NO_CHECKERS: i32 = 3;
+pub const EXIT_ISSUES_FOUND: i32 = 4;
+
+const DEFAULT_WATCH_INTERVAL_MINUTES: u64 = 5;
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Check(CheckArgs),
+    Serve,
+    State,
+    Stats,
+    Help,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Human,
+    /// One JSON document per run on stdout, and nothing else.
+    Json,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CheckArgs {
+    pub watch: bool,
+    pub interval_minutes: u64,
+    /// Lowercased checker names to run; empty means all of them.
+    pub only: Vec<String>,
+    pub notify: bool,
+    /// Overrides `notifier.renotify_minutes` with "never".
+    pub once_per_issue: bool,
+    pub dry_run: bool,
+    pub format: OutputFormat,
+}
+
+impl Default for CheckArgs {
+    fn default() -> Self {
+        Self {
+            watch: false,
+            interval_minutes: DEFAULT_WATCH_INTERVAL_MINUTES,
+            only: Vec::new(),
+            notify: true,
+            once_per_issue: false,
+            dry_run: false,
+            format: OutputFormat::Human,
+        }
+    }
+}
+
+impl CheckArgs {
+    /// Applies options that override config file settings.
+    pub fn apply_to(&self, config: &mut Config) {
+        if self.once_per_issue {
+            config.notifier.renotify_minutes = Renotify::Never;
+        }
+    }
+
+    pub fn run_options(&self) -> RunOptions {
+        RunOptions {
+            only: self.only.clone(),
+            notify: self.notify,
+            dry_run: self.dry_run,
+        }
+    }
+}
+
+/// Parses the arguments after the program name.
+pub fn parse(args: &[String]) -> Result<Command> {
+    let (command, rest) = match args.split_first() {
+        None => return Ok(Command::Check(CheckArgs::default())),
+        Some((first, rest)) => (first.as_str(), rest),
+    };
+
+    match command {
+        "check" => parse_check(rest).map(Command::Check),
+        // Allow `work-driver --watch` etc. as shorthand for `work-driver check --watch`
+        flag if flag.starts_with("--") && flag != "--help" => parse_check(args).map(Command::Check),
+        "serve" | "state" | "stats" if !rest.is_empty() => {
+            bail!("'{}' takes no arguments", command)
+        }
+        "serve" => Ok(Command::Serve),
+        "state" => Ok(Command::State),
+        "stats" => Ok(Command::Stats),
+        "help" | "--help" | "-h" => Ok(Command::Help),
+        other => bail!("Unknown command '{}'", other),
+    }
+}
+
+fn parse_check(args: &[String]) -> Result<CheckArgs> {
+    let mut check_args = CheckArgs::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--watch" => check_args.watch = true,
+            "--no-notify" => check_args.notify = false,
+            "--once-per-issue" => check_args.once_per_issue = true,
+            "--dry-run" => check_args.dry_run = true,
+            "--interval" => {
+                check_args.interval_minutes = args
+                    .next()
+                    .context("--interval requires a number of minutes")?
+                    .parse()
+                    .context("--interval must be a whole number of minutes")?;
+                if check_args.interval_minutes == 0 {
+                    bail!("--interval must be at least 1 minute");
+                }
+            }
+            "--format" => {
+                check_args.format = match args.next().map(String::as_str) {
+                    Some("human") => OutputFormat::Human,
+                    Some("json") => OutputFormat::Json,
+                    Some(other) => bail!("Unknown format '{}' (expected human or json)", other),
+                    None => bail!("--format requires human or json"),
+                };
+            }
+            "--only" => {
+                let name = args
+                    .next()
+                    .context("--only requires a checker name")?
+                    .to_lowercase();
+                if !CHECKER_NAMES.contains(&name.as_str()) {
+                    bail!(
+                        "Unknown checker '{}' (expected one of: {})",
+                        name,
+                        CHECKER_NAMES.join(", ")
+                    );
+                }
+                check_args.only.push(name);
+            }
+            other => bail!("Unknown option '{}' for check", other),
+        }
+    }
+    Ok(check_args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_defaults_to_check() {
+        assert_eq!(parse(&[]).unwrap(), Command::Check(CheckArgs::default()));
+    }
+
+    #[test]
+    fn test_parse_check_options() {
+        let Command::Check(check_args) = parse(&args(
+            "check --watch --interval 10 --only GitHub --no-notify --once-per-issue",
+        ))
+        .unwrap() else {
+            panic!("expected check command");
+        };
+        assert!(check_args.watch);
+        assert!(!check_args.notify);
+        let mut config = Config::default();
+        check_args.apply_to(&mut config);
+        assert_eq!(config.notifier.renotify_minutes, Renotify::Never);
+        assert_eq!(check_args.interval_minutes, 10);
+        let options = check_args.run_options();
+        assert!(options.includes("GitHub"));
+        assert!(!options.includes("LaunchDarkly"));
+        assert_eq!(check_args.format, OutputFormat::Human);
+
+        let Command::Check(check_args) = parse(&args("--dry-run --format json")).unwrap() else {
+            panic!("expected check command");
+        };
+        assert!(check_args.dry_run);
+        assert_eq!(check_args.format, OutputFormat::Json);
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_input() {
+        assert!(parse(&args("check --only jira")).is_err());
+        assert!(parse(&args("check --interval")).is_err());
+        assert!(parse(&args("check --watch --interval 0")).is_err());
+        assert!(parse(&args("check --format yaml")).is_err());
+        assert!(parse(&args("serve --watch")).is_err());
+        assert!(parse(&args("frobnicate")).is_err());
+    }
+}
@@ -0,0 +1,1302 @@
+mod toml;
+
+use anyhow::{Context, Result};
+use chrono::{
+    DateTime, Datelike, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Names accepted by `WORK_DRIVER_CHECKS` and `--only`.
+pub const CHECKER_NAMES: &[&str] = &["github", "launchdarkly"];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub github: GitHubConfig,
+    pub launchdarkly: LaunchDarklyConfig,
+    pub notifier: NotifierConfig,
+    pub server: ServerConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitHubConfig {
+    pub enabled: bool,
+    /// `owner/name` of each repository to check, or empty for the current directory's. Also
+    /// accepts a single `repo = "..."`, and `GITHUB_REPOS=org/a,org/b` overrides it.
+    #[serde(alias = "repo", deserialize_with = "one_or_many")]
+    pub repos: Vec<String>,
+    /// Falls back to `GITHUB_TOKEN`. When set, PRs are fetched from the API directly
+    /// instead of through the `gh` CLI.
+    pub token: Option<String>,
+    /// Where GitHub is served from, e.g. "github.mycorp.com" for GitHub Enterprise.
+    pub host: String,
+    /// Where the REST API is served from. Defaults to `https://<host>/api/v3` for hosts
+    /// other than github.com.
+    pub api_url: String,
+    /// Cache API responses in the state directory and revalidate them with ETags, which
+    /// doesn't count against the rate limit.
+    pub cache_responses: bool,
+    /// Fetch each repo's PRs with one GraphQL query rather than REST calls per PR. Needs
+    /// a token.
+    pub graphql: bool,
+    /// When fewer API requests than this remain in the rate limit, optional queries
+    /// (review threads, branch comparisons) are skipped.
+    pub rate_limit_reserve: u64,
+    /// How many repos are queried at once.
+    pub max_concurrent_repos: usize,
+    /// Don't report approved PRs as ready to merge when auto-merge will merge them anyway.
+    pub skip_auto_merge: bool,
+    /// Also report failing checks on draft PRs, which are skipped by default.
+    pub include_drafts: bool,
+    /// How long checks can stay queued or running before the PR is reported as stuck.
+    pub pending_minutes: i64,
+    /// How long a PR can sit with auto-merge enabled before it's reported as stuck.
+    pub auto_merge_stuck_minutes: i64,
+    /// Report unresolved review threads on otherwise green PRs. Costs a query per PR.
+    pub review_threads: bool,
+    /// Report review threads stalled waiting on a reply: on our PRs when a reviewer had
+    /// the last word, and on PRs awaiting our review when we did. Costs a query per PR.
+    pub thread_replies: bool,
+    /// How long a reviewer's comment on our PR can go without our reply.
+    pub your_reply_minutes: i64,
+    /// How long our comment on someone else's PR can go without their reply.
+    pub their_reply_minutes: i64,
+    /// Report review requests on PRs we've reviewed before as re-requested, and urgent.
+    /// Costs a query per PR awaiting review.
+    pub re_requested_reviews: bool,
+    /// Report PRs awaiting our review where we've started a review but never submitted it.
+    /// Needs a token, and costs a query per PR.
+    pub pending_reviews: bool,
+    /// Report PRs waiting on a check their base branch requires that was never reported.
+    /// Costs a query per base branch.
+    pub missing_required_checks: bool,
+    /// How long a required check can be missing before it's reported.
+    pub missing_check_minutes: i64,
+    /// Report non-draft PRs that have no CI checks at all, e.g. because a workflow never
+    /// triggered.
+    pub no_checks: bool,
+    /// How old a PR has to be before missing checks are reported.
+    pub no_checks_minutes: i64,
+    /// Report PRs that have fallen behind their base branch. Needs a token, and costs a
+    /// query per PR.
+    pub behind_base: bool,
+    /// How many commits behind a PR can get before it's reported. Approved PRs with green
+    /// checks are reported as soon as they're behind at all.
+    pub behind_base_commits: u64,
+    /// Report how many approvals our otherwise green PRs have, out of the number branch
+    /// protection requires when we can read it. Costs a query per base branch.
+    pub approval_progress: bool,
+    /// Report our PRs with green checks that nobody has reviewed.
+    pub unreviewed_prs: bool,
+    /// How long after its last push (or since we first saw it) a PR can go without a
+    /// review before it's reported.
+    pub unreviewed_minutes: i64,
+    /// Check conclusions (or commit status states) that count as a failure. `CANCELLED`
+    /// is left out by default since cancelled runs are usually superseded by a newer push.
+    pub failing_conclusions: Vec<String>,
+    /// Checks known to be flaky, by name with `*` wildcards. Failures of only these are
+    /// reported at low priority as worth a rerun.
+    pub flaky_checks: Vec<String>,
+    /// Also treat checks as flaky when their result recently changed on the same commit,
+    /// remembering results in the state file.
+    pub track_flaky_checks: bool,
+    /// Report PRs stacked on a branch whose own PR has merged. Costs a query per PR
+    /// based on a branch other than the default one.
+    pub stacked_prs: bool,
+    /// Report our PRs changing files no CODEOWNERS entry covers, so review requests
+    /// don't go to anyone. Costs a query per PR.
+    pub code_owners: bool,
+    /// Report our PRs closed without merging since the last run, remembering which were
+    /// open in the state file.
+    pub closed_prs: bool,
+    /// When set, report our PRs that will soon have gone this many days without activity,
+    /// for repos where a stale bot closes inactive PRs.
+    pub auto_close_days: Option<i64>,
+    /// How many days before `auto_close_days` to start reporting a PR.
+    pub auto_close_warning_days: i64,
+    /// Also report review requests in repos outside `repos`, found with one search across
+    /// the whole account.
+    pub all_review_requests: bool,
+    /// `org/team` slugs whose review requests are reported too, at lower priority.
+    pub teams: Vec<String>,
+    /// When set, review requests older than this are reported as urgent.
+    pub stale_review_minutes: Option<i64>,
+    /// Logins whose review requests are skipped; `*` matches any characters.
+    pub ignored_authors: Vec<String>,
+    /// Report one summary issue per ignored author instead of skipping their PRs.
+    pub collapse_ignored_authors: bool,
+    /// PRs with any of these labels aren't reported, e.g. `["fyi"]`. Case-insensitive.
+    pub ignore_labels: Vec<String>,
+    /// When set, only PRs with one of these labels are reported. Case-insensitive.
+    pub only_labels: Vec<String>,
+    /// PRs that are never reported, as `owner/repo#number`. More can be muted from the
+    /// dashboard.
+    pub muted_prs: Vec<String>,
+    /// Above this many review requests, notify about them as a single group.
+    pub review_group_threshold: usize,
+    pub assigned_issues: AssignedIssuesConfig,
+    pub milestones: MilestonesConfig,
+    pub follow_ups: FollowUpsConfig,
+    /// Repositories whose default branch is watched for failing workflows.
+    pub default_branch_workflows: Vec<String>,
+    /// Repositories whose deployments waiting on our approval are reported.
+    pub deployment_approvals: Vec<String>,
+    /// Report open Dependabot security alerts in `repos`. Needs a token allowed to read them.
+    pub dependabot_alerts: bool,
+    /// Report unread mentions and review requests from the notifications API. Needs a token.
+    pub notifications: bool,
+    pub timeout_seconds: u64,
+    /// How often watch mode runs this checker; defaults to the watch `--interval`.
+    #[serde(deserialize_with = "interval_minutes")]
+    pub interval_minutes: Option<u64>,
+}
+
+const DEFAULT_GITHUB_API_URL: &str = "https://api.github.com";
+
+impl GitHubConfig {
+    /// `api_url`, or the GitHub Enterprise API on `host` when that's set and `api_url` isn't.
+    pub fn api_base_url(&self) -> String {
+        if self.host != "github.com" && self.api_url == DEFAULT_GITHUB_API_URL {
+            format!("https://{}/api/v3", self.host)
+        } else {
+            self.api_url.clone()
+        }
+    }
+}
+
+impl Default for GitHubConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            repos: Vec::new(),
+            token: None,
+            host: "github.com".to_string(),
+            api_url: DEFAULT_GITHUB_API_URL.to_string(),
+            cache_responses: true,
+            graphql: false,
+            rate_limit_reserve: 500,
+            max_concurrent_repos: 4,
+            skip_auto_merge: true,
+            include_drafts: false,
+            pending_minutes: 45,
+            auto_merge_stuck_minutes: 6 * 60,
+            review_threads: false,
+            thread_replies: false,
+            your_reply_minutes: 24 * 60,
+            their_reply_minutes: 48 * 60,
+            re_requested_reviews: false,
+            pending_reviews: false,
+            missing_required_checks: false,
+            missing_check_minutes: 30,
+            no_checks: false,
+            no_checks_minutes: 60,
+            behind_base: false,
+            behind_base_commits: 50,
+            approval_progress: false,
+            unreviewed_prs: false,
+            unreviewed_minutes: 24 * 60,
+            failing_conclusions: vec![
+                "FAILURE".to_string(),
+                "ERROR".to_string(),
+                "TIMED_OUT".to_string(),
+                "ACTION_REQUIRED".to_string(),
+            ],
+            flaky_checks: Vec::new(),
+            track_flaky_checks: false,
+            stacked_prs: false,
+            code_owners: false,
+            closed_prs: false,
+            auto_close_days: None,
+            auto_close_warning_days: 5,
+            all_review_requests: false,
+            teams: Vec::new(),
+            stale_review_minutes: None,
+            ignored_authors: vec!["dependabot[bot]".to_string(), "renovate[bot]".to_string()],
+            collapse_ignored_authors: false,
+            ignore_labels: Vec::new(),
+            only_labels: Vec::new(),
+            muted_prs: Vec::new(),
+            review_group_threshold: 5,
+            assigned_issues: AssignedIssuesConfig::default(),
+            milestones: MilestonesConfig::default(),
+            follow_ups: FollowUpsConfig::default(),
+            default_branch_workflows: Vec::new(),
+            deployment_approvals: Vec::new(),
+            dependabot_alerts: false,
+            notifications: false,
+            timeout_seconds: 60,
+            interval_minutes: None,
+        }
+    }
+}
+
+/// Reporting open GitHub issues assigned to you.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AssignedIssuesConfig {
+    pub enabled: bool,
+    /// Only report issues with at least one of these labels; empty means any issue.
+    pub labels: Vec<String>,
+    /// Repositories to search; empty means `github.repos`.
+    pub repos: Vec<String>,
+}
+
+/// Reminders about open milestones that are nearly or already due.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MilestonesConfig {
+    pub enabled: bool,
+    /// Milestones to watch by title; empty means the ones you created.
+    pub titles: Vec<String>,
+    /// Repositories to check; empty means `github.repos`.
+    pub repos: Vec<String>,
+    /// Report milestones due within this many days, as well as overdue ones.
+    pub due_within_days: i64,
+}
+
+impl Default for MilestonesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            titles: Vec::new(),
+            repos: Vec::new(),
+            due_within_days: 3,
+        }
+    }
+}
+
+/// Reminders about follow-ups we noted in our own comments on PRs that have since merged.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FollowUpsConfig {
+    pub enabled: bool,
+    /// How far back to look for merged PRs.
+    pub days: i64,
+    /// Words or phrases that mark a comment as a follow-up, matched case-insensitively as
+    /// whole words.
+    pub patterns: Vec<String>,
+}
+
+impl Default for FollowUpsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            days: 14,
+            patterns: vec![
+                "TODO".to_string(),
+                "follow up".to_string(),
+                "follow-up".to_string(),
+                "followup".to_string(),
+            ],
+        }
+    }
+}
+
+/// A list setting that may also be written as a single string.
+fn one_or_many<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LaunchDarklyConfig {
+    pub enabled: bool,
+    /// Falls back to `LAUNCHDARKLY_API_TOKEN`.
+    pub api_token: Option<String>,
+    /// Falls back to `LAUNCHDARKLY_MAINTAINER_ID`.
+    pub maintainer_id: Option<String>,
+    /// Keys of teams we're on whose flags are checked too. Falls back to
+    /// `LAUNCHDARKLY_MAINTAINER_TEAM_KEYS=platform,web-infra`.
+    #[serde(deserialize_with = "one_or_many")]
+    pub maintainer_team_keys: Vec<String>,
+    /// Keys of the projects whose flags are checked. Also accepts a single
+    /// `project_key = "..."`. Falls back to `LAUNCHDARKLY_PROJECT_KEYS=web,mobile` or
+    /// `LAUNCHDARKLY_PROJECT_KEY`, then "default".
+    #[serde(alias = "project_key", deserialize_with = "one_or_many")]
+    pub project_keys: Vec<String>,
+    /// What a boolean flag's enabled variation may be named, in order of preference,
+    /// ignoring case. Otherwise it's the unnamed variation with value `true`.
+    #[serde(deserialize_with = "one_or_many")]
+    pub enabled_variation_names: Vec<String>,
+    /// Only check flags with these tags, when set. Filtered by LaunchDarkly.
+    #[serde(deserialize_with = "one_or_many")]
+    pub tags: Vec<String>,
+    /// Flags with any of these tags aren't checked at all.
+    #[serde(deserialize_with = "one_or_many")]
+    pub ignore_tags: Vec<String>,
+    /// Flags with these tags skipped by individual rules, e.g. `stale = ["permanent"]`.
+    pub ignore_tags_by_rule: IgnoreTagsByRule,
+    /// Where the API and flag pages are served from, e.g. "https://app.eu.launchdarkly.com"
+    /// for the EU instance or "https://app.launchdarkly.us" for the federal one.
+    pub base_url: String,
+    /// How many flags' details are fetched at once.
+    pub max_concurrent_requests: usize,
+    /// How many of a project's flags are checked each time. With more, the most recently
+    /// changed are checked first and the rest take turns.
+    pub max_flags_per_check: usize,
+    /// The environments flags roll out through, in order. Flags in other environments
+    /// aren't checked. Also accepts a table keyed by environment name, e.g.
+    /// `environments.staging = { stale_after = "4h" }`; see `pipeline`.
+    #[serde(deserialize_with = "pipeline")]
+    pub environments: Vec<LaunchDarklyEnvironment>,
+    /// Treat an environment serving a flag to individual targets as started, rather than
+    /// reporting "only individual targets in production".
+    pub targets_count_as_started: bool,
+    /// When set, only time on business days counts towards `stale_minutes`, so a rollout
+    /// paused on Friday afternoon isn't stale first thing Monday.
+    pub business_days: Option<BusinessDays>,
+    /// How many days a temporary flag can be fully rolled out in the last environment
+    /// before it's reported for removal.
+    pub removal_days: i64,
+    /// Report an environment turned off while the one before it is still rolled out, once
+    /// it's been off this many minutes. Which environments flags were on in is tracked in
+    /// the state file while this is set.
+    pub drift_minutes: Option<i64>,
+    /// Remind you to bump a partial rollout once it's gone this many minutes without
+    /// going up, e.g. `ramp_every = "4h"`, counting only business days with
+    /// `business_days`. Gentler than a stale rollout, which it turns into at the
+    /// environment's `stale_minutes`. None to not.
+    #[serde(
+        alias = "ramp_every",
+        deserialize_with = "optional_minutes_or_duration"
+    )]
+    pub ramp_minutes: Option<i64>,
+    /// `ramp_minutes` for flags with these tags, e.g. `{ slow-ramp = "1d" }`. The longest
+    /// applies to a flag with several.
+    #[serde(
+        alias = "ramp_every_by_tag",
+        deserialize_with = "minutes_or_duration_by_key"
+    )]
+    pub ramp_minutes_by_tag: HashMap<String, i64>,
+    /// Count a partial rollout as stale from when its percentage last went up, rather
+    /// than the environment's last change, which any edit resets. Percentages are
+    /// tracked in the state file, which also catches rollouts going backwards.
+    pub track_rollouts: bool,
+    /// Check the API token works, and can read every project, before the first check
+    /// and once a day after that, for a clearer issue than every request failing.
+    pub validate_token: bool,
+    /// How many days an experiment can hold a flag at a partial rollout before it's
+    /// reported, to check its results. Until then the rollout isn't reported as stale.
+    pub experiment_days: i64,
+    /// Every this many days, report temporary flags that have been off in every pipeline
+    /// environment for `dead_flag_days` as one rolled-up issue. None to not look for them.
+    pub dead_flags_report_days: Option<i64>,
+    /// How many days a flag has to have been off and untouched to be reported as dead.
+    pub dead_flag_days: i64,
+    /// Report segments our flags' rules use that have nobody in them, or that haven't
+    /// changed in `segment_stale_days` while a flag using them is mid-rollout.
+    pub segments: bool,
+    pub segment_stale_days: i64,
+    /// Report approval requests waiting on our review. Only some plans have approvals.
+    pub approval_requests: bool,
+    /// Report scheduled flag changes still waiting to be made after their date. Asks for
+    /// each flag's changes in each environment.
+    pub scheduled_changes: bool,
+    /// Report changes others make to our flags, from the audit log. Where it was last
+    /// read up to is kept in the state file.
+    pub audit_log: bool,
+    pub timeout_seconds: u64,
+    /// How often watch mode runs this checker; defaults to the watch `--interval`.
+    #[serde(deserialize_with = "interval_minutes")]
+    pub interval_minutes: Option<u64>,
+}
+
+impl Default for LaunchDarklyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            api_token: None,
+            maintainer_id: None,
+            maintainer_team_keys: Vec::new(),
+            project_keys: Vec::new(),
+            enabled_variation_names: vec![
+                "enabled".to_string(),
+                "on".to_string(),
+                "true".to_string(),
+            ],
+            tags: Vec::new(),
+            ignore_tags: Vec::new(),
+            ignore_tags_by_rule: IgnoreTagsByRule::default(),
+            base_url: "https://app.launchdarkly.com".to_string(),
+            max_concurrent_requests: 5,
+            max_flags_per_check: 100,
+            environments: default_environments(),
+            targets_count_as_started: false,
+            business_days: None,
+            removal_days: 14,
+            drift_minutes: None,
+            ramp_minutes: None,
+            ramp_minutes_by_tag: HashMap::new(),
+            track_rollouts: true,
+            validate_token: true,
+            experiment_days: 14,
+            dead_flags_report_days: None,
+            dead_flag_days: 90,
+            segments: false,
+            segment_stale_days: 180,
+            approval_requests: false,
+            scheduled_changes: false,
+            audit_log: false,
+            timeout_seconds: 60,
+            interval_minutes: None,
+        }
+    }
+}
+
+/// One step of a flag's rollout, e.g. staging.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LaunchDarklyEnvironment {
+    /// The environment's key, e.g. "production".
+    pub name: String,
+    /// The rollout at which the environment is done, so the next one should have started.
+    /// Partial rollouts below this are reported once they go stale.
+    #[serde(alias = "complete_at")]
+    pub done_percent: f64,
+    /// How long a partial rollout can go without changes before it's reported. Also
+    /// accepts a duration like `stale_after = "4h"`.
+    #[serde(alias = "stale_after", deserialize_with = "minutes_or_duration")]
+    pub stale_minutes: i64,
+}
+
+fn default_environments() -> Vec<LaunchDarklyEnvironment> {
+    vec![
+        LaunchDarklyEnvironment {
+            name: "staging".to_string(),
+            done_percent: 50.0,
+            stale_minutes: 120,
+        },
+        LaunchDarklyEnvironment {
+            name: "production".to_string(),
+            done_percent: 100.0,
+            stale_minutes: 18 * 60,
+        },
+    ]
+}
+
+/// An environment's settings in the table form of `environments`, where its name is the
+/// key and anything left out comes from the default environment of that name.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EnvironmentSettings {
+    #[serde(alias = "complete_at")]
+    done_percent: Option<f64>,
+    #[serde(
+        default,
+        alias = "stale_after",
+        deserialize_with = "optional_minutes_or_duration"
+    )]
+    stale_minutes: Option<i64>,
+}
+
+/// The rollout pipeline, either as an array of environments in order, or as a table of
+/// settings by environment name, e.g.
+///
+/// ```toml
+/// [launchdarkly.environments]
+/// order = ["dev", "staging", "production"]
+/// dev = { complete_at = 100, stale_after = "1h" }
+/// staging = { stale_after = "4h" }
+/// ```
+///
+/// Tables are unordered, so without `order` the pipeline is the default one (staging, then
+/// production) with these settings applied.
+fn pipeline<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<LaunchDarklyEnvironment>, D::Error> {
+    struct PipelineVisitor;
+
+    impl<'de> serde::de::Visitor<'de> for PipelineVisitor {
+        type Value = Vec<LaunchDarklyEnvironment>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("an array of environments, or a table of them by name")
+        }
+
+        fn visit_seq<A: serde::de::SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+            Vec::deserialize(serde::de::value::SeqAccessDeserializer::new(seq))
+        }
+
+        fn visit_map<A: serde::de::MapAccess<'de>>(
+            self,
+            mut map: A,
+        ) -> Result<Self::Value, A::Error> {
+            use serde::de::Error;
+
+            let mut order: Option<Vec<String>> = None;
+            let mut settings: Vec<(String, EnvironmentSettings)> = Vec::new();
+            while let Some(key) = map.next_key::<String>()? {
+                if key == "order" {
+                    order = Some(map.next_value()?);
+                } else {
+                    settings.push((key, map.next_value()?));
+                }
+            }
+
+            let defaults = default_environments();
+            let order = order.unwrap_or_else(|| defaults.iter().map(|e| e.name.clone()).collect());
+            if let Some((name, _)) = settings.iter().find(|(name, _)| !order.contains(name)) {
+                return Err(A::Error::custom(format!(
+                    "'{}' isn't in the pipeline; list every environment in `order`",
+                    name
+                )));
+            }
+            order
+                .into_iter()
+                .map(|name| {
+                    let set = settings
+                        .iter()
+                        .find(|(key, _)| *key == name)
+                        .map(|(_, s)| s);
+                    let default = defaults.iter().find(|env| env.name == name);
+                    let done_percent = set
+                        .and_then(|s| s.done_percent)
+                        .or(default.map(|env| env.done_percent));
+                    let stale_minutes = set
+                        .and_then(|s| s.stale_minutes)
+                        .or(default.map(|env| env.stale_minutes));
+                    match (done_percent, stale_minutes) {
+                        (Some(done_percent), Some(stale_minutes)) => Ok(LaunchDarklyEnvironment {
+                            name,
+                            done_percent,
+                            stale_minutes,
+                        }),
+                        _ => Err(A::Error::custom(format!(
+                            "'{}' needs both done_percent and stale_minutes",
+                            name
+                        ))),
+                    }
+                })
+                .collect()
+        }
+    }
+
+    deserializer.deserialize_any(PipelineVisitor)
+}
+
+/// Tags of flags each LaunchDarkly rule skips.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IgnoreTagsByRule {
+    /// Stale partial rollouts.
+    pub stale: Vec<String>,
+    /// Environments not started after the one before them is done.
+    pub not_started: Vec<String>,
+    /// Environments turned off while the one before them is on.
+    pub drift: Vec<String>,
+    /// Temporary flags fully rolled out.
+    pub removal: Vec<String>,
+    /// Prerequisites that are off.
+    pub prerequisites: Vec<String>,
+}
+
+/// Which days count when measuring how long a rollout has gone untouched.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BusinessDays {
+    /// Days of the week that don't count, e.g. ["Sat", "Sun"].
+    pub weekend: Vec<Weekday>,
+    /// Dates that don't count either, e.g. "2024-12-25".
+    pub holidays: Vec<NaiveDate>,
+    /// The time zone days start and end in, as an offset from UTC like "-08:00";
+    /// defaults to the local one.
+    #[serde(deserialize_with = "utc_offset")]
+    pub utc_offset: Option<FixedOffset>,
+}
+
+impl BusinessDays {
+    pub fn is_business_day(&self, date: NaiveDate) -> bool {
+        !self.weekend.contains(&date.weekday()) && !self.holidays.contains(&date)
+    }
+}
+
+impl Default for BusinessDays {
+    fn default() -> Self {
+        Self {
+            weekend: vec![Weekday::Sat, Weekday::Sun],
+            holidays: Vec::new(),
+            utc_offset: None,
+        }
+    }
+}
+
+fn utc_offset<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<FixedOffset>, D::Error> {
+    let offset = String::deserialize(deserializer)?;
+    offset.parse().map(Some).map_err(|_| {
+        serde::de::Error::custom(format!(
+            "expected an offset from UTC like \"-08:00\", found \"{}\"",
+            offset
+        ))
+    })
+}
+
+/// How often to run a checker, which can't be 0 or watch mode would never sleep.
+fn interval_minutes<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u64>, D::Error> {
+    match u64::deserialize(deserializer)? {
+        0 => Err(serde::de::Error::custom("must be at least 1 minute")),
+        minutes => Ok(Some(minutes)),
+    }
+}
+
+/// A number of minutes, or a duration like "4h", "1h30m" or "2 days".
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MinutesOrDuration {
+    Minutes(i64),
+    Duration(String),
+}
+
+impl MinutesOrDuration {
+    fn minutes<E: serde::de::Error>(self) -> Result<i64, E> {
+        match self {
+            MinutesOrDuration::Minutes(minutes) => Ok(minutes),
+            MinutesOrDuration::Duration(duration) => {
+                parse_duration_minutes(&duration).map_err(E::custom)
+            }
+        }
+    }
+}
+
+fn minutes_or_duration<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    MinutesOrDuration::deserialize(deserializer)?.minutes()
+}
+
+fn optional_minutes_or_duration<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<i64>, D::Error> {
+    minutes_or_duration(deserializer).map(Some)
+}
+
+/// A table of `minutes_or_duration`s, e.g. `{ slow-ramp = "1d", fast-ramp = 60 }`.
+fn minutes_or_duration_by_key<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<String, i64>, D::Error> {
+    HashMap::<String, MinutesOrDuration>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(key, value)| Ok((key, value.minutes()?)))
+        .collect()
+}
+
+/// Parses a duration made of numbers with units of days, hours or minutes, e.g. "4h",
+/// "1h 30m" or "2 days", into minutes.
+fn parse_duration_minutes(duration: &str) -> Result<i64, String> {
+    let invalid = || {
+        format!(
+            "expected a duration like \"4h\" or \"1h30m\", found \"{}\"",
+            duration
+        )
+    };
+    let mut rest = duration.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    let mut minutes = 0;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let number: i64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = rest[digits..].trim_start();
+        let letters = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let per_unit = match &rest[..letters] {
+            "d" | "day" | "days" => 24 * 60,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 60,
+            "m" | "min" | "mins" | "minute" | "minutes" => 1,
+            _ => return Err(invalid()),
+        };
+        minutes += number * per_unit;
+        rest = rest[letters..].trim_start();
+    }
+    Ok(minutes)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotifierConfig {
+    pub backend: NotifierBackend,
+    pub html_path: String,
+    /// How long clicking an issue in the dashboard keeps it in "Recently Reviewed".
+    pub seen_minutes: i64,
+    /// Minimum time between notifications for the same issue, or "never" to only
+    /// notify once per issue.
+    pub renotify_minutes: Renotify,
+    /// How long the dashboard's snooze button silences an issue.
+    pub snooze_minutes: i64,
+    /// When set, desktop notifications are held until the window ends.
+    pub quiet_hours: Option<QuietHours>,
+    pub escalation: EscalationConfig,
+}
+
+/// How notifications change once an issue has been ignored for a long time.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EscalationConfig {
+    pub enabled: bool,
+    /// How long an issue must have been open before its notifications escalate.
+    pub after_minutes: i64,
+    /// Notification sound used instead of the usual one.
+    pub sound: String,
+    /// Also show a macOS alert dialog that stays up until dismissed.
+    pub alert: bool,
+}
+
+impl Default for EscalationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            after_minutes: 240,
+            sound: "Sosumi".to_string(),
+            alert: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "RenotifySetting")]
+pub enum Renotify {
+    Minutes(i64),
+    Never,
+}
+
+/// `renotify_minutes` as written in the config file: a number or "never".
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RenotifySetting {
+    Minutes(i64),
+    Word(String),
+}
+
+impl TryFrom<RenotifySetting> for Renotify {
+    type Error = String;
+
+    fn try_from(setting: RenotifySetting) -> Result<Self, Self::Error> {
+        match setting {
+            RenotifySetting::Minutes(minutes) => Ok(Renotify::Minutes(minutes)),
+            RenotifySetting::Word(word) if word == "never" => Ok(Renotify::Never),
+            RenotifySetting::Word(word) => Err(format!(
+                "expected a number of minutes or \"never\", found \"{}\"",
+                word
+            )),
+        }
+    }
+}
+
+/// Where notifications are delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifierBackend {
+    /// Desktop notifications via `terminal-notifier`.
+    Macos,
+    /// Log each notification at info level instead of sending it.
+    Log,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuietHours {
+    /// Local time the window starts, e.g. "19:00".
+    pub start: NaiveTime,
+    /// Local time the window ends; before `start` means it runs past midnight.
+    pub end: NaiveTime,
+    /// Also quiet all day on Saturday and Sunday.
+    #[serde(default)]
+    pub weekends: bool,
+}
+
+impl QuietHours {
+    pub fn contains(&self, local: NaiveDateTime) -> bool {
+        if self.weekends && matches!(local.weekday(), Weekday::Sat | Weekday::Sun) {
+            return true;
+        }
+        let time = local.time();
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl Default for NotifierConfig {
+    fn default() -> Self {
+        Self {
+            backend: NotifierBackend::Macos,
+            html_path: "~/Desktop/work-driver-issues.html".to_string(),
+            seen_minutes: 30,
+            renotify_minutes: Renotify::Minutes(19),
+            snooze_minutes: 240,
+            quiet_hours: None,
+            escalation: EscalationConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub port: u16,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self { port: 9845 }
+    }
+}
+
+impl NotifierConfig {
+    pub fn html_path(&self) -> PathBuf {
+        PathBuf::from(shellexpand::tilde(&self.html_path).as_ref())
+    }
+
+    /// Whether `now` falls in the configured quiet hours, in local time.
+    pub fn is_quiet(&self, now: DateTime<Utc>) -> bool {
+        self.quiet_hours
+            .as_ref()
+            .is_some_and(|quiet| quiet.contains(now.with_timezone(&Local).naive_local()))
+    }
+}
+
+impl ServerConfig {
+    pub fn url(&self) -> String {
+        format!("http://localhost:{}/", self.port)
+    }
+}
+
+pub fn config_path() -> Result<PathBuf> {
+    let home = std::env::var("HOME").context("HOME not set")?;
+    Ok(PathBuf::from(home).join(".config/work-driver/config.toml"))
+}
+
+/// Loads the config file, using defaults when it doesn't exist, then fills unset secrets
+/// from their environment variables.
+pub fn load_config() -> Result<Config> {
+    let path = config_path()?;
+    let mut config = if path.exists() {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        parse_config(&content).with_context(|| format!("Invalid config in {}", path.display()))?
+    } else {
+        Config::default()
+    };
+
+    if let Ok(checks) = std::env::var("WORK_DRIVER_CHECKS") {
+        config.apply_enabled_checks(&checks)?;
+    }
+
+    let github = &mut config.github;
+    if let Ok(repos) = std::env::var("GITHUB_REPOS") {
+        github.repos = split_list(&repos);
+    }
+    github.token = github
+        .token
+        .take()
+        .or_else(|| std::env::var("GITHUB_TOKEN").ok());
+
+    let ld = &mut config.launchdarkly;
+    ld.api_token = ld
+        .api_token
+        .take()
+        .or_else(|| std::env::var("LAUNCHDARKLY_API_TOKEN").ok());
+    ld.maintainer_id = ld
+        .maintainer_id
+        .take()
+        .or_else(|| std::env::var("LAUNCHDARKLY_MAINTAINER_ID").ok());
+    if ld.maintainer_team_keys.is_empty()
+        && let Ok(keys) = std::env::var("LAUNCHDARKLY_MAINTAINER_TEAM_KEYS")
+    {
+        ld.maintainer_team_keys = split_list(&keys);
+    }
+    if ld.project_keys.is_empty() {
+        ld.project_keys = std::env::var("LAUNCHDARKLY_PROJECT_KEYS")
+            .or_else(|_| std::env::var("LAUNCHDARKLY_PROJECT_KEY"))
+            .map(|keys| split_list(&keys))
+            .unwrap_or_default();
+    }
+
+    Ok(config)
+}
+
+impl Config {
+    /// Enables exactly the checkers named in a comma-separated list like "github,launchdarkly",
+    /// overriding the per-checker `enabled` settings.
+    pub fn apply_enabled_checks(&mut self, checks: &str) -> Result<()> {
+        let names: Vec<String> = split_list(checks)
+            .iter()
+            .map(|name| name.to_lowercase())
+            .collect();
+        if let Some(unknown) = names
+            .iter()
+            .find(|name| !CHECKER_NAMES.contains(&name.as_str()))
+        {
+            anyhow::bail!(
+                "Unknown checker '{}' in WORK_DRIVER_CHECKS (expected one of: {})",
+                unknown,
+                CHECKER_NAMES.join(", ")
+            );
+        }
+        self.github.enabled = names.iter().any(|name| name == "github");
+        self.launchdarkly.enabled = names.iter().any(|name| name == "launchdarkly");
+        Ok(())
+    }
+}
+
+/// Splits a comma-separated environment variable, ignoring blank entries.
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+pub fn parse_config(content: &str) -> Result<Config> {
+    let value = toml::parse(content)?;
+    serde_path_to_error::deserialize(value).map_err(|e| {
+        let path = e.path().to_string();
+        anyhow::anyhow!("{}: {}", path, e.into_inner())
+    })
+}
+
+/// Returns the value of a required setting, or an error explaining where to set it.
+pub fn require<'a>(value: &'a Option<String>, key: &str, env_var: &str) -> Result<&'a str> {
+    value.as_deref().filter(|v| !v.is_empty()).with_context(|| {
+        format!(
+            "{} is not set (add it to ~/.config/work-driver/config.toml or set {})",
+            key, env_var
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config_defaults_and_overrides() {
+        let config = parse_config(
+            r#"
+[github]
+repo = "acme/widgets"
+
+[launchdarkly]
+enabled = false
+project_key = "web"
+
+[server]
+port = 8000
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.github.repos, ["acme/widgets"]);
+        assert!(config.github.enabled);
+        assert!(!config.launchdarkly.enabled);
+        assert_eq!(config.launchdarkly.project_keys, ["web"]);
+        assert_eq!(config.notifier.renotify_minutes, Renotify::Minutes(19));
+        assert_eq!(config.notifier.backend, NotifierBackend::Macos);
+        assert_eq!(config.server.port, 8000);
+    }
+
+    #[test]
+    fn test_parse_config_github_repos() {
+        let config = parse_config("[github]\nrepos = [\"org/a\", \"org/b\"]\n").unwrap();
+        assert_eq!(config.github.repos, ["org/a", "org/b"]);
+        assert_eq!(split_list(" org/a,, org/b "), ["org/a", "org/b"]);
+    }
+
+    #[test]
+    fn test_parse_config_launchdarkly_environments() {
+        let config = parse_config(
+            "[[launchdarkly.environments]]\n\
+             name = \"stage\"\n\
+             done_percent = 50\n\
+             stale_minutes = 240\n",
+        )
+        .unwrap();
+        let names: Vec<&str> = config
+            .launchdarkly
+            .environments
+            .iter()
+            .map(|env| env.name.as_str())
+            .collect();
+        assert_eq!(names, ["stage"]);
+        assert_eq!(LaunchDarklyConfig::default().environments.len(), 2);
+
+        let config = parse_config(
+            "[[launchdarkly.environments]]\n\
+             name = \"staging\"\n\
+             complete_at = 50\n\
+             stale_after = \"4h\"\n",
+        )
+        .unwrap();
+        let staging = &config.launchdarkly.environments[0];
+        assert_eq!(staging.done_percent, 50.0);
+        assert_eq!(staging.stale_minutes, 240);
+
+        let err = parse_config(
+            "[[launchdarkly.environments]]\n\
+             name = \"staging\"\n\
+             done_percent = 50\n\
+             stale_after = \"soon\"\n",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "launchdarkly.environments[0].stale_after: expected a duration like \"4h\" or \"1h30m\", found \"soon\""
+        );
+    }
+
+    #[test]
+    fn test_parse_config_launchdarkly_environments_by_name() {
+        let pipeline = |config: &str| -> Vec<(String, f64, i64)> {
+            parse_config(config)
+                .unwrap()
+                .launchdarkly
+                .environments
+                .into_iter()
+                .map(|env| (env.name, env.done_percent, env.stale_minutes))
+                .collect()
+        };
+        // Overrides the default pipeline's staging, keeping production
+        let expected = [
+            ("staging".to_string(), 50.0, 240),
+            ("production".to_string(), 100.0, 18 * 60),
+        ];
+        assert_eq!(
+            pipeline(
+                "[launchdarkly]\n\
+                 environments.staging = { stale_after = \"4h\", complete_at = 50 }\n"
+            ),
+            expected
+        );
+        assert_eq!(
+            pipeline("[launchdarkly.environments.staging]\nstale_after = \"4h\"\n"),
+            expected
+        );
+
+        assert_eq!(
+            pipeline(
+                "[launchdarkly.environments]\n\
+                 order = [\"dev\", \"production\"]\n\
+                 dev = { complete_at = 100, stale_after = \"1h\" }\n"
+            ),
+            [
+                ("dev".to_string(), 100.0, 60),
+                ("production".to_string(), 100.0, 18 * 60),
+            ]
+        );
+
+        let err = parse_config("[launchdarkly.environments]\ndev = { stale_after = \"1h\" }\n")
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("launchdarkly.environments: 'dev' isn't in the pipeline"),
+            "{}",
+            err
+        );
+        let err = parse_config(
+            "[launchdarkly.environments]\n\
+             order = [\"dev\"]\n\
+             dev = { stale_after = \"1h\" }\n",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "launchdarkly.environments: 'dev' needs both done_percent and stale_minutes"
+        );
+    }
+
+    #[test]
+    fn test_parse_config_launchdarkly_tags() {
+        let config = parse_config(
+            "[launchdarkly]\n\
+             tags = \"experiment\"\n\
+             ignore_tags = [\"ops\", \"sunset\"]\n\
+             ignore_tags_by_rule = { stale = [\"permanent\"], removal = [\"permanent\"] }\n",
+        )
+        .unwrap();
+        let ld = config.launchdarkly;
+        assert_eq!(ld.tags, ["experiment"]);
+        assert_eq!(ld.ignore_tags, ["ops", "sunset"]);
+        assert_eq!(ld.ignore_tags_by_rule.stale, ["permanent"]);
+        assert_eq!(ld.ignore_tags_by_rule.removal, ["permanent"]);
+        assert!(ld.ignore_tags_by_rule.not_started.is_empty());
+
+        let err = parse_config("[launchdarkly.ignore_tags_by_rule]\nstaleness = [\"permanent\"]\n")
+            .unwrap_err();
+        assert!(
+            err.to_string().starts_with(
+                "launchdarkly.ignore_tags_by_rule.staleness: unknown field `staleness`"
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_parse_config_ramp_schedule() {
+        let config = parse_config(
+            "[launchdarkly]\n\
+             ramp_every = \"4h\"\n\
+             ramp_every_by_tag = { slow-ramp = \"1d\", fast-ramp = 60 }\n",
+        )
+        .unwrap();
+        let ld = config.launchdarkly;
+        assert_eq!(ld.ramp_minutes, Some(4 * 60));
+        assert_eq!(ld.ramp_minutes_by_tag["slow-ramp"], 24 * 60);
+        assert_eq!(ld.ramp_minutes_by_tag["fast-ramp"], 60);
+        assert_eq!(parse_config("").unwrap().launchdarkly.ramp_minutes, None);
+    }
+
+    #[test]
+    fn test_parse_config_interval_minutes() {
+        let config = parse_config("[github]\ninterval_minutes = 3\n").unwrap();
+        assert_eq!(config.github.interval_minutes, Some(3));
+        let err = parse_config("[launchdarkly]\ninterval_minutes = 0\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "launchdarkly.interval_minutes: must be at least 1 minute"
+        );
+    }
+
+    #[test]
+    fn test_parse_config_business_days() {
+        let config = parse_config(
+            "[launchdarkly.business_days]\n\
+             weekend = [\"Fri\", \"Saturday\"]\n\
+             holidays = [\"2024-12-25\"]\n\
+             utc_offset = \"+09:00\"\n",
+        )
+        .unwrap();
+        let days = config.launchdarkly.business_days.unwrap();
+        assert_eq!(days.weekend, [Weekday::Fri, Weekday::Sat]);
+        assert_eq!(days.utc_offset, FixedOffset::east_opt(9 * 60 * 60));
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        assert!(days.is_business_day(date("2024-12-24")));
+        assert!(!days.is_business_day(date("2024-12-25")));
+        assert!(!days.is_business_day(date("2024-12-27")));
+        assert!(days.is_business_day(date("2024-12-29")));
+
+        let defaults = parse_config("[launchdarkly.business_days]\n").unwrap();
+        let days = defaults.launchdarkly.business_days.unwrap();
+        assert_eq!(days.weekend, [Weekday::Sat, Weekday::Sun]);
+        assert_eq!(days.utc_offset, None);
+
+        let err = parse_config("[launchdarkly.business_days]\nutc_offset = \"PST\"\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "launchdarkly.business_days.utc_offset: expected an offset from UTC like \"-08:00\", found \"PST\""
+        );
+    }
+
+    #[test]
+    fn test_parse_duration_minutes() {
+        assert_eq!(parse_duration_minutes("4h"), Ok(240));
+        assert_eq!(parse_duration_minutes("90m"), Ok(90));
+        assert_eq!(parse_duration_minutes("1h30m"), Ok(90));
+        assert_eq!(parse_duration_minutes(" 1h 30m "), Ok(90));
+        assert_eq!(parse_duration_minutes("2 days"), Ok(2 * 24 * 60));
+        assert_eq!(parse_duration_minutes("18 hours"), Ok(18 * 60));
+        for invalid in ["", "4", "h", "4 weeks", "1.5h", "-4h"] {
+            assert!(parse_duration_minutes(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_parse_config_reports_bad_keys() {
+        let err = parse_config("[github]\nrepository = \"a/b\"\n").unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("github.repository: unknown field `repository`"),
+            "{}",
+            err
+        );
+
+        let err = parse_config("[server]\nport = \"high\"\n").unwrap_err();
+        assert!(
+            err.to_string().starts_with("server.port: invalid type"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_parse_renotify() {
+        let config = parse_config("[notifier]\nrenotify_minutes = \"never\"\n").unwrap();
+        assert_eq!(config.notifier.renotify_minutes, Renotify::Never);
+
+        let config = parse_config("[notifier]\nrenotify_minutes = 60\n").unwrap();
+        assert_eq!(config.notifier.renotify_minutes, Renotify::Minutes(60));
+
+        let err = parse_config("[notifier]\nrenotify_minutes = \"sometimes\"\n").unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("notifier.renotify_minutes: expected"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_quiet_hours() {
+        let config = parse_config(
+            "[notifier.quiet_hours]\nstart = \"19:00\"\nend = \"09:00\"\nweekends = true\n",
+        )
+        .unwrap();
+        let quiet = config.notifier.quiet_hours.unwrap();
+        let at = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+
+        // 2024-06-03 is a Monday
+        assert!(quiet.contains(at("2024-06-03 23:00")));
+        assert!(quiet.contains(at("2024-06-03 08:59")));
+        assert!(!quiet.contains(at("2024-06-03 09:00")));
+        assert!(!quiet.contains(at("2024-06-03 18:59")));
+        assert!(quiet.contains(at("2024-06-08 12:00")));
+
+        let daytime = QuietHours {
+            start: NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(13, 0, 0).unwrap(),
+            weekends: false,
+        };
+        assert!(daytime.contains(at("2024-06-03 12:30")));
+        assert!(!daytime.contains(at("2024-06-03 13:30")));
+        assert!(!daytime.contains(at("2024-06-08 08:00")));
+    }
+
+    #[test]
+    fn test_apply_enabled_checks() {
+        let mut config = Config::default();
+        config.apply_enabled_checks("GitHub, ").unwrap();
+        assert!(config.github.enabled);
+        assert!(!config.launchdarkly.enabled);
+
+        assert!(config.apply_enabled_checks("github,jira").is_err());
+    }
+
+    #[test]
+    fn test_require_explains_missing_setting() {
+        let err = require(&None, "launchdarkly.api_token", "LAUNCHDARKLY_API_TOKEN").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "launchdarkly.api_token is not set (add it to ~/.config/work-driver/config.toml or set LAUNCHDARKLY_API_TOKEN)"
+        );
+    }
+}
@@ -0,0 +1,420 @@
+//! A small TOML reader covering what config.toml needs: tables, arrays of tables, dotted
+//! keys, strings, integers, floats, booleans, arrays and inline tables. Dates and multi-line
+//! strings are not supported. The document is returned as JSON so it can be deserialized
+//! with serde_json.
+
+use anyhow::{Result, bail};
+use serde_json::{Map, Value};
+
+pub fn parse(input: &str) -> Result<Value> {
+    Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+    }
+    .document()
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn line(&self) -> usize {
+        self.chars[..self.pos]
+            .iter()
+            .filter(|c| **c == '\n')
+            .count()
+            + 1
+    }
+
+    fn error<T>(&self, message: impl std::fmt::Display) -> Result<T> {
+        bail!("line {}: {}", self.line(), message)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => self.error(format!("expected '{}', found '{}'", expected, c)),
+            None => self.error(format!("expected '{}', found end of file", expected)),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn skip_blank_lines(&mut self) {
+        loop {
+            self.skip_whitespace();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n' | '\r') => self.pos += 1,
+                _ => return,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<()> {
+        self.skip_whitespace();
+        self.skip_comment();
+        if self.peek() == Some('\r') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => self.error(format!("expected end of line, found '{}'", c)),
+        }
+    }
+
+    fn document(&mut self) -> Result<Value> {
+        let mut root = Map::new();
+        let mut current_table: Vec<String> = Vec::new();
+
+        loop {
+            self.skip_blank_lines();
+            match self.peek() {
+                None => break,
+                Some('[') => {
+                    self.pos += 1;
+                    let is_array = self.peek() == Some('[');
+                    if is_array {
+                        self.pos += 1;
+                    }
+                    self.skip_whitespace();
+                    let path = self.key()?;
+                    self.skip_whitespace();
+                    self.expect(']')?;
+                    if is_array {
+                        self.expect(']')?;
+                    }
+                    let line = self.line();
+                    self.end_of_line()?;
+
+                    if is_array {
+                        let (last, parents) = path.split_last().expect("keys are non-empty");
+                        let parent = table_at(&mut root, parents, line)?;
+                        let entry = parent
+                            .entry(last.clone())
+                            .or_insert_with(|| Value::Array(Vec::new()));
+                        match entry {
+                            Value::Array(items) => items.push(Value::Object(Map::new())),
+                            _ => bail!("line {}: '{}' is not an array of tables", line, last),
+                        }
+                    } else {
+                        table_at(&mut root, &path, line)?;
+                    }
+                    current_table = path;
+                }
+                Some(_) => {
+                    let line = self.line();
+                    let key = self.key()?;
+                    self.skip_whitespace();
+                    self.expect('=')?;
+                    self.skip_whitespace();
+                    let value = self.value()?;
+                    self.end_of_line()?;
+                    let table = table_at(&mut root, &current_table, line)?;
+                    insert(table, &key, value, line)?;
+                }
+            }
+        }
+
+        Ok(Value::Object(root))
+    }
+
+    /// Parses a possibly dotted key like `launchdarkly.environments."my env"`.
+    fn key(&mut self) -> Result<Vec<String>> {
+        let mut parts = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let part = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let start = self.pos;
+                    while self
+                        .peek()
+                        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                    {
+                        self.pos += 1;
+                    }
+                    if start == self.pos {
+                        return self.error("expected a key");
+                    }
+                    self.chars[start..self.pos].iter().collect()
+                }
+            };
+            parts.push(part);
+            self.skip_whitespace();
+            if self.peek() == Some('.') {
+                self.pos += 1;
+            } else {
+                return Ok(parts);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        match self.peek() {
+            Some('"') => Ok(Value::String(self.basic_string()?)),
+            Some('\'') => Ok(Value::String(self.literal_string()?)),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some('t' | 'f') => self.boolean(),
+            Some(c) if c.is_ascii_digit() || c == '+' || c == '-' => self.number(),
+            Some(c) => self.error(format!("unexpected '{}' at start of value", c)),
+            None => self.error("expected a value, found end of file"),
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.peek() {
+                None | Some('\n') => return self.error("unterminated string"),
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    let escaped = match self.peek() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('u') => {
+                            let hex: String =
+                                self.chars.iter().skip(self.pos + 1).take(4).collect();
+                            let Some(c) =
+                                u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+                            else {
+                                return self.error(format!("invalid unicode escape '\\u{}'", hex));
+                            };
+                            self.pos += 4;
+                            c
+                        }
+                        _ => return self.error("invalid escape sequence"),
+                    };
+                    out.push(escaped);
+                    self.pos += 1;
+                }
+                Some(c) => {
+                    out.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String> {
+        self.expect('\'')?;
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                None | Some('\n') => return self.error("unterminated string"),
+                Some('\'') => {
+                    let out = self.chars[start..self.pos].iter().collect();
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    fn boolean(&mut self) -> Result<Value> {
+        for (word, value) in [("true", true), ("false", false)] {
+            let end = self.pos + word.len();
+            if end <= self.chars.len() && self.chars[self.pos..end].iter().copied().eq(word.chars())
+            {
+                self.pos = end;
+                return Ok(Value::Bool(value));
+            }
+        }
+        self.error("expected a value")
+    }
+
+    fn number(&mut self) -> Result<Value> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | '_' | 'e' | 'E'))
+        {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos]
+            .iter()
+            .filter(|c| **c != '_')
+            .collect();
+        if let Ok(int) = text.parse::<i64>() {
+            return Ok(Value::from(int));
+        }
+        match text
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+        {
+            Some(float) => Ok(Value::Number(float)),
+            None => self.error(format!("invalid number '{}'", text)),
+        }
+    }
+
+    fn array(&mut self) -> Result<Value> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank_lines();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_blank_lines();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {}
+                _ => return self.error("expected ',' or ']' in array"),
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value> {
+        self.expect('{')?;
+        let mut table = Map::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Value::Object(table));
+        }
+        loop {
+            let line = self.line();
+            let key = self.key()?;
+            self.skip_whitespace();
+            self.expect('=')?;
+            self.skip_whitespace();
+            let value = self.value()?;
+            insert(&mut table, &key, value, line)?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(table));
+                }
+                _ => return self.error("expected ',' or '}' in inline table"),
+            }
+        }
+    }
+}
+
+/// Walks (creating as needed) to the table at `path`. Arrays of tables resolve to their
+/// most recently added element, matching how `[[x]]` followed by `[x.y]` behaves in TOML.
+fn table_at<'a>(
+    mut table: &'a mut Map<String, Value>,
+    path: &[String],
+    line: usize,
+) -> Result<&'a mut Map<String, Value>> {
+    for part in path {
+        let entry = table
+            .entry(part.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        table = match entry {
+            Value::Object(inner) => inner,
+            Value::Array(items) => match items.last_mut() {
+                Some(Value::Object(inner)) => inner,
+                _ => bail!("line {}: '{}' is not a table", line, part),
+            },
+            _ => bail!("line {}: '{}' is not a table", line, part),
+        };
+    }
+    Ok(table)
+}
+
+fn insert(table: &mut Map<String, Value>, key: &[String], value: Value, line: usize) -> Result<()> {
+    let (last, parents) = key.split_last().expect("keys are non-empty");
+    let table = table_at(table, parents, line)?;
+    if table.contains_key(last) {
+        bail!("line {}: duplicate key '{}'", line, key.join("."));
+    }
+    table.insert(last.clone(), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_document() {
+        let input = r#"
+# Top-level comment
+title = "work \"driver\"" # trailing comment
+
+[github]
+enabled = false
+repos = [
+    "org/a",
+    'org/b',
+]
+
+[launchdarkly]
+environments.staging = { stale_after = "4h", complete_at = 50 }
+ratio = 0.5
+count = 1_000
+
+[[launchdarkly.projects]]
+key = "web"
+
+[[launchdarkly.projects]]
+key = "mobile"
+"#;
+        assert_eq!(
+            parse(input).unwrap(),
+            json!({
+                "title": "work \"driver\"",
+                "github": { "enabled": false, "repos": ["org/a", "org/b"] },
+                "launchdarkly": {
+                    "environments": { "staging": { "stale_after": "4h", "complete_at": 50 } },
+                    "ratio": 0.5,
+                    "count": 1000,
+                    "projects": [{ "key": "web" }, { "key": "mobile" }],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_include_line() {
+        let err = parse("[github]\nenabled = yes\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 2: unexpected 'y' at start of value");
+
+        let err = parse("a = 1\na = 2\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 2: duplicate key 'a'");
+    }
+}
@@ -1,166 +1,5141 @@
-use crate::check::Check;
-use anyhow::{Context, Result};
+mod api;
+mod codeowners;
+mod graphql;
+
+use crate::check::{Check, CheckOutput};
+use crate::config::{AssignedIssuesConfig, FollowUpsConfig, GitHubConfig, MilestonesConfig};
+use crate::issue::{CheckSource, Issue, IssueKind, Severity};
+use crate::notifier::format_age;
+use crate::state::{CheckHistory, MutedPr, State, StateUpdate, load_state, state_dir};
+use anyhow::{Context, Result, bail};
+use api::{
+    Comment, DependabotAlert, GitHubApi, MergedPull, Milestone, Notification, PendingDeployment,
+    ResponseCache, WorkflowRun, WorkflowRuns,
+};
 use async_trait::async_trait;
-use std::process::Command;
+use chrono::{DateTime, Datelike, Utc};
+use codeowners::CodeOwners;
+use futures_util::{StreamExt, stream};
+use serde::de::DeserializeOwned;
+use std::collections::{HashMap, HashSet};
+use std::process::{Command, Output};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// The last notifications fetched, reused until GitHub allows polling again.
+#[derive(Default)]
+struct NotificationCache {
+    last_modified: Option<String>,
+    next_poll: Option<Instant>,
+    issues: Vec<Issue>,
+}
+
+/// Runs commands for the CLI backend, so tests can stand in for `gh`.
+pub trait CommandRunner: Send + Sync {
+    /// Runs `command` to completion, like `Command::output`.
+    fn output(&self, command: &mut Command) -> std::io::Result<Output>;
+}
+
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn output(&self, command: &mut Command) -> std::io::Result<Output> {
+        command.output()
+    }
+}
+
+/// What's tracked about our PRs across runs, as of the state file when the check started,
+/// updated as each repo is checked.
+struct TrackedPrs {
+    auto_merge_since: HashMap<String, DateTime<Utc>>,
+    unreviewed_since: HashMap<String, DateTime<Utc>>,
+    check_history: HashMap<String, CheckHistory>,
+    /// Whether anything changed, so the state file needs saving.
+    changed: bool,
+}
+
+/// What checking one repo found.
+struct RepoResults {
+    issues: Vec<Issue>,
+    muted_open: Vec<(String, MutedPr)>,
+    /// Our open PRs, when `closed_prs` is on.
+    open: HashSet<String>,
+}
+
+/// Where PR status comes from.
+enum Backend {
+    /// `gh pr status`, as whichever account `gh` is logged in as.
+    Cli,
+    /// The REST API, authenticated with `github.token`.
+    Api(GitHubApi),
+}
 
-pub struct GitHubChecker;
+pub struct GitHubChecker {
+    repos: Vec<String>,
+    /// With no `repos`, the current directory's repository once `gh` has found it.
+    current_repo: OnceLock<String>,
+    /// e.g. "github.com", used for links and by `gh`.
+    host: String,
+    backend: Backend,
+    gh: Gh,
+    rate_limit_reserve: u64,
+    max_concurrent_repos: usize,
+    graphql: bool,
+    skip_auto_merge: bool,
+    include_drafts: bool,
+    /// How long a check can stay queued or running before it's reported as stuck.
+    pending_after: chrono::Duration,
+    auto_merge_stuck_after: chrono::Duration,
+    review_threads: bool,
+    /// How long reviewers' and our own thread comments can go unanswered, when enabled.
+    your_reply_after: Option<chrono::Duration>,
+    their_reply_after: Option<chrono::Duration>,
+    re_requested_reviews: bool,
+    pending_reviews: bool,
+    /// How long a required check can be missing before it's reported, when enabled.
+    missing_check_after: Option<chrono::Duration>,
+    /// How old a PR without any checks can get before it's reported, when enabled.
+    no_checks_after: Option<chrono::Duration>,
+    /// Report PRs at least this many commits behind their base, when set.
+    behind_base_commits: Option<u64>,
+    approval_progress: bool,
+    /// How long a green PR can go without a review before it's reported.
+    unreviewed_after: Option<chrono::Duration>,
+    /// Uppercased check conclusions and status states that count as failing.
+    failing_conclusions: Vec<String>,
+    flaky_checks: Vec<String>,
+    track_flaky_checks: bool,
+    stacked_prs: bool,
+    code_owners: bool,
+    closed_prs: bool,
+    /// The stale bot's limit and how long before it to start reporting, in days, when set.
+    auto_close: Option<(i64, i64)>,
+    all_review_requests: bool,
+    /// `org/team` slugs whose review requests are reported too.
+    teams: Vec<String>,
+    /// How old a review request can get before it's reported as urgent.
+    stale_review_after: Option<chrono::Duration>,
+    ignored_authors: Vec<String>,
+    collapse_ignored_authors: bool,
+    /// Lowercased, for case-insensitive matching.
+    ignore_labels: Vec<String>,
+    only_labels: Vec<String>,
+    /// Lowercased `owner/repo#number` references.
+    muted_prs: Vec<String>,
+    assigned_issues: AssignedIssuesConfig,
+    milestones: MilestonesConfig,
+    follow_ups: FollowUpsConfig,
+    default_branch_workflows: Vec<String>,
+    deployment_approvals: Vec<String>,
+    dependabot_alerts: bool,
+    /// Present when mention notifications are enabled.
+    notifications: Option<Mutex<NotificationCache>>,
+    timeout: Duration,
+    interval: Option<Duration>,
+}
 
 impl GitHubChecker {
-    pub fn new() -> Self {
-        Self
+    /// Uses the API when a token is configured, falling back to the `gh` CLI otherwise.
+    pub fn new(config: &GitHubConfig) -> Self {
+        let backend = match api_token(config) {
+            Some(token) => {
+                let mut api = GitHubApi::new(token, &config.api_base_url());
+                if config.cache_responses
+                    && let Ok(dir) = state_dir()
+                {
+                    api = api.with_cache(ResponseCache::load(dir.join("github-cache.json")));
+                }
+                Backend::Api(api)
+            }
+            None => Backend::Cli,
+        };
+        Self {
+            repos: config.repos.clone(),
+            current_repo: OnceLock::new(),
+            host: config.host.clone(),
+            backend,
+            gh: Gh {
+                host: config.host.clone(),
+                runner: Arc::new(SystemRunner),
+            },
+            rate_limit_reserve: config.rate_limit_reserve,
+            max_concurrent_repos: config.max_concurrent_repos.max(1),
+            graphql: config.graphql,
+            skip_auto_merge: config.skip_auto_merge,
+            include_drafts: config.include_drafts,
+            pending_after: chrono::Duration::minutes(config.pending_minutes),
+            auto_merge_stuck_after: chrono::Duration::minutes(config.auto_merge_stuck_minutes),
+            review_threads: config.review_threads,
+            your_reply_after: config
+                .thread_replies
+                .then(|| chrono::Duration::minutes(config.your_reply_minutes)),
+            their_reply_after: config
+                .thread_replies
+                .then(|| chrono::Duration::minutes(config.their_reply_minutes)),
+            re_requested_reviews: config.re_requested_reviews,
+            pending_reviews: config.pending_reviews,
+            missing_check_after: config
+                .missing_required_checks
+                .then(|| chrono::Duration::minutes(config.missing_check_minutes)),
+            no_checks_after: config
+                .no_checks
+                .then(|| chrono::Duration::minutes(config.no_checks_minutes)),
+            behind_base_commits: config.behind_base.then_some(config.behind_base_commits),
+            unreviewed_after: config
+                .unreviewed_prs
+                .then(|| chrono::Duration::minutes(config.unreviewed_minutes)),
+            approval_progress: config.approval_progress,
+            failing_conclusions: config
+                .failing_conclusions
+                .iter()
+                .map(|c| c.to_uppercase())
+                .collect(),
+            flaky_checks: config.flaky_checks.clone(),
+            track_flaky_checks: config.track_flaky_checks,
+            stacked_prs: config.stacked_prs,
+            code_owners: config.code_owners,
+            closed_prs: config.closed_prs,
+            auto_close: config
+                .auto_close_days
+                .map(|days| (days, config.auto_close_warning_days)),
+            all_review_requests: config.all_review_requests,
+            teams: config.teams.clone(),
+            stale_review_after: config.stale_review_minutes.map(chrono::Duration::minutes),
+            ignored_authors: config.ignored_authors.clone(),
+            collapse_ignored_authors: config.collapse_ignored_authors,
+            ignore_labels: config
+                .ignore_labels
+                .iter()
+                .map(|l| l.to_lowercase())
+                .collect(),
+            only_labels: config
+                .only_labels
+                .iter()
+                .map(|l| l.to_lowercase())
+                .collect(),
+            muted_prs: config
+                .muted_prs
+                .iter()
+                .map(|pr| pr.to_lowercase())
+                .collect(),
+            assigned_issues: config.assigned_issues.clone(),
+            milestones: config.milestones.clone(),
+            follow_ups: config.follow_ups.clone(),
+            default_branch_workflows: config.default_branch_workflows.clone(),
+            deployment_approvals: config.deployment_approvals.clone(),
+            dependabot_alerts: config.dependabot_alerts,
+            notifications: config
+                .notifications
+                .then(|| Mutex::new(NotificationCache::default())),
+            timeout: Duration::from_secs(config.timeout_seconds),
+            interval: config
+                .interval_minutes
+                .map(|minutes| Duration::from_secs(minutes * 60)),
+        }
+    }
+
+    /// Runs `gh` with `runner` instead of as a subprocess.
+    pub fn with_runner(mut self, runner: Arc<dyn CommandRunner>) -> Self {
+        self.gh.runner = runner;
+        self
+    }
+
+    /// An issue about `pr`, an entry in `gh pr status --json` output for `repo`.
+    fn pr_issue(
+        &self,
+        repo: &str,
+        pr: &serde_json::Value,
+        kind: IssueKind,
+        severity: Severity,
+        detail: &str,
+    ) -> Issue {
+        let number = pr.get("number").and_then(|v| v.as_u64()).unwrap_or(0);
+        let title = pr
+            .get("title")
+            .and_then(|v| v.as_str())
+            .unwrap_or("Unknown PR");
+        // gh reports the real URL, which matters for hosts other than github.com
+        let url = match pr.get("url").and_then(|v| v.as_str()) {
+            Some(url) => url.to_string(),
+            None => format!("{}/{}/pull/{}", self.web_url(), repo, number),
+        };
+        let mut id = format!("github:{}:pr:{}", repo, number);
+        if let Some(suffix) = id_suffix(kind) {
+            id = format!("{}:{}", id, suffix);
+        }
+        Issue {
+            id,
+            source: CheckSource::GitHub,
+            kind,
+            severity,
+            title: format!("PR {} '{}'", self.reference(repo, number), title),
+            detail: detail.to_string(),
+            url: Some(url),
+            items: Vec::new(),
+        }
     }
 
-    pub fn check_output(&self, github_pr_status_output: &str) -> Result<Vec<String>> {
+    pub fn check_output(&self, repo: &str, github_pr_status_output: &str) -> Result<Vec<Issue>> {
         let data: serde_json::Value = serde_json::from_str(github_pr_status_output)?;
+        Ok(self.pr_status_issues(repo, &data, Utc::now()))
+    }
+
+    /// Whether the API rate limit is low enough that optional queries should be skipped.
+    fn rate_limited(&self, query: &str) -> bool {
+        let Backend::Api(api) = &self.backend else {
+            return false;
+        };
+        match api.rate_limit_remaining() {
+            Some(remaining) if remaining < self.rate_limit_reserve => {
+                warn!(remaining, "GitHub rate limit is low, skipping {}", query);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The configured repos, or else the current directory's. Without `gh` to find it,
+    /// there's none.
+    async fn repos_to_check(&self) -> Result<&[String]> {
+        if !self.repos.is_empty() || matches!(self.backend, Backend::Api(_)) {
+            return Ok(&self.repos);
+        }
+        if self.current_repo.get().is_none() {
+            let repo = gh_current_repo(&self.gh)
+                .await
+                .context("Failed to find the current directory's repository")?;
+            let _ = self.current_repo.set(repo);
+        }
+        Ok(self
+            .current_repo
+            .get()
+            .map(std::slice::from_ref)
+            .unwrap_or_default())
+    }
+
+    fn web_url(&self) -> String {
+        format!("https://{}", self.host)
+    }
+
+    /// "#123", or "org/a#123" when numbers alone could be ambiguous.
+    fn reference(&self, repo: &str, number: u64) -> String {
+        // Without configured repos, before `gh` has found the current one, there's only one
+        let ours = self
+            .current_repo
+            .get()
+            .map(std::slice::from_ref)
+            .unwrap_or(&self.repos);
+        if ours.is_empty() || ours == [repo] {
+            format!("#{}", number)
+        } else {
+            format!("{}#{}", repo, number)
+        }
+    }
+
+    /// An issue for a GitHub issue assigned to us, from a search entry.
+    fn assigned_issue(&self, repo: &str, item: &serde_json::Value) -> Issue {
+        let number = item.get("number").and_then(|v| v.as_u64()).unwrap_or(0);
+        let title = str_field(item, "title").unwrap_or("Unknown issue");
+        let url = match str_field(item, "url") {
+            Some(url) => url.to_string(),
+            None => format!("{}/{}/issues/{}", self.web_url(), repo, number),
+        };
+        Issue {
+            id: format!("github:{}:issue:{}", repo, number),
+            source: CheckSource::GitHub,
+            kind: IssueKind::AssignedIssue,
+            severity: Severity::Low,
+            title: format!("Issue {} '{}'", self.reference(repo, number), title),
+            detail: "assigned to you".to_string(),
+            url: Some(url),
+            items: Vec::new(),
+        }
+    }
+
+    /// An issue for each workflow whose latest completed run on the default branch failed.
+    /// Ids include the commit, so a workflow broken by a new commit is reported afresh.
+    fn workflow_issues(&self, repo: &str, runs: &[WorkflowRun]) -> Vec<Issue> {
+        let mut latest: Vec<&WorkflowRun> = Vec::new();
+        // Runs are newest first; still-running ones don't tell us whether it's fixed yet
+        for run in runs.iter().filter(|run| run.status == "completed") {
+            if !latest
+                .iter()
+                .any(|seen| seen.workflow_id == run.workflow_id)
+            {
+                latest.push(run);
+            }
+        }
+
+        latest
+            .into_iter()
+            .filter(|run| run.conclusion.as_deref() == Some("failure"))
+            .map(|run| Issue {
+                id: format!(
+                    "github:{}:workflow:{}:{}",
+                    repo, run.workflow_id, run.head_sha
+                ),
+                source: CheckSource::GitHub,
+                kind: IssueKind::WorkflowFailing,
+                severity: Severity::High,
+                title: format!("Workflow '{}'", run.name),
+                detail: format!("failing on {} in {}", run.head_branch, repo),
+                url: Some(run.html_url.clone()),
+                items: Vec::new(),
+            })
+            .collect()
+    }
+
+    /// An issue per watched milestone that's due within `due_within_days` or overdue.
+    /// Without configured titles, the milestones `login` created are watched.
+    fn milestone_issues(
+        &self,
+        repo: &str,
+        milestones: &[Milestone],
+        login: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Vec<Issue> {
+        let titles = &self.milestones.titles;
+        milestones
+            .iter()
+            .filter(|milestone| {
+                if titles.is_empty() {
+                    milestone.creator.as_ref().map(|c| c.login.as_str()) == login
+                } else {
+                    titles.contains(&milestone.title)
+                }
+            })
+            .filter_map(|milestone| {
+                let days = days_until_due(milestone.due_on?, now);
+                if days > self.milestones.due_within_days {
+                    return None;
+                }
+                Some(Issue {
+                    id: format!("github:{}:milestone:{}", repo, milestone.number),
+                    source: CheckSource::GitHub,
+                    kind: IssueKind::MilestoneDue,
+                    severity: if days < 0 {
+                        Severity::High
+                    } else {
+                        Severity::Medium
+                    },
+                    title: format!("Milestone '{}'", milestone.title),
+                    detail: format!(
+                        "{} with {} open issue{}",
+                        due_phrase(days),
+                        milestone.open_issues,
+                        if milestone.open_issues == 1 { "" } else { "s" }
+                    ),
+                    url: Some(milestone.html_url.clone()),
+                    items: Vec::new(),
+                })
+            })
+            .collect()
+    }
+
+    /// An issue per PR in `gone`, ones that were open last run, that's among `closed`,
+    /// our recently closed and unmerged PRs. Merged PRs aren't among them.
+    fn closed_unmerged_issues(
+        &self,
+        repo: &str,
+        gone: &[u64],
+        closed: &[serde_json::Value],
+    ) -> Vec<Issue> {
+        closed
+            .iter()
+            .filter(|pr| str_field(pr, "state") == Some("CLOSED"))
+            .filter(|pr| {
+                pr.get("number")
+                    .and_then(|v| v.as_u64())
+                    .is_some_and(|number| gone.contains(&number))
+            })
+            .map(|pr| {
+                self.pr_issue(
+                    repo,
+                    pr,
+                    IssueKind::ClosedUnmerged,
+                    Severity::Medium,
+                    "was closed without merging",
+                )
+            })
+            .collect()
+    }
+
+    /// An issue per comment of ours on `pr`, one of our merged PRs, that notes a follow-up.
+    fn follow_up_issues(
+        &self,
+        repo: &str,
+        pr: &serde_json::Value,
+        comments: &[Comment],
+        login: &str,
+    ) -> Vec<Issue> {
+        let number = pr.get("number").and_then(|v| v.as_u64()).unwrap_or(0);
+        let title = str_field(pr, "title").unwrap_or("");
+        comments
+            .iter()
+            .filter(|comment| {
+                comment
+                    .user
+                    .as_ref()
+                    .is_some_and(|user| user.login == login)
+            })
+            .filter(|comment| {
+                comment
+                    .body
+                    .as_deref()
+                    .is_some_and(|body| mentions_any(body, &self.follow_ups.patterns))
+            })
+            .map(|comment| Issue {
+                id: format!("github:{}:follow-up:{}", repo, comment.id),
+                source: CheckSource::GitHub,
+                kind: IssueKind::FollowUp,
+                severity: Severity::Low,
+                title: "Follow-up".to_string(),
+                detail: format!("noted on merged PR #{} '{}'", number, title),
+                url: Some(comment.html_url.clone()),
+                items: Vec::new(),
+            })
+            .collect()
+    }
+
+    /// An issue per run with deployments waiting on our approval. Ids are per run, so a run
+    /// waiting on several environments is one issue.
+    fn deployment_issues(
+        &self,
+        repo: &str,
+        waiting: &[(WorkflowRun, Vec<PendingDeployment>)],
+        now: DateTime<Utc>,
+    ) -> Vec<Issue> {
+        waiting
+            .iter()
+            .filter_map(|(run, deployments)| {
+                let environments: Vec<&str> = deployments
+                    .iter()
+                    .filter(|deployment| deployment.current_user_can_approve)
+                    .map(|deployment| deployment.environment.name.as_str())
+                    .collect();
+                if environments.is_empty() {
+                    return None;
+                }
+                Some(Issue {
+                    id: format!("github:{}:deployment:{}", repo, run.id),
+                    source: CheckSource::GitHub,
+                    kind: IssueKind::DeploymentWaiting,
+                    severity: Severity::High,
+                    title: format!("Deployment to {}", environments.join(", ")),
+                    detail: format!(
+                        "waiting for your approval (run #{}, {})",
+                        run.run_number,
+                        format_age(now - run.created_at)
+                    ),
+                    // The run's page is where deployments are reviewed
+                    url: Some(run.html_url.clone()),
+                    items: Vec::new(),
+                })
+            })
+            .collect()
+    }
+
+    /// An issue per critical or high severity alert, and one per lower severity summing up
+    /// the rest. `None` alerts means we weren't allowed to read them.
+    fn dependabot_issues(&self, repo: &str, alerts: Option<&[DependabotAlert]>) -> Vec<Issue> {
+        let url = format!("{}/{}/security/dependabot", self.web_url(), repo);
+        let Some(alerts) = alerts else {
+            return vec![Issue {
+                id: format!("github:{}:dependabot", repo),
+                source: CheckSource::GitHub,
+                kind: IssueKind::SecurityAlert,
+                severity: Severity::Low,
+                title: format!("Dependabot alerts for {}", repo),
+                detail: "can't be read with this token (it needs the security_events scope \
+                         or Dependabot alerts permission)"
+                    .to_string(),
+                url: Some(url),
+                items: Vec::new(),
+            }];
+        };
+
+        let mut issues = Vec::new();
+        let mut rolled_up: Vec<(&str, usize)> = Vec::new();
+        for alert in alerts {
+            let severity = alert.security_advisory.severity.as_str();
+            if !matches!(severity, "critical" | "high") {
+                match rolled_up.iter_mut().find(|(s, _)| *s == severity) {
+                    Some((_, count)) => *count += 1,
+                    None => rolled_up.push((severity, 1)),
+                }
+                continue;
+            }
+            issues.push(Issue {
+                id: format!("github:{}:dependabot:{}", repo, alert.number),
+                source: CheckSource::GitHub,
+                kind: IssueKind::SecurityAlert,
+                severity: Severity::High,
+                title: format!(
+                    "Dependabot alert {} '{}'",
+                    self.reference(repo, alert.number),
+                    alert.security_advisory.summary
+                ),
+                detail: format!(
+                    "is {} severity ({})",
+                    severity, alert.dependency.package.name
+                ),
+                url: Some(alert.html_url.clone()),
+                items: Vec::new(),
+            });
+        }
+        for (severity, count) in rolled_up {
+            issues.push(Issue {
+                id: format!("github:{}:dependabot:{}", repo, severity),
+                source: CheckSource::GitHub,
+                kind: IssueKind::SecurityAlert,
+                severity: Severity::Low,
+                title: format!(
+                    "{} {} severity alert{}",
+                    count,
+                    severity,
+                    if count == 1 { "" } else { "s" }
+                ),
+                detail: format!("in {}", repo),
+                url: Some(url.clone()),
+                items: Vec::new(),
+            });
+        }
+        issues
+    }
+
+    /// Checks that `gh` is installed and logged in, so a broken setup is reported once up
+    /// front instead of failing every run. The API needs no such check.
+    pub fn preflight(&self) -> Result<()> {
+        match &self.backend {
+            Backend::Api(_) => Ok(()),
+            Backend::Cli => {
+                let mut command = self
+                    .gh
+                    .command(&["auth", "status", "--hostname", &self.host]);
+                run_gh(self.gh.runner.as_ref(), &mut command, "gh auth status")?;
+                Ok(())
+            }
+        }
+    }
+
+    fn is_ignored_author(&self, login: &str) -> bool {
+        self.ignored_authors
+            .iter()
+            .any(|pattern| matches_pattern(pattern, login))
+    }
+
+    /// Whether a check's conclusion, or a status's state, is in `failing_conclusions`.
+    fn check_failed(&self, check: &serde_json::Value) -> bool {
+        ["conclusion", "state"].iter().any(|field| {
+            str_field(check, field)
+                .is_some_and(|value| self.failing_conclusions.iter().any(|c| c == value))
+        })
+    }
+
+    /// The failed entries of a status rollup, as (name, link to the failure).
+    fn failing_checks<'a>(
+        &self,
+        checks: &'a [serde_json::Value],
+    ) -> Vec<(&'a str, Option<&'a str>)> {
+        checks
+            .iter()
+            .filter(|check| self.check_failed(check))
+            .map(|check| {
+                // CheckRuns have a detailsUrl, StatusContexts a targetUrl
+                let url = str_field(check, "detailsUrl").or_else(|| str_field(check, "targetUrl"));
+                (check_name(check), url)
+            })
+            .collect()
+    }
+
+    /// Whether every check on the PR has finished without failing.
+    fn checks_green(&self, pr: &serde_json::Value) -> bool {
+        pr.get("statusCheckRollup")
+            .and_then(|v| v.as_array())
+            .is_some_and(|checks| {
+                all_checks_complete(checks) && self.failing_checks(checks).is_empty()
+            })
+    }
+
+    /// Why auto-merge hasn't merged the PR yet, when the PR itself says.
+    fn auto_merge_blocker(&self, pr: &serde_json::Value) -> Option<String> {
+        let checks = pr
+            .get("statusCheckRollup")
+            .and_then(|v| v.as_array())
+            .map_or(&[][..], |checks| checks.as_slice());
+        let failing = self.failing_checks(checks);
+        if !failing.is_empty() {
+            return Some(failing_detail(&failing));
+        }
+        match str_field(pr, "reviewDecision") {
+            Some("CHANGES_REQUESTED") => return Some("changes requested".to_string()),
+            Some("REVIEW_REQUIRED") => return Some("missing approval".to_string()),
+            _ => {}
+        }
+        if str_field(pr, "mergeable") == Some("CONFLICTING") {
+            return Some("merge conflicts".to_string());
+        }
+        None
+    }
+
+    /// Whether every failing check in `checks` is in `flaky_checks` or has flipped recently.
+    fn failures_look_flaky(&self, checks: &[serde_json::Value]) -> bool {
+        checks
+            .iter()
+            .filter(|check| self.check_failed(check))
+            .all(|check| {
+                check.get("likelyFlaky").and_then(|v| v.as_bool()) == Some(true)
+                    || self
+                        .flaky_checks
+                        .iter()
+                        .any(|pattern| matches_pattern(pattern, check_name(check)))
+            })
+    }
+
+    /// Whether `pr` is left out by `ignore_labels` or `only_labels`.
+    fn is_filtered_by_labels(&self, pr: &serde_json::Value) -> bool {
+        let labels: Vec<String> = pr
+            .get("labels")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|l| l.get("name").and_then(|n| n.as_str()))
+            .map(|name| name.to_lowercase())
+            .collect();
+        labels.iter().any(|l| self.ignore_labels.contains(l))
+            || (!self.only_labels.is_empty()
+                && !labels.iter().any(|l| self.only_labels.contains(l)))
+    }
 
+    /// Issues for the PRs in `gh pr status --json` output for `repo`.
+    fn pr_status_issues(
+        &self,
+        repo: &str,
+        data: &serde_json::Value,
+        now: DateTime<Utc>,
+    ) -> Vec<Issue> {
         let mut issues = Vec::new();
 
         // Check created PRs
         if let Some(created) = data.get("createdBy").and_then(|v| v.as_array()) {
             for pr in created {
-                let title = pr
-                    .get("title")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown PR");
                 let number = pr.get("number").and_then(|v| v.as_u64()).unwrap_or(0);
+                if self.is_filtered_by_labels(pr) {
+                    debug!(number, "skipping PR by label");
+                    continue;
+                }
                 let is_draft = pr.get("isDraft").and_then(|v| v.as_bool()).unwrap_or(false);
 
                 let review_decision = pr.get("reviewDecision").and_then(|v| v.as_str());
-                let has_ready_label = pr
-                    .get("labels")
+                let auto_merge = pr.get("autoMergeRequest").is_some_and(|v| !v.is_null());
+                let has_ready_label =
+                    pr.get("labels")
+                        .and_then(|v| v.as_array())
+                        .is_some_and(|labels| {
+                            labels.iter().any(|l| {
+                                l.get("name").and_then(|n| n.as_str()) == Some("ready-to-merge")
+                            })
+                        });
+
+                // gh reports a PR without checks as null, an empty list, or not at all
+                let checks = pr
+                    .get("statusCheckRollup")
                     .and_then(|v| v.as_array())
-                    .is_some_and(|labels| {
-                        labels.iter().any(|l| {
-                            l.get("name").and_then(|n| n.as_str()) == Some("ready-to-merge")
-                        })
-                    });
+                    .filter(|checks| !checks.is_empty());
+                if checks.is_none()
+                    && let Some(no_checks_after) = self.no_checks_after
+                    && !is_draft
+                    && time_field(pr, "createdAt")
+                        .is_some_and(|created| now - created > no_checks_after)
+                {
+                    issues.push(self.pr_issue(
+                        repo,
+                        pr,
+                        IssueKind::NoChecks,
+                        Severity::Medium,
+                        "has no CI checks",
+                    ));
+                }
 
-                if let Some(checks) = pr.get("statusCheckRollup").and_then(|v| v.as_array()) {
-                    let has_failures = checks.iter().any(|check| {
-                        check.get("state").and_then(|s| s.as_str()) == Some("FAILURE")
-                            || check.get("conclusion").and_then(|s| s.as_str()) == Some("FAILURE")
-                    });
+                if let Some(checks) = checks {
+                    let failing = self.failing_checks(checks);
+                    let has_failures = !failing.is_empty();
 
-                    // CheckRun uses status:"COMPLETED", StatusContext uses state:"SUCCESS"
-                    let all_complete = !checks.is_empty() && checks.iter().all(|check| {
-                        check.get("status").and_then(|s| s.as_str()) == Some("COMPLETED")
-                            || check.get("state").and_then(|s| s.as_str()) == Some("SUCCESS")
-                    });
+                    let all_complete = all_checks_complete(checks);
+
+                    debug!(
+                        number,
+                        is_draft,
+                        has_failures,
+                        all_complete,
+                        review_decision,
+                        has_ready_label,
+                        auto_merge,
+                        "evaluated created PR"
+                    );
 
                     if has_failures {
-                        issues.push(format!(
-                            "PR #{} '{}' has failing checks",
-                            number, title
+                        // Drafts often fail on purpose while work is in progress
+                        if !is_draft || self.include_drafts {
+                            let mut detail = failing_detail(&failing);
+                            let mut severity = Severity::High;
+                            if self.failures_look_flaky(checks) {
+                                detail.push_str(" (likely flaky — consider rerun)");
+                                severity = Severity::Low;
+                            }
+                            let mut issue = self.pr_issue(
+                                repo,
+                                pr,
+                                IssueKind::FailingChecks,
+                                severity,
+                                &detail,
+                            );
+                            // Link straight to the first failed job when we can
+                            if let Some((_, Some(url))) = failing.first() {
+                                issue.url = Some(url.to_string());
+                            }
+                            issues.push(issue);
+                        }
+                    } else if let Some(since) = pending_since(pr, checks)
+                        && now - since > self.pending_after
+                        && (!is_draft || self.include_drafts)
+                    {
+                        issues.push(self.pr_issue(
+                            repo,
+                            pr,
+                            IssueKind::PendingChecks,
+                            Severity::Medium,
+                            &format!(
+                                "has checks pending for over {}",
+                                format_age(self.pending_after)
+                            ),
                         ));
                     } else if is_draft && all_complete {
-                        issues.push(format!(
-                            "PR #{} '{}' is draft with all checks passing",
-                            number, title
+                        issues.push(self.pr_issue(
+                            repo,
+                            pr,
+                            IssueKind::DraftReady,
+                            Severity::Low,
+                            "is draft with all checks passing",
                         ));
                     } else if !is_draft
                         && all_complete
                         && review_decision == Some("APPROVED")
-                        && !has_ready_label
+                        && !(auto_merge && self.skip_auto_merge)
                     {
-                        issues.push(format!(
-                            "PR #{} '{}' approved but missing ready-to-merge label",
-                            number, title
+                        issues.push(if has_ready_label {
+                            self.pr_issue(
+                                repo,
+                                pr,
+                                IssueKind::ReadyToMerge,
+                                Severity::Medium,
+                                "is approved and ready to merge",
+                            )
+                        } else {
+                            self.pr_issue(
+                                repo,
+                                pr,
+                                IssueKind::MissingReadyLabel,
+                                Severity::Medium,
+                                "approved but missing ready-to-merge label",
+                            )
+                        });
+                    }
+                }
+
+                // Auto-merge quietly gives up waiting when a required check fails or an
+                // approval is dismissed
+                if let Some(since) = auto_merge_since(pr)
+                    && now - since > self.auto_merge_stuck_after
+                {
+                    let mut detail = format!(
+                        "has been set to auto-merge for {} without merging",
+                        format_age(now - since)
+                    );
+                    if let Some(reason) = self.auto_merge_blocker(pr) {
+                        detail.push_str(&format!(" ({})", reason));
+                    }
+                    issues.push(self.pr_issue(
+                        repo,
+                        pr,
+                        IssueKind::AutoMergeStuck,
+                        Severity::Medium,
+                        &detail,
+                    ));
+                }
+
+                let threads = pr
+                    .get("unresolvedReviewThreads")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0);
+                if threads > 0 && self.checks_green(pr) {
+                    issues.push(self.pr_issue(
+                        repo,
+                        pr,
+                        IssueKind::UnresolvedThreads,
+                        Severity::Medium,
+                        &format!(
+                            "has {} unresolved review thread{}",
+                            threads,
+                            if threads == 1 { "" } else { "s" }
+                        ),
+                    ));
+                }
+
+                if let Some(your_reply_after) = self.your_reply_after {
+                    let waiting = waiting_since(pr, "threadsAwaitingYou", now - your_reply_after);
+                    if let Some(oldest) = waiting.iter().min() {
+                        issues.push(self.pr_issue(
+                            repo,
+                            pr,
+                            IssueKind::AwaitingYourReply,
+                            Severity::Medium,
+                            &format!(
+                                "has {} review thread{} waiting on your reply (oldest {})",
+                                waiting.len(),
+                                if waiting.len() == 1 { "" } else { "s" },
+                                format_age(now - *oldest)
+                            ),
+                        ));
+                    }
+                }
+
+                if let Some(unreviewed_after) = self.unreviewed_after
+                    && !is_draft
+                    && self.checks_green(pr)
+                    && let Some(since) = unreviewed_since(pr)
+                    && now - since > unreviewed_after
+                {
+                    issues.push(self.pr_issue(
+                        repo,
+                        pr,
+                        IssueKind::Unreviewed,
+                        Severity::Medium,
+                        &format!(
+                            "has had no review for {} — consider pinging reviewers",
+                            format_age(now - since)
+                        ),
+                    ));
+                }
+
+                if self.approval_progress
+                    && !is_draft
+                    && self.checks_green(pr)
+                    && !matches!(review_decision, Some("APPROVED" | "CHANGES_REQUESTED"))
+                {
+                    let approvals = approvals(pr);
+                    let detail = match pr.get("requiredApprovals").and_then(|v| v.as_u64()) {
+                        Some(required) => {
+                            format!("has {} of {} required approvals", approvals, required)
+                        }
+                        None if approvals == 1 => "has 1 approval".to_string(),
+                        None => format!("has {} approvals", approvals),
+                    };
+                    issues.push(self.pr_issue(
+                        repo,
+                        pr,
+                        IssueKind::AwaitingApprovals,
+                        Severity::Low,
+                        &detail,
+                    ));
+                }
+
+                // Stacked PRs need moving once the PR below them merges
+                if let Some(parent) = pr.get("mergedParent") {
+                    issues.push(self.pr_issue(
+                        repo,
+                        pr,
+                        IssueKind::BaseMerged,
+                        Severity::Medium,
+                        &format!(
+                            "is based on merged branch '{}' — rebase onto {}",
+                            str_field(pr, "baseRefName").unwrap_or("unknown"),
+                            str_field(parent, "baseRefName").unwrap_or("its base")
+                        ),
+                    ));
+                }
+
+                if let Some(unowned) = pr.get("unownedFiles").and_then(|v| v.as_u64())
+                    && unowned > 0
+                {
+                    issues.push(self.pr_issue(
+                        repo,
+                        pr,
+                        IssueKind::NoCodeOwner,
+                        Severity::Medium,
+                        &format!(
+                            "touches {} {} with no code owner",
+                            unowned,
+                            if unowned == 1 { "file" } else { "files" }
+                        ),
+                    ));
+                }
+
+                // Any push, comment or review resets the stale bot's clock, like updatedAt
+                if let Some((limit, warning)) = self.auto_close
+                    && let Some(updated) = time_field(pr, "updatedAt")
+                {
+                    let inactive = (now - updated).num_days();
+                    if inactive >= limit - warning {
+                        issues.push(self.pr_issue(
+                            repo,
+                            pr,
+                            IssueKind::AutoCloseSoon,
+                            Severity::Medium,
+                            &format!(
+                                "inactive for {} day{} — will be auto-closed at {}",
+                                inactive,
+                                if inactive == 1 { "" } else { "s" },
+                                limit
+                            ),
                         ));
                     }
                 }
+
+                // A misconfigured workflow can leave a required check unreported forever
+                let missing = missing_required_checks(pr);
+                if let Some(missing_after) = self.missing_check_after
+                    && !missing.is_empty()
+                    && checks_started(pr).is_some_and(|since| now - since > missing_after)
+                {
+                    let names: Vec<String> =
+                        missing.iter().map(|name| format!("'{}'", name)).collect();
+                    issues.push(self.pr_issue(
+                        repo,
+                        pr,
+                        IssueKind::MissingRequiredCheck,
+                        Severity::Medium,
+                        &format!(
+                            "is waiting on required check{} {} that never started",
+                            if missing.len() == 1 { "" } else { "s" },
+                            names.join(", ")
+                        ),
+                    ));
+                }
+
+                let behind = pr
+                    .get("commitsBehind")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0);
+                // Approved, green PRs can only be blocked by being out of date
+                let mergeable_but_behind =
+                    behind > 0 && review_decision == Some("APPROVED") && self.checks_green(pr);
+                if let Some(threshold) = self.behind_base_commits
+                    && (behind > threshold || mergeable_but_behind)
+                {
+                    let base = str_field(pr, "baseRefName").unwrap_or("its base");
+                    issues.push(self.pr_issue(
+                        repo,
+                        pr,
+                        IssueKind::BehindBase,
+                        Severity::Medium,
+                        &format!(
+                            "is {} commit{} behind {}",
+                            behind,
+                            if behind == 1 { "" } else { "s" },
+                            base
+                        ),
+                    ));
+                }
+
+                // GitHub computes mergeability lazily, so UNKNOWN just means "not yet"
+                if pr.get("mergeable").and_then(|v| v.as_str()) == Some("CONFLICTING") {
+                    issues.push(self.pr_issue(
+                        repo,
+                        pr,
+                        IssueKind::MergeConflicts,
+                        Severity::Medium,
+                        "has merge conflicts",
+                    ));
+                }
+
+                // Reported alongside any CI issue, since fixing one doesn't fix the other
+                if review_decision == Some("CHANGES_REQUESTED") {
+                    issues.push(self.pr_issue(
+                        repo,
+                        pr,
+                        IssueKind::ChangesRequested,
+                        Severity::Medium,
+                        "has requested changes",
+                    ));
+                }
             }
         }
 
         // Check PRs requesting review from us (all should create an issue)
         if let Some(needs_review) = data.get("needsReview").and_then(|v| v.as_array()) {
+            // PRs from ignored authors, e.g. bots, by login
+            let mut ignored: Vec<(&str, usize)> = Vec::new();
             for pr in needs_review {
-                let title = pr
-                    .get("title")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown PR");
                 let number = pr.get("number").and_then(|v| v.as_u64()).unwrap_or(0);
+                let author = pr
+                    .pointer("/author/login")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                if self.is_filtered_by_labels(pr) {
+                    debug!(number, "skipping review request by label");
+                    continue;
+                }
+                if self.is_ignored_author(author) {
+                    debug!(number, author, "skipping ignored author's review request");
+                    match ignored.iter_mut().find(|(login, _)| *login == author) {
+                        Some((_, count)) => *count += 1,
+                        None => ignored.push((author, 1)),
+                    }
+                    continue;
+                }
+
+                let age = time_field(pr, "createdAt").map(|created_at| now - created_at);
+                // We've already got context on PRs we're asked to look at again
+                let re_requested = str_field(pr, "previousReview").is_some();
+                debug!(number, ?age, re_requested, "PR awaiting review");
+
+                let severity = match (age, self.stale_review_after) {
+                    _ if re_requested => Severity::High,
+                    (Some(age), Some(stale_after)) if age > stale_after => Severity::High,
+                    _ => Severity::Medium,
+                };
+                let detail = match (re_requested, age) {
+                    (true, _) => "re-requested your review".to_string(),
+                    (false, Some(age)) => {
+                        format!("awaiting your review (requested {} ago)", format_age(age))
+                    }
+                    (false, None) => "awaiting your review".to_string(),
+                };
+                issues.push(self.pr_issue(repo, pr, IssueKind::AwaitingReview, severity, &detail));
+
+                // Drafted review comments are invisible to the author until submitted
+                if pr.get("pendingReview").and_then(|v| v.as_bool()) == Some(true) {
+                    issues.push(self.pr_issue(
+                        repo,
+                        pr,
+                        IssueKind::PendingReview,
+                        Severity::Medium,
+                        "— you have an unsubmitted pending review",
+                    ));
+                }
+
+                if let Some(their_reply_after) = self.their_reply_after {
+                    let waiting = waiting_since(pr, "threadsAwaitingThem", now - their_reply_after);
+                    let detail = match waiting.as_slice() {
+                        [] => None,
+                        [since] => Some(format!(
+                            "— your comment has gone unanswered for {}",
+                            format_age(now - *since)
+                        )),
+                        waiting => Some(format!(
+                            "— {} of your comments have gone unanswered (oldest {})",
+                            waiting.len(),
+                            format_age(now - *waiting.iter().min().unwrap())
+                        )),
+                    };
+                    if let Some(detail) = detail {
+                        issues.push(self.pr_issue(
+                            repo,
+                            pr,
+                            IssueKind::AwaitingTheirReply,
+                            Severity::Low,
+                            &detail,
+                        ));
+                    }
+                }
+            }
+
+            if self.collapse_ignored_authors {
+                for (author, count) in ignored {
+                    issues.push(Issue {
+                        id: format!("github:{}:author:{}", repo, author),
+                        source: CheckSource::GitHub,
+                        kind: IssueKind::AwaitingReview,
+                        severity: Severity::Low,
+                        title: format!(
+                            "{} {} PR{}",
+                            count,
+                            author.trim_end_matches("[bot]"),
+                            if count == 1 { "" } else { "s" }
+                        ),
+                        detail: "awaiting review".to_string(),
+                        url: Some(format!("{}/pulls/review-requested", self.web_url())),
+                        items: Vec::new(),
+                    });
+                }
+            }
+        }
 
-                issues.push(format!(
-                    "PR #{} '{}' awaiting your review",
-                    number,
-                    title
+        // Team requests are lower priority, and skipped when we were also asked directly
+        if let Some(team_reviews) = data.get("teamReviewRequests").and_then(|v| v.as_array()) {
+            let requested_directly: Vec<u64> = data
+                .get("needsReview")
+                .and_then(|v| v.as_array())
+                .map(|prs| {
+                    prs.iter()
+                        .filter_map(|pr| pr.get("number")?.as_u64())
+                        .collect()
+                })
+                .unwrap_or_default();
+            let mut reported = Vec::new();
+            for pr in team_reviews {
+                let number = pr.get("number").and_then(|v| v.as_u64()).unwrap_or(0);
+                if requested_directly.contains(&number) || reported.contains(&number) {
+                    continue;
+                }
+                reported.push(number);
+                let team = str_field(pr, "team").unwrap_or("your team");
+                issues.push(self.pr_issue(
+                    repo,
+                    pr,
+                    IssueKind::AwaitingTeamReview,
+                    Severity::Low,
+                    &format!("awaiting @{} review", team),
                 ));
             }
         }
+        issues
+    }
+
+    /// Everything reported about `repo`'s PRs, merging in `review_requests` for it from
+    /// the account-wide search.
+    async fn check_repo(
+        &self,
+        repo: &str,
+        review_requests: Option<Vec<serde_json::Value>>,
+        muted: &HashSet<String>,
+        previous_open: &HashSet<String>,
+        tracked: &Mutex<TrackedPrs>,
+    ) -> Result<RepoResults> {
+        let mut data = match &self.backend {
+            Backend::Api(api) if self.graphql => {
+                graphql::pr_status(api, repo, self.review_threads).await
+            }
+            Backend::Api(api) => api.pr_status(repo).await,
+            // gh takes a couple of seconds, so it runs off the async threads
+            Backend::Cli => gh_pr_status(&self.gh, repo).await,
+        }
+        .with_context(|| format!("Failed to fetch PRs for {}", repo))?;
+        if let Some(review_requests) = review_requests {
+            merge_review_requests(&mut data, review_requests);
+        }
+        if !self.teams.is_empty() {
+            self.add_team_reviews(repo, &mut data)
+                .await
+                .with_context(|| format!("Failed to fetch team review requests for {}", repo))?;
+        }
+        if self.re_requested_reviews && !self.rate_limited("previous reviews") {
+            self.add_previous_reviews(repo, &mut data)
+                .await
+                .with_context(|| format!("Failed to fetch previous reviews for {}", repo))?;
+        }
+        if self.pending_reviews && !self.rate_limited("pending reviews") {
+            match &self.backend {
+                Backend::Api(api) => add_pending_reviews(api, repo, &mut data)
+                    .await
+                    .with_context(|| format!("Failed to fetch pending reviews for {}", repo))?,
+                Backend::Cli => {
+                    warn!("github.pending_reviews needs github.token or GITHUB_TOKEN")
+                }
+            }
+        }
+        // The GraphQL query fetches threads along with everything else
+        let has_threads = self.graphql && matches!(self.backend, Backend::Api(_));
+        if self.review_threads && !has_threads && !self.rate_limited("review threads") {
+            self.add_review_threads(repo, &mut data)
+                .await
+                .with_context(|| format!("Failed to fetch review threads for {}", repo))?;
+        }
+        if self.your_reply_after.is_some() && !self.rate_limited("review thread replies") {
+            self.add_thread_replies(repo, &mut data)
+                .await
+                .with_context(|| format!("Failed to fetch review comments for {}", repo))?;
+        }
+        self.track_prs(repo, &mut data, &mut tracked.lock().unwrap());
+        if self.approval_progress {
+            self.add_required_approvals(repo, &mut data)
+                .await
+                .with_context(|| format!("Failed to fetch required approvals for {}", repo))?;
+        }
+        if self.missing_check_after.is_some() {
+            self.add_required_checks(repo, &mut data)
+                .await
+                .with_context(|| format!("Failed to fetch required checks for {}", repo))?;
+        }
+        if self.stacked_prs && !self.rate_limited("stacked PR lookups") {
+            self.add_merged_parents(repo, &mut data)
+                .await
+                .with_context(|| format!("Failed to look up stacked PRs for {}", repo))?;
+        }
+        if self.code_owners && !self.rate_limited("code owner lookups") {
+            self.add_unowned_files(repo, &mut data)
+                .await
+                .with_context(|| format!("Failed to check code owners for {}", repo))?;
+        }
+        if self.behind_base_commits.is_some() && !self.rate_limited("branch comparisons") {
+            match &self.backend {
+                Backend::Api(api) => add_commits_behind(api, repo, &mut data)
+                    .await
+                    .with_context(|| format!("Failed to compare branches for {}", repo))?,
+                Backend::Cli => warn!("github.behind_base needs github.token or GITHUB_TOKEN"),
+            }
+        }
+        let mut results = RepoResults {
+            muted_open: take_muted(repo, &mut data, muted),
+            issues: self.pr_status_issues(repo, &data, Utc::now()),
+            open: HashSet::new(),
+        };
 
-        Ok(issues)
+        // Ones that were open last run and aren't now were merged or closed
+        if self.closed_prs {
+            let repo_open = open_pr_refs(repo, &data);
+            let gone = no_longer_open(previous_open, repo, &repo_open);
+            if !gone.is_empty() {
+                let since = (Utc::now() - chrono::Duration::days(CLOSED_PR_DAYS)).date_naive();
+                let query = format!(
+                    "is:pr is:closed is:unmerged author:@me closed:>={} repo:{}",
+                    since, repo
+                );
+                let closed = match &self.backend {
+                    Backend::Api(api) => api.search_issues(&query).await,
+                    Backend::Cli => gh_search_issues(&self.gh, &query).await,
+                }
+                .with_context(|| format!("Failed to fetch closed PRs for {}", repo))?;
+                results
+                    .issues
+                    .extend(self.closed_unmerged_issues(repo, &gone, &closed));
+            }
+            results.open = repo_open;
+        }
+        Ok(results)
     }
-}
 
-#[async_trait]
-impl Check for GitHubChecker {
-    async fn check(&self) -> Result<Vec<String>> {
-        let output = Command::new("gh")
-            .args([
-                "pr",
-                "status",
-                "--json",
-                "number,title,state,isDraft,labels,statusCheckRollup,reviewDecision",
-            ])
-            .output()
-            .context("Failed to execute gh pr status")?;
+    /// Updates what's tracked about `repo`'s PRs across runs, and records it in `data`.
+    fn track_prs(&self, repo: &str, data: &mut serde_json::Value, tracked: &mut TrackedPrs) {
+        let now = Utc::now();
+        if !untimed_auto_merges(data).is_empty() {
+            tracked.changed |= track_auto_merge(&mut tracked.auto_merge_since, repo, data, now);
+        }
+        if self.track_flaky_checks {
+            tracked.changed |=
+                self.record_check_results(&mut tracked.check_history, repo, data, now);
+            self.mark_flaky_checks(&tracked.check_history, repo, data, now);
+        }
+        if self.unreviewed_after.is_some() {
+            tracked.changed |= track_unreviewed(&mut tracked.unreviewed_since, repo, data, now);
+        }
+    }
+
+    /// Records `unresolvedReviewThreads` on each of our PRs that's otherwise green. That
+    /// costs a query per PR, so PRs still waiting on CI are left alone.
+    async fn add_review_threads(&self, repo: &str, data: &mut serde_json::Value) -> Result<()> {
+        let Some(created) = data.get_mut("createdBy").and_then(|v| v.as_array_mut()) else {
+            return Ok(());
+        };
+        for pr in created.iter_mut().filter(|pr| self.checks_green(pr)) {
+            let number = pr.get("number").and_then(|v| v.as_u64()).unwrap_or(0);
+            let response = match &self.backend {
+                Backend::Api(api) => api.pr_graphql(REVIEW_THREADS_QUERY, repo, number).await?,
+                Backend::Cli => gh_pr_graphql(&self.gh, REVIEW_THREADS_QUERY, repo, number).await?,
+            };
+            pr["unresolvedReviewThreads"] = unresolved_review_threads(&response)?.into();
+        }
+        Ok(())
+    }
+
+    /// Records `unownedFiles` on our PRs that aren't drafts, in repos with a CODEOWNERS
+    /// file on the PR's base branch. Costs a query per PR.
+    async fn add_unowned_files(&self, repo: &str, data: &mut serde_json::Value) -> Result<()> {
+        let Some(created) = data.get_mut("createdBy").and_then(|v| v.as_array_mut()) else {
+            return Ok(());
+        };
+        for pr in created.iter_mut() {
+            if pr.get("isDraft").and_then(|v| v.as_bool()) == Some(true) {
+                continue;
+            }
+            let number = pr.get("number").and_then(|v| v.as_u64()).unwrap_or(0);
+            let response = match &self.backend {
+                Backend::Api(api) => api.pr_graphql(CODE_OWNERS_QUERY, repo, number).await?,
+                Backend::Cli => gh_pr_graphql(&self.gh, CODE_OWNERS_QUERY, repo, number).await?,
+            };
+            if let Some(unowned) = unowned_files(&response)? {
+                pr["unownedFiles"] = unowned.into();
+            }
+        }
+        Ok(())
+    }
+
+    /// Records `threadsAwaitingYou` on our PRs, when each unresolved thread a reviewer had
+    /// the last word on was last commented on, and `threadsAwaitingThem` on PRs awaiting
+    /// our review, for the threads we had the last word on. Costs a query per PR.
+    async fn add_thread_replies(&self, repo: &str, data: &mut serde_json::Value) -> Result<()> {
+        for (field, injected, by_viewer) in [
+            ("createdBy", "threadsAwaitingYou", false),
+            ("needsReview", "threadsAwaitingThem", true),
+        ] {
+            let Some(prs) = data.get_mut(field).and_then(|v| v.as_array_mut()) else {
+                continue;
+            };
+            for pr in prs.iter_mut() {
+                let number = pr.get("number").and_then(|v| v.as_u64()).unwrap_or(0);
+                let response = match &self.backend {
+                    Backend::Api(api) => api.pr_graphql(THREAD_REPLIES_QUERY, repo, number).await?,
+                    Backend::Cli => {
+                        gh_pr_graphql(&self.gh, THREAD_REPLIES_QUERY, repo, number).await?
+                    }
+                };
+                pr[injected] = last_thread_comments(&response, by_viewer)?.into();
+            }
+        }
+        Ok(())
+    }
+
+    /// Records the result of each finished check on our PRs' head commits in `history`.
+    /// Returns whether `history` changed.
+    fn record_check_results(
+        &self,
+        history: &mut HashMap<String, CheckHistory>,
+        repo: &str,
+        data: &serde_json::Value,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let forget_before = now - chrono::Duration::days(FLAKY_FLIP_DAYS);
+        let mut changed = false;
+        let created = data.get("createdBy").and_then(|v| v.as_array());
+        for pr in created.into_iter().flatten() {
+            let Some(sha) = str_field(pr, "headRefOid") else {
+                continue;
+            };
+            let checks = pr.get("statusCheckRollup").and_then(|v| v.as_array());
+            for check in checks.into_iter().flatten() {
+                let passed = if self.check_failed(check) {
+                    false
+                } else if str_field(check, "conclusion") == Some("SUCCESS")
+                    || str_field(check, "state") == Some("SUCCESS")
+                {
+                    true
+                } else {
+                    continue;
+                };
+                changed |= history
+                    .entry(format!("{}:{}", repo, check_name(check)))
+                    .or_default()
+                    .record(sha, passed, now, forget_before);
+            }
+        }
+        changed
+    }
+
+    /// Sets `likelyFlaky` on our PRs' failing checks whose result flipped recently in `history`.
+    fn mark_flaky_checks(
+        &self,
+        history: &HashMap<String, CheckHistory>,
+        repo: &str,
+        data: &mut serde_json::Value,
+        now: DateTime<Utc>,
+    ) {
+        let since = now - chrono::Duration::days(FLAKY_FLIP_DAYS);
+        let Some(created) = data.get_mut("createdBy").and_then(|v| v.as_array_mut()) else {
+            return;
+        };
+        for pr in created.iter_mut() {
+            let Some(checks) = pr
+                .get_mut("statusCheckRollup")
+                .and_then(|v| v.as_array_mut())
+            else {
+                continue;
+            };
+            for check in checks.iter_mut().filter(|check| self.check_failed(check)) {
+                let key = format!("{}:{}", repo, check_name(check));
+                if history.get(&key).is_some_and(|h| h.flipped_since(since)) {
+                    check["likelyFlaky"] = true.into();
+                }
+            }
+        }
+    }
+
+    /// Records `requiredChecks` on each of our PRs, from its base branch's protection.
+    async fn add_required_checks(&self, repo: &str, data: &mut serde_json::Value) -> Result<()> {
+        let Some(created) = data.get_mut("createdBy").and_then(|v| v.as_array_mut()) else {
+            return Ok(());
+        };
+        // Most PRs share a base, so each branch is only looked up once
+        let mut required: HashMap<String, Vec<String>> = HashMap::new();
+        for pr in created.iter_mut() {
+            let Some(base) = str_field(pr, "baseRefName").map(str::to_string) else {
+                continue;
+            };
+            if !required.contains_key(&base) {
+                let checks = match &self.backend {
+                    Backend::Api(api) => api.required_checks(repo, &base).await?,
+                    Backend::Cli => gh_required_checks(&self.gh, repo, &base).await?,
+                };
+                required.insert(base.clone(), checks);
+            }
+            pr["requiredChecks"] = required[&base].clone().into();
+        }
+        Ok(())
+    }
+
+    /// Records `requiredApprovals` on each of our PRs whose base branch protection says,
+    /// looking each base up once.
+    async fn add_required_approvals(&self, repo: &str, data: &mut serde_json::Value) -> Result<()> {
+        let Some(created) = data.get_mut("createdBy").and_then(|v| v.as_array_mut()) else {
+            return Ok(());
+        };
+        let mut required: HashMap<String, Option<u64>> = HashMap::new();
+        for pr in created.iter_mut() {
+            let Some(base) = str_field(pr, "baseRefName").map(str::to_string) else {
+                continue;
+            };
+            if !required.contains_key(&base) {
+                let approvals = match &self.backend {
+                    Backend::Api(api) => api.required_approvals(repo, &base).await?,
+                    Backend::Cli => gh_required_approvals(&self.gh, repo, &base).await?,
+                };
+                required.insert(base.clone(), approvals);
+            }
+            if let Some(approvals) = required[&base] {
+                pr["requiredApprovals"] = approvals.into();
+            }
+        }
+        Ok(())
+    }
 
-        if !output.status.success() {
-            return Err(anyhow::anyhow!("gh pr status failed"));
+    /// Records `mergedParent`, the merged PR from the PR's base branch, on each of our PRs
+    /// based on a branch other than the default one.
+    async fn add_merged_parents(&self, repo: &str, data: &mut serde_json::Value) -> Result<()> {
+        let Some(created) = data.get_mut("createdBy").and_then(|v| v.as_array_mut()) else {
+            return Ok(());
+        };
+        let mut default_branch = None;
+        for pr in created.iter_mut() {
+            let Some(base) = str_field(pr, "baseRefName").map(str::to_string) else {
+                continue;
+            };
+            // Only looked up once there's a PR to check it against
+            let default_branch = match &mut default_branch {
+                Some(branch) => branch,
+                None => default_branch.insert(match &self.backend {
+                    Backend::Api(api) => api.default_branch(repo).await?,
+                    Backend::Cli => gh_default_branch(&self.gh, repo).await?,
+                }),
+            };
+            if base == *default_branch {
+                continue;
+            }
+            let parent = match &self.backend {
+                Backend::Api(api) => api.merged_pull_from(repo, &base).await?,
+                Backend::Cli => gh_merged_pull_from(&self.gh, repo, &base).await?,
+            };
+            if let Some(parent) = parent {
+                debug!(
+                    base,
+                    parent = parent.number,
+                    "PR is based on a merged branch"
+                );
+                pr["mergedParent"] = serde_json::json!({
+                    "number": parent.number,
+                    "baseRefName": parent.base_ref_name,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Records `previousReview`, our latest review's state, on each PR awaiting our review
+    /// that we've reviewed before.
+    async fn add_previous_reviews(&self, repo: &str, data: &mut serde_json::Value) -> Result<()> {
+        let Some(needs_review) = data.get_mut("needsReview").and_then(|v| v.as_array_mut()) else {
+            return Ok(());
+        };
+        for pr in needs_review.iter_mut() {
+            let number = pr.get("number").and_then(|v| v.as_u64()).unwrap_or(0);
+            let response = match &self.backend {
+                Backend::Api(api) => api.pr_graphql(REVIEW_REQUESTS_QUERY, repo, number).await?,
+                Backend::Cli => {
+                    gh_pr_graphql(&self.gh, REVIEW_REQUESTS_QUERY, repo, number).await?
+                }
+            };
+            if let Some(state) = previous_review(&response)? {
+                pr["previousReview"] = state.into();
+            }
         }
+        Ok(())
+    }
 
-        let stdout = String::from_utf8(output.stdout)?;
-        self.check_output(&stdout)
+    /// Records `teamReviewRequests`: open PRs requesting review from any configured team,
+    /// each tagged with the team it was requested from.
+    async fn add_team_reviews(&self, repo: &str, data: &mut serde_json::Value) -> Result<()> {
+        let mut requests = Vec::new();
+        for team in &self.teams {
+            let query = format!("is:pr is:open team-review-requested:{} repo:{}", team, repo);
+            let items = match &self.backend {
+                Backend::Api(api) => api.search_issues(&query).await?,
+                Backend::Cli => gh_search_issues(&self.gh, &query).await?,
+            };
+            requests.extend(items.into_iter().map(|mut pr| {
+                pr["team"] = team.as_str().into();
+                pr
+            }));
+        }
+        data["teamReviewRequests"] = requests.into();
+        Ok(())
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Matches a login against a pattern where `*` stands for any run of characters.
+fn matches_pattern(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            let Some(text) = text.strip_prefix(prefix) else {
+                return false;
+            };
+            // Try every split point for the wildcard
+            (0..=text.len())
+                .filter(|&i| text.is_char_boundary(i))
+                .any(|i| matches_pattern(rest, &text[i..]))
+        }
+    }
+}
 
-    #[test]
-    fn test_github_checker() {
-        const EXPECTED_OUTPUT: &str = include_str!("github/check_output_1.txt");
-        const TEST_JSON: &str = include_str!("github/check_output_1.json");
+/// CheckRuns have a name, StatusContexts a context.
+fn check_name(check: &serde_json::Value) -> &str {
+    str_field(check, "name")
+        .or_else(|| str_field(check, "context"))
+        .unwrap_or("unknown check")
+}
 
-        let checker = GitHubChecker::new();
-        let issues = checker.check_output(TEST_JSON).unwrap();
+// CheckRun uses status:"COMPLETED", StatusContext uses state:"SUCCESS"
+fn all_checks_complete(checks: &[serde_json::Value]) -> bool {
+    !checks.is_empty()
+        && checks.iter().all(|check| {
+            str_field(check, "status") == Some("COMPLETED")
+                || str_field(check, "state") == Some("SUCCESS")
+        })
+}
 
-        // Based on check_output_1.txt:
-        // - PR #591209 (Created by you) - checks passing, so should NOT appear
-        // - PR #591746 (Requesting review) - should appear
-        // - PR #591547 (Requesting review) - should appear
-        // - PR #590962 (Requesting review) - should appear
-        assert_eq!(issues.len(), 3, "Expected 3 issues, got: {:#?}", issues);
+/// Counts the unresolved threads in a `REVIEW_THREADS_QUERY` response.
+fn unresolved_review_threads(response: &serde_json::Value) -> Result<usize> {
+    let threads = response
+        .pointer("/data/repository/pullRequest/reviewThreads/nodes")
+        .and_then(|v| v.as_array())
+        .context("Review threads response is missing reviewThreads")?;
+    Ok(threads
+        .iter()
+        .filter(|thread| thread.get("isResolved").and_then(|v| v.as_bool()) == Some(false))
+        .count())
+}
 
-        // All issues should be from needsReview
-        assert!(
-            issues.iter().any(|i| i.contains("#591746") && i.contains("awaiting your review")),
-            "Expected PR #591746 awaiting review. Got: {:#?}\n\nExpected output:\n{}",
-            issues,
-            EXPECTED_OUTPUT
-        );
+/// Counts the files a `CODE_OWNERS_QUERY` response's PR changes without a code owner, or
+/// None when the base branch has no CODEOWNERS file.
+fn unowned_files(response: &serde_json::Value) -> Result<Option<usize>> {
+    let pr = response
+        .pointer("/data/repository/pullRequest")
+        .context("Code owners response is missing the pull request")?;
+    let files = pr
+        .pointer("/files/nodes")
+        .and_then(|v| v.as_array())
+        .context("Code owners response is missing files")?;
+    // GitHub uses the first it finds, looking in this order
+    let Some(content) = ["github", "root", "docs"].iter().find_map(|alias| {
+        pr.pointer(&format!("/baseRef/target/{}/object/text", alias))
+            .and_then(|v| v.as_str())
+    }) else {
+        return Ok(None);
+    };
+    let codeowners = CodeOwners::parse(content);
+    Ok(Some(
+        files
+            .iter()
+            .filter(|file| str_field(file, "changeType") != Some("DELETED"))
+            .filter_map(|file| str_field(file, "path"))
+            .filter(|path| codeowners.owners(path).is_empty())
+            .count(),
+    ))
+}
 
-        assert!(
-            issues.iter().any(|i| i.contains("#591547") && i.contains("awaiting your review")),
-            "Expected PR #591547 awaiting review. Got: {:#?}\n\nExpected output:\n{}",
-            issues,
-            EXPECTED_OUTPUT
-        );
+/// When the last comment of each unresolved thread in a `THREAD_REPLIES_QUERY` response was
+/// left, for the threads where we (`by_viewer`) or someone else had the last word.
+fn last_thread_comments(response: &serde_json::Value, by_viewer: bool) -> Result<Vec<String>> {
+    let viewer = response
+        .pointer("/data/viewer/login")
+        .and_then(|v| v.as_str())
+        .context("Review threads response is missing the viewer")?;
+    let threads = response
+        .pointer("/data/repository/pullRequest/reviewThreads/nodes")
+        .and_then(|v| v.as_array())
+        .context("Review threads response is missing reviewThreads")?;
+    Ok(threads
+        .iter()
+        .filter(|thread| thread.get("isResolved").and_then(|v| v.as_bool()) == Some(false))
+        .filter_map(|thread| thread.pointer("/comments/nodes/0"))
+        .filter(|comment| {
+            (comment.pointer("/author/login").and_then(|v| v.as_str()) == Some(viewer)) == by_viewer
+        })
+        .filter_map(|comment| str_field(comment, "createdAt").map(String::from))
+        .collect())
+}
 
-        assert!(
-            issues.iter().any(|i| i.contains("#590962") && i.contains("awaiting your review")),
-            "Expected PR #590962 awaiting review. Got: {:#?}\n\nExpected output:\n{}",
-            issues,
-            EXPECTED_OUTPUT
-        );
+/// The times in `pr`'s `field` from before `before`.
+fn waiting_since(pr: &serde_json::Value, field: &str, before: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+    pr.get(field)
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str()?.parse::<DateTime<Utc>>().ok())
+        .filter(|at| *at < before)
+        .collect()
+}
 
-        // PR #591209 should NOT appear (checks passing)
+/// The state of our latest review ("APPROVED", "DISMISSED", ...) in a
+/// `REVIEW_REQUESTS_QUERY` response, when we've reviewed the PR before and have been asked
+/// to review it again.
+fn previous_review(response: &serde_json::Value) -> Result<Option<&str>> {
+    let viewer = response
+        .pointer("/data/viewer/login")
+        .and_then(|v| v.as_str())
+        .context("Review requests response is missing the viewer")?;
+    let pr = response
+        .pointer("/data/repository/pullRequest")
+        .context("Review requests response is missing pullRequest")?;
+    let nodes = |field: &str| {
+        pr.pointer(&format!("/{}/nodes", field))
+            .and_then(|v| v.as_array())
+            .map_or(&[][..], |nodes| nodes.as_slice())
+    };
+
+    let requested = nodes("reviewRequests").iter().any(|request| {
+        request
+            .pointer("/requestedReviewer/login")
+            .and_then(|v| v.as_str())
+            == Some(viewer)
+    });
+    let latest_review = nodes("latestReviews")
+        .iter()
+        .find(|review| review.pointer("/author/login").and_then(|v| v.as_str()) == Some(viewer));
+    Ok(latest_review
+        .filter(|_| requested)
+        .and_then(|review| str_field(review, "state")))
+}
+
+/// Whether a `PENDING_REVIEWS_QUERY` response has a pending review of ours.
+fn has_pending_review(response: &serde_json::Value) -> Result<bool> {
+    let viewer = response
+        .pointer("/data/viewer/login")
+        .and_then(|v| v.as_str())
+        .context("Pending reviews response is missing the viewer")?;
+    let reviews = response
+        .pointer("/data/repository/pullRequest/reviews/nodes")
+        .and_then(|v| v.as_array())
+        .context("Pending reviews response is missing reviews")?;
+    Ok(reviews
+        .iter()
+        .any(|review| review.pointer("/author/login").and_then(|v| v.as_str()) == Some(viewer)))
+}
+
+/// The `requiredChecks` of the PR's base branch that aren't in its status rollup at all.
+fn missing_required_checks(pr: &serde_json::Value) -> Vec<&str> {
+    let required = pr
+        .get("requiredChecks")
+        .and_then(|v| v.as_array())
+        .map_or(&[][..], |required| required.as_slice());
+    let checks = pr
+        .get("statusCheckRollup")
+        .and_then(|v| v.as_array())
+        .map_or(&[][..], |checks| checks.as_slice());
+    required
+        .iter()
+        .filter_map(|name| name.as_str())
+        .filter(|name| {
+            !checks.iter().any(|check| {
+                str_field(check, "name").or_else(|| str_field(check, "context")) == Some(name)
+            })
+        })
+        .collect()
+}
+
+/// When the PR's checks started, i.e. when the first one was reported for its head commit,
+/// falling back to the PR's last update.
+fn checks_started(pr: &serde_json::Value) -> Option<DateTime<Utc>> {
+    pr.get("statusCheckRollup")
+        .and_then(|v| v.as_array())
+        .and_then(|checks| {
+            checks
+                .iter()
+                .filter_map(|check| time_field(check, "startedAt"))
+                .min()
+        })
+        .or_else(|| time_field(pr, "updatedAt"))
+}
+
+/// When the longest-waiting queued or running check in the rollup started, falling back to
+/// the PR's last update for checks that haven't started.
+fn pending_since(pr: &serde_json::Value, checks: &[serde_json::Value]) -> Option<DateTime<Utc>> {
+    let updated_at = time_field(pr, "updatedAt");
+    checks
+        .iter()
+        .filter(|check| {
+            matches!(
+                str_field(check, "status"),
+                Some("QUEUED" | "IN_PROGRESS" | "PENDING" | "WAITING" | "REQUESTED")
+            ) || matches!(str_field(check, "state"), Some("PENDING" | "EXPECTED"))
+        })
+        .filter_map(|check| time_field(check, "startedAt").or(updated_at))
+        .min()
+}
+
+/// When auto-merge was enabled on the PR: gh reports `enabledAt`, and for the API
+/// `track_auto_merge` records when we first saw it.
+fn auto_merge_since(pr: &serde_json::Value) -> Option<DateTime<Utc>> {
+    let request = pr.get("autoMergeRequest").filter(|v| !v.is_null())?;
+    time_field(request, "enabledAt").or_else(|| time_field(pr, "autoMergeSince"))
+}
+
+/// How many reviewers' latest review of `pr` approves it.
+fn approvals(pr: &serde_json::Value) -> usize {
+    pr.get("latestReviews")
+        .and_then(|v| v.as_array())
+        .map_or(0, |reviews| {
+            reviews
+                .iter()
+                .filter(|review| str_field(review, "state") == Some("APPROVED"))
+                .count()
+        })
+}
+
+/// How long an unreviewed PR has been waiting: since its checks last started, i.e. its
+/// last push, or since `track_unreviewed` first saw it if that's later. None for PRs
+/// that have been reviewed, or whose reviews weren't fetched.
+fn unreviewed_since(pr: &serde_json::Value) -> Option<DateTime<Utc>> {
+    let reviews = pr.get("latestReviews").and_then(|v| v.as_array())?;
+    if !reviews.is_empty() {
+        return None;
+    }
+    let pushed = checks_started(pr)?;
+    Some(time_field(pr, "unreviewedSince").map_or(pushed, |seen| seen.max(pushed)))
+}
+
+/// gh reports unset times as "0001-01-01T00:00:00Z", which count as missing.
+fn time_field(value: &serde_json::Value, name: &str) -> Option<DateTime<Utc>> {
+    str_field(value, name)?
+        .parse::<DateTime<Utc>>()
+        .ok()
+        .filter(|time| time.year() > 1)
+}
+
+fn str_field<'a>(value: &'a serde_json::Value, name: &str) -> Option<&'a str> {
+    value.get(name).and_then(|v| v.as_str())
+}
+
+/// e.g. "failing: lint, unit-tests, e2e (+2 more)".
+fn failing_detail(failing: &[(&str, Option<&str>)]) -> String {
+    const SHOWN: usize = 3;
+    let names: Vec<&str> = failing.iter().take(SHOWN).map(|(name, _)| *name).collect();
+    let mut detail = format!("failing: {}", names.join(", "));
+    if failing.len() > SHOWN {
+        detail.push_str(&format!(" (+{} more)", failing.len() - SHOWN));
+    }
+    detail
+}
+
+/// CI-state issues are mutually exclusive and share the PR's id, so a PR going from failing
+/// to approved stays one issue. Conditions that can hold alongside them get their own.
+fn id_suffix(kind: IssueKind) -> Option<&'static str> {
+    match kind {
+        IssueKind::ChangesRequested => Some("changes-requested"),
+        IssueKind::MergeConflicts => Some("conflicts"),
+        IssueKind::UnresolvedThreads => Some("threads"),
+        IssueKind::AwaitingYourReply => Some("your-reply"),
+        IssueKind::AwaitingTheirReply => Some("their-reply"),
+        IssueKind::BehindBase => Some("behind"),
+        IssueKind::MissingRequiredCheck => Some("missing-check"),
+        IssueKind::AutoMergeStuck => Some("auto-merge"),
+        IssueKind::Unreviewed => Some("unreviewed"),
+        IssueKind::AwaitingApprovals => Some("approvals"),
+        IssueKind::BaseMerged => Some("base-merged"),
+        IssueKind::NoCodeOwner => Some("no-owner"),
+        IssueKind::ClosedUnmerged => Some("closed"),
+        IssueKind::AutoCloseSoon => Some("auto-close"),
+        IssueKind::PendingReview => Some("pending-review"),
+        _ => None,
+    }
+}
+
+fn api_token(config: &GitHubConfig) -> Option<&str> {
+    config.token.as_deref().filter(|token| !token.is_empty())
+}
+
+/// Whether the checker needs the `gh` CLI, i.e. no API token is configured.
+pub fn gh_required(config: &GitHubConfig) -> bool {
+    api_token(config).is_none()
+}
+
+pub const GH_MISSING: &str =
+    "`gh` not found on PATH (install it with `brew install gh`, or set GITHUB_TOKEN)";
+
+/// Whether the `gh` CLI the checker shells out to is on `PATH`.
+pub fn gh_installed() -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join("gh").is_file()))
+}
+
+#[async_trait]
+impl Check for GitHubChecker {
+    fn name(&self) -> &str {
+        "GitHub"
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    async fn check(&self) -> Result<CheckOutput> {
+        let repos = self.repos_to_check().await?;
+        let mut issues = Vec::new();
+        let state = load_state().unwrap_or_default();
+        // Each repo updates what's tracked about its PRs as it's checked
+        let tracked = Mutex::new(TrackedPrs {
+            auto_merge_since: state.auto_merge_since.clone(),
+            unreviewed_since: state.unreviewed_since.clone(),
+            check_history: state.check_history.clone(),
+            changed: false,
+        });
+        let muted: HashSet<String> = state
+            .muted
+            .iter()
+            .map(|pr| pr.to_lowercase())
+            .chain(self.muted_prs.iter().cloned())
+            .collect();
+        let mut other_review_requests = if self.all_review_requests {
+            let items = match &self.backend {
+                Backend::Api(api) => api.search_all(ALL_REVIEW_REQUESTS_QUERY).await,
+                Backend::Cli => gh_search_all(&self.gh, ALL_REVIEW_REQUESTS_QUERY).await,
+            }
+            .context("Failed to search for review requests")?;
+            review_requests_by_repo(items)
+        } else {
+            Vec::new()
+        };
+        // Each repo's review requests from the account-wide search go with its own PRs
+        let checks: Vec<_> = repos
+            .iter()
+            .map(|repo| {
+                let review_requests = other_review_requests
+                    .iter()
+                    .position(|(other, _)| other.eq_ignore_ascii_case(repo))
+                    .map(|i| other_review_requests.remove(i).1);
+                self.check_repo(repo, review_requests, &muted, &state.open_prs, &tracked)
+            })
+            .collect();
+        // Results come back in `repos` order however long each repo takes
+        let results: Vec<Result<RepoResults>> = stream::iter(checks)
+            .buffered(self.max_concurrent_repos)
+            .collect()
+            .await;
+        let mut muted_open = HashMap::new();
+        let mut open = HashSet::new();
+        let mut failures = Vec::new();
+        for (repo, result) in repos.iter().zip(results) {
+            match result {
+                Ok(results) => {
+                    issues.extend(results.issues);
+                    muted_open.extend(results.muted_open);
+                    open.extend(results.open);
+                }
+                Err(e) => {
+                    warn!("Failed to check {}: {:#}", repo, e);
+                    // Keep what we knew about its PRs until it can be checked again
+                    let prefix = format!("{}#", repo.to_lowercase());
+                    open.extend(
+                        state
+                            .open_prs
+                            .iter()
+                            .filter(|pr| pr.starts_with(&prefix))
+                            .cloned(),
+                    );
+                    muted_open.extend(
+                        state
+                            .muted_open
+                            .iter()
+                            .filter(|(pr, _)| pr.starts_with(&prefix))
+                            .map(|(pr, muted_pr)| (pr.clone(), muted_pr.clone())),
+                    );
+                    failures.push((repo, e));
+                }
+            }
+        }
+        // When no repo could be checked, e.g. without `gh`, the checker itself failed
+        if failures.len() == repos.len()
+            && let Some((_, e)) = failures.pop()
+        {
+            return Err(e);
+        }
+        issues.extend(
+            failures
+                .iter()
+                .map(|(repo, e)| Issue::checker_failed(&format!("GitHub {}", repo), e)),
+        );
+        // Review requests in repos we don't otherwise watch
+        for (repo, review_requests) in other_review_requests {
+            let mut data = serde_json::json!({ "needsReview": review_requests });
+            muted_open.extend(take_muted(&repo, &mut data, &muted));
+            issues.extend(self.pr_status_issues(&repo, &data, Utc::now()));
+        }
+        let mut state_updates: Vec<StateUpdate> = Vec::new();
+        if state.muted_open != muted_open || state.open_prs != open {
+            state_updates.push(Box::new(move |state: &mut State| {
+                state.muted_open = muted_open;
+                state.open_prs = open;
+            }));
+        }
+
+        if self.assigned_issues.enabled {
+            let repos = match self.assigned_issues.repos.as_slice() {
+                [] => repos,
+                configured => configured,
+            };
+            for repo in repos {
+                let query = assigned_issues_query(repo, &self.assigned_issues.labels);
+                let items = match &self.backend {
+                    Backend::Api(api) => api.search_issues(&query).await,
+                    Backend::Cli => gh_search_issues(&self.gh, &query).await,
+                }
+                .with_context(|| format!("Failed to fetch assigned issues for {}", repo))?;
+                issues.extend(items.iter().map(|item| self.assigned_issue(repo, item)));
+            }
+        }
+
+        if self.milestones.enabled {
+            // Only needed to find the milestones we created
+            let login = match (self.milestones.titles.is_empty(), &self.backend) {
+                (false, _) => None,
+                (true, Backend::Api(api)) => Some(api.login().await),
+                (true, Backend::Cli) => Some(gh_login(&self.gh).await),
+            }
+            .transpose()
+            .context("Failed to fetch the GitHub login")?;
+            let repos = match self.milestones.repos.as_slice() {
+                [] => repos,
+                configured => configured,
+            };
+            for repo in repos {
+                let milestones = match &self.backend {
+                    Backend::Api(api) => api.open_milestones(repo).await,
+                    Backend::Cli => gh_open_milestones(&self.gh, repo).await,
+                }
+                .with_context(|| format!("Failed to fetch milestones for {}", repo))?;
+                issues.extend(self.milestone_issues(
+                    repo,
+                    &milestones,
+                    login.as_deref(),
+                    Utc::now(),
+                ));
+            }
+        }
+
+        if self.follow_ups.enabled {
+            let login = match &self.backend {
+                Backend::Api(api) => api.login().await,
+                Backend::Cli => gh_login(&self.gh).await,
+            }
+            .context("Failed to fetch the GitHub login")?;
+            let since = (Utc::now() - chrono::Duration::days(self.follow_ups.days)).date_naive();
+            let mut follow_ups = Vec::new();
+            for repo in repos {
+                let query = format!(
+                    "is:pr is:merged author:@me merged:>={} repo:{}",
+                    since, repo
+                );
+                let merged = match &self.backend {
+                    Backend::Api(api) => api.search_issues(&query).await,
+                    Backend::Cli => gh_search_issues(&self.gh, &query).await,
+                }
+                .with_context(|| format!("Failed to fetch merged PRs for {}", repo))?;
+                for pr in &merged {
+                    let number = pr.get("number").and_then(|v| v.as_u64()).unwrap_or(0);
+                    let comments = match &self.backend {
+                        Backend::Api(api) => api.pr_comments(repo, number).await,
+                        Backend::Cli => gh_pr_comments(&self.gh, repo, number).await,
+                    }
+                    .with_context(|| format!("Failed to fetch comments on {}#{}", repo, number))?;
+                    follow_ups.extend(self.follow_up_issues(repo, pr, &comments, &login));
+                }
+            }
+            // Acknowledgements are kept only while their comments are still being found
+            let found: HashSet<String> = follow_ups.iter().map(|issue| issue.id.clone()).collect();
+            let kept = |id: &String| !id.contains(":follow-up:") || found.contains(id);
+            if !state.acknowledged.iter().all(kept) {
+                state_updates.push(Box::new(move |state: &mut State| {
+                    state
+                        .acknowledged
+                        .retain(|id| !id.contains(":follow-up:") || found.contains(id));
+                }));
+            }
+            issues.extend(
+                follow_ups
+                    .into_iter()
+                    .filter(|issue| !state.acknowledged.contains(&issue.id)),
+            );
+        }
+
+        for repo in &self.default_branch_workflows {
+            let runs = match &self.backend {
+                Backend::Api(api) => api.default_branch_runs(repo).await,
+                Backend::Cli => gh_default_branch_runs(&self.gh, repo).await,
+            }
+            .with_context(|| format!("Failed to fetch workflow runs for {}", repo))?;
+            issues.extend(self.workflow_issues(repo, &runs));
+        }
+
+        for repo in &self.deployment_approvals {
+            let waiting = match &self.backend {
+                Backend::Api(api) => api.waiting_deployments(repo).await,
+                Backend::Cli => gh_waiting_deployments(&self.gh, repo).await,
+            }
+            .with_context(|| format!("Failed to fetch waiting deployments for {}", repo))?;
+            issues.extend(self.deployment_issues(repo, &waiting, Utc::now()));
+        }
+
+        if self.dependabot_alerts {
+            match &self.backend {
+                Backend::Api(api) => {
+                    for repo in repos {
+                        let alerts = api
+                            .dependabot_alerts(repo)
+                            .await
+                            .with_context(|| format!("Failed to fetch alerts for {}", repo))?;
+                        issues.extend(self.dependabot_issues(repo, alerts.as_deref()));
+                    }
+                }
+                Backend::Cli => {
+                    warn!("github.dependabot_alerts needs github.token or GITHUB_TOKEN")
+                }
+            }
+        }
+
+        if let Some(cache) = &self.notifications {
+            match &self.backend {
+                Backend::Api(api) => issues.extend(
+                    notification_issues(api, cache, &self.web_url())
+                        .await
+                        .context("Failed to fetch notifications")?,
+                ),
+                Backend::Cli => warn!("github.notifications needs github.token or GITHUB_TOKEN"),
+            }
+        }
+
+        if let Backend::Api(api) = &self.backend
+            && let Err(e) = api.save_cache()
+        {
+            warn!("Failed to save GitHub response cache: {:#}", e);
+        }
+        let tracked = tracked.into_inner().unwrap();
+        if tracked.changed {
+            state_updates.push(Box::new(move |state: &mut State| {
+                state.auto_merge_since = tracked.auto_merge_since;
+                state.unreviewed_since = tracked.unreviewed_since;
+                state.check_history = tracked.check_history;
+            }));
+        }
+        let state_update = (!state_updates.is_empty()).then(|| {
+            Box::new(move |state: &mut State| {
+                for update in state_updates {
+                    update(state);
+                }
+            }) as StateUpdate
+        });
+        Ok(CheckOutput {
+            issues,
+            state_update,
+        })
+    }
+}
+
+/// Issues for unread mentions and review requests, fetched at most once per poll interval
+/// and only when something changed.
+async fn notification_issues(
+    api: &GitHubApi,
+    cache: &Mutex<NotificationCache>,
+    web_url: &str,
+) -> Result<Vec<Issue>> {
+    let last_modified = {
+        let cache = cache.lock().unwrap();
+        if cache
+            .next_poll
+            .is_some_and(|next_poll| Instant::now() < next_poll)
+        {
+            return Ok(cache.issues.clone());
+        }
+        cache.last_modified.clone()
+    };
+
+    let page = api.notifications(last_modified.as_deref()).await?;
+    let mut cache = cache.lock().unwrap();
+    cache.next_poll = Some(Instant::now() + page.poll_interval);
+    if let Some(notifications) = page.notifications {
+        cache.issues = notifications
+            .iter()
+            .filter_map(|notification| notification_issue(notification, web_url))
+            .collect();
+        cache.last_modified = page.last_modified;
+    }
+    Ok(cache.issues.clone())
+}
+
+/// An issue for an unread mention or review request; ids are per thread so the same
+/// thread isn't reported twice.
+fn notification_issue(notification: &Notification, web_url: &str) -> Option<Issue> {
+    let detail = match notification.reason.as_str() {
+        "mention" => "mentioned you",
+        "team_mention" => "mentioned your team",
+        "review_requested" => "requested your review",
+        _ => return None,
+    };
+    if !notification.unread {
+        return None;
+    }
+
+    let repo = &notification.repository.full_name;
+    let subject = &notification.subject;
+    let number = subject
+        .url
+        .as_deref()
+        .and_then(|url| url.rsplit('/').next())
+        .filter(|number| number.parse::<u64>().is_ok());
+    Some(Issue {
+        id: format!("github:notification:{}", notification.id),
+        source: CheckSource::GitHub,
+        kind: IssueKind::Mentioned,
+        severity: Severity::Medium,
+        title: match number {
+            Some(number) => format!("{}#{} '{}'", repo, number, subject.title),
+            None => format!("{} '{}'", repo, subject.title),
+        },
+        detail: detail.to_string(),
+        url: Some(notification_link(notification, web_url)),
+        items: Vec::new(),
+    })
+}
+
+/// The web page for a notification's thread, scrolled to the comment that triggered it.
+fn notification_link(notification: &Notification, web_url: &str) -> String {
+    let subject = &notification.subject;
+    let Some((_, path)) = subject
+        .url
+        .as_deref()
+        .and_then(|url| url.split_once("/repos/"))
+    else {
+        return format!("{}/{}", web_url, notification.repository.full_name);
+    };
+
+    let mut link = format!("{}/{}", web_url, path.replacen("/pulls/", "/pull/", 1));
+    let comment = subject.latest_comment_url.as_deref().unwrap_or("");
+    if let Some((_, id)) = comment.rsplit_once("/issues/comments/") {
+        link.push_str(&format!("#issuecomment-{}", id));
+    } else if let Some((_, id)) = comment.rsplit_once("/pulls/comments/") {
+        link.push_str(&format!("#discussion_r{}", id));
+    }
+    link
+}
+
+/// Calendar days from `now` until `due_on`, negative once it's past. GitHub stores due
+/// dates as a time on the due day, so days are counted by UTC date rather than by 24h.
+fn days_until_due(due_on: DateTime<Utc>, now: DateTime<Utc>) -> i64 {
+    (due_on.date_naive() - now.date_naive()).num_days()
+}
+
+/// e.g. "due in 2 days", "due today" or "3 days overdue".
+fn due_phrase(days: i64) -> String {
+    let plural = |n: i64| if n == 1 { "" } else { "s" };
+    match days {
+        0 => "due today".to_string(),
+        1 => "due tomorrow".to_string(),
+        days if days > 0 => format!("due in {} day{}", days, plural(days)),
+        days => format!("{} day{} overdue", -days, plural(-days)),
+    }
+}
+
+/// Search query for open issues assigned to us with any of `labels` (or any labels at all).
+fn assigned_issues_query(repo: &str, labels: &[String]) -> String {
+    let mut query = format!("is:issue is:open assignee:@me repo:{}", repo);
+    if !labels.is_empty() {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|label| format!("\"{}\"", label))
+            .collect();
+        // A comma-separated label list matches issues with any of them
+        query.push_str(&format!(" label:{}", labels.join(",")));
+    }
+    query
+}
+
+/// Our PRs with auto-merge enabled but no `enabledAt`, which the REST API doesn't report.
+fn untimed_auto_merges(data: &mut serde_json::Value) -> Vec<&mut serde_json::Value> {
+    let Some(created) = data.get_mut("createdBy").and_then(|v| v.as_array_mut()) else {
+        return Vec::new();
+    };
+    created
+        .iter_mut()
+        .filter(|pr| {
+            pr.get("autoMergeRequest").is_some_and(|request| {
+                !request.is_null() && time_field(request, "enabledAt").is_none()
+            })
+        })
+        .collect()
+}
+
+/// Records `autoMergeSince` on each of `untimed_auto_merges`, from when we first saw it in
+/// `since` (keyed by PR id). Entries for other PRs of `repo` are dropped, so turning
+/// auto-merge off and on again restarts the clock. Returns whether `since` changed.
+fn track_auto_merge(
+    since: &mut HashMap<String, DateTime<Utc>>,
+    repo: &str,
+    data: &mut serde_json::Value,
+    now: DateTime<Utc>,
+) -> bool {
+    track_first_seen(
+        since,
+        repo,
+        untimed_auto_merges(data),
+        "autoMergeSince",
+        now,
+    )
+}
+
+/// Our open PRs that haven't had a review yet.
+fn unreviewed_prs(data: &mut serde_json::Value) -> Vec<&mut serde_json::Value> {
+    let Some(created) = data.get_mut("createdBy").and_then(|v| v.as_array_mut()) else {
+        return Vec::new();
+    };
+    created
+        .iter_mut()
+        .filter(|pr| {
+            pr.get("latestReviews")
+                .and_then(|v| v.as_array())
+                .is_some_and(|reviews| reviews.is_empty())
+        })
+        .collect()
+}
+
+/// Records `unreviewedSince` on each of `unreviewed_prs` like `track_auto_merge` does, so
+/// a review restarts the clock.
+fn track_unreviewed(
+    since: &mut HashMap<String, DateTime<Utc>>,
+    repo: &str,
+    data: &mut serde_json::Value,
+    now: DateTime<Utc>,
+) -> bool {
+    track_first_seen(since, repo, unreviewed_prs(data), "unreviewedSince", now)
+}
+
+/// Sets `field` on each of `prs` to when it was first seen, from `since` (keyed by PR id),
+/// dropping entries for `repo`'s other PRs. Returns whether `since` changed.
+fn track_first_seen(
+    since: &mut HashMap<String, DateTime<Utc>>,
+    repo: &str,
+    prs: Vec<&mut serde_json::Value>,
+    field: &str,
+    now: DateTime<Utc>,
+) -> bool {
+    let prefix = format!("github:{}:pr:", repo);
+    let mut changed = false;
+    let mut tracked = Vec::new();
+    for pr in prs {
+        let number = pr.get("number").and_then(|v| v.as_u64()).unwrap_or(0);
+        let id = format!("{}{}", prefix, number);
+        let first_seen = *since.entry(id.clone()).or_insert_with(|| {
+            changed = true;
+            now
+        });
+        pr[field] = first_seen.to_rfc3339().into();
+        tracked.push(id);
+    }
+
+    let count = since.len();
+    since.retain(|id, _| !id.starts_with(&prefix) || tracked.contains(id));
+    changed || since.len() != count
+}
+
+/// How far back to look for our PRs closed since the last run.
+const CLOSED_PR_DAYS: i64 = 30;
+
+/// Open PRs requesting our review anywhere, skipping archived repos.
+const ALL_REVIEW_REQUESTS_QUERY: &str = "is:open is:pr review-requested:@me archived:false";
+
+/// Groups account-wide search results by their `repository`, in order of first appearance.
+fn review_requests_by_repo(items: Vec<serde_json::Value>) -> Vec<(String, Vec<serde_json::Value>)> {
+    let mut by_repo: Vec<(String, Vec<serde_json::Value>)> = Vec::new();
+    for item in items {
+        let repo = str_field(&item, "repository")
+            .unwrap_or_default()
+            .to_string();
+        match by_repo.iter_mut().find(|(other, _)| *other == repo) {
+            Some((_, prs)) => prs.push(item),
+            None => by_repo.push((repo, vec![item])),
+        }
+    }
+    by_repo
+}
+
+/// Adds `review_requests` to `data`'s `needsReview`, skipping PRs it already has by node ID.
+fn merge_review_requests(data: &mut serde_json::Value, review_requests: Vec<serde_json::Value>) {
+    let Some(needs_review) = data
+        .as_object_mut()
+        .map(|data| {
+            data.entry("needsReview")
+                .or_insert_with(|| serde_json::json!([]))
+        })
+        .and_then(|v| v.as_array_mut())
+    else {
+        return;
+    };
+    for pr in review_requests {
+        let id = str_field(&pr, "id");
+        if id.is_none()
+            || !needs_review
+                .iter()
+                .any(|known| str_field(known, "id") == id)
+        {
+            needs_review.push(pr);
+        }
+    }
+}
+
+/// Whether `text` contains any of `patterns` as whole words, ignoring case.
+fn mentions_any(text: &str, patterns: &[String]) -> bool {
+    let text = text.to_lowercase();
+    patterns
+        .iter()
+        .map(|pattern| pattern.to_lowercase())
+        .filter(|pattern| !pattern.is_empty())
+        .any(|pattern| {
+            text.match_indices(&pattern).any(|(i, _)| {
+                let before = text[..i].chars().next_back();
+                let after = text[i + pattern.len()..].chars().next();
+                !before.is_some_and(char::is_alphanumeric)
+                    && !after.is_some_and(char::is_alphanumeric)
+            })
+        })
+}
+
+/// References to our open PRs in `data`, like `pr_ref`.
+fn open_pr_refs(repo: &str, data: &serde_json::Value) -> HashSet<String> {
+    data.get("createdBy")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|pr| pr.get("number").and_then(|v| v.as_u64()))
+        .map(|number| pr_ref(repo, number))
+        .collect()
+}
+
+/// Numbers of our PRs in `repo` that were open last run but aren't now.
+fn no_longer_open(previous: &HashSet<String>, repo: &str, open: &HashSet<String>) -> Vec<u64> {
+    let prefix = format!("{}#", repo.to_lowercase());
+    let mut gone: Vec<u64> = previous
+        .difference(open)
+        .filter_map(|pr| pr.strip_prefix(&prefix)?.parse().ok())
+        .collect();
+    gone.sort();
+    gone
+}
+
+/// A PR as it's muted, e.g. "figma/figma#123".
+fn pr_ref(repo: &str, number: u64) -> String {
+    format!("{}#{}", repo, number).to_lowercase()
+}
+
+/// Whether `pr` is an `owner/repo#number` reference.
+pub fn is_pr_ref(pr: &str) -> bool {
+    pr.split_once('#').is_some_and(|(repo, number)| {
+        repo.split_once('/').is_some_and(|(owner, name)| {
+            !owner.is_empty() && !name.is_empty() && !name.contains('/')
+        }) && number.parse::<u64>().is_ok()
+    })
+}
+
+/// Removes muted PRs from `data` so nothing is reported about them, returning the ones
+/// still open by reference.
+fn take_muted(
+    repo: &str,
+    data: &mut serde_json::Value,
+    muted: &HashSet<String>,
+) -> Vec<(String, MutedPr)> {
+    let mut taken = Vec::new();
+    if muted.is_empty() {
+        return taken;
+    }
+    for field in ["createdBy", "needsReview", "teamReviewRequests"] {
+        let Some(prs) = data.get_mut(field).and_then(|v| v.as_array_mut()) else {
+            continue;
+        };
+        prs.retain(|pr| {
+            let number = pr.get("number").and_then(|v| v.as_u64()).unwrap_or(0);
+            let pr_ref = pr_ref(repo, number);
+            if !muted.contains(&pr_ref) {
+                return true;
+            }
+            // Team review requests repeat PRs that may already have been taken
+            if !taken.iter().any(|(taken_ref, _)| *taken_ref == pr_ref) {
+                let muted_pr = MutedPr {
+                    title: str_field(pr, "title").unwrap_or_default().to_string(),
+                    url: str_field(pr, "url").map(String::from),
+                };
+                taken.push((pr_ref, muted_pr));
+            }
+            false
+        });
+    }
+    taken
+}
+
+/// How long after a check's result flips on an unchanged commit it's treated as flaky.
+const FLAKY_FLIP_DAYS: i64 = 7;
+
+/// Records `pendingReview` on each PR awaiting our review that we've started reviewing
+/// without submitting. Pending reviews are only visible to their author, so this needs
+/// our own token and is API only.
+async fn add_pending_reviews(
+    api: &GitHubApi,
+    repo: &str,
+    data: &mut serde_json::Value,
+) -> Result<()> {
+    let Some(needs_review) = data.get_mut("needsReview").and_then(|v| v.as_array_mut()) else {
+        return Ok(());
+    };
+    for pr in needs_review.iter_mut() {
+        let number = pr.get("number").and_then(|v| v.as_u64()).unwrap_or(0);
+        let response = api.pr_graphql(PENDING_REVIEWS_QUERY, repo, number).await?;
+        if has_pending_review(&response)? {
+            pr["pendingReview"] = true.into();
+        }
+    }
+    Ok(())
+}
+
+/// Records `commitsBehind` on each of our PRs. `gh pr status` has no equivalent, so this
+/// is API only.
+async fn add_commits_behind(
+    api: &GitHubApi,
+    repo: &str,
+    data: &mut serde_json::Value,
+) -> Result<()> {
+    let Some(created) = data.get_mut("createdBy").and_then(|v| v.as_array_mut()) else {
+        return Ok(());
+    };
+    for pr in created.iter_mut() {
+        let (Some(base), Some(head)) = (str_field(pr, "baseRefName"), str_field(pr, "headRefOid"))
+        else {
+            continue;
+        };
+        pr["commitsBehind"] = api.commits_behind(repo, base, head).await?.into();
+    }
+    Ok(())
+}
+
+/// `gh` against one host, run by `runner`.
+#[derive(Clone)]
+struct Gh {
+    host: String,
+    runner: Arc<dyn CommandRunner>,
+}
+
+impl Gh {
+    /// `gh` with `args`, against `host`. `gh pr status` has no `--hostname`, so this sets
+    /// `GH_HOST`, which every subcommand respects.
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new("gh");
+        command.args(args);
+        if self.host != "github.com" {
+            command.env("GH_HOST", &self.host);
+        }
+        command
+    }
+
+    /// Runs `command` like `run_gh`. `gh` waits on the network, so it runs on a blocking
+    /// thread rather than holding up the other repos' checks.
+    async fn run(&self, command: Command, what: &str) -> Result<Output> {
+        succeeded(self.output(command, what).await?, what)
+    }
+
+    /// Runs `command` like `execute_gh`, on a blocking thread like `run`.
+    async fn output(&self, mut command: Command, what: &str) -> Result<Output> {
+        let (runner, what) = (self.runner.clone(), what.to_string());
+        tokio::task::spawn_blocking(move || execute_gh(runner.as_ref(), &mut command, &what))
+            .await?
+    }
+
+    /// Runs `command` like `run`, returning what it printed.
+    async fn text(&self, command: Command, what: &str) -> Result<String> {
+        String::from_utf8(self.run(command, what).await?.stdout)
+            .with_context(|| format!("{} printed invalid UTF-8", what))
+    }
+
+    /// Runs `command` like `run`, parsing what it printed as JSON.
+    async fn json<T: DeserializeOwned>(&self, command: Command, what: &str) -> Result<T> {
+        serde_json::from_str(&self.text(command, what).await?)
+            .with_context(|| format!("Failed to parse {} output", what))
+    }
+}
+
+/// Runs a `gh` command, described as `what` in errors, returning its output if it succeeded.
+/// Failures include what `gh` printed, e.g. that it isn't logged in.
+fn run_gh(runner: &dyn CommandRunner, command: &mut Command, what: &str) -> Result<Output> {
+    succeeded(execute_gh(runner, command, what)?, what)
+}
+
+/// Runs a `gh` command like `run_gh`, returning its output whether or not it succeeded.
+fn execute_gh(runner: &dyn CommandRunner, command: &mut Command, what: &str) -> Result<Output> {
+    match runner.output(command) {
+        Ok(output) => Ok(output),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => bail!(GH_MISSING),
+        Err(e) => Err(e).with_context(|| format!("Failed to execute {}", what)),
+    }
+}
+
+/// `output` of the `gh` command described as `what`, or why it failed.
+fn succeeded(output: Output, what: &str) -> Result<Output> {
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match stderr.trim() {
+            "" => bail!("{} failed", what),
+            stderr => bail!("{} failed: {}", what, stderr),
+        }
+    }
+    Ok(output)
+}
+
+/// Reshapes a search result like a `gh pr status` entry, as the API backend does.
+const SEARCH_ITEM_JQ: &str = "{number, title, url: .html_url, createdAt: .created_at, \
+    state: (.state | ascii_upcase), closedAt: .closed_at, id: .node_id, repository: (.repository_url | split(\"/repos/\")[1]), \
+    author: {login: .user.login}, labels: [.labels[] | {name}]}";
+
+/// Open issues or PRs matching a search query, as `{number, title, url, createdAt, author}`
+/// like `gh pr status` entries.
+async fn gh_search_issues(gh: &Gh, query: &str) -> Result<Vec<serde_json::Value>> {
+    gh.json(
+        gh.command(&[
+            "api",
+            "-X",
+            "GET",
+            "search/issues",
+            "-f",
+            &format!("q={}", query),
+            "-f",
+            "per_page=100",
+            "--jq",
+            &format!("[.items[] | {}]", SEARCH_ITEM_JQ),
+        ]),
+        "gh api search/issues",
+    )
+    .await
+}
+
+/// Like `gh_search_issues`, but follows every page of results rather than just the first.
+async fn gh_search_all(gh: &Gh, query: &str) -> Result<Vec<serde_json::Value>> {
+    let stdout = gh
+        .text(
+            gh.command(&[
+                "api",
+                "-X",
+                "GET",
+                "search/issues",
+                "--paginate",
+                "-f",
+                &format!("q={}", query),
+                "-f",
+                "per_page=100",
+                "--jq",
+                &format!(".items[] | {}", SEARCH_ITEM_JQ),
+            ]),
+            "gh api search/issues",
+        )
+        .await?;
+
+    // One item per line, across all the pages
+    serde_json::Deserializer::from_str(&stdout)
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .context("Failed to parse gh api search/issues output")
+}
+
+async fn gh_required_checks(gh: &Gh, repo: &str, branch: &str) -> Result<Vec<String>> {
+    let path = format!("repos/{}/branches/{}", repo, branch);
+    gh.json(
+        gh.command(&[
+            "api",
+            &path,
+            "--jq",
+            ".protection.required_status_checks.contexts // []",
+        ]),
+        &format!("gh api {}", path),
+    )
+    .await
+}
+
+async fn gh_required_approvals(gh: &Gh, repo: &str, branch: &str) -> Result<Option<u64>> {
+    let path = format!(
+        "repos/{}/branches/{}/protection/required_pull_request_reviews",
+        repo, branch
+    );
+    let what = format!("gh api {}", path);
+    // `--include` prints the response's status line, since `gh` fails the same way
+    // whatever the status
+    let output = gh
+        .output(gh.command(&["api", "--include", &path]), &what)
+        .await?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match included_response(&stdout) {
+        // Unprotected branches are a 404, and reading protection needs admin access
+        Some((403 | 404, _)) => Ok(None),
+        Some((_, body)) if output.status.success() => {
+            let reviews: serde_json::Value = serde_json::from_str(body)
+                .with_context(|| format!("Failed to parse {} output", what))?;
+            Ok(Some(
+                reviews
+                    .get("required_approving_review_count")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0),
+            ))
+        }
+        _ => {
+            succeeded(output, &what)?;
+            bail!("{} printed no HTTP status line", what)
+        }
+    }
+}
+
+/// The status code and body of a response printed by `gh api --include`.
+fn included_response(stdout: &str) -> Option<(u16, &str)> {
+    let (head, body) = stdout
+        .split_once("\r\n\r\n")
+        .or_else(|| stdout.split_once("\n\n"))?;
+    // e.g. "HTTP/2.0 404 Not Found"
+    let status = head.lines().next()?.split_whitespace().nth(1)?;
+    Some((status.parse().ok()?, body))
+}
+
+async fn gh_default_branch(gh: &Gh, repo: &str) -> Result<String> {
+    let path = format!("repos/{}", repo);
+    let stdout = gh
+        .text(
+            gh.command(&["api", &path, "--jq", ".default_branch"]),
+            &format!("gh api {}", path),
+        )
+        .await?;
+    Ok(stdout.trim().to_string())
+}
+
+async fn gh_default_branch_runs(gh: &Gh, repo: &str) -> Result<Vec<WorkflowRun>> {
+    let branch = gh_default_branch(gh, repo).await?;
+    let path = format!("repos/{}/actions/runs", repo);
+    let runs: WorkflowRuns = gh
+        .json(
+            gh.command(&[
+                "api",
+                "-X",
+                "GET",
+                &path,
+                "-f",
+                &format!("branch={}", branch),
+                "-f",
+                "per_page=10",
+            ]),
+            &format!("gh api {}", path),
+        )
+        .await?;
+    Ok(runs.workflow_runs)
+}
+
+/// `owner/name` of the repository `gh` finds from the current directory.
+async fn gh_current_repo(gh: &Gh) -> Result<String> {
+    let stdout = gh
+        .text(
+            gh.command(&[
+                "repo",
+                "view",
+                "--json",
+                "nameWithOwner",
+                "--jq",
+                ".nameWithOwner",
+            ]),
+            "gh repo view",
+        )
+        .await?;
+    Ok(stdout.trim().to_string())
+}
+
+async fn gh_login(gh: &Gh) -> Result<String> {
+    let stdout = gh
+        .text(
+            gh.command(&["api", "user", "--jq", ".login"]),
+            "gh api user",
+        )
+        .await?;
+    Ok(stdout.trim().to_string())
+}
+
+async fn gh_pr_comments(gh: &Gh, repo: &str, number: u64) -> Result<Vec<Comment>> {
+    let mut comments = Vec::new();
+    for path in [
+        format!("repos/{}/issues/{}/comments", repo, number),
+        format!("repos/{}/pulls/{}/comments", repo, number),
+    ] {
+        let page: Vec<Comment> = gh
+            .json(
+                gh.command(&["api", "-X", "GET", &path, "-f", "per_page=100"]),
+                &format!("gh api {}", path),
+            )
+            .await?;
+        comments.extend(page);
+    }
+    Ok(comments)
+}
+
+async fn gh_open_milestones(gh: &Gh, repo: &str) -> Result<Vec<Milestone>> {
+    let path = format!("repos/{}/milestones", repo);
+    gh.json(
+        gh.command(&[
+            "api",
+            "-X",
+            "GET",
+            &path,
+            "-f",
+            "state=open",
+            "-f",
+            "per_page=100",
+        ]),
+        &format!("gh api {}", path),
+    )
+    .await
+}
+
+async fn gh_waiting_deployments(
+    gh: &Gh,
+    repo: &str,
+) -> Result<Vec<(WorkflowRun, Vec<PendingDeployment>)>> {
+    let path = format!("repos/{}/actions/runs", repo);
+    let runs: WorkflowRuns = gh
+        .json(
+            gh.command(&[
+                "api",
+                "-X",
+                "GET",
+                &path,
+                "-f",
+                "status=waiting",
+                "-f",
+                "per_page=100",
+            ]),
+            &format!("gh api {}", path),
+        )
+        .await?;
+
+    let mut waiting = Vec::new();
+    for run in runs.workflow_runs {
+        let path = format!("repos/{}/actions/runs/{}/pending_deployments", repo, run.id);
+        let deployments = gh
+            .json(gh.command(&["api", &path]), &format!("gh api {}", path))
+            .await?;
+        waiting.push((run, deployments));
+    }
+    Ok(waiting)
+}
+
+async fn gh_merged_pull_from(gh: &Gh, repo: &str, branch: &str) -> Result<Option<MergedPull>> {
+    let pulls: Vec<MergedPull> = gh
+        .json(
+            gh.command(&[
+                "pr",
+                "list",
+                "--repo",
+                repo,
+                "--head",
+                branch,
+                "--state",
+                "merged",
+                "--limit",
+                "1",
+                "--json",
+                "number,baseRefName",
+            ]),
+            &format!("gh pr list --head {}", branch),
+        )
+        .await?;
+    Ok(pulls.into_iter().next())
+}
+
+/// Review threads of one PR; the first 100 covers all but the most contentious PRs.
+const REVIEW_THREADS_QUERY: &str = "query($owner: String!, $name: String!, $number: Int!) {
+  repository(owner: $owner, name: $name) {
+    pullRequest(number: $number) {
+      reviewThreads(first: 100) { nodes { isResolved } }
+    }
+  }
+}";
+
+/// The last comment of each review thread on one PR, and who we are.
+const THREAD_REPLIES_QUERY: &str = "query($owner: String!, $name: String!, $number: Int!) {
+  viewer { login }
+  repository(owner: $owner, name: $name) {
+    pullRequest(number: $number) {
+      reviewThreads(first: 100) {
+        nodes { isResolved comments(last: 1) { nodes { author { login } createdAt } } }
+      }
+    }
+  }
+}";
+
+/// The files one PR changes, and the CODEOWNERS file on its base branch wherever it is.
+/// Files past the first 100 go unchecked.
+const CODE_OWNERS_QUERY: &str = "query($owner: String!, $name: String!, $number: Int!) {
+  repository(owner: $owner, name: $name) {
+    pullRequest(number: $number) {
+      files(first: 100) { nodes { path changeType } }
+      baseRef {
+        target {
+          ... on Commit {
+            github: file(path: \".github/CODEOWNERS\") { object { ... on Blob { text } } }
+            root: file(path: \"CODEOWNERS\") { object { ... on Blob { text } } }
+            docs: file(path: \"docs/CODEOWNERS\") { object { ... on Blob { text } } }
+          }
+        }
+      }
+    }
+  }
+}";
+
+/// Who's been asked to review one PR, and everyone's latest review of it.
+const REVIEW_REQUESTS_QUERY: &str = "query($owner: String!, $name: String!, $number: Int!) {
+  viewer { login }
+  repository(owner: $owner, name: $name) {
+    pullRequest(number: $number) {
+      reviewRequests(first: 100) { nodes { requestedReviewer { ... on User { login } } } }
+      latestReviews(first: 100) { nodes { author { login } state } }
+    }
+  }
+}";
+
+/// Reviews of one PR that haven't been submitted; GitHub only returns our own.
+const PENDING_REVIEWS_QUERY: &str = "query($owner: String!, $name: String!, $number: Int!) {
+  viewer { login }
+  repository(owner: $owner, name: $name) {
+    pullRequest(number: $number) {
+      reviews(states: PENDING, first: 10) { nodes { author { login } } }
+    }
+  }
+}";
+
+/// Runs a GraphQL query about one PR through `gh api graphql`.
+async fn gh_pr_graphql(gh: &Gh, query: &str, repo: &str, number: u64) -> Result<serde_json::Value> {
+    let (owner, name) = repo
+        .split_once('/')
+        .with_context(|| format!("Expected owner/name, found '{}'", repo))?;
+    gh.json(
+        gh.command(&[
+            "api",
+            "graphql",
+            "-f",
+            &format!("query={}", query),
+            "-f",
+            &format!("owner={}", owner),
+            "-f",
+            &format!("name={}", name),
+            "-F",
+            &format!("number={}", number),
+        ]),
+        "gh api graphql",
+    )
+    .await
+}
+
+async fn gh_pr_status(gh: &Gh, repo: &str) -> Result<serde_json::Value> {
+    gh.json(gh.command(&[
+            "pr",
+            "status",
+            "--repo",
+            repo,
+            "--json",
+            "number,title,url,state,isDraft,labels,statusCheckRollup,reviewDecision,latestReviews,autoMergeRequest,mergeable,createdAt,updatedAt,author,baseRefName,headRefOid,id",
+        ]), "gh pr status").await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_server::MockServer;
+    use serde_json::json;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_github_checker() {
+        const EXPECTED_OUTPUT: &str = include_str!("github/check_output_1.txt");
+        const TEST_JSON: &str = include_str!("github/check_output_1.json");
+
+        let checker = GitHubChecker::new(&GitHubConfig::default());
+        let issues: Vec<String> = checker
+            .check_output("figma/figma", TEST_JSON)
+            .unwrap()
+            .iter()
+            .map(|i| i.to_string())
+            .collect();
+
+        // Based on check_output_1.txt:
+        // - PR #591209 (Created by you) - checks passing, so should NOT appear
+        // - PR #591746 (Requesting review) - should appear
+        // - PR #591547 (Requesting review) - should appear
+        // - PR #590962 (Requesting review) - should appear
+        assert_eq!(issues.len(), 3, "Expected 3 issues, got: {:#?}", issues);
+
+        // All issues should be from needsReview
+        assert!(
+            issues
+                .iter()
+                .any(|i| i.contains("#591746") && i.contains("awaiting your review")),
+            "Expected PR #591746 awaiting review. Got: {:#?}\n\nExpected output:\n{}",
+            issues,
+            EXPECTED_OUTPUT
+        );
+
+        assert!(
+            issues
+                .iter()
+                .any(|i| i.contains("#591547") && i.contains("awaiting your review")),
+            "Expected PR #591547 awaiting review. Got: {:#?}\n\nExpected output:\n{}",
+            issues,
+            EXPECTED_OUTPUT
+        );
+
+        assert!(
+            issues
+                .iter()
+                .any(|i| i.contains("#590962") && i.contains("awaiting your review")),
+            "Expected PR #590962 awaiting review. Got: {:#?}\n\nExpected output:\n{}",
+            issues,
+            EXPECTED_OUTPUT
+        );
+
+        // PR #591209 should NOT appear (checks passing)
+        assert!(
+            !issues.iter().any(|i| i.contains("#591209")),
+            "PR #591209 should not appear (checks passing). Got: {:#?}\n\nExpected output:\n{}",
+            issues,
+            EXPECTED_OUTPUT
+        );
+    }
+
+    const CREATED_SEARCH: &str =
+        "/search/issues?q=is%3Apr+is%3Aopen+author%3A%40me+repo%3Afigma%2Ffigma&per_page=100";
+    const REVIEW_SEARCH: &str = "/search/issues?q=is%3Apr+is%3Aopen+review-requested%3A%40me+repo%3Afigma%2Ffigma&per_page=100";
+
+    fn pull(number: u64, title: &str, draft: bool, sha: &str) -> String {
+        json!({
+            "number": number,
+            "title": title,
+            "html_url": format!("https://github.com/figma/figma/pull/{}", number),
+            "state": "open",
+            "draft": draft,
+            "labels": [{ "name": "web" }],
+            "head": { "sha": sha },
+            "base": { "ref": "main" },
+            "created_at": "2025-10-06T18:00:00Z",
+            "updated_at": "2025-10-06T19:00:00Z",
+        })
+        .to_string()
+    }
+
+    /// Talks to `server`, without reading or writing the on-disk response cache.
+    fn api_config(server: &MockServer) -> GitHubConfig {
+        GitHubConfig {
+            repos: vec!["figma/figma".to_string()],
+            token: Some("ghp_test".to_string()),
+            api_url: server.url.clone(),
+            cache_responses: false,
+            ..GitHubConfig::default()
+        }
+    }
+
+    fn api_checker(server: &MockServer) -> GitHubChecker {
+        GitHubChecker::new(&api_config(server))
+    }
+
+    #[tokio::test]
+    async fn test_api_backend_matches_gh_pr_status() {
+        let passing_runs = json!({
+            "total_count": 1,
+            "check_runs": [{ "name": "build", "status": "completed", "conclusion": "success" }],
+        })
+        .to_string();
+        let server = MockServer::new()
+            .json(
+                CREATED_SEARCH,
+                json!({ "items": [
+                    { "number": 101, "title": "Add retries", "html_url": "https://github.com/figma/figma/pull/101", "created_at": "2025-10-06T19:00:00Z", "node_id": "PR_101", "repository_url": "https://api.github.com/repos/figma/figma", "user": { "login": "octocat" } },
+                    { "number": 102, "title": "New sidebar", "html_url": "https://github.com/figma/figma/pull/102", "created_at": "2025-10-06T19:00:00Z", "node_id": "PR_102", "repository_url": "https://api.github.com/repos/figma/figma", "user": { "login": "octocat" } },
+                    { "number": 103, "title": "Bump deps", "html_url": "https://github.com/figma/figma/pull/103", "created_at": "2025-10-06T19:00:00Z", "node_id": "PR_103", "repository_url": "https://api.github.com/repos/figma/figma", "user": { "login": "octocat" } },
+                ] })
+                .to_string(),
+            )
+            .json(
+                REVIEW_SEARCH,
+                json!({ "items": [{ "number": 201, "title": "Fix flaky test", "html_url": "https://github.com/figma/figma/pull/201", "created_at": "2025-10-06T19:00:00Z", "node_id": "PR_201", "repository_url": "https://api.github.com/repos/figma/figma", "user": { "login": "octocat" } }] }).to_string(),
+            )
+            // Failing check run
+            .json(
+                "/repos/figma/figma/pulls/101",
+                pull(101, "Add retries", false, "aaa"),
+            )
+            .json("/repos/figma/figma/pulls/101/reviews", "[]")
+            .json(
+                "/repos/figma/figma/commits/aaa/check-runs",
+                json!({
+                    "total_count": 2,
+                    "check_runs": [
+                        { "name": "build", "status": "completed", "conclusion": "failure" },
+                        { "name": "lint", "status": "in_progress", "conclusion": null },
+                    ],
+                })
+                .to_string(),
+            )
+            .json(
+                "/repos/figma/figma/commits/aaa/status",
+                r#"{"statuses": []}"#,
+            )
+            // Draft with everything green, including a commit status
+            .json(
+                "/repos/figma/figma/pulls/102",
+                pull(102, "New sidebar", true, "bbb"),
+            )
+            .json("/repos/figma/figma/pulls/102/reviews", "[]")
+            .json(
+                "/repos/figma/figma/commits/bbb/check-runs",
+                passing_runs.clone(),
+            )
+            .json(
+                "/repos/figma/figma/commits/bbb/status",
+                json!({ "statuses": [{ "context": "buildkite", "state": "success" }] }).to_string(),
+            )
+            // Approved after changes were requested, without the ready-to-merge label
+            .json(
+                "/repos/figma/figma/pulls/103",
+                pull(103, "Bump deps", false, "ccc"),
+            )
+            .json(
+                "/repos/figma/figma/pulls/103/reviews",
+                json!([
+                    { "user": { "login": "alice" }, "state": "CHANGES_REQUESTED" },
+                    { "user": { "login": "bob" }, "state": "COMMENTED" },
+                    { "user": { "login": "alice" }, "state": "APPROVED" },
+                ])
+                .to_string(),
+            )
+            .json("/repos/figma/figma/commits/ccc/check-runs", passing_runs)
+            .json(
+                "/repos/figma/figma/commits/ccc/status",
+                r#"{"statuses": []}"#,
+            )
+            .start()
+            .await;
+
+        let issues: Vec<(String, IssueKind)> = api_checker(&server)
+            .check()
+            .await
+            .unwrap()
+            .issues
+            .into_iter()
+            .map(|issue| (issue.id, issue.kind))
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                (
+                    "github:figma/figma:pr:101".to_string(),
+                    IssueKind::FailingChecks
+                ),
+                (
+                    "github:figma/figma:pr:102".to_string(),
+                    IssueKind::DraftReady
+                ),
+                (
+                    "github:figma/figma:pr:103".to_string(),
+                    IssueKind::MissingReadyLabel
+                ),
+                (
+                    "github:figma/figma:pr:201".to_string(),
+                    IssueKind::AwaitingReview
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_api_backend_reports_error_responses() {
+        let server = MockServer::new()
+            .respond("/search/issues", 422, r#"{"message": "Validation Failed"}"#)
+            .start()
+            .await;
+
+        let err = api_checker(&server).check().await.unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "Failed to fetch PRs for figma/figma: \
+             GitHub API returned 422 Unprocessable Entity for /search/issues"
+        );
+    }
+
+    #[test]
+    fn test_same_pr_number_in_two_repos() {
+        let checker = GitHubChecker::new(&GitHubConfig {
+            repos: vec!["org/a".to_string(), "org/b".to_string()],
+            ..GitHubConfig::default()
+        });
+        let output = r#"{"createdBy": [], "needsReview": [{"number": 7, "title": "Fix"}]}"#;
+        let mut issues = checker.check_output("org/a", output).unwrap();
+        issues.extend(checker.check_output("org/b", output).unwrap());
+
+        let ids: Vec<&str> = issues.iter().map(|issue| issue.id.as_str()).collect();
+        assert_eq!(ids, ["github:org/a:pr:7", "github:org/b:pr:7"]);
+        assert_eq!(issues[1].title, "PR org/b#7 'Fix'");
+        assert_eq!(
+            issues[1].url.as_deref(),
+            Some("https://github.com/org/b/pull/7")
+        );
+    }
+
+    #[test]
+    fn test_changes_requested() {
+        let checker = GitHubChecker::new(&GitHubConfig::default());
+        let issues: Vec<(String, IssueKind)> = checker
+            .check_output("figma/figma", include_str!("github/changes_requested.json"))
+            .unwrap()
+            .into_iter()
+            .map(|issue| (issue.id, issue.kind))
+            .collect();
+
+        // #1001 is failing as well, #1002 only has changes requested, #1003 awaits review
+        assert_eq!(
+            issues,
+            vec![
+                (
+                    "github:figma/figma:pr:1001".to_string(),
+                    IssueKind::FailingChecks
+                ),
+                (
+                    "github:figma/figma:pr:1001:changes-requested".to_string(),
+                    IssueKind::ChangesRequested
+                ),
+                (
+                    "github:figma/figma:pr:1002:changes-requested".to_string(),
+                    IssueKind::ChangesRequested
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_approved_and_ready_to_merge() {
+        let data = serde_json::from_str(include_str!("github/approved.json")).unwrap();
+        // Shortly after #2002's auto-merge was enabled
+        let now = "2025-10-06T20:00:00Z".parse().unwrap();
+        let ready = |config: &GitHubConfig| -> Vec<String> {
+            GitHubChecker::new(config)
+                .pr_status_issues("figma/figma", &data, now)
+                .into_iter()
+                .map(|issue| issue.to_string())
+                .collect()
+        };
+
+        // #2002 and #2004 already have auto-merge enabled, so #2004 needs no label either,
+        // and #2003 is still running checks
+        assert_eq!(
+            ready(&GitHubConfig::default()),
+            ["PR #2001 'Ship new toolbar' is approved and ready to merge"]
+        );
+        assert_eq!(
+            ready(&GitHubConfig {
+                skip_auto_merge: false,
+                ..GitHubConfig::default()
+            }),
+            [
+                "PR #2001 'Ship new toolbar' is approved and ready to merge",
+                "PR #2002 'Tidy imports' is approved and ready to merge",
+                "PR #2004 'Rename settings' approved but missing ready-to-merge label",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_auto_merge_stuck() {
+        let data = serde_json::from_str(include_str!("github/auto_merge.json")).unwrap();
+        let now = "2025-10-06T20:00:00Z".parse().unwrap();
+        let issues: Vec<String> = GitHubChecker::new(&GitHubConfig::default())
+            .pr_status_issues("figma/figma", &data, now)
+            .into_iter()
+            .map(|issue| issue.to_string())
+            .collect();
+
+        // #3002 was only set to auto-merge 3 hours ago
+        assert_eq!(
+            issues,
+            [
+                "PR #3001 'Ship new toolbar' failing: e2e",
+                "PR #3001 'Ship new toolbar' has been set to auto-merge for 12h 0m without merging (failing: e2e)",
+                "PR #3003 'Bump toolchain' has been set to auto-merge for 1d 0h without merging (missing approval)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_track_auto_merge_without_enabled_at() {
+        let mut data = json!({
+            "createdBy": [
+                { "number": 1, "autoMergeRequest": { "mergeMethod": "SQUASH" } },
+                { "number": 2, "autoMergeRequest": null },
+            ],
+        });
+        let first_seen: DateTime<Utc> = "2025-10-06T08:00:00Z".parse().unwrap();
+        let mut since = HashMap::from([
+            ("github:figma/figma:pr:1".to_string(), first_seen),
+            // Auto-merge was turned off since the last check
+            ("github:figma/figma:pr:2".to_string(), first_seen),
+            ("github:figma/docs:pr:2".to_string(), first_seen),
+        ]);
+
+        assert!(track_auto_merge(
+            &mut since,
+            "figma/figma",
+            &mut data,
+            Utc::now()
+        ));
+        assert_eq!(auto_merge_since(&data["createdBy"][0]), Some(first_seen));
+        let mut ids: Vec<&String> = since.keys().collect();
+        ids.sort();
+        assert_eq!(ids, ["github:figma/docs:pr:2", "github:figma/figma:pr:1"]);
+        // Nothing new to record the second time round
+        assert!(!track_auto_merge(
+            &mut since,
+            "figma/figma",
+            &mut data,
+            Utc::now()
+        ));
+    }
+
+    #[test]
+    fn test_unreviewed_prs() {
+        let now: DateTime<Utc> = "2025-10-08T12:00:00Z".parse().unwrap();
+        let pr = |number: u64, title: &str, conclusion: &str, reviews: serde_json::Value| {
+            json!({
+                "number": number,
+                "title": title,
+                "latestReviews": reviews,
+                "statusCheckRollup": [{
+                    "name": "test",
+                    "status": "COMPLETED",
+                    "conclusion": conclusion,
+                    "startedAt": "2025-10-07T06:00:00Z",
+                }],
+            })
+        };
+        let mut data = json!({
+            "createdBy": [
+                pr(1, "Add cache", "SUCCESS", json!([])),
+                pr(2, "Fix typo", "SUCCESS", json!([{ "author": { "login": "octocat" }, "state": "COMMENTED" }])),
+                pr(3, "Flaky test", "FAILURE", json!([])),
+                // Pushed as long ago, but only just started being watched
+                pr(4, "Old branch", "SUCCESS", json!([])),
+            ],
+        });
+        let mut since = HashMap::from([
+            (
+                "github:figma/figma:pr:1".to_string(),
+                now - chrono::Duration::days(2),
+            ),
+            (
+                "github:figma/figma:pr:2".to_string(),
+                now - chrono::Duration::days(2),
+            ),
+        ]);
+        assert!(track_unreviewed(
+            &mut since,
+            "figma/figma",
+            &mut data,
+            now - chrono::Duration::hours(2)
+        ));
+        assert!(!since.contains_key("github:figma/figma:pr:2"));
+
+        let checker = GitHubChecker::new(&GitHubConfig {
+            unreviewed_prs: true,
+            ..GitHubConfig::default()
+        });
+        let issues: Vec<String> = checker
+            .pr_status_issues("figma/figma", &data, now)
+            .into_iter()
+            .filter(|issue| issue.kind == IssueKind::Unreviewed)
+            .map(|issue| issue.to_string())
+            .collect();
+        assert_eq!(
+            issues,
+            ["PR #1 'Add cache' has had no review for 1d 6h — consider pinging reviewers"]
+        );
+    }
+
+    #[test]
+    fn test_flaky_checks() {
+        let start: DateTime<Utc> = "2025-10-06T09:00:00Z".parse().unwrap();
+        let run = |sha: &str, e2e: &str, lint: &str| {
+            json!({
+                "createdBy": [{
+                    "number": 1,
+                    "title": "Add cache",
+                    "headRefOid": sha,
+                    "statusCheckRollup": [
+                        { "name": "e2e", "status": "COMPLETED", "conclusion": e2e },
+                        { "name": "lint-rust", "status": "COMPLETED", "conclusion": lint },
+                    ],
+                }],
+            })
+        };
+        let mut history = HashMap::new();
+        let checker = GitHubChecker::new(&GitHubConfig {
+            flaky_checks: vec!["lint-*".to_string()],
+            track_flaky_checks: true,
+            ..GitHubConfig::default()
+        });
+        let mut check = |mut data: serde_json::Value, now| -> Vec<String> {
+            checker.record_check_results(&mut history, "figma/figma", &data, now);
+            checker.mark_flaky_checks(&history, "figma/figma", &mut data, now);
+            checker
+                .pr_status_issues("figma/figma", &data, now)
+                .into_iter()
+                .map(|issue| format!("{:?} {}", issue.severity, issue))
+                .collect()
+        };
+
+        assert_eq!(
+            check(run("abc", "FAILURE", "SUCCESS"), start),
+            ["High PR #1 'Add cache' failing: e2e"]
+        );
+        // A rerun passes on the same commit, so the next failure looks flaky
+        assert_eq!(
+            check(
+                run("abc", "SUCCESS", "SUCCESS"),
+                start + chrono::Duration::hours(1)
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            check(
+                run("def", "FAILURE", "FAILURE"),
+                start + chrono::Duration::hours(2)
+            ),
+            ["Low PR #1 'Add cache' failing: e2e, lint-rust (likely flaky — consider rerun)"]
+        );
+        // Until the flip is a week old
+        assert_eq!(
+            check(
+                run("ghi", "FAILURE", "SUCCESS"),
+                start + chrono::Duration::days(8)
+            ),
+            ["High PR #1 'Add cache' failing: e2e"]
+        );
+    }
+
+    #[test]
+    fn test_merge_conflicts() {
+        let checker = GitHubChecker::new(&GitHubConfig::default());
+        let issues: Vec<String> = checker
+            .check_output("figma/figma", include_str!("github/merge_conflicts.json"))
+            .unwrap()
+            .into_iter()
+            .map(|issue| issue.id)
+            .collect();
+
+        // #3002 is UNKNOWN while GitHub computes it, and #3003 is MERGEABLE
+        assert_eq!(issues, ["github:figma/figma:pr:3001:conflicts"]);
+    }
+
+    #[test]
+    fn test_drafts_with_failing_checks() {
+        let kinds = |config: &GitHubConfig| -> Vec<(u64, IssueKind)> {
+            GitHubChecker::new(config)
+                .check_output("figma/figma", include_str!("github/drafts.json"))
+                .unwrap()
+                .into_iter()
+                .map(|issue| {
+                    (
+                        issue.id.rsplit(':').next().unwrap().parse().unwrap(),
+                        issue.kind,
+                    )
+                })
+                .collect()
+        };
+
+        // The failing draft is skipped, but a draft someone asked us to review isn't
+        assert_eq!(
+            kinds(&GitHubConfig::default()),
+            [(4002, IssueKind::AwaitingReview)]
+        );
+        assert_eq!(
+            kinds(&GitHubConfig {
+                include_drafts: true,
+                ..GitHubConfig::default()
+            }),
+            [
+                (4001, IssueKind::FailingChecks),
+                (4002, IssueKind::AwaitingReview)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_failing_checks_are_named() {
+        let checker = GitHubChecker::new(&GitHubConfig::default());
+        let issues = checker
+            .check_output("figma/figma", include_str!("github/failing_checks.json"))
+            .unwrap();
+        let summaries: Vec<(String, Option<&str>)> = issues
+            .iter()
+            .map(|issue| (issue.to_string(), issue.url.as_deref()))
+            .collect();
+
+        assert_eq!(
+            summaries,
+            [
+                (
+                    "PR #5001 'Speed up exports' failing: buildkite/ci".to_string(),
+                    Some("https://buildkite.com/figma/ci/builds/812")
+                ),
+                (
+                    "PR #5002 'Migrate to new router' failing: lint, unit-tests, e2e (+2 more)"
+                        .to_string(),
+                    Some("https://github.com/figma/figma/actions/runs/1/job/11")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_failing_conclusions() {
+        let failing = |config: &GitHubConfig| -> Vec<u64> {
+            GitHubChecker::new(config)
+                .check_output("figma/figma", include_str!("github/check_conclusions.json"))
+                .unwrap()
+                .into_iter()
+                .filter(|issue| issue.kind == IssueKind::FailingChecks)
+                .map(|issue| issue.id.rsplit(':').next().unwrap().parse().unwrap())
+                .collect()
+        };
+
+        // FAILURE, ERROR, TIMED_OUT and ACTION_REQUIRED runs, and failed or errored statuses
+        assert_eq!(
+            failing(&GitHubConfig::default()),
+            [6001, 6002, 6003, 6004, 6010, 6011]
+        );
+        // Cancelled runs are opt-in, in any case
+        let mut failing_conclusions = GitHubConfig::default().failing_conclusions;
+        failing_conclusions.push("cancelled".to_string());
+        assert_eq!(
+            failing(&GitHubConfig {
+                failing_conclusions,
+                ..GitHubConfig::default()
+            }),
+            [6001, 6002, 6003, 6004, 6005, 6010, 6011]
+        );
+    }
+
+    #[test]
+    fn test_checks_pending_too_long() {
+        let data = serde_json::from_str(include_str!("github/pending_checks.json")).unwrap();
+        let now = "2025-10-06T20:00:00Z".parse().unwrap();
+        let pending = |pending_minutes| -> Vec<String> {
+            GitHubChecker::new(&GitHubConfig {
+                pending_minutes,
+                ..GitHubConfig::default()
+            })
+            .pr_status_issues("figma/figma", &data, now)
+            .into_iter()
+            .map(|issue| issue.to_string())
+            .collect()
+        };
+
+        // #6001 has been running for 2h, #6002 has been queued since its 19:30 update and
+        // #6003's status was posted at 19:40
+        assert_eq!(
+            pending(45),
+            ["PR #6001 'Profile cold start' has checks pending for over 45m"]
+        );
+        assert_eq!(
+            pending(15),
+            [
+                "PR #6001 'Profile cold start' has checks pending for over 15m",
+                "PR #6002 'Add export presets' has checks pending for over 15m",
+                "PR #6003 'Fix scroll jank' has checks pending for over 15m",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prs_without_checks() {
+        let data = serde_json::from_str(include_str!("github/no_checks.json")).unwrap();
+        let now = "2025-10-06T20:00:00Z".parse().unwrap();
+        let issues = |no_checks| -> Vec<String> {
+            GitHubChecker::new(&GitHubConfig {
+                no_checks,
+                ..GitHubConfig::default()
+            })
+            .pr_status_issues("figma/figma", &data, now)
+            .into_iter()
+            .map(|issue| issue.to_string())
+            .collect()
+        };
+
+        assert_eq!(issues(false), ["PR #7007 'Failing' failing: build"]);
+        // A null, empty or missing rollup all mean no checks; drafts and PRs opened in the
+        // last hour are left alone
+        assert_eq!(
+            issues(true),
+            [
+                "PR #7001 'Null rollup' has no CI checks",
+                "PR #7002 'Empty rollup' has no CI checks",
+                "PR #7003 'No rollup' has no CI checks",
+                "PR #7007 'Failing' failing: build",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unresolved_review_threads() {
+        let response = serde_json::from_str(include_str!("github/review_threads.json")).unwrap();
+        assert_eq!(unresolved_review_threads(&response).unwrap(), 4);
+        assert!(unresolved_review_threads(&json!({ "data": null })).is_err());
+
+        let checker = GitHubChecker::new(&GitHubConfig::default());
+        let mut data: serde_json::Value =
+            serde_json::from_str(include_str!("github/approved.json")).unwrap();
+        // #2001 is green, #2003 is still running checks
+        data["createdBy"][0]["unresolvedReviewThreads"] = 4.into();
+        data["createdBy"][2]["unresolvedReviewThreads"] = 1.into();
+        let threads: Vec<String> = checker
+            .pr_status_issues("figma/figma", &data, Utc::now())
+            .into_iter()
+            .filter(|issue| issue.kind == IssueKind::UnresolvedThreads)
+            .map(|issue| issue.to_string())
+            .collect();
+        assert_eq!(
+            threads,
+            ["PR #2001 'Ship new toolbar' has 4 unresolved review threads"]
+        );
+    }
+
+    #[test]
+    fn test_threads_awaiting_replies() {
+        let response = serde_json::from_str(include_str!("github/thread_replies.json")).unwrap();
+        let awaiting_you = last_thread_comments(&response, false).unwrap();
+        assert_eq!(
+            awaiting_you,
+            ["2025-10-05T10:00:00Z", "2025-10-06T18:00:00Z"]
+        );
+        let awaiting_them = last_thread_comments(&response, true).unwrap();
+        assert_eq!(awaiting_them, ["2025-10-03T09:00:00Z"]);
+
+        let data = json!({
+            "createdBy": [{ "number": 101, "title": "Add cache", "threadsAwaitingYou": awaiting_you }],
+            "needsReview": [
+                { "number": 201, "title": "Fix flaky test", "threadsAwaitingThem": awaiting_them },
+                {
+                    "number": 202,
+                    "title": "Rename module",
+                    "threadsAwaitingThem": ["2025-10-02T09:00:00Z", "2025-10-04T09:00:00Z"],
+                },
+                { "number": 203, "title": "Bump version", "threadsAwaitingThem": ["2025-10-06T09:00:00Z"] },
+            ],
+        });
+        let now = "2025-10-06T20:00:00Z".parse().unwrap();
+        let replies: Vec<String> = GitHubChecker::new(&GitHubConfig {
+            thread_replies: true,
+            ..GitHubConfig::default()
+        })
+        .pr_status_issues("figma/figma", &data, now)
+        .into_iter()
+        .filter(|issue| {
+            matches!(
+                issue.kind,
+                IssueKind::AwaitingYourReply | IssueKind::AwaitingTheirReply
+            )
+        })
+        .map(|issue| format!("{} {}", issue.id, issue))
+        .collect();
+
+        // Bob's comment is only 2h old, and #203's comment less than two days
+        assert_eq!(
+            replies,
+            [
+                "github:figma/figma:pr:101:your-reply PR #101 'Add cache' has 1 review thread waiting on your reply (oldest 1d 10h)",
+                "github:figma/figma:pr:201:their-reply PR #201 'Fix flaky test' — your comment has gone unanswered for 3d 11h",
+                "github:figma/figma:pr:202:their-reply PR #202 'Rename module' — 2 of your comments have gone unanswered (oldest 4d 11h)",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_api_backend_counts_review_threads() {
+        let server = MockServer::new()
+            .json(
+                CREATED_SEARCH,
+                json!({ "items": [{ "number": 101, "title": "Add retries", "html_url": "https://github.com/figma/figma/pull/101", "created_at": "2025-10-06T19:00:00Z", "node_id": "PR_101", "repository_url": "https://api.github.com/repos/figma/figma", "user": { "login": "octocat" } }] })
+                    .to_string(),
+            )
+            .json(REVIEW_SEARCH, r#"{"items": []}"#)
+            .json("/repos/figma/figma/pulls/101", pull(101, "Add retries", false, "aaa"))
+            .json("/repos/figma/figma/pulls/101/reviews", "[]")
+            .json(
+                "/repos/figma/figma/commits/aaa/check-runs",
+                json!({
+                    "check_runs": [{ "name": "build", "status": "completed", "conclusion": "success" }],
+                })
+                .to_string(),
+            )
+            .json("/repos/figma/figma/commits/aaa/status", r#"{"statuses": []}"#)
+            .json("/graphql", include_str!("github/review_threads.json"))
+            .start()
+            .await;
+        let checker = GitHubChecker::new(&GitHubConfig {
+            review_threads: true,
+            ..api_config(&server)
+        });
+
+        let issues: Vec<String> = checker
+            .check()
+            .await
+            .unwrap()
+            .issues
+            .into_iter()
+            .map(|issue| issue.to_string())
+            .collect();
+        assert_eq!(
+            issues,
+            ["PR #101 'Add retries' has 4 unresolved review threads"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_review_requests_across_account() {
+        const ACCOUNT_SEARCH: &str = "/search/issues?q=is%3Aopen+is%3Apr+review-requested%3A%40me+archived%3Afalse&per_page=100";
+        let item = |repo: &str, number: u64| {
+            json!({
+                "number": number,
+                "title": format!("Change {}", number),
+                "html_url": format!("https://github.com/{}/pull/{}", repo, number),
+                "created_at": "2025-10-06T19:00:00Z",
+                "node_id": format!("PR_{}_{}", repo, number),
+                "repository_url": format!("https://api.github.com/repos/{}", repo),
+                "user": { "login": "octocat" },
+            })
+        };
+        // Already found by the per-repo search, then a full page of another repo's
+        let mut first_page = vec![item("figma/figma", 201)];
+        first_page.extend((1..=99).map(|number| item("figma/infra", number)));
+        let server = MockServer::new()
+            .json(CREATED_SEARCH, r#"{"items": []}"#)
+            .json(
+                REVIEW_SEARCH,
+                json!({ "items": [item("figma/figma", 201)] }).to_string(),
+            )
+            .json(
+                &format!("{}&page=1", ACCOUNT_SEARCH),
+                json!({ "total_count": 101, "items": first_page }).to_string(),
+            )
+            .json(
+                &format!("{}&page=2", ACCOUNT_SEARCH),
+                json!({ "total_count": 101, "items": [item("figma/infra", 100)] }).to_string(),
+            )
+            .start()
+            .await;
+        let checker = GitHubChecker::new(&GitHubConfig {
+            all_review_requests: true,
+            ..api_config(&server)
+        });
+
+        let ids: Vec<String> = checker
+            .check()
+            .await
+            .unwrap()
+            .issues
+            .into_iter()
+            .map(|issue| issue.id)
+            .collect();
+        assert_eq!(ids.len(), 101);
+        assert_eq!(ids[0], "github:figma/figma:pr:201");
+        assert_eq!(ids[1], "github:figma/infra:pr:1");
+        assert_eq!(ids[100], "github:figma/infra:pr:100");
         assert!(
-            !issues.iter().any(|i| i.contains("#591209")),
-            "PR #591209 should not appear (checks passing). Got: {:#?}\n\nExpected output:\n{}",
+            !server
+                .requests()
+                .iter()
+                .any(|request| request.ends_with("&page=3"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_optional_queries_skipped_when_rate_limited() {
+        let server = MockServer::new()
+            .respond_with_headers(
+                CREATED_SEARCH,
+                200,
+                &[("x-ratelimit-remaining", "50")],
+                json!({ "items": [{ "number": 101, "title": "Add retries", "html_url": "https://github.com/figma/figma/pull/101", "created_at": "2025-10-06T19:00:00Z", "node_id": "PR_101", "repository_url": "https://api.github.com/repos/figma/figma", "user": { "login": "octocat" } }] })
+                    .to_string(),
+            )
+            .json(REVIEW_SEARCH, r#"{"items": []}"#)
+            .json("/repos/figma/figma/pulls/101", pull(101, "Add retries", false, "aaa"))
+            .json("/repos/figma/figma/pulls/101/reviews", "[]")
+            .json(
+                "/repos/figma/figma/commits/aaa/check-runs",
+                json!({
+                    "check_runs": [{ "name": "build", "status": "completed", "conclusion": "success" }],
+                })
+                .to_string(),
+            )
+            .json("/repos/figma/figma/commits/aaa/status", r#"{"statuses": []}"#)
+            .json("/graphql", include_str!("github/review_threads.json"))
+            .start()
+            .await;
+        let checker = GitHubChecker::new(&GitHubConfig {
+            review_threads: true,
+            rate_limit_reserve: 100,
+            ..api_config(&server)
+        });
+
+        assert_eq!(checker.check().await.unwrap().issues, []);
+        assert!(!server.requests().iter().any(|path| path == "/graphql"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_required_checks() {
+        let server = MockServer::new()
+            .json(
+                "/repos/figma/figma/branches/main",
+                r#"{"name": "main", "protected": true, "protection": {"enabled": true, "required_status_checks": {"enforcement_level": "everyone", "contexts": ["unit-tests", "ci/build"]}}}"#,
+            )
+            .json(
+                "/repos/figma/figma/branches/scratch",
+                r#"{"name": "scratch", "protected": false, "protection": {"enabled": false, "required_status_checks": {"enforcement_level": "off", "contexts": []}}}"#,
+            )
+            .start()
+            .await;
+        let api = GitHubApi::new("ghp_test", &server.url);
+        let required = api.required_checks("figma/figma", "main").await.unwrap();
+        assert_eq!(required, ["unit-tests", "ci/build"]);
+        assert!(
+            api.required_checks("figma/figma", "scratch")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let mut data: serde_json::Value =
+            serde_json::from_str(include_str!("github/missing_required_check.json")).unwrap();
+        for pr in data["createdBy"].as_array_mut().unwrap() {
+            pr["requiredChecks"] = required.clone().into();
+        }
+        let checker = GitHubChecker::new(&GitHubConfig {
+            missing_required_checks: true,
+            ..GitHubConfig::default()
+        });
+        let issues: Vec<String> = checker
+            .pr_status_issues(
+                "figma/figma",
+                &data,
+                "2025-10-06T20:00:00Z".parse().unwrap(),
+            )
+            .iter()
+            .map(|issue| issue.to_string())
+            .collect();
+
+        // #4002's checks only started 10 minutes ago, and #4003 has them all
+        assert_eq!(
             issues,
-            EXPECTED_OUTPUT
+            ["PR #4001 'Add retries' is waiting on required check 'ci/build' that never started"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_approval_progress() {
+        let server = MockServer::new()
+            .json(
+                "/repos/figma/figma/branches/main/protection/required_pull_request_reviews",
+                r#"{"dismiss_stale_reviews": true, "require_code_owner_reviews": false, "required_approving_review_count": 2}"#,
+            )
+            .respond(
+                "/repos/figma/figma/branches/release/protection/required_pull_request_reviews",
+                404,
+                r#"{"message": "Branch not protected"}"#,
+            )
+            .respond(
+                "/repos/figma/figma/branches/locked/protection/required_pull_request_reviews",
+                403,
+                r#"{"message": "Must have admin rights to Repository."}"#,
+            )
+            .start()
+            .await;
+        let api = GitHubApi::new("ghp_test", &server.url);
+        let required = |branch: &'static str| api.required_approvals("figma/figma", branch);
+        assert_eq!(required("main").await.unwrap(), Some(2));
+        assert_eq!(required("release").await.unwrap(), None);
+        assert_eq!(required("locked").await.unwrap(), None);
+
+        let mut data: serde_json::Value =
+            serde_json::from_str(include_str!("github/approvals.json")).unwrap();
+        for pr in data["createdBy"].as_array_mut().unwrap() {
+            if pr["baseRefName"] == "main" {
+                pr["requiredApprovals"] = 2.into();
+            }
+        }
+        let checker = GitHubChecker::new(&GitHubConfig {
+            approval_progress: true,
+            ..GitHubConfig::default()
+        });
+        let issues: Vec<String> = checker
+            .pr_status_issues("figma/figma", &data, Utc::now())
+            .iter()
+            .filter(|issue| issue.kind == IssueKind::AwaitingApprovals)
+            .map(|issue| issue.to_string())
+            .collect();
+
+        // Approved, failing and draft PRs are left alone, and without branch protection
+        // there's only the count
+        assert_eq!(
+            issues,
+            [
+                "PR #8001 'Add retries' has 0 of 2 required approvals",
+                "PR #8002 'Tune retries' has 1 of 2 required approvals",
+                "PR #8004 'Fix typo' has 1 approval",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prs_behind_base() {
+        let checker = GitHubChecker::new(&GitHubConfig {
+            behind_base: true,
+            ..GitHubConfig::default()
+        });
+        let green = json!([{ "__typename": "CheckRun", "name": "build", "status": "COMPLETED", "conclusion": "SUCCESS" }]);
+        let pr = |number: u64, review_decision: &str, behind: u64| {
+            json!({
+                "number": number,
+                "title": format!("PR {}", number),
+                "reviewDecision": review_decision,
+                "labels": [{ "name": "ready-to-merge" }],
+                "autoMergeRequest": { "mergeMethod": "SQUASH" },
+                "statusCheckRollup": green,
+                "baseRefName": "main",
+                "commitsBehind": behind,
+            })
+        };
+        let data = json!({
+            "createdBy": [
+                pr(1, "REVIEW_REQUIRED", 120),
+                pr(2, "REVIEW_REQUIRED", 12),
+                pr(3, "APPROVED", 1),
+                pr(4, "APPROVED", 0),
+            ],
+        });
+
+        let issues: Vec<String> = checker
+            .pr_status_issues("figma/figma", &data, Utc::now())
+            .iter()
+            .map(|issue| issue.to_string())
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                "PR #1 'PR 1' is 120 commits behind main",
+                "PR #3 'PR 3' is 1 commit behind main",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_files_without_code_owners() {
+        // The .github CODEOWNERS file takes precedence, and deleted files don't count
+        let response: serde_json::Value =
+            serde_json::from_str(include_str!("github/code_owners.json")).unwrap();
+        assert_eq!(unowned_files(&response).unwrap(), Some(3));
+
+        // Without one anywhere, nothing is reported
+        let mut no_codeowners = response.clone();
+        no_codeowners["data"]["repository"]["pullRequest"]["baseRef"] = json!(null);
+        assert_eq!(unowned_files(&no_codeowners).unwrap(), None);
+
+        let data = json!({ "createdBy": [
+            { "number": 101, "title": "Add release script", "unownedFiles": 3 },
+            { "number": 102, "title": "Fix typo", "unownedFiles": 1 },
+            { "number": 103, "title": "Tweak styles", "unownedFiles": 0 },
+        ]});
+        let issues: Vec<String> = GitHubChecker::new(&GitHubConfig::default())
+            .pr_status_issues("figma/figma", &data, Utc::now())
+            .iter()
+            .filter(|issue| issue.kind == IssueKind::NoCodeOwner)
+            .map(|issue| issue.to_string())
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                "PR #101 'Add release script' touches 3 files with no code owner",
+                "PR #102 'Fix typo' touches 1 file with no code owner",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_api_backend_compares_with_base() {
+        let server = MockServer::new()
+            .json(
+                CREATED_SEARCH,
+                json!({ "items": [{ "number": 101, "title": "Add retries", "html_url": "https://github.com/figma/figma/pull/101", "created_at": "2025-10-06T19:00:00Z", "node_id": "PR_101", "repository_url": "https://api.github.com/repos/figma/figma", "user": { "login": "octocat" } }] })
+                    .to_string(),
+            )
+            .json(REVIEW_SEARCH, r#"{"items": []}"#)
+            .json("/repos/figma/figma/pulls/101", pull(101, "Add retries", false, "aaa"))
+            .json("/repos/figma/figma/pulls/101/reviews", "[]")
+            .json("/repos/figma/figma/commits/aaa/check-runs", r#"{"check_runs": []}"#)
+            .json("/repos/figma/figma/commits/aaa/status", r#"{"statuses": []}"#)
+            .json(
+                "/repos/figma/figma/compare/main...aaa",
+                r#"{"ahead_by": 3, "behind_by": 75}"#,
+            )
+            .start()
+            .await;
+        let checker = GitHubChecker::new(&GitHubConfig {
+            behind_base: true,
+            ..api_config(&server)
+        });
+
+        let issues: Vec<String> = checker
+            .check()
+            .await
+            .unwrap()
+            .issues
+            .into_iter()
+            .map(|issue| issue.to_string())
+            .collect();
+        assert_eq!(issues, ["PR #101 'Add retries' is 75 commits behind main"]);
+    }
+
+    #[tokio::test]
+    async fn test_graphql_mode_fetches_each_repo_in_one_query() {
+        let server = MockServer::new()
+            .json("/graphql", include_str!("github/graphql_pr_status.json"))
+            .start()
+            .await;
+        let checker = GitHubChecker::new(&GitHubConfig {
+            graphql: true,
+            review_threads: true,
+            ..api_config(&server)
+        });
+
+        let issues: Vec<(String, IssueKind)> = checker
+            .check()
+            .await
+            .unwrap()
+            .issues
+            .into_iter()
+            .map(|issue| (issue.id, issue.kind))
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                (
+                    "github:figma/figma:pr:3101".to_string(),
+                    IssueKind::FailingChecks
+                ),
+                (
+                    "github:figma/figma:pr:3201".to_string(),
+                    IssueKind::AwaitingReview
+                ),
+            ]
+        );
+        assert_eq!(server.requests(), ["/graphql"]);
+    }
+
+    #[tokio::test]
+    async fn test_api_backend_finds_prs_on_merged_branches() {
+        let search_item = |number: u64, title: &str| json!({ "number": number, "title": title, "html_url": format!("https://github.com/figma/figma/pull/{}", number), "created_at": "2025-10-06T19:00:00Z", "node_id": format!("PR_{}", number), "repository_url": "https://api.github.com/repos/figma/figma", "user": { "login": "octocat" } });
+        let stacked_pull = |number: u64, title: &str, sha: &str, base: &str| {
+            let mut pull: serde_json::Value =
+                serde_json::from_str(&pull(number, title, false, sha)).unwrap();
+            pull["base"]["ref"] = base.into();
+            pull.to_string()
+        };
+        let server = MockServer::new()
+            .json(
+                CREATED_SEARCH,
+                json!({ "items": [search_item(101, "Add retries"), search_item(102, "Tune retries")] }).to_string(),
+            )
+            .json(REVIEW_SEARCH, r#"{"items": []}"#)
+            .json("/repos/figma/figma", r#"{"default_branch": "main"}"#)
+            .json("/repos/figma/figma/pulls/101", stacked_pull(101, "Add retries", "aaa", "feature/parent"))
+            .json("/repos/figma/figma/pulls/102", stacked_pull(102, "Tune retries", "bbb", "feature/open"))
+            .json("/repos/figma/figma/pulls/101/reviews", "[]")
+            .json("/repos/figma/figma/pulls/102/reviews", "[]")
+            .json("/repos/figma/figma/commits/aaa/check-runs", r#"{"check_runs": []}"#)
+            .json("/repos/figma/figma/commits/bbb/check-runs", r#"{"check_runs": []}"#)
+            .json("/repos/figma/figma/commits/aaa/status", r#"{"statuses": []}"#)
+            .json("/repos/figma/figma/commits/bbb/status", r#"{"statuses": []}"#)
+            .json(
+                "/repos/figma/figma/pulls?head=figma%3Afeature%2Fparent&state=closed",
+                r#"[{ "number": 100, "merged_at": "2025-10-06T20:00:00Z", "base": { "ref": "main" } }]"#,
+            )
+            // The parent of #102 is still open
+            .json(
+                "/repos/figma/figma/pulls?head=figma%3Afeature%2Fopen&state=closed",
+                "[]",
+            )
+            .start()
+            .await;
+        let checker = GitHubChecker::new(&GitHubConfig {
+            stacked_prs: true,
+            ..api_config(&server)
+        });
+
+        let issues: Vec<String> = checker
+            .check()
+            .await
+            .unwrap()
+            .issues
+            .into_iter()
+            .map(|issue| issue.to_string())
+            .collect();
+        assert_eq!(
+            issues,
+            ["PR #101 'Add retries' is based on merged branch 'feature/parent' — rebase onto main"]
+        );
+    }
+
+    #[test]
+    fn test_team_review_requests() {
+        let checker = GitHubChecker::new(&GitHubConfig::default());
+        let data = json!({
+            "needsReview": [{ "number": 201, "title": "Fix flaky test" }],
+            "teamReviewRequests": [
+                { "number": 201, "title": "Fix flaky test", "team": "figma/platform" },
+                { "number": 301, "title": "Shard CI", "team": "figma/platform" },
+                { "number": 301, "title": "Shard CI", "team": "figma/infra" },
+            ],
+        });
+
+        let issues: Vec<(String, Severity)> = checker
+            .pr_status_issues("figma/figma", &data, Utc::now())
+            .into_iter()
+            .map(|issue| (issue.to_string(), issue.severity))
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                (
+                    "PR #201 'Fix flaky test' awaiting your review".to_string(),
+                    Severity::Medium
+                ),
+                (
+                    "PR #301 'Shard CI' awaiting @figma/platform review".to_string(),
+                    Severity::Low
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_review_request_age() {
+        let data = json!({
+            "needsReview": [
+                { "number": 201, "title": "Fix flaky test", "createdAt": "2025-10-06T19:40:00Z" },
+                { "number": 202, "title": "Shard CI", "createdAt": "2025-10-04T15:00:00Z" },
+            ],
+        });
+        let now = "2025-10-06T20:00:00Z".parse().unwrap();
+        let reviews = |stale_review_minutes| -> Vec<(String, Severity)> {
+            GitHubChecker::new(&GitHubConfig {
+                stale_review_minutes,
+                ..GitHubConfig::default()
+            })
+            .pr_status_issues("figma/figma", &data, now)
+            .into_iter()
+            .map(|issue| (issue.to_string(), issue.severity))
+            .collect()
+        };
+
+        assert_eq!(
+            reviews(None),
+            [
+                (
+                    "PR #201 'Fix flaky test' awaiting your review (requested 20m ago)".to_string(),
+                    Severity::Medium
+                ),
+                (
+                    "PR #202 'Shard CI' awaiting your review (requested 2d 5h ago)".to_string(),
+                    Severity::Medium
+                ),
+            ]
+        );
+        let severities: Vec<Severity> = reviews(Some(24 * 60))
+            .into_iter()
+            .map(|(_, severity)| severity)
+            .collect();
+        assert_eq!(severities, [Severity::Medium, Severity::High]);
+    }
+
+    #[test]
+    fn test_pending_reviews() {
+        let response: serde_json::Value =
+            serde_json::from_str(include_str!("github/pending_review.json")).unwrap();
+        assert!(has_pending_review(&response).unwrap());
+        assert!(
+            !has_pending_review(&json!({
+                "data": {
+                    "viewer": { "login": "me" },
+                    "repository": { "pullRequest": { "reviews": { "nodes": [] } } },
+                },
+            }))
+            .unwrap()
+        );
+        assert!(has_pending_review(&json!({ "data": null })).is_err());
+
+        let data = json!({
+            "needsReview": [
+                { "number": 12, "title": "Add retries", "pendingReview": true },
+                { "number": 13, "title": "New sidebar" },
+            ],
+        });
+        let issues: Vec<String> = GitHubChecker::new(&GitHubConfig::default())
+            .pr_status_issues("figma/figma", &data, Utc::now())
+            .into_iter()
+            .map(|issue| issue.to_string())
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                "PR #12 'Add retries' awaiting your review",
+                "PR #12 'Add retries' — you have an unsubmitted pending review",
+                "PR #13 'New sidebar' awaiting your review",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_re_requested_reviews() {
+        // Our review was dismissed after new commits and we were asked again
+        let mut response: serde_json::Value =
+            serde_json::from_str(include_str!("github/re_requested.json")).unwrap();
+        assert_eq!(previous_review(&response).unwrap(), Some("DISMISSED"));
+        response["data"]["repository"]["pullRequest"]["reviewRequests"]["nodes"] = json!([]);
+        assert_eq!(previous_review(&response).unwrap(), None);
+        assert!(previous_review(&json!({ "data": null })).is_err());
+
+        let now: DateTime<Utc> = "2025-10-06T20:00:00Z".parse().unwrap();
+        let data = json!({
+            "needsReview": [
+                { "number": 12, "title": "Add retries", "createdAt": "2025-10-06T19:00:00Z", "previousReview": "DISMISSED" },
+                { "number": 13, "title": "New sidebar", "createdAt": "2025-10-06T19:00:00Z" },
+            ],
+        });
+        let issues: Vec<(Severity, String)> = GitHubChecker::new(&GitHubConfig::default())
+            .pr_status_issues("figma/figma", &data, now)
+            .into_iter()
+            .map(|issue| (issue.severity, issue.to_string()))
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                (
+                    Severity::High,
+                    "PR #12 'Add retries' re-requested your review".to_string()
+                ),
+                (
+                    Severity::Medium,
+                    "PR #13 'New sidebar' awaiting your review (requested 1h 0m ago)".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_review_requests_from_ignored_authors() {
+        let data = json!({
+            "needsReview": [
+                { "number": 201, "title": "Fix flaky test", "author": { "login": "octocat" } },
+                { "number": 202, "title": "Bump serde", "author": { "login": "dependabot[bot]" } },
+                { "number": 203, "title": "Bump tokio", "author": { "login": "dependabot[bot]" } },
+                { "number": 204, "title": "Lock file", "author": { "login": "renovate[bot]" } },
+            ],
+        });
+        let reviews = |config: GitHubConfig| -> Vec<String> {
+            GitHubChecker::new(&config)
+                .pr_status_issues("figma/figma", &data, Utc::now())
+                .into_iter()
+                .map(|issue| issue.to_string())
+                .collect()
+        };
+
+        assert_eq!(
+            reviews(GitHubConfig::default()),
+            ["PR #201 'Fix flaky test' awaiting your review"]
+        );
+        assert_eq!(
+            reviews(GitHubConfig {
+                collapse_ignored_authors: true,
+                ..GitHubConfig::default()
+            }),
+            [
+                "PR #201 'Fix flaky test' awaiting your review",
+                "2 dependabot PRs awaiting review",
+                "1 renovate PR awaiting review",
+            ]
+        );
+        assert_eq!(
+            reviews(GitHubConfig {
+                ignored_authors: vec!["*[bot]".to_string(), "octo*".to_string()],
+                ..GitHubConfig::default()
+            }),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_label_filters() {
+        let data = json!({
+            "createdBy": [
+                {
+                    "number": 101,
+                    "title": "Add cache",
+                    "labels": [{ "name": "Team-Web" }],
+                    "statusCheckRollup": [{ "name": "test", "status": "COMPLETED", "conclusion": "FAILURE" }],
+                },
+            ],
+            "needsReview": [
+                { "number": 201, "title": "Fix flaky test", "labels": [{ "name": "team-web" }] },
+                { "number": 202, "title": "Rename module", "labels": [{ "name": "FYI" }, { "name": "team-web" }] },
+                { "number": 203, "title": "Bump version" },
+            ],
+        });
+        let ids = |config: GitHubConfig| -> Vec<String> {
+            GitHubChecker::new(&config)
+                .pr_status_issues("figma/figma", &data, Utc::now())
+                .into_iter()
+                .map(|issue| issue.id)
+                .collect()
+        };
+
+        assert_eq!(
+            ids(GitHubConfig {
+                ignore_labels: vec!["fyi".to_string()],
+                ..GitHubConfig::default()
+            }),
+            [
+                "github:figma/figma:pr:101",
+                "github:figma/figma:pr:201",
+                "github:figma/figma:pr:203",
+            ]
+        );
+        assert_eq!(
+            ids(GitHubConfig {
+                ignore_labels: vec!["fyi".to_string()],
+                only_labels: vec!["team-web".to_string()],
+                ..GitHubConfig::default()
+            }),
+            ["github:figma/figma:pr:101", "github:figma/figma:pr:201"]
+        );
+    }
+
+    #[test]
+    fn test_muted_prs() {
+        let mut data = json!({
+            "createdBy": [{ "number": 101, "title": "Add cache", "url": "https://github.com/figma/figma/pull/101" }],
+            "needsReview": [
+                { "number": 201, "title": "Fix flaky test" },
+                { "number": 202, "title": "Rewrite everything", "url": "https://github.com/figma/figma/pull/202" },
+            ],
+            "teamReviewRequests": [{ "number": 202, "title": "Rewrite everything", "team": "figma/web" }],
+        });
+        let muted: HashSet<String> = ["figma/figma#202", "figma/figma#999", "other/repo#101"]
+            .map(String::from)
+            .into();
+
+        let taken: HashMap<String, MutedPr> = take_muted("Figma/Figma", &mut data, &muted)
+            .into_iter()
+            .collect();
+        assert_eq!(
+            taken,
+            HashMap::from([(
+                "figma/figma#202".to_string(),
+                MutedPr {
+                    title: "Rewrite everything".to_string(),
+                    url: Some("https://github.com/figma/figma/pull/202".to_string()),
+                }
+            )])
+        );
+        let ids: Vec<String> = GitHubChecker::new(&GitHubConfig::default())
+            .pr_status_issues("figma/figma", &data, Utc::now())
+            .into_iter()
+            .map(|issue| issue.id)
+            .collect();
+        assert_eq!(ids, ["github:figma/figma:pr:201"]);
+
+        assert!(is_pr_ref("figma/figma#202"));
+        assert!(!is_pr_ref("figma#202"));
+        assert!(!is_pr_ref("figma/figma/extra#202"));
+        assert!(!is_pr_ref("figma/figma#latest"));
+    }
+
+    #[test]
+    fn test_prs_closed_without_merging() {
+        let checker = GitHubChecker::new(&GitHubConfig::default());
+        let created = |numbers: &[u64]| json!({ "createdBy": numbers.iter().map(|n| json!({ "number": n })).collect::<Vec<_>>() });
+        let closed = |number: u64, state: &str| json!({ "number": number, "title": format!("PR {}", number), "state": state });
+        // PR 9 was closed before we ever saw it open, and 4 isn't closed after all
+        let recently_closed = [closed(3, "CLOSED"), closed(9, "CLOSED"), closed(4, "OPEN")];
+
+        // First run: nothing was open before
+        let first = open_pr_refs("Figma/figma", &created(&[1, 2, 3, 4]));
+        assert!(no_longer_open(&HashSet::new(), "figma/figma", &first).is_empty());
+
+        // Second run: 2 merged, 3 was closed, and 4 dropped out of the search
+        let second = open_pr_refs("figma/figma", &created(&[1]));
+        let gone = no_longer_open(&first, "figma/figma", &second);
+        assert_eq!(gone, [2, 3, 4]);
+        assert!(no_longer_open(&first, "figma/other", &second).is_empty());
+        let issues = checker.closed_unmerged_issues("figma/figma", &gone, &recently_closed);
+        assert_eq!(
+            issues
+                .iter()
+                .map(|issue| (issue.id.as_str(), issue.to_string()))
+                .collect::<Vec<_>>(),
+            [(
+                "github:figma/figma:pr:3:closed",
+                "PR #3 'PR 3' was closed without merging".to_string()
+            )]
+        );
+
+        // Third run: it's only reported once
+        let third = open_pr_refs("figma/figma", &created(&[1]));
+        assert!(no_longer_open(&second, "figma/figma", &third).is_empty());
+    }
+
+    #[test]
+    fn test_prs_about_to_be_auto_closed() {
+        let now: DateTime<Utc> = "2025-10-31T12:00:00Z".parse().unwrap();
+        let pr = |number: u64, title: &str, updated_at: &str| json!({ "number": number, "title": title, "updatedAt": updated_at });
+        let data = json!({
+            "createdBy": [
+                pr(1, "Add cache", "2025-10-05T09:00:00Z"),
+                pr(2, "Fix typo", "2025-10-06T13:00:00Z"),
+                pr(3, "Bump deps", "2025-10-30T12:00:00Z"),
+            ],
+        });
+        let issues = |auto_close_days| -> Vec<String> {
+            GitHubChecker::new(&GitHubConfig {
+                auto_close_days,
+                auto_close_warning_days: 5,
+                ..GitHubConfig::default()
+            })
+            .pr_status_issues("figma/figma", &data, now)
+            .into_iter()
+            .map(|issue| issue.to_string())
+            .collect()
+        };
+
+        assert!(issues(None).is_empty());
+        // #2 is a few hours short of 25 days
+        assert_eq!(
+            issues(Some(30)),
+            ["PR #1 'Add cache' inactive for 26 days — will be auto-closed at 30"]
+        );
+    }
+
+    #[test]
+    fn test_assigned_issues() {
+        assert_eq!(
+            assigned_issues_query("org/a", &[]),
+            "is:issue is:open assignee:@me repo:org/a"
+        );
+        assert_eq!(
+            assigned_issues_query("org/a", &["bug".to_string(), "on call".to_string()]),
+            "is:issue is:open assignee:@me repo:org/a label:\"bug\",\"on call\""
+        );
+
+        let checker = GitHubChecker::new(&GitHubConfig {
+            repos: vec!["figma/figma".to_string()],
+            ..GitHubConfig::default()
+        });
+        let issue = checker.assigned_issue(
+            "figma/figma",
+            &json!({ "number": 88, "title": "Crash on paste" }),
+        );
+        assert_eq!(issue.id, "github:figma/figma:issue:88");
+        assert_eq!(
+            issue.to_string(),
+            "Issue #88 'Crash on paste' assigned to you"
+        );
+        assert_eq!(
+            issue.url.as_deref(),
+            Some("https://github.com/figma/figma/issues/88")
+        );
+
+        // Outside the configured repos, the repo is part of the reference
+        let issue = checker.assigned_issue("org/tools", &json!({ "number": 3, "title": "Docs" }));
+        assert_eq!(
+            issue.to_string(),
+            "Issue org/tools#3 'Docs' assigned to you"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mention_notifications() {
+        let server = MockServer::new()
+            .respond_with_headers(
+                "/notifications",
+                200,
+                &[
+                    ("last-modified", "Mon, 06 Oct 2025 19:00:00 GMT"),
+                    ("x-poll-interval", "60"),
+                ],
+                include_str!("github/notifications.json"),
+            )
+            .start()
+            .await;
+        let api = GitHubApi::new("ghp_test", &server.url);
+        let cache = Mutex::new(NotificationCache::default());
+
+        let issues = notification_issues(&api, &cache, "https://github.com")
+            .await
+            .unwrap();
+        let summaries: Vec<(&str, String, Option<&str>)> = issues
+            .iter()
+            .map(|issue| (issue.id.as_str(), issue.to_string(), issue.url.as_deref()))
+            .collect();
+        // The read mention and the CI activity notification aren't reported
+        assert_eq!(
+            summaries,
+            [
+                (
+                    "github:notification:1001",
+                    "figma/figma#42 'Add retries' mentioned you".to_string(),
+                    Some("https://github.com/figma/figma/pull/42#issuecomment-9001")
+                ),
+                (
+                    "github:notification:1002",
+                    "figma/docs#7 'Typo in intro' requested your review".to_string(),
+                    Some("https://github.com/figma/docs/pull/7#discussion_r9002")
+                ),
+                (
+                    "github:notification:1003",
+                    "figma/figma#88 'Crash on paste' mentioned your team".to_string(),
+                    Some("https://github.com/figma/figma/issues/88")
+                ),
+            ]
+        );
+
+        // Polling again within the interval reuses the previous results
+        assert_eq!(
+            notification_issues(&api, &cache, "https://github.com")
+                .await
+                .unwrap(),
+            issues
+        );
+        assert_eq!(server.requests(), ["/notifications?participating=true"]);
+    }
+
+    #[tokio::test]
+    async fn test_default_branch_workflows() {
+        let server = MockServer::new()
+            .json("/repos/figma/figma", r#"{"default_branch": "main"}"#)
+            .json(
+                "/repos/figma/figma/actions/runs?branch=main&per_page=10",
+                include_str!("github/workflow_runs.json"),
+            )
+            .start()
+            .await;
+        let checker = GitHubChecker::new(&GitHubConfig {
+            repos: Vec::new(),
+            default_branch_workflows: vec!["figma/figma".to_string()],
+            ..api_config(&server)
+        });
+
+        // ci has since passed, and deploy's newer run hasn't finished
+        let issues: Vec<(String, String)> = checker
+            .check()
+            .await
+            .unwrap()
+            .issues
+            .into_iter()
+            .map(|issue| (issue.id.clone(), issue.to_string()))
+            .collect();
+        assert_eq!(
+            issues,
+            [(
+                "github:figma/figma:workflow:11:bbb222".to_string(),
+                "Workflow 'deploy' failing on main in figma/figma".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_milestone_due_dates() {
+        let now: DateTime<Utc> = "2025-10-06T23:30:00Z".parse().unwrap();
+        let due = |due_on: &str| days_until_due(due_on.parse().unwrap(), now);
+        // Counted by date, so later the same day is still today and early tomorrow is 1 day
+        assert_eq!(due("2025-10-06T00:00:00Z"), 0);
+        assert_eq!(due("2025-10-07T00:30:00Z"), 1);
+        assert_eq!(due("2025-10-05T23:59:00Z"), -1);
+        assert_eq!(due("2025-11-06T07:00:00Z"), 31);
+
+        assert_eq!(due_phrase(0), "due today");
+        assert_eq!(due_phrase(1), "due tomorrow");
+        assert_eq!(due_phrase(2), "due in 2 days");
+        assert_eq!(due_phrase(-1), "1 day overdue");
+        assert_eq!(due_phrase(-3), "3 days overdue");
+    }
+
+    #[test]
+    fn test_milestone_issues() {
+        let milestones: Vec<Milestone> =
+            serde_json::from_str(include_str!("github/milestones.json")).unwrap();
+        let now: DateTime<Utc> = "2025-10-06T12:00:00Z".parse().unwrap();
+        let issues = |milestones_config: MilestonesConfig| -> Vec<(Severity, String)> {
+            GitHubChecker::new(&GitHubConfig {
+                milestones: milestones_config,
+                ..GitHubConfig::default()
+            })
+            .milestone_issues("figma/figma", &milestones, Some("me"), now)
+            .into_iter()
+            .map(|issue| (issue.severity, issue.to_string()))
+            .collect()
+        };
+
+        // Ours, within three days or overdue
+        assert_eq!(
+            issues(MilestonesConfig::default()),
+            [
+                (
+                    Severity::Medium,
+                    "Milestone 'v2.3' due in 2 days with 7 open issues".to_string()
+                ),
+                (
+                    Severity::High,
+                    "Milestone 'v2.2' 3 days overdue with 1 open issue".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            issues(MilestonesConfig {
+                titles: vec!["Q4 cleanup".to_string(), "Backlog".to_string()],
+                ..MilestonesConfig::default()
+            }),
+            [(
+                Severity::Medium,
+                "Milestone 'Q4 cleanup' due today with 3 open issues".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_follow_ups() {
+        let patterns = FollowUpsConfig::default().patterns;
+        assert!(mentions_any("todo: rename", &patterns));
+        assert!(mentions_any("I'll Follow-Up tomorrow", &patterns));
+        assert!(!mentions_any("see the todos", &patterns));
+        assert!(!mentions_any(
+            "mastodon",
+            &["TODO".to_string(), String::new()]
+        ));
+
+        let comments: Vec<Comment> =
+            serde_json::from_str(include_str!("github/pr_comments.json")).unwrap();
+        let pr = json!({ "number": 812, "title": "Roll out new renderer" });
+        let issues = GitHubChecker::new(&GitHubConfig::default()).follow_up_issues(
+            "figma/figma",
+            &pr,
+            &comments,
+            "me",
+        );
+        // Only our own comments that mention a follow-up
+        assert_eq!(
+            issues
+                .iter()
+                .map(|issue| (issue.id.as_str(), issue.to_string()))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    "github:figma/figma:follow-up:9001",
+                    "Follow-up noted on merged PR #812 'Roll out new renderer'".to_string()
+                ),
+                (
+                    "github:figma/figma:follow-up:9002",
+                    "Follow-up noted on merged PR #812 'Roll out new renderer'".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            issues[1].url.as_deref(),
+            Some("https://github.com/figma/figma/pull/812#discussion_r9002")
+        );
+        assert!(
+            issues
+                .iter()
+                .all(|issue| issue.kind.acknowledged_when_seen())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deployments_waiting_for_approval() {
+        let run = |id: u64| {
+            json!({
+                "id": id,
+                "run_number": id - 1000,
+                "name": "release",
+                "workflow_id": 12,
+                "head_branch": "main",
+                "head_sha": "ddd444",
+                "status": "waiting",
+                "conclusion": null,
+                "html_url": format!("https://github.com/figma/figma/actions/runs/{}", id),
+                "created_at": "2025-10-06T17:00:00Z",
+            })
+        };
+        let server = MockServer::new()
+            .json(
+                "/repos/figma/figma/actions/runs?status=waiting&per_page=100",
+                json!({ "total_count": 2, "workflow_runs": [run(1123), run(1124)] }).to_string(),
+            )
+            .json(
+                "/repos/figma/figma/actions/runs/1123/pending_deployments",
+                include_str!("github/pending_deployments.json"),
+            )
+            // Waiting on someone else's approval
+            .json(
+                "/repos/figma/figma/actions/runs/1124/pending_deployments",
+                r#"[{ "environment": { "name": "staging" }, "current_user_can_approve": false }]"#,
+            )
+            .start()
+            .await;
+        let waiting = GitHubApi::new("ghp_test", &server.url)
+            .waiting_deployments("figma/figma")
+            .await
+            .unwrap();
+
+        let now: DateTime<Utc> = "2025-10-06T20:00:00Z".parse().unwrap();
+        let issues: Vec<(String, String, Option<String>)> =
+            GitHubChecker::new(&GitHubConfig::default())
+                .deployment_issues("figma/figma", &waiting, now)
+                .into_iter()
+                .map(|issue| (issue.id.clone(), issue.to_string(), issue.url))
+                .collect();
+        assert_eq!(
+            issues,
+            [(
+                "github:figma/figma:deployment:1123".to_string(),
+                "Deployment to production waiting for your approval (run #123, 3h 0m)".to_string(),
+                Some("https://github.com/figma/figma/actions/runs/1123".to_string())
+            )]
+        );
+    }
+
+    #[test]
+    fn test_dependabot_alerts() {
+        let checker = GitHubChecker::new(&GitHubConfig::default());
+        let alerts: Vec<DependabotAlert> =
+            serde_json::from_str(include_str!("github/dependabot_alerts.json")).unwrap();
+        let issues: Vec<(Severity, String)> = checker
+            .dependabot_issues("figma/figma", Some(&alerts))
+            .into_iter()
+            .map(|issue| (issue.severity, issue.to_string()))
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                (
+                    Severity::High,
+                    "Dependabot alert #12 'Prototype pollution in lodash' is critical severity (lodash)".to_string()
+                ),
+                (
+                    Severity::High,
+                    "Dependabot alert #16 'SSRF in axios' is high severity (axios)".to_string()
+                ),
+                (
+                    Severity::Low,
+                    "3 medium severity alerts in figma/figma".to_string()
+                ),
+                (
+                    Severity::Low,
+                    "1 low severity alert in figma/figma".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dependabot_alerts_without_permission() {
+        let server = MockServer::new()
+            .respond(
+                "/repos/figma/figma/dependabot/alerts",
+                403,
+                r#"{"message": "Resource not accessible by personal access token"}"#,
+            )
+            .start()
+            .await;
+        let api = GitHubApi::new("ghp_test", &server.url);
+        let alerts = api.dependabot_alerts("figma/figma").await.unwrap();
+        assert!(alerts.is_none());
+
+        let issues = GitHubChecker::new(&GitHubConfig::default())
+            .dependabot_issues("figma/figma", alerts.as_deref());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].id, "github:figma/figma:dependabot");
+        assert_eq!(
+            server.requests(),
+            ["/repos/figma/figma/dependabot/alerts?state=open&per_page=100"]
+        );
+    }
+
+    #[test]
+    fn test_pr_links_use_reported_url() {
+        let checker = GitHubChecker::new(&GitHubConfig::default());
+        let output = r#"{"needsReview": [
+            {"number": 7, "title": "Fix", "url": "https://github.example.com/org/a/pull/7"}
+        ]}"#;
+        let issues = checker.check_output("org/a", output).unwrap();
+        assert_eq!(
+            issues[0].url.as_deref(),
+            Some("https://github.example.com/org/a/pull/7")
+        );
+    }
+
+    #[test]
+    fn test_enterprise_host_links() {
+        let config = GitHubConfig {
+            host: "github.mycorp.com".to_string(),
+            ..GitHubConfig::default()
+        };
+        assert_eq!(config.api_base_url(), "https://github.mycorp.com/api/v3");
+
+        let checker = GitHubChecker::new(&config);
+        let issue = checker.pr_issue(
+            "figma/figma",
+            &json!({ "number": 7, "title": "Fix login" }),
+            IssueKind::AwaitingReview,
+            Severity::Medium,
+            "awaiting your review",
+        );
+        assert_eq!(
+            issue.url.as_deref(),
+            Some("https://github.mycorp.com/figma/figma/pull/7")
+        );
+
+        let notification: Notification = serde_json::from_value(json!({
+            "id": "1",
+            "unread": true,
+            "reason": "mention",
+            "subject": {
+                "title": "Fix login",
+                "url": "https://github.mycorp.com/api/v3/repos/figma/figma/pulls/7",
+                "latest_comment_url": "https://github.mycorp.com/api/v3/repos/figma/figma/issues/comments/5",
+            },
+            "repository": { "full_name": "figma/figma" },
+        }))
+        .unwrap();
+        assert_eq!(
+            notification_link(&notification, &checker.web_url()),
+            "https://github.mycorp.com/figma/figma/pull/7#issuecomment-5"
+        );
+    }
+
+    #[test]
+    fn test_gh_only_required_without_token() {
+        assert!(gh_required(&GitHubConfig::default()));
+        assert!(gh_required(&GitHubConfig {
+            token: Some(String::new()),
+            ..GitHubConfig::default()
+        }));
+        assert!(!gh_required(&GitHubConfig {
+            token: Some("ghp_test".to_string()),
+            ..GitHubConfig::default()
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_required_approvals_through_gh() {
+        let approvals = |stdout: &str, stderr: &str| {
+            let runner = FakeGh::new([(
+                "",
+                Output {
+                    status: ExitStatus::from_raw(if stderr.is_empty() { 0 } else { 1 << 8 }),
+                    stdout: stdout.into(),
+                    stderr: stderr.into(),
+                },
+            )]);
+            async move {
+                let gh = Gh {
+                    host: "github.com".to_string(),
+                    runner,
+                };
+                gh_required_approvals(&gh, "figma/figma", "main")
+                    .await
+                    .map_err(|e| format!("{:#}", e))
+            }
+        };
+
+        assert_eq!(
+            approvals(
+                "HTTP/2.0 200 OK\r\nContent-Type: application/json\r\n\r\n\
+                 {\"required_approving_review_count\": 2}",
+                ""
+            )
+            .await,
+            Ok(Some(2))
+        );
+        // Unprotected branches are told apart by the status line, not gh's message
+        assert_eq!(
+            approvals(
+                "HTTP/2.0 404 Not Found\r\n\r\n{\"message\": \"Branch not protected\"}",
+                "gh: Branch not protected (HTTP 404)"
+            )
+            .await,
+            Ok(None)
+        );
+        assert_eq!(
+            approvals(
+                "HTTP/2.0 502 Bad Gateway\r\n\r\n",
+                "gh: Server Error (HTTP 502)"
+            )
+            .await,
+            Err(
+                "gh api repos/figma/figma/branches/main/protection/required_pull_request_reviews \
+                 failed: gh: Server Error (HTTP 502)"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            approvals("", "error connecting to api.github.com").await,
+            Err(
+                "gh api repos/figma/figma/branches/main/protection/required_pull_request_reviews \
+                 failed: error connecting to api.github.com"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_gh_failures_explain_themselves() {
+        let error = run_gh(
+            &SystemRunner,
+            &mut Command::new("work-driver-no-such-gh"),
+            "gh pr status",
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), GH_MISSING);
+
+        // gh explains on stderr, e.g. that it isn't logged in
+        let error = run_gh(
+            &SystemRunner,
+            Command::new("sh").args([
+                "-c",
+                "echo 'To get started, run: gh auth login' >&2; exit 4",
+            ]),
+            "gh pr status",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "gh pr status failed: To get started, run: gh auth login"
+        );
+        let error = run_gh(
+            &SystemRunner,
+            Command::new("sh").args(["-c", "exit 1"]),
+            "gh api user",
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "gh api user failed");
+
+        // The API needs no gh at all
+        let checker = GitHubChecker::new(&GitHubConfig {
+            token: Some("ghp_test".to_string()),
+            ..GitHubConfig::default()
+        });
+        assert!(checker.preflight().is_ok());
+    }
+
+    /// Stands in for `gh pr status`, answering for each repo after a delay and counting how
+    /// many calls overlap.
+    struct FakeGh {
+        /// By repo, or "" for commands without `--repo`.
+        responses: HashMap<&'static str, Output>,
+        running: AtomicUsize,
+        most_running: AtomicUsize,
+    }
+
+    impl FakeGh {
+        fn new(responses: impl IntoIterator<Item = (&'static str, Output)>) -> Arc<Self> {
+            Arc::new(Self {
+                responses: responses.into_iter().collect(),
+                running: AtomicUsize::new(0),
+                most_running: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    fn printed(stdout: impl Into<Vec<u8>>) -> Output {
+        Output {
+            status: ExitStatus::from_raw(0),
+            stdout: stdout.into(),
+            stderr: Vec::new(),
+        }
+    }
+
+    fn failed(stderr: &str) -> Output {
+        Output {
+            status: ExitStatus::from_raw(1 << 8),
+            stdout: Vec::new(),
+            stderr: stderr.into(),
+        }
+    }
+
+    impl CommandRunner for FakeGh {
+        fn output(&self, command: &mut Command) -> std::io::Result<Output> {
+            let args: Vec<String> = command
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect();
+            let repo = args
+                .iter()
+                .skip_while(|arg| *arg != "--repo")
+                .nth(1)
+                .map_or("", |repo| repo.as_str());
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.most_running.fetch_max(running, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(50));
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Ok(self.responses[repo].clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repos_checked_concurrently() {
+        let review_request = |number: u64| {
+            json!({ "createdBy": [], "needsReview": [{ "number": number, "title": "Add cache" }] })
+                .to_string()
+        };
+        let gh = FakeGh::new([
+            ("figma/a", printed(review_request(1))),
+            ("figma/b", printed(review_request(2))),
+            ("figma/c", failed("HTTP 502: Bad Gateway")),
+            ("figma/d", printed(review_request(4))),
+        ]);
+        let checker = |repos: &[&str]| {
+            GitHubChecker::new(&GitHubConfig {
+                repos: repos.iter().map(|repo| repo.to_string()).collect(),
+                max_concurrent_repos: 2,
+                ..GitHubConfig::default()
+            })
+            .with_runner(gh.clone())
+        };
+
+        // One repo failing doesn't hide the others, which stay in order
+        let issues = checker(&["figma/a", "figma/b", "figma/c", "figma/d"])
+            .check()
+            .await
+            .unwrap()
+            .issues;
+        assert_eq!(
+            issues
+                .iter()
+                .map(|issue| issue.id.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "github:figma/a:pr:1",
+                "github:figma/b:pr:2",
+                "github:figma/d:pr:4",
+                "checker:GitHub figma/c",
+            ]
+        );
+        assert_eq!(
+            issues[3].detail,
+            "failed: Failed to fetch PRs for figma/c: gh pr status failed: HTTP 502: Bad Gateway"
+        );
+        assert_eq!(gh.most_running.load(Ordering::SeqCst), 2);
+
+        // With nothing checked, the checker fails as a whole
+        let error = checker(&["figma/c"]).check().await.unwrap_err();
+        assert_eq!(
+            format!("{:#}", error),
+            "Failed to fetch PRs for figma/c: gh pr status failed: HTTP 502: Bad Gateway"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_through_gh() {
+        let check = |output: Output| async move {
+            GitHubChecker::new(&GitHubConfig {
+                repos: vec!["figma/figma".to_string()],
+                ..GitHubConfig::default()
+            })
+            .with_runner(FakeGh::new([("figma/figma", output)]))
+            .check()
+            .await
+            .map(|output| {
+                output
+                    .issues
+                    .iter()
+                    .map(|issue| issue.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .map_err(|e| format!("{:#}", e))
+        };
+
+        // The whole flow reports what `check_output` does for the same output
+        const TEST_JSON: &str = include_str!("github/check_output_1.json");
+        let expected: Vec<String> = GitHubChecker::new(&GitHubConfig::default())
+            .check_output("figma/figma", TEST_JSON)
+            .unwrap()
+            .iter()
+            .map(|issue| issue.to_string())
+            .collect();
+        assert_eq!(check(printed(TEST_JSON)).await.unwrap(), expected);
+
+        // Without configured repos, `gh` finds the current directory's
+        let issues: Vec<String> = GitHubChecker::new(&GitHubConfig::default())
+            .with_runner(FakeGh::new([
+                ("", printed("figma/figma\n")),
+                ("figma/figma", printed(TEST_JSON)),
+            ]))
+            .check()
+            .await
+            .unwrap()
+            .issues
+            .iter()
+            .map(|issue| issue.to_string())
+            .collect();
+        assert_eq!(issues, expected);
+
+        assert_eq!(
+            check(failed("error connecting to api.github.com\n"))
+                .await
+                .unwrap_err(),
+            "Failed to fetch PRs for figma/figma: gh pr status failed: error connecting to api.github.com"
+        );
+        assert_eq!(
+            check(printed([0xff, 0xfe])).await.unwrap_err(),
+            "Failed to fetch PRs for figma/figma: gh pr status printed invalid UTF-8: \
+             invalid utf-8 sequence of 1 bytes from index 0"
+        );
+        assert_eq!(
+            check(printed(r#"{"createdBy": ["#)).await.unwrap_err(),
+            "Failed to fetch PRs for figma/figma: Failed to parse gh pr status output: \
+             EOF while parsing a list at line 1 column 15"
         );
     }
 }
@@ -0,0 +1,959 @@
+//! Talks to the GitHub REST API directly for machines without an authenticated `gh`.
+//! Responses are reshaped into what `gh pr status --json` prints, so both paths share
+//! `GitHubChecker::check_output`.
+
+use crate::http::{TokenRejected, send_with_retry};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use futures_util::future::join_all;
+use reqwest::header::{
+    ACCEPT, AUTHORIZATION, ETAG, HeaderName, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+    USER_AGENT,
+};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tracing::debug;
+
+/// GitHub documents 60 seconds as the usual `X-Poll-Interval`.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The search API returns at most 1000 results, 100 to a page.
+const MAX_SEARCH_PAGES: usize = 10;
+
+#[derive(Debug, Deserialize)]
+struct SearchResults {
+    #[serde(default)]
+    total_count: usize,
+    items: Vec<SearchItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchItem {
+    number: u64,
+    title: String,
+    html_url: String,
+    created_at: String,
+    /// "open" or "closed".
+    #[serde(default)]
+    state: String,
+    closed_at: Option<String>,
+    node_id: String,
+    /// e.g. "https://api.github.com/repos/figma/figma"
+    repository_url: String,
+    user: User,
+    #[serde(default)]
+    labels: Vec<Label>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PullRequest {
+    number: u64,
+    title: String,
+    html_url: String,
+    state: String,
+    #[serde(default)]
+    draft: bool,
+    labels: Vec<Label>,
+    head: Head,
+    base: Base,
+    /// Null unless auto-merge is enabled.
+    auto_merge: Option<Value>,
+    /// Null until GitHub has computed it.
+    mergeable: Option<bool>,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Label {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Head {
+    sha: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Base {
+    #[serde(rename = "ref")]
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Comparison {
+    behind_by: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Review {
+    /// Null for reviewers whose accounts were deleted.
+    user: Option<User>,
+    state: String,
+    submitted_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct User {
+    pub login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckRuns {
+    check_runs: Vec<CheckRun>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckRun {
+    name: String,
+    status: String,
+    conclusion: Option<String>,
+    details_url: Option<String>,
+    started_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CombinedStatus {
+    statuses: Vec<StatusContext>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusContext {
+    context: String,
+    state: String,
+    target_url: Option<String>,
+    created_at: Option<String>,
+}
+
+/// A thread from the notifications API.
+#[derive(Debug, Deserialize)]
+pub struct Notification {
+    pub id: String,
+    pub unread: bool,
+    /// Why we were notified, e.g. "mention" or "review_requested".
+    pub reason: String,
+    pub subject: NotificationSubject,
+    pub repository: NotificationRepository,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotificationSubject {
+    pub title: String,
+    /// API URL of the issue or PR, e.g. "https://api.github.com/repos/o/r/pulls/1".
+    pub url: Option<String>,
+    /// API URL of the comment that triggered the notification.
+    pub latest_comment_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotificationRepository {
+    pub full_name: String,
+}
+
+pub struct NotificationsPage {
+    /// None when nothing changed since the `If-Modified-Since` time.
+    pub notifications: Option<Vec<Notification>>,
+    pub last_modified: Option<String>,
+    /// How long GitHub asks clients to wait before polling again.
+    pub poll_interval: Duration,
+}
+
+#[derive(Debug, Deserialize)]
+struct Repository {
+    default_branch: String,
+}
+
+/// A merged PR, as `gh pr list --json number,baseRefName` prints it.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergedPull {
+    pub number: u64,
+    pub base_ref_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClosedPull {
+    number: u64,
+    merged_at: Option<String>,
+    base: Base,
+}
+
+#[derive(Debug, Deserialize)]
+struct Branch {
+    #[serde(default)]
+    protection: BranchProtection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct BranchProtection {
+    #[serde(default)]
+    required_status_checks: RequiredStatusChecks,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RequiredStatusChecks {
+    #[serde(default)]
+    contexts: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RequiredReviews {
+    #[serde(default)]
+    required_approving_review_count: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WorkflowRuns {
+    pub workflow_runs: Vec<WorkflowRun>,
+}
+
+/// A run from `/repos/{owner}/{repo}/actions/runs`, which `gh api` returns as is.
+#[derive(Debug, Deserialize)]
+pub struct WorkflowRun {
+    pub id: u64,
+    pub run_number: u64,
+    pub workflow_id: u64,
+    pub name: String,
+    pub head_branch: String,
+    pub head_sha: String,
+    pub status: String,
+    /// Null until the run completes.
+    pub conclusion: Option<String>,
+    pub html_url: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An open milestone from `/repos/{owner}/{repo}/milestones`.
+#[derive(Debug, Deserialize)]
+pub struct Milestone {
+    pub number: u64,
+    pub title: String,
+    pub html_url: String,
+    pub open_issues: u64,
+    /// Null for milestones without a due date.
+    pub due_on: Option<DateTime<Utc>>,
+    /// Null for deleted accounts.
+    pub creator: Option<MilestoneCreator>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MilestoneCreator {
+    pub login: String,
+}
+
+/// A comment on a PR's conversation or on its diff, from
+/// `/repos/{owner}/{repo}/issues/{number}/comments` or `/pulls/{number}/comments`.
+#[derive(Debug, Deserialize)]
+pub struct Comment {
+    pub id: u64,
+    pub html_url: String,
+    #[serde(default)]
+    pub body: Option<String>,
+    /// Null for deleted accounts.
+    pub user: Option<User>,
+}
+
+/// An environment a waiting run needs approval to deploy to, from
+/// `/repos/{owner}/{repo}/actions/runs/{id}/pending_deployments`.
+#[derive(Debug, Deserialize)]
+pub struct PendingDeployment {
+    pub environment: Environment,
+    /// Whether we're one of the environment's required reviewers.
+    pub current_user_can_approve: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Environment {
+    pub name: String,
+}
+
+/// An open alert from `/repos/{owner}/{repo}/dependabot/alerts`.
+#[derive(Debug, Deserialize)]
+pub struct DependabotAlert {
+    pub number: u64,
+    pub html_url: String,
+    pub dependency: Dependency,
+    pub security_advisory: SecurityAdvisory,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Dependency {
+    pub package: Package,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Package {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SecurityAdvisory {
+    pub summary: String,
+    /// "critical", "high", "medium" or "low".
+    pub severity: String,
+}
+
+/// A response kept to revalidate with `If-None-Match`/`If-Modified-Since`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedResponse {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: Value,
+}
+
+/// GET responses by path and query, persisted between runs. GitHub doesn't count 304s
+/// against the rate limit, so unchanged endpoints are free to poll.
+pub struct ResponseCache {
+    path: PathBuf,
+    entries: Mutex<HashMap<String, CachedResponse>>,
+    /// Keys requested since the last save; the rest (closed PRs, old commits) are dropped.
+    used: Mutex<HashSet<String>>,
+}
+
+impl ResponseCache {
+    /// Loads the cache at `path`; a missing or unreadable file starts it empty.
+    pub fn load(path: PathBuf) -> Self {
+        let entries = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            entries: Mutex::new(entries),
+            used: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Writes out the responses requested since the last save.
+    pub fn save(&self) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        let used = std::mem::take(&mut *self.used.lock().unwrap());
+        entries.retain(|key, _| used.contains(key));
+        let content =
+            serde_json::to_string(&*entries).context("Failed to serialize response cache")?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, content).context("Failed to write response cache")?;
+        std::fs::rename(&tmp_path, &self.path).context("Failed to rename response cache")
+    }
+
+    fn get(&self, key: &str) -> Option<CachedResponse> {
+        self.used.lock().unwrap().insert(key.to_string());
+        self.entries.lock().unwrap().get(key).cloned()
+    }
+
+    fn insert(&self, key: String, response: CachedResponse) {
+        self.entries.lock().unwrap().insert(key, response);
+    }
+}
+
+pub struct GitHubApi {
+    client: reqwest::Client,
+    token: String,
+    base_url: String,
+    cache: Option<ResponseCache>,
+    /// `X-RateLimit-Remaining` from the latest REST response, `u64::MAX` until there is one.
+    rate_limit_remaining: AtomicU64,
+}
+
+impl GitHubApi {
+    pub fn new(token: &str, base_url: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            token: token.to_string(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            cache: None,
+            rate_limit_remaining: AtomicU64::new(u64::MAX),
+        }
+    }
+
+    pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn save_cache(&self) -> Result<()> {
+        match &self.cache {
+            Some(cache) => cache.save(),
+            None => Ok(()),
+        }
+    }
+
+    /// How many REST requests the rate limit has left, once a response has said.
+    pub fn rate_limit_remaining(&self) -> Option<u64> {
+        Some(self.rate_limit_remaining.load(Ordering::Relaxed)).filter(|r| *r != u64::MAX)
+    }
+
+    /// The open PRs `gh pr status` would list for `repo`, in the same JSON shape.
+    pub async fn pr_status(&self, repo: &str) -> Result<Value> {
+        let created = self
+            .search(&format!("is:pr is:open author:@me repo:{}", repo))
+            .await?;
+        let needs_review = self
+            .search(&format!("is:pr is:open review-requested:@me repo:{}", repo))
+            .await?;
+
+        let created = join_all(
+            created
+                .items
+                .iter()
+                .map(|item| self.created_pr(repo, item.number)),
+        )
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+        let needs_review: Vec<Value> = needs_review.items.iter().map(search_item_json).collect();
+
+        Ok(json!({ "createdBy": created, "needsReview": needs_review }))
+    }
+
+    async fn search(&self, query: &str) -> Result<SearchResults> {
+        self.get("/search/issues", &[("q", query), ("per_page", "100")])
+            .await
+    }
+
+    /// Open issues or PRs matching a search query, as `{number, title, url, createdAt,
+    /// author}` like `gh pr status` entries.
+    pub async fn search_issues(&self, query: &str) -> Result<Vec<Value>> {
+        Ok(self
+            .search(query)
+            .await?
+            .items
+            .iter()
+            .map(search_item_json)
+            .collect())
+    }
+
+    /// Like `search_issues`, but follows every page of results rather than just the first.
+    pub async fn search_all(&self, query: &str) -> Result<Vec<Value>> {
+        let mut items = Vec::new();
+        for page in 1..=MAX_SEARCH_PAGES {
+            let page = page.to_string();
+            let results: SearchResults = self
+                .get(
+                    "/search/issues",
+                    &[("q", query), ("per_page", "100"), ("page", &page)],
+                )
+                .await?;
+            let last_page = results.items.len() < 100;
+            items.extend(results.items.iter().map(search_item_json));
+            if last_page || items.len() >= results.total_count {
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    /// One of our PRs with the details `gh` includes: draft state, labels, review
+    /// decision and the status rollup of its head commit.
+    async fn created_pr(&self, repo: &str, number: u64) -> Result<Value> {
+        let pr: PullRequest = self
+            .get(&format!("/repos/{}/pulls/{}", repo, number), &[])
+            .await?;
+        let reviews_path = format!("/repos/{}/pulls/{}/reviews", repo, number);
+        let check_runs_path = format!("/repos/{}/commits/{}/check-runs", repo, pr.head.sha);
+        let status_path = format!("/repos/{}/commits/{}/status", repo, pr.head.sha);
+        let (reviews, check_runs, status) = tokio::join!(
+            self.get::<Vec<Review>>(&reviews_path, &[("per_page", "100")]),
+            self.get::<CheckRuns>(&check_runs_path, &[("per_page", "100")]),
+            self.get::<CombinedStatus>(&status_path, &[]),
+        );
+        Ok(pr_json(&pr, &reviews?, &check_runs?, &status?))
+    }
+
+    /// How many commits `base` has that `head` doesn't.
+    pub async fn commits_behind(&self, repo: &str, base: &str, head: &str) -> Result<u64> {
+        let comparison: Comparison = self
+            .get(&format!("/repos/{}/compare/{}...{}", repo, base, head), &[])
+            .await?;
+        Ok(comparison.behind_by)
+    }
+
+    /// The raw response to a GraphQL query about one PR, taking `$owner`, `$name` and
+    /// `$number` variables.
+    pub async fn pr_graphql(&self, query: &str, repo: &str, number: u64) -> Result<Value> {
+        let (owner, name) = repo
+            .split_once('/')
+            .with_context(|| format!("Expected owner/name, found '{}'", repo))?;
+        self.graphql(
+            query,
+            json!({ "owner": owner, "name": name, "number": number }),
+        )
+        .await
+    }
+
+    /// The raw response to a GraphQL query, which may hold `errors` alongside its `data`.
+    pub async fn graphql(&self, query: &str, variables: Value) -> Result<Value> {
+        let body = json!({ "query": query, "variables": variables });
+        // GitHub Enterprise serves GraphQL beside the REST API rather than under it
+        let url = match self.base_url.strip_suffix("/v3") {
+            Some(api_root) => format!("{}/graphql", api_root),
+            None => format!("{}/graphql", self.base_url),
+        };
+        let request = self.authorize(self.client.post(url));
+        let response = send_with_retry(request.json(&body), "GitHub")
+            .await
+            .context("Failed to query GitHub GraphQL")?;
+
+        if !response.status().is_success() {
+            bail!("GitHub API returned {} for /graphql", response.status());
+        }
+        response
+            .json()
+            .await
+            .context("Failed to parse GitHub GraphQL response")
+    }
+
+    /// The status check contexts `branch`'s protection requires before merging. Unlike the
+    /// protection endpoint itself, this doesn't need admin access.
+    pub async fn required_checks(&self, repo: &str, branch: &str) -> Result<Vec<String>> {
+        let branch: Branch = self
+            .get(&format!("/repos/{}/branches/{}", repo, branch), &[])
+            .await?;
+        Ok(branch.protection.required_status_checks.contexts)
+    }
+
+    /// How many approvals `branch`'s protection requires, or None when it's unprotected
+    /// or the token can't read its protection, which needs admin access.
+    pub async fn required_approvals(&self, repo: &str, branch: &str) -> Result<Option<u64>> {
+        let path = format!(
+            "/repos/{}/branches/{}/protection/required_pull_request_reviews",
+            repo, branch
+        );
+        let request = self.authorize(self.client.get(format!("{}{}", self.base_url, path)));
+        let response = match send_with_retry(request, "GitHub").await {
+            Ok(response) => response,
+            Err(e)
+                if e.downcast_ref::<TokenRejected>()
+                    .is_some_and(|rejected| rejected.status == StatusCode::FORBIDDEN) =>
+            {
+                return Ok(None);
+            }
+            Err(e) => return Err(e).with_context(|| format!("Failed to fetch {}", path)),
+        };
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => {
+                let reviews: RequiredReviews = response
+                    .json()
+                    .await
+                    .with_context(|| format!("Failed to parse GitHub response for {}", path))?;
+                Ok(Some(reviews.required_approving_review_count))
+            }
+            status => bail!("GitHub API returned {} for {}", status, path),
+        }
+    }
+
+    pub async fn default_branch(&self, repo: &str) -> Result<String> {
+        let repository: Repository = self.get(&format!("/repos/{}", repo), &[]).await?;
+        Ok(repository.default_branch)
+    }
+
+    /// The latest workflow runs on `repo`'s default branch, newest first.
+    pub async fn default_branch_runs(&self, repo: &str) -> Result<Vec<WorkflowRun>> {
+        let branch = self.default_branch(repo).await?;
+        let runs: WorkflowRuns = self
+            .get(
+                &format!("/repos/{}/actions/runs", repo),
+                &[("branch", &branch), ("per_page", "10")],
+            )
+            .await?;
+        Ok(runs.workflow_runs)
+    }
+
+    /// The most recent merged PR from `branch` in `repo`, if any.
+    pub async fn merged_pull_from(&self, repo: &str, branch: &str) -> Result<Option<MergedPull>> {
+        let owner = repo.split('/').next().unwrap_or(repo);
+        let pulls: Vec<ClosedPull> = self
+            .get(
+                &format!("/repos/{}/pulls", repo),
+                &[
+                    ("head", &format!("{}:{}", owner, branch)),
+                    ("state", "closed"),
+                ],
+            )
+            .await?;
+        Ok(pulls
+            .into_iter()
+            .find(|pull| pull.merged_at.is_some())
+            .map(|pull| MergedPull {
+                number: pull.number,
+                base_ref_name: pull.base.name,
+            }))
+    }
+
+    /// The login of the token's user.
+    pub async fn login(&self) -> Result<String> {
+        let user: User = self.get("/user", &[]).await?;
+        Ok(user.login)
+    }
+
+    /// Comments on a PR, both on the conversation and on its diff.
+    pub async fn pr_comments(&self, repo: &str, number: u64) -> Result<Vec<Comment>> {
+        let conversation_path = format!("/repos/{}/issues/{}/comments", repo, number);
+        let diff_path = format!("/repos/{}/pulls/{}/comments", repo, number);
+        let (conversation, diff) = tokio::join!(
+            self.get::<Vec<Comment>>(&conversation_path, &[("per_page", "100")]),
+            self.get::<Vec<Comment>>(&diff_path, &[("per_page", "100")]),
+        );
+        Ok(conversation?.into_iter().chain(diff?).collect())
+    }
+
+    pub async fn open_milestones(&self, repo: &str) -> Result<Vec<Milestone>> {
+        self.get(
+            &format!("/repos/{}/milestones", repo),
+            &[("state", "open"), ("per_page", "100")],
+        )
+        .await
+    }
+
+    /// Runs in `repo` waiting on an environment's approval, with the deployments they're
+    /// waiting to make.
+    pub async fn waiting_deployments(
+        &self,
+        repo: &str,
+    ) -> Result<Vec<(WorkflowRun, Vec<PendingDeployment>)>> {
+        let runs: WorkflowRuns = self
+            .get(
+                &format!("/repos/{}/actions/runs", repo),
+                &[("status", "waiting"), ("per_page", "100")],
+            )
+            .await?;
+        let mut waiting = Vec::new();
+        for run in runs.workflow_runs {
+            let deployments = self
+                .get(
+                    &format!(
+                        "/repos/{}/actions/runs/{}/pending_deployments",
+                        repo, run.id
+                    ),
+                    &[],
+                )
+                .await?;
+            waiting.push((run, deployments));
+        }
+        Ok(waiting)
+    }
+
+    /// Open Dependabot alerts for `repo`, or None when the token isn't allowed to read them.
+    pub async fn dependabot_alerts(&self, repo: &str) -> Result<Option<Vec<DependabotAlert>>> {
+        let path = format!("/repos/{}/dependabot/alerts", repo);
+        let request = self.authorize(
+            self.client
+                .get(format!("{}{}", self.base_url, path))
+                .query(&[("state", "open"), ("per_page", "100")]),
+        );
+        let response = match send_with_retry(request, "GitHub").await {
+            Ok(response) => response,
+            // Reading alerts needs a scope the rest of the checker doesn't
+            Err(e)
+                if e.downcast_ref::<TokenRejected>()
+                    .is_some_and(|rejected| rejected.status == StatusCode::FORBIDDEN) =>
+            {
+                return Ok(None);
+            }
+            Err(e) => return Err(e).with_context(|| format!("Failed to fetch {}", path)),
+        };
+
+        match response.status() {
+            status if status.is_success() => response
+                .json()
+                .await
+                .map(Some)
+                .with_context(|| format!("Failed to parse GitHub response for {}", path)),
+            status => bail!("GitHub API returned {} for {}", status, path),
+        }
+    }
+
+    /// Unread notifications for threads we participate in. Pass the previous page's
+    /// `last_modified` to get an empty page when nothing has changed.
+    pub async fn notifications(&self, last_modified: Option<&str>) -> Result<NotificationsPage> {
+        let mut request = self.authorize(
+            self.client
+                .get(format!("{}/notifications", self.base_url))
+                .query(&[("participating", "true")]),
+        );
+        if let Some(last_modified) = last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        let response = send_with_retry(request, "GitHub")
+            .await
+            .context("Failed to fetch notifications")?;
+
+        let last_modified = header_string(&response, LAST_MODIFIED);
+        let poll_interval = header_string(&response, HeaderName::from_static("x-poll-interval"))
+            .and_then(|seconds| seconds.parse().ok())
+            .map_or(DEFAULT_POLL_INTERVAL, Duration::from_secs);
+
+        let notifications = match response.status() {
+            StatusCode::NOT_MODIFIED => None,
+            status if status.is_success() => Some(
+                response
+                    .json()
+                    .await
+                    .context("Failed to parse GitHub notifications")?,
+            ),
+            status => bail!("GitHub API returned {} for /notifications", status),
+        };
+        Ok(NotificationsPage {
+            notifications,
+            last_modified,
+            poll_interval,
+        })
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        request
+            .header(AUTHORIZATION, format!("Bearer {}", self.token))
+            .header(ACCEPT, "application/vnd.github+json")
+            .header(USER_AGENT, "work-driver")
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, &str)]) -> Result<T> {
+        let key = cache_key(path, query);
+        let cached = self.cache.as_ref().and_then(|cache| cache.get(&key));
+        let mut request = self.authorize(
+            self.client
+                .get(format!("{}{}", self.base_url, path))
+                .query(query),
+        );
+        if let Some(cached) = &cached {
+            if let Some(etag) = &cached.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let response = send_with_retry(request, "GitHub")
+            .await
+            .with_context(|| format!("Failed to fetch {}", path))?;
+        self.record_rate_limit(&response);
+
+        let body: Value = match (response.status(), cached) {
+            (StatusCode::NOT_MODIFIED, Some(cached)) => {
+                debug!(path, "not modified, using cached response");
+                cached.body
+            }
+            (status, _) if status.is_success() => {
+                let etag = header_string(&response, ETAG);
+                let last_modified = header_string(&response, LAST_MODIFIED);
+                let body: Value = response
+                    .json()
+                    .await
+                    .with_context(|| format!("Failed to parse GitHub response for {}", path))?;
+                if let Some(cache) = &self.cache
+                    && (etag.is_some() || last_modified.is_some())
+                {
+                    let response = CachedResponse {
+                        etag,
+                        last_modified,
+                        body: body.clone(),
+                    };
+                    cache.insert(key, response);
+                }
+                body
+            }
+            (status, _) => bail!("GitHub API returned {} for {}", status, path),
+        };
+        serde_json::from_value(body)
+            .with_context(|| format!("Failed to parse GitHub response for {}", path))
+    }
+
+    fn record_rate_limit(&self, response: &Response) {
+        let remaining = header_string(response, HeaderName::from_static("x-ratelimit-remaining"))
+            .and_then(|remaining| remaining.parse().ok());
+        if let Some(remaining) = remaining {
+            self.rate_limit_remaining
+                .store(remaining, Ordering::Relaxed);
+        }
+    }
+}
+
+fn header_string(response: &Response, name: HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+fn cache_key(path: &str, query: &[(&str, &str)]) -> String {
+    let query: Vec<String> = query
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect();
+    format!("{}?{}", path, query.join("&"))
+}
+
+fn search_item_json(item: &SearchItem) -> Value {
+    json!({
+        "number": item.number,
+        "title": item.title,
+        "url": item.html_url,
+        "createdAt": item.created_at,
+        "state": item.state.to_uppercase(),
+        "closedAt": item.closed_at,
+        "id": item.node_id,
+        // The part after "/repos/", e.g. "figma/figma"
+        "repository": item.repository_url.split_once("/repos/").map_or("", |(_, repo)| repo),
+        "author": { "login": item.user.login },
+        "labels": item.labels.iter().map(|label| json!({ "name": label.name })).collect::<Vec<_>>(),
+    })
+}
+
+/// A PR as `gh pr status --json` prints it. REST states are lowercase where GraphQL's
+/// are uppercase, and the rollup mixes check runs with commit statuses like GraphQL does.
+fn pr_json(
+    pr: &PullRequest,
+    reviews: &[Review],
+    check_runs: &CheckRuns,
+    status: &CombinedStatus,
+) -> Value {
+    let mut rollup: Vec<Value> = check_runs
+        .check_runs
+        .iter()
+        .map(|run| {
+            json!({
+                "__typename": "CheckRun",
+                "name": run.name,
+                "status": run.status.to_uppercase(),
+                "conclusion": run.conclusion.as_deref().unwrap_or("").to_uppercase(),
+                "detailsUrl": run.details_url,
+                "startedAt": run.started_at,
+            })
+        })
+        .collect();
+    rollup.extend(status.statuses.iter().map(|context| {
+        json!({
+            "__typename": "StatusContext",
+            "context": context.context,
+            "state": context.state.to_uppercase(),
+            "targetUrl": context.target_url,
+            "startedAt": context.created_at,
+        })
+    }));
+
+    json!({
+        "number": pr.number,
+        "title": pr.title,
+        "url": pr.html_url,
+        "state": pr.state.to_uppercase(),
+        "isDraft": pr.draft,
+        "labels": pr.labels.iter().map(|label| json!({ "name": label.name })).collect::<Vec<_>>(),
+        "reviewDecision": review_decision(reviews),
+        "latestReviews": latest_reviews(reviews),
+        "autoMergeRequest": pr.auto_merge,
+        "createdAt": pr.created_at,
+        "updatedAt": pr.updated_at,
+        "baseRefName": pr.base.name,
+        "headRefOid": pr.head.sha,
+        "mergeable": match pr.mergeable {
+            Some(true) => "MERGEABLE",
+            Some(false) => "CONFLICTING",
+            None => "UNKNOWN",
+        },
+        "statusCheckRollup": rollup,
+    })
+}
+
+/// Each reviewer's most recent review, like GraphQL's `latestReviews`.
+fn latest_reviews(reviews: &[Review]) -> Vec<Value> {
+    let mut latest: Vec<(&str, &Review)> = Vec::new();
+    for review in reviews {
+        let Some(user) = &review.user else {
+            continue;
+        };
+        latest.retain(|(login, _)| *login != user.login);
+        latest.push((&user.login, review));
+    }
+    latest
+        .into_iter()
+        .map(|(login, review)| {
+            json!({
+                "author": { "login": login },
+                "state": review.state,
+                "submittedAt": review.submitted_at,
+            })
+        })
+        .collect()
+}
+
+/// Approximates GraphQL's `reviewDecision` from each reviewer's latest approval or change
+/// request. Reviews are listed oldest first, so later ones replace earlier ones.
+fn review_decision(reviews: &[Review]) -> &'static str {
+    let mut latest: HashMap<&str, &str> = HashMap::new();
+    for review in reviews {
+        if let Some(user) = &review.user
+            && matches!(
+                review.state.as_str(),
+                "APPROVED" | "CHANGES_REQUESTED" | "DISMISSED"
+            )
+        {
+            latest.insert(&user.login, &review.state);
+        }
+    }
+
+    if latest.values().any(|state| *state == "CHANGES_REQUESTED") {
+        "CHANGES_REQUESTED"
+    } else if latest.values().any(|state| *state == "APPROVED") {
+        "APPROVED"
+    } else {
+        "REVIEW_REQUIRED"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_server::MockServer;
+
+    #[tokio::test]
+    async fn test_unchanged_responses_come_from_the_cache() {
+        let path = std::env::temp_dir().join(format!(
+            "work-driver-github-cache-{}.json",
+            std::process::id()
+        ));
+        let changed = MockServer::new()
+            .respond_with_headers(
+                "/repos/figma/figma",
+                200,
+                &[("etag", "\"abc123\""), ("x-ratelimit-remaining", "4999")],
+                r#"{"default_branch": "main"}"#,
+            )
+            .start()
+            .await;
+        let api =
+            GitHubApi::new("ghp_test", &changed.url).with_cache(ResponseCache::load(path.clone()));
+        let repository: Repository = api.get("/repos/figma/figma", &[]).await.unwrap();
+        assert_eq!(repository.default_branch, "main");
+        assert_eq!(api.rate_limit_remaining(), Some(4999));
+        api.save_cache().unwrap();
+
+        // A later run gets a 304 and reuses what was cached
+        let unchanged = MockServer::new()
+            .respond("/repos/figma/figma", 304, "")
+            .start()
+            .await;
+        let api = GitHubApi::new("ghp_test", &unchanged.url)
+            .with_cache(ResponseCache::load(path.clone()));
+        let repository: Repository = api.get("/repos/figma/figma", &[]).await.unwrap();
+        assert_eq!(repository.default_branch, "main");
+
+        let uncached = GitHubApi::new("ghp_test", &unchanged.url);
+        assert!(
+            uncached
+                .get::<Repository>("/repos/figma/figma", &[])
+                .await
+                .is_err()
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,181 @@
+//! Which files a CODEOWNERS file assigns an owner to. Patterns follow GitHub's rules,
+//! which are gitignore's minus negation and character ranges: the last matching line
+//! wins, and a line without owners leaves its files unowned.
+
+pub struct CodeOwners {
+    rules: Vec<Rule>,
+}
+
+struct Rule {
+    pattern: String,
+    owners: Vec<String>,
+}
+
+impl CodeOwners {
+    pub fn parse(content: &str) -> Self {
+        let rules = content
+            .lines()
+            .filter_map(|line| {
+                let mut words = line
+                    .split_whitespace()
+                    .take_while(|word| !word.starts_with('#'));
+                let pattern = words.next()?.replace("\\#", "#");
+                Some(Rule {
+                    pattern,
+                    owners: words.map(String::from).collect(),
+                })
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// The owners of `path`, relative to the repository root; empty when it has none.
+    pub fn owners(&self, path: &str) -> &[String] {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| matches(&rule.pattern, path))
+            .map_or(&[], |rule| &rule.owners)
+    }
+}
+
+/// What a pattern's segments have to leave of a path once they've matched.
+#[derive(Clone, Copy)]
+enum Rest {
+    /// The path itself, e.g. `docs/*`, which GitHub doesn't apply to nested files.
+    Nothing,
+    /// Something inside it, for directory patterns like `logs/`.
+    Descendants,
+    /// Either, since a pattern matching a directory matches everything in it.
+    Anything,
+}
+
+/// Whether a CODEOWNERS `pattern` matches the file at `path`.
+pub fn matches(pattern: &str, path: &str) -> bool {
+    let directory = pattern.ends_with('/');
+    let pattern = pattern.trim_end_matches('/');
+    // A slash anywhere but the end anchors the pattern to the root
+    let anchored = pattern.contains('/');
+    let pattern = pattern.trim_start_matches('/');
+    if pattern.is_empty() {
+        return false;
+    }
+
+    let mut segments: Vec<&str> = pattern.split('/').collect();
+    if !anchored {
+        segments.insert(0, "**");
+    }
+    let rest = if directory {
+        Rest::Descendants
+    } else if segments.len() > 1 && segments.last() == Some(&"*") {
+        Rest::Nothing
+    } else {
+        Rest::Anything
+    };
+    let path: Vec<&str> = path.split('/').collect();
+    matches_segments(&segments, &path, rest)
+}
+
+fn matches_segments(pattern: &[&str], path: &[&str], rest: Rest) -> bool {
+    match pattern.split_first() {
+        None => match rest {
+            Rest::Nothing => path.is_empty(),
+            Rest::Descendants => !path.is_empty(),
+            Rest::Anything => true,
+        },
+        Some((&"**", pattern)) => {
+            (0..=path.len()).any(|skipped| matches_segments(pattern, &path[skipped..], rest))
+        }
+        Some((segment, pattern)) => path.split_first().is_some_and(|(name, path)| {
+            matches_glob(segment.as_bytes(), name.as_bytes())
+                && matches_segments(pattern, path, rest)
+        }),
+    }
+}
+
+/// Matches one path segment against `*` and `?` wildcards.
+fn matches_glob(glob: &[u8], name: &[u8]) -> bool {
+    match glob.split_first() {
+        None => name.is_empty(),
+        Some((b'*', glob)) => (0..=name.len()).any(|skipped| matches_glob(glob, &name[skipped..])),
+        Some((b'?', glob)) => !name.is_empty() && matches_glob(glob, &name[1..]),
+        Some((c, glob)) => name.first() == Some(c) && matches_glob(glob, &name[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let cases = [
+            // Patterns without a slash match at any depth
+            ("*", "README.md", true),
+            ("*", "src/main.rs", true),
+            ("*.js", "app.js", true),
+            ("*.js", "web/src/app.js", true),
+            ("*.js", "app.jsx", false),
+            ("Makefile", "tools/Makefile", true),
+            ("?.md", "a.md", true),
+            ("?.md", "ab.md", false),
+            // ...including directories, and everything in them
+            ("docs", "docs/guide.md", true),
+            ("docs", "web/docs/guide.md", true),
+            ("docs", "documents/guide.md", false),
+            ("apps/", "apps/web/index.ts", true),
+            ("apps/", "tools/apps/cli.rs", true),
+            ("apps/", "apps", false),
+            // A leading or inner slash anchors to the root
+            ("/build/logs/", "build/logs/today.log", true),
+            ("/build/logs/", "src/build/logs/today.log", false),
+            ("/README.md", "README.md", true),
+            ("/README.md", "docs/README.md", false),
+            ("src/github", "src/github/api.rs", true),
+            ("src/github", "lib/src/github/api.rs", false),
+            // A trailing `*` only matches direct children
+            ("docs/*", "docs/getting-started.md", true),
+            ("docs/*", "docs/build-app/troubleshooting.md", false),
+            ("src/*.rs", "src/main.rs", true),
+            ("src/*.rs", "src/github/api.rs", false),
+            // `**` matches any number of directories
+            ("**/logs", "logs/a.log", true),
+            ("**/logs", "deeply/nested/logs/a.log", true),
+            ("/docs/**/*.md", "docs/a.md", true),
+            ("/docs/**/*.md", "docs/guides/setup/b.md", true),
+            ("/docs/**/*.md", "docs/guides/b.txt", false),
+            ("/", "README.md", false),
+        ];
+        for (pattern, path, expected) in cases {
+            assert_eq!(
+                matches(pattern, path),
+                expected,
+                "{} against {}",
+                pattern,
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_last_match_wins() {
+        let codeowners = CodeOwners::parse(
+            "# Default owners\n\
+             *       @figma/web\n\
+             \n\
+             *.rs    @figma/rust  @octocat # the Rust folks\n\
+             /docs/  @figma/docs\n\
+             /docs/generated/\n\
+             \\#notes.md @me\n",
+        );
+        assert_eq!(codeowners.owners("index.ts"), ["@figma/web"]);
+        assert_eq!(
+            codeowners.owners("src/main.rs"),
+            ["@figma/rust", "@octocat"]
+        );
+        assert_eq!(codeowners.owners("docs/main.rs"), ["@figma/docs"]);
+        assert!(codeowners.owners("docs/generated/api.md").is_empty());
+        assert_eq!(codeowners.owners("#notes.md"), ["@me"]);
+        assert!(CodeOwners::parse("").owners("index.ts").is_empty());
+    }
+}
@@ -0,0 +1,358 @@
+//! Fetches everything `GitHubChecker` looks at for a repo in a single GraphQL query,
+//! instead of a REST call per PR for each detail. The response is reshaped into what
+//! `gh pr status --json` prints, like the REST path in `api`.
+
+use super::api::GitHubApi;
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tracing::warn;
+
+/// Our open PRs with their checks and reviews, and the PRs requesting our review.
+/// `$threads` adds each PR's review threads.
+const PR_STATUS_QUERY: &str =
+    "query($created: String!, $reviewRequested: String!, $threads: Boolean!) {
+  created: search(query: $created, type: ISSUE, first: 50) {
+    nodes { ...createdPr }
+  }
+  needsReview: search(query: $reviewRequested, type: ISSUE, first: 100) {
+    nodes {
+      ... on PullRequest {
+        id number title url createdAt
+        author { login }
+        labels(first: 20) { nodes { name } }
+      }
+    }
+  }
+}
+
+fragment createdPr on PullRequest {
+  number title url state isDraft createdAt updatedAt baseRefName headRefOid
+  reviewDecision mergeable
+  author { login }
+  labels(first: 20) { nodes { name } }
+  autoMergeRequest { enabledAt mergeMethod }
+  latestReviews(first: 20) { nodes { author { login } state submittedAt } }
+  reviewThreads(first: 100) @include(if: $threads) { nodes { isResolved } }
+  commits(last: 1) {
+    nodes {
+      commit {
+        statusCheckRollup {
+          contexts(first: 100) {
+            nodes {
+              __typename
+              ... on CheckRun { name status conclusion detailsUrl startedAt }
+              ... on StatusContext { context state targetUrl createdAt }
+            }
+          }
+        }
+      }
+    }
+  }
+}";
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    /// Null when the whole query failed.
+    data: Option<PrStatusData>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlError {
+    message: String,
+    /// Where in `data` the error happened, e.g. `["created", "nodes", 2, "commits"]`.
+    #[serde(default)]
+    path: Vec<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PrStatusData {
+    created: Search<CreatedPr>,
+    needs_review: Search<ReviewRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Search<T> {
+    /// Nodes that failed to resolve are null.
+    nodes: Vec<Option<T>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Connection<T> {
+    nodes: Vec<T>,
+}
+
+impl<T> Default for Connection<T> {
+    fn default() -> Self {
+        Self { nodes: Vec::new() }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreatedPr {
+    number: u64,
+    title: String,
+    url: String,
+    state: String,
+    is_draft: bool,
+    created_at: String,
+    updated_at: String,
+    base_ref_name: String,
+    head_ref_oid: String,
+    review_decision: Option<String>,
+    mergeable: String,
+    author: Option<Actor>,
+    #[serde(default)]
+    labels: Connection<Label>,
+    auto_merge_request: Option<Value>,
+    #[serde(default)]
+    latest_reviews: Connection<LatestReview>,
+    /// Only fetched when asked for.
+    review_threads: Option<Connection<ReviewThread>>,
+    #[serde(default)]
+    commits: Connection<CommitNode>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReviewRequest {
+    id: String,
+    number: u64,
+    title: String,
+    url: String,
+    created_at: String,
+    author: Option<Actor>,
+    #[serde(default)]
+    labels: Connection<Label>,
+}
+
+/// Null for deleted accounts.
+#[derive(Debug, Deserialize)]
+struct Actor {
+    login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Label {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LatestReview {
+    author: Option<Actor>,
+    state: String,
+    submitted_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReviewThread {
+    is_resolved: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommitNode {
+    commit: Commit,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Commit {
+    /// Null for commits without any checks.
+    status_check_rollup: Option<StatusCheckRollup>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusCheckRollup {
+    /// Already in the shape `gh` prints, apart from StatusContext's `createdAt`.
+    contexts: Connection<Value>,
+}
+
+/// The open PRs `gh pr status` would list for `repo`, in the same JSON shape, with
+/// `unresolvedReviewThreads` on our PRs when `review_threads` is set.
+pub async fn pr_status(api: &GitHubApi, repo: &str, review_threads: bool) -> Result<Value> {
+    let variables = json!({
+        "created": format!("is:pr is:open author:@me repo:{}", repo),
+        "reviewRequested": format!("is:pr is:open review-requested:@me repo:{}", repo),
+        "threads": review_threads,
+    });
+    let response = api.graphql(PR_STATUS_QUERY, variables).await?;
+    pr_status_json(response)
+}
+
+/// Reshapes a `PR_STATUS_QUERY` response. Errors that only affected part of the response
+/// are logged and whatever did resolve is used.
+fn pr_status_json(response: Value) -> Result<Value> {
+    let response: Response =
+        serde_json::from_value(response).context("Failed to parse GitHub GraphQL response")?;
+    let messages: Vec<String> = response
+        .errors
+        .iter()
+        .map(|error| {
+            let path: Vec<String> = error.path.iter().map(|p| p.to_string()).collect();
+            match path.as_slice() {
+                [] => error.message.clone(),
+                path => format!("{} (at {})", error.message, path.join(".")),
+            }
+        })
+        .collect();
+    let Some(data) = response.data else {
+        bail!("GitHub GraphQL query failed: {}", messages.join("; "));
+    };
+    for message in &messages {
+        warn!("GitHub GraphQL query partly failed: {}", message);
+    }
+
+    let created: Vec<Value> = data
+        .created
+        .nodes
+        .into_iter()
+        .flatten()
+        .map(created_pr_json)
+        .collect();
+    let needs_review: Vec<Value> = data
+        .needs_review
+        .nodes
+        .into_iter()
+        .flatten()
+        .map(|pr| {
+            json!({
+                "number": pr.number,
+                "title": pr.title,
+                "url": pr.url,
+                "createdAt": pr.created_at,
+                "id": pr.id,
+                "author": pr.author.map(|author| json!({ "login": author.login })),
+                "labels": labels_json(&pr.labels),
+            })
+        })
+        .collect();
+    Ok(json!({ "createdBy": created, "needsReview": needs_review }))
+}
+
+fn created_pr_json(pr: CreatedPr) -> Value {
+    let rollup: Vec<Value> = pr
+        .commits
+        .nodes
+        .into_iter()
+        .filter_map(|node| node.commit.status_check_rollup)
+        .flat_map(|rollup| rollup.contexts.nodes)
+        .map(|mut context| {
+            // gh calls it startedAt for both kinds of check
+            if let Some(created_at) = context.get("createdAt").cloned() {
+                context["startedAt"] = created_at;
+            }
+            context
+        })
+        .collect();
+
+    let mut json = json!({
+        "number": pr.number,
+        "title": pr.title,
+        "url": pr.url,
+        "state": pr.state,
+        "isDraft": pr.is_draft,
+        "createdAt": pr.created_at,
+        "updatedAt": pr.updated_at,
+        "baseRefName": pr.base_ref_name,
+        "headRefOid": pr.head_ref_oid,
+        "reviewDecision": pr.review_decision,
+        "mergeable": pr.mergeable,
+        "author": pr.author.map(|author| json!({ "login": author.login })),
+        "labels": labels_json(&pr.labels),
+        "autoMergeRequest": pr.auto_merge_request,
+        "latestReviews": pr.latest_reviews.nodes.iter().map(|review| {
+            json!({
+                "author": review.author.as_ref().map(|author| json!({ "login": author.login })),
+                "state": review.state,
+                "submittedAt": review.submitted_at,
+            })
+        }).collect::<Vec<_>>(),
+        "statusCheckRollup": rollup,
+    });
+    if let Some(threads) = pr.review_threads {
+        json["unresolvedReviewThreads"] = threads
+            .nodes
+            .iter()
+            .filter(|thread| !thread.is_resolved)
+            .count()
+            .into();
+    }
+    json
+}
+
+fn labels_json(labels: &Connection<Label>) -> Vec<Value> {
+    labels
+        .nodes
+        .iter()
+        .map(|label| json!({ "name": label.name }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pr_status_json_matches_gh() {
+        let response = serde_json::from_str(include_str!("graphql_pr_status.json")).unwrap();
+        let data = pr_status_json(response).unwrap();
+
+        let pr = &data["createdBy"][0];
+        assert_eq!(pr["number"], 3101);
+        assert_eq!(pr["labels"], json!([{ "name": "web" }]));
+        assert_eq!(pr["unresolvedReviewThreads"], 1);
+        assert_eq!(
+            pr["statusCheckRollup"],
+            json!([
+                {
+                    "__typename": "CheckRun",
+                    "name": "build",
+                    "status": "COMPLETED",
+                    "conclusion": "FAILURE",
+                    "detailsUrl": "https://github.com/figma/figma/actions/runs/1/job/2",
+                    "startedAt": "2025-10-06T18:00:00Z",
+                },
+                {
+                    "__typename": "StatusContext",
+                    "context": "ci/circleci",
+                    "state": "SUCCESS",
+                    "targetUrl": "https://circleci.com/gh/figma/figma/1",
+                    "createdAt": "2025-10-06T18:01:00Z",
+                    "startedAt": "2025-10-06T18:01:00Z",
+                },
+            ])
+        );
+        // Checks haven't been reported for the second PR's new commit yet
+        assert_eq!(data["createdBy"][1]["statusCheckRollup"], json!([]));
+        assert_eq!(data["needsReview"][0]["author"]["login"], "octocat");
+    }
+
+    #[test]
+    fn test_pr_status_json_with_errors() {
+        // One PR couldn't be resolved; the rest of the response is still used
+        let response = serde_json::from_str(include_str!("graphql_partial_error.json")).unwrap();
+        let data = pr_status_json(response).unwrap();
+        assert_eq!(data["createdBy"].as_array().unwrap().len(), 1);
+        assert!(
+            data["createdBy"][0]
+                .get("unresolvedReviewThreads")
+                .is_none()
+        );
+
+        let error = pr_status_json(json!({
+            "data": null,
+            "errors": [{ "message": "Something went wrong", "path": ["created"] }],
+        }))
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "GitHub GraphQL query failed: Something went wrong (at \"created\")"
+        );
+    }
+}
@@ -0,0 +1,317 @@
+//! Retrying HTTP requests for API-based checkers.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tracing::warn;
+
+/// The longest we'll honour a `Retry-After` or rate limit reset header, so one response
+/// can't stall a run.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// A 401 or 403 from `RetryPolicy::send`, for callers that treat a missing permission as
+/// something other than a failure.
+#[derive(Debug)]
+pub struct TokenRejected {
+    pub service: String,
+    pub status: StatusCode,
+}
+
+impl fmt::Display for TokenRejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} rejected the API token ({}); check that it is valid and has access",
+            self.service, self.status
+        )
+    }
+}
+
+impl std::error::Error for TokenRejected {}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt.
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled for each one after that.
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Sends an idempotent request, retrying connection errors, timeouts, 429s and 5xx
+    /// responses. A 429 is retried once its rate limit resets, when the response says
+    /// when. So is a 403 that's really a rate limit, as GitHub sends: with no requests
+    /// remaining, or a `Retry-After`. Other 401s and 403s fail immediately with an error
+    /// about `service`'s token; any other response (including the last retryable one) is
+    /// returned for the caller to check.
+    pub async fn send(&self, request: RequestBuilder, service: &str) -> Result<Response> {
+        let mut attempt = 0;
+        loop {
+            let this_attempt = request
+                .try_clone()
+                .context("Only requests without streaming bodies can be retried")?;
+            let retries_left = attempt < self.max_retries;
+
+            let delay = match this_attempt.send().await {
+                Ok(response) => {
+                    let status = response.status();
+                    let rate_limited = status == StatusCode::TOO_MANY_REQUESTS
+                        || (status == StatusCode::FORBIDDEN
+                            && is_rate_limit_403(response.headers()));
+                    if !rate_limited
+                        && matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+                    {
+                        return Err(TokenRejected {
+                            service: service.to_string(),
+                            status,
+                        }
+                        .into());
+                    }
+                    if !(rate_limited || status.is_server_error()) || !retries_left {
+                        return Ok(response);
+                    }
+                    let delay = retry_after(&response)
+                        .or_else(|| {
+                            rate_limited
+                                .then(|| rate_limit_reset(response.headers()))
+                                .flatten()
+                        })
+                        .unwrap_or_else(|| self.backoff(attempt));
+                    warn!(%service, %status, ?delay, "retryable response, retrying");
+                    delay
+                }
+                Err(e) if retries_left && (e.is_connect() || e.is_timeout()) => {
+                    let delay = self.backoff(attempt);
+                    warn!(%service, error = %e, ?delay, "request failed, retrying");
+                    delay
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Request to {} failed", service));
+                }
+            };
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Exponential backoff with up to 50% random jitter added.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self.base_delay * 2u32.saturating_pow(attempt);
+        let max_jitter_ms = delay.as_millis() as u64 / 2;
+        if max_jitter_ms == 0 {
+            return delay;
+        }
+        let random = RandomState::new().build_hasher().finish();
+        delay + Duration::from_millis(random % (max_jitter_ms + 1))
+    }
+}
+
+/// Sends a GET-style request with the default retry policy.
+pub async fn send_with_retry(request: RequestBuilder, service: &str) -> Result<Response> {
+    RetryPolicy::default().send(request, service).await
+}
+
+/// Whether a 403 means the rate limit ran out rather than the token lacking access:
+/// GitHub's primary limit says none are remaining, and its secondary limits say when to
+/// retry.
+fn is_rate_limit_403(headers: &HeaderMap) -> bool {
+    headers.contains_key(RETRY_AFTER)
+        || headers
+            .get("x-ratelimit-remaining")
+            .is_some_and(|remaining| remaining.as_bytes() == b"0")
+}
+
+/// Parses a `Retry-After` header given either as seconds or as an HTTP date.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    let delay = match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => {
+            let at = DateTime::parse_from_rfc2822(value).ok()?;
+            (at.with_timezone(&Utc) - Utc::now())
+                .to_std()
+                .unwrap_or_default()
+        }
+    };
+    Some(delay.min(MAX_RETRY_AFTER))
+}
+
+/// How long until a rate limit's quota is back, from an `X-Ratelimit-Reset` header given
+/// in Unix seconds (GitHub) or milliseconds (LaunchDarkly).
+pub fn rate_limit_reset(headers: &HeaderMap) -> Option<Duration> {
+    let value: i64 = headers
+        .get("x-ratelimit-reset")?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    // Timestamps in milliseconds have been past 10^11 since 1973
+    let at = if value > 100_000_000_000 {
+        DateTime::from_timestamp_millis(value)?
+    } else {
+        DateTime::from_timestamp(value, 0)?
+    };
+    Some(
+        (at - Utc::now())
+            .to_std()
+            .unwrap_or_default()
+            .min(MAX_RETRY_AFTER),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::http::HeaderMap;
+    use axum::routing::get;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    const FAST: RetryPolicy = RetryPolicy {
+        max_retries: 3,
+        base_delay: Duration::from_millis(1),
+    };
+
+    /// Serves the given statuses and headers in order (repeating the last), returning the
+    /// base URL and a counter of requests received.
+    async fn serve(
+        statuses: Vec<(u16, Option<(&'static str, &'static str)>)>,
+    ) -> (String, Arc<AtomicU32>) {
+        let hits = Arc::new(AtomicU32::new(0));
+        let counter = hits.clone();
+        let app = Router::new().route(
+            "/",
+            get(move || {
+                let n = counter.fetch_add(1, Ordering::SeqCst) as usize;
+                let (status, header) = statuses[n.min(statuses.len() - 1)];
+                async move {
+                    let mut headers = HeaderMap::new();
+                    if let Some((name, value)) = header {
+                        headers.insert(name, value.parse().unwrap());
+                    }
+                    (StatusCode::from_u16(status).unwrap(), headers, "body")
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, hits)
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_failures() {
+        let (url, hits) = serve(vec![
+            (502, None),
+            (429, Some(("retry-after", "0"))),
+            (200, None),
+        ])
+        .await;
+        let response = FAST
+            .send(reqwest::Client::new().get(&url), "Test")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_rate_limit_reset() {
+        let reset = |value: String| {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-ratelimit-reset", value.parse().unwrap());
+            rate_limit_reset(&headers)
+        };
+        let in_30s = Utc::now() + chrono::Duration::seconds(30);
+
+        let seconds = reset(in_30s.timestamp().to_string()).unwrap();
+        assert!(seconds > Duration::from_secs(28) && seconds <= Duration::from_secs(30));
+        let millis = reset(in_30s.timestamp_millis().to_string()).unwrap();
+        assert!(millis > Duration::from_secs(29) && millis <= Duration::from_secs(30));
+        // Already reset, or too far off to wait for
+        assert_eq!(reset("1000".to_string()), Some(Duration::ZERO));
+        let tomorrow = Utc::now() + chrono::Duration::days(1);
+        assert_eq!(
+            reset(tomorrow.timestamp().to_string()),
+            Some(MAX_RETRY_AFTER)
+        );
+        assert_eq!(reset("soon".to_string()), None);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() {
+        let (url, hits) = serve(vec![(503, None)]).await;
+        let response = FAST
+            .send(reqwest::Client::new().get(&url), "Test")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(hits.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_auth_failures_are_not_retried() {
+        let (url, hits) = serve(vec![(401, None)]).await;
+        let err = FAST
+            .send(reqwest::Client::new().get(&url), "Test")
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().starts_with("Test rejected the API token"),
+            "{}",
+            err
+        );
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_403s_are_retried() {
+        let (url, hits) = serve(vec![
+            (403, Some(("x-ratelimit-remaining", "0"))),
+            (403, Some(("retry-after", "0"))),
+            (200, None),
+        ])
+        .await;
+        let response = FAST
+            .send(reqwest::Client::new().get(&url), "Test")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+        // Still rate limited after every retry, it's returned for the caller to check
+        let (url, hits) = serve(vec![(403, Some(("retry-after", "0")))]).await;
+        let response = FAST
+            .send(reqwest::Client::new().get(&url), "Test")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(hits.load(Ordering::SeqCst), 4);
+
+        // A 403 with requests remaining is about the token
+        let (url, hits) = serve(vec![(403, Some(("x-ratelimit-remaining", "4999")))]).await;
+        let err = FAST
+            .send(reqwest::Client::new().get(&url), "Test")
+            .await
+            .unwrap_err();
+        assert!(err.downcast_ref::<TokenRejected>().is_some(), "{}", err);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CheckSource {
+    GitHub,
+    LaunchDarkly,
+    /// Problems with work-driver itself, e.g. a checker that failed to run.
+    WorkDriver,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IssueKind {
+    FailingChecks,
+    PendingChecks,
+    AwaitingReview,
+    AwaitingTeamReview,
+    DraftReady,
+    MissingReadyLabel,
+    ReadyToMerge,
+    ChangesRequested,
+    MergeConflicts,
+    UnresolvedThreads,
+    AwaitingYourReply,
+    AwaitingTheirReply,
+    BehindBase,
+    MissingRequiredCheck,
+    NoChecks,
+    AutoMergeStuck,
+    Unreviewed,
+    AwaitingApprovals,
+    BaseMerged,
+    NoCodeOwner,
+    ClosedUnmerged,
+    AutoCloseSoon,
+    PendingReview,
+    AssignedIssue,
+    MilestoneDue,
+    FollowUp,
+    Mentioned,
+    WorkflowFailing,
+    DeploymentWaiting,
+    SecurityAlert,
+    RolloutNotStarted,
+    StaleRollout,
+    RampDue,
+    ReadyForRemoval,
+    FlagDrift,
+    ApprovalRequested,
+    ScheduledChangeOverdue,
+    PrerequisiteOff,
+    FlagModified,
+    RolloutRolledBack,
+    ExperimentRunning,
+    DeadFlags,
+    TokenAccess,
+    EmptySegment,
+    StaleSegment,
+    UnknownEnabledVariation,
+    CheckerFailed,
+}
+
+/// How urgently an issue needs attention. Ordered so `High` is the greatest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    pub fn css_name(self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Issue {
+    /// Identifies the underlying thing (PR, flag environment) independent of its wording, so
+    /// seen/notified state survives title or percentage changes. e.g. "github:figma/figma:pr:591746" or
+    /// "ld:default:my-flag:production".
+    pub id: String,
+    pub source: CheckSource,
+    pub kind: IssueKind,
+    pub severity: Severity,
+    /// What the issue is about, e.g. "PR #123 'title'" or "Flag 'name'".
+    pub title: String,
+    /// What needs attention, e.g. "has failing checks".
+    pub detail: String,
+    pub url: Option<String>,
+    /// What a rolled-up issue covers, listed under it on the dashboard but left out of
+    /// notifications. Empty for most issues.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<IssueItem>,
+}
+
+/// One of the things a rolled-up issue covers, e.g. a flag.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IssueItem {
+    pub text: String,
+    pub url: Option<String>,
+}
+
+impl IssueKind {
+    /// Kinds that notify once and are never re-notified, because each new occurrence
+    /// gets its own id, e.g. a workflow failing on a new commit.
+    pub fn notifies_once(self) -> bool {
+        matches!(
+            self,
+            IssueKind::WorkflowFailing | IssueKind::FlagModified | IssueKind::RolloutRolledBack
+        )
+    }
+
+    /// Kinds that are reminders rather than problems that go away, so marking one seen
+    /// acknowledges it for good.
+    pub fn acknowledged_when_seen(self) -> bool {
+        matches!(
+            self,
+            IssueKind::FollowUp | IssueKind::FlagModified | IssueKind::DeadFlags
+        )
+    }
+}
+
+impl Issue {
+    pub fn checker_failed(checker_name: &str, error: &anyhow::Error) -> Self {
+        Self {
+            id: format!("checker:{}", checker_name),
+            source: CheckSource::WorkDriver,
+            kind: IssueKind::CheckerFailed,
+            severity: Severity::High,
+            title: format!("Checker '{}'", checker_name),
+            detail: format!("failed: {:#}", error),
+            url: None,
+            items: Vec::new(),
+        }
+    }
+
+    pub fn checker_misconfigured(checker_name: &str, error: &anyhow::Error) -> Self {
+        Self {
+            detail: format!("is misconfigured: {:#}", error),
+            ..Self::checker_failed(checker_name, error)
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.title, self.detail)
+    }
+}
+
+/// Drops later issues that share an ID with an earlier one, keeping the first.
+pub fn dedup_by_id(issues: &mut Vec<Issue>) {
+    let mut ids = HashSet::new();
+    issues.retain(|issue| ids.insert(issue.id.clone()));
+}
@@ -1,23 +1,102 @@
-use crate::check::Check;
+use crate::check::{Check, CheckOutput};
+use crate::config::{
+    BusinessDays, IgnoreTagsByRule, LaunchDarklyConfig, LaunchDarklyEnvironment, require,
+};
+use crate::http::{TokenRejected, rate_limit_reset, send_with_retry};
+use crate::issue::{CheckSource, Issue, IssueItem, IssueKind, Severity};
+use crate::notifier::{format_age, format_minutes};
+use crate::state::{FlagModification, RolloutProgress, State, StateUpdate, load_state};
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Local, NaiveTime, TimeZone, Utc};
+use futures_util::{StreamExt, stream};
+use reqwest::header::HeaderMap;
+use reqwest::{RequestBuilder, Response, StatusCode, Url};
 use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{debug, warn};
 
+/// A flag in the list. The list's expanded representation includes the details of the
+/// environments asked for, so they only need fetching separately when it doesn't.
 #[derive(Debug, Deserialize)]
 struct LaunchDarklyFlag {
     key: String,
     name: String,
+    kind: Option<String>,
+    variations: Option<Vec<Variation>>,
+    environments: Option<HashMap<String, Environment>>,
+    #[serde(default)]
+    temporary: bool,
+    #[serde(rename = "creationDate")]
+    creation_date: Option<i64>,
+    #[serde(default)]
+    tags: Vec<String>,
+    /// The maintainer team it was listed for, when we don't maintain it ourselves.
+    #[serde(skip)]
+    team: Option<String>,
+}
+
+impl LaunchDarklyFlag {
+    /// When the flag was last changed in any of `pipeline`'s environments, if the list
+    /// included them.
+    fn last_modified(&self, pipeline: &[LaunchDarklyEnvironment]) -> Option<i64> {
+        let environments = self.environments.as_ref()?;
+        pipeline
+            .iter()
+            .filter_map(|env| environments.get(&env.name)?.last_modified)
+            .max()
+    }
+
+    /// Takes the flag's details out of the list entry, when the list included all of them.
+    fn take_detail(&mut self) -> Option<LaunchDarklyFlagDetail> {
+        if self.kind.is_none() || self.variations.is_none() || self.environments.is_none() {
+            return None;
+        }
+        Some(LaunchDarklyFlagDetail {
+            key: self.key.clone(),
+            name: self.name.clone(),
+            kind: self.kind.take()?,
+            variations: self.variations.take()?,
+            environments: self.environments.take()?,
+            temporary: self.temporary,
+            creation_date: self.creation_date,
+            tags: std::mem::take(&mut self.tags),
+            team: self.team.clone(),
+            experiments: HashMap::new(),
+        })
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct LaunchDarklyFlagDetail {
-    #[allow(dead_code)]
     key: String,
     name: String,
     kind: String,
     variations: Vec<Variation>,
-    environments: std::collections::HashMap<String, Environment>,
+    environments: HashMap<String, Environment>,
+    /// Whether the flag is meant to be removed once rolled out.
+    #[serde(default)]
+    temporary: bool,
+    /// In milliseconds since the epoch, like `lastModified`.
+    #[serde(rename = "creationDate")]
+    creation_date: Option<i64>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(skip)]
+    team: Option<String>,
+    /// Experiments running on the flag, by environment, when they could be fetched.
+    #[serde(skip)]
+    experiments: HashMap<String, Experiment>,
+}
+
+impl LaunchDarklyFlagDetail {
+    fn tagged_any(&self, tags: &[String]) -> bool {
+        self.tags.iter().any(|tag| tags.contains(tag))
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -34,17 +113,89 @@ struct Environment {
     #[serde(rename = "lastModified")]
     last_modified: Option<i64>,
     on: bool,
+    /// The variation everyone is served while targeting is off.
+    #[serde(rename = "offVariation")]
+    off_variation: Option<i32>,
     fallthrough: Option<Fallthrough>,
+    /// Targeting rules, which serve a variation or a rollout to contexts matching their
+    /// clauses. The clauses themselves aren't needed.
+    #[serde(default)]
+    rules: Vec<TargetingRule>,
+    /// Contexts served a variation individually: users in `targets`, other context kinds
+    /// in `contextTargets`.
+    #[serde(default)]
+    targets: Vec<Target>,
+    #[serde(default, rename = "contextTargets")]
+    context_targets: Vec<Target>,
+    /// Flags that must serve a certain variation here for this one to be evaluated at
+    /// all; otherwise everyone gets the off variation.
+    #[serde(default)]
+    prerequisites: Vec<Prerequisite>,
+}
+
+impl Environment {
+    /// The keys of the segments targeting rules here match contexts against.
+    fn segment_keys(&self) -> impl Iterator<Item = &str> {
+        self.rules
+            .iter()
+            .flat_map(|rule| &rule.clauses)
+            .filter(|clause| clause.op == "segmentMatch")
+            .flat_map(|clause| &clause.values)
+            .filter_map(|value| value.as_str())
+    }
+
+    /// Whether part of the traffic is allocated to an experiment, which holds the
+    /// rollout where it is while it's measured.
+    fn runs_experiment(&self) -> bool {
+        let fallthrough = self.fallthrough.as_ref().and_then(|f| f.rollout.as_ref());
+        fallthrough
+            .into_iter()
+            .chain(self.rules.iter().filter_map(|rule| rule.rollout.as_ref()))
+            .any(|rollout| rollout.experiment_allocation.is_some())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Prerequisite {
+    key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Target {
+    values: Vec<String>,
+    variation: i32,
+}
+
+#[derive(Debug, Deserialize)]
+struct TargetingRule {
+    variation: Option<i32>,
+    rollout: Option<Rollout>,
+    /// What contexts must match; only needed for the segments rules use.
+    #[serde(default)]
+    clauses: Vec<Clause>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Clause {
+    /// E.g. "in", or "segmentMatch" when `values` are segment keys.
+    op: String,
+    #[serde(default)]
+    values: Vec<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
 struct Fallthrough {
+    /// Set instead of `rollout` when everyone falls through to the same variation.
+    variation: Option<i32>,
     rollout: Option<Rollout>,
 }
 
 #[derive(Debug, Deserialize)]
 struct Rollout {
     variations: Vec<WeightedVariation>,
+    /// Set when the rollout allocates traffic to an experiment.
+    #[serde(rename = "experimentAllocation")]
+    experiment_allocation: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -54,205 +205,4302 @@ struct WeightedVariation {
 }
 
 #[derive(Debug, Deserialize)]
-struct LaunchDarklyResponse {
-    items: Vec<LaunchDarklyFlag>,
+struct LaunchDarklyResponse<T> {
+    items: Vec<T>,
+    #[serde(default, rename = "_links")]
+    links: Links,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Links {
+    /// Absent on the last page.
+    next: Option<Link>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Link {
+    /// Usually a path, e.g. "/api/v2/flags/default?limit=20&offset=20".
+    href: String,
+}
+
+/// A request to approve changes to a flag, from the account's list of them.
+#[derive(Debug, Deserialize)]
+struct ApprovalRequest {
+    #[serde(rename = "_id")]
+    id: String,
+    #[serde(rename = "requestorId")]
+    requestor_id: String,
+    #[serde(rename = "creationDate")]
+    creation_date: i64,
+    /// "pending" until it's approved or declined.
+    #[serde(rename = "reviewStatus")]
+    review_status: String,
+    /// The members asked to review it.
+    #[serde(default, rename = "notifyMemberIds")]
+    notify_member_ids: Vec<String>,
+    /// What it changes, e.g. "proj/default:env/production:flag/new-checkout".
+    #[serde(rename = "resourceId")]
+    resource_id: String,
+    /// Included with `expand=flag`.
+    flag: Option<ApprovalFlag>,
+}
+
+/// A segment in one environment, from the list of them.
+#[derive(Debug, Deserialize)]
+struct Segment {
+    key: String,
+    name: String,
+    /// Targeting rules; only how many there are is needed.
+    #[serde(default)]
+    rules: Vec<serde_json::Value>,
+    /// Users included individually, and other context kinds in `includedContexts`.
+    #[serde(default)]
+    included: Vec<String>,
+    #[serde(default, rename = "includedContexts")]
+    included_contexts: Vec<serde_json::Value>,
+    /// Big segments are synced from elsewhere and don't list who's in them.
+    #[serde(default)]
+    unbounded: bool,
+    /// In milliseconds since the epoch, like flags' `lastModified`.
+    #[serde(rename = "lastModifiedDate")]
+    last_modified: Option<i64>,
+    #[serde(rename = "creationDate")]
+    creation_date: Option<i64>,
+}
+
+impl Segment {
+    /// Whether nobody can be in it: no rules, and nobody included individually.
+    fn is_empty(&self) -> bool {
+        !self.unbounded
+            && self.rules.is_empty()
+            && self.included.is_empty()
+            && self.included_contexts.is_empty()
+    }
+}
+
+/// An experiment on a flag in one environment, from the experiments API.
+#[derive(Debug, Clone, Deserialize)]
+struct Experiment {
+    key: String,
+    name: String,
+    #[serde(rename = "currentIteration")]
+    current_iteration: Option<ExperimentIteration>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ExperimentIteration {
+    /// In milliseconds since the epoch; absent until it's started.
+    #[serde(rename = "startedAt")]
+    started_at: Option<i64>,
+}
+
+/// A change to a flag in one environment, scheduled to be made at `execution_date`.
+/// Changes are only listed until they've been made.
+#[derive(Debug, Deserialize)]
+struct ScheduledChange {
+    #[serde(rename = "_id")]
+    id: String,
+    #[serde(rename = "executionDate")]
+    execution_date: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApprovalFlag {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Member {
+    #[serde(rename = "_id")]
+    id: String,
+    #[serde(rename = "firstName")]
+    first_name: Option<String>,
+    email: String,
+}
+
+/// A change recorded in the audit log.
+#[derive(Debug, Deserialize)]
+struct AuditEntry {
+    #[serde(rename = "_id")]
+    id: String,
+    /// In milliseconds since the epoch.
+    date: i64,
+    /// What was done, e.g. "turned off the flag".
+    #[serde(rename = "titleVerb")]
+    title_verb: Option<String>,
+    /// Absent for changes made with an API token.
+    member: Option<Member>,
+    #[serde(default)]
+    target: AuditTarget,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AuditTarget {
+    /// Like an approval request's `resource_id`.
+    #[serde(default)]
+    resources: Vec<String>,
+}
+
+/// What's been read from the audit log, kept in the state file between checks.
+#[derive(Debug, Default)]
+struct AuditLog {
+    /// The time each project's entries were last read up to, in milliseconds.
+    after: HashMap<String, i64>,
+    /// Changes others made to our flags, by entry ID.
+    modifications: HashMap<String, FlagModification>,
+}
+
+/// What the checker keeps in the state file between checks.
+#[derive(Debug, Default)]
+struct Tracked {
+    flags_on: FlagsOn,
+    rollouts: Rollouts,
+    audit: AuditLog,
+    /// Per project, the key of the last flag checked in turn, for when there are too many
+    /// to check at once.
+    cursors: HashMap<String, String>,
+    /// Each flag's issues as of the last time it was checked, by "{project}:{flag}",
+    /// reported again while it waits its turn.
+    flag_issues: HashMap<String, Vec<Issue>>,
+    /// Whether any project had too many flags to check at once.
+    rotated: bool,
+    /// Flags found off everywhere for a long time, when they're due to be reported.
+    dead_flags: Option<Vec<IssueItem>>,
+}
+
+/// How far flags' rollouts had got in each pipeline environment, by
+/// "{project}:{flag}:{env}", so a rollout is only stale once the percentage stops going up.
+#[derive(Debug, Default)]
+struct Rollouts {
+    /// As of the last check.
+    before: HashMap<String, RolloutProgress>,
+    /// As of this one.
+    now: HashMap<String, RolloutProgress>,
+}
+
+/// Which pipeline environments flags have been seen on in, as "{project}:{flag}:{env}",
+/// for telling a flag turned off from one that hasn't started yet.
+#[derive(Debug, Default)]
+struct FlagsOn {
+    /// As of the last check.
+    before: HashSet<String>,
+    /// As of this one. Environments since turned off stay in while they're drifting.
+    now: HashSet<String>,
+}
+
+/// Stops a `next` link that never runs out from paging forever.
+const MAX_LIST_PAGES: usize = 100;
+
+/// How far back each read of the audit log overlaps the last one, for entries that
+/// show up late. Entries already seen are skipped by ID.
+const AUDIT_OVERLAP_MS: i64 = 10 * 60 * 1000;
+
+/// How long a change someone else made to our flags is reported if it's not marked seen.
+const MODIFICATION_DAYS: i64 = 7;
+
+/// How long a token that worked for every project is trusted before it's checked again.
+const TOKEN_VALIDATION_HOURS: i64 = 24;
+
+/// What the API token turned out to be able to do.
+#[derive(Debug, PartialEq)]
+enum TokenAccess {
+    /// It can't be used at all: a 401 when it's invalid or revoked, a 403 when its role
+    /// can't use the API.
+    Rejected(StatusCode),
+    /// It works, but can't read these projects: a 403 without a role that can, a 404
+    /// when there's no such project.
+    Usable {
+        unreadable: Vec<(String, StatusCode)>,
+    },
 }
 
+/// How long connecting to the API, and each request as a whole, can take.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub struct LaunchDarklyChecker {
+    /// Shared by every request, so connections are reused across checks.
+    client: reqwest::Client,
     api_token: String,
     maintainer_id: String,
-    project_key: String,
+    /// Teams we're on whose flags are checked too.
+    maintainer_team_keys: Vec<String>,
+    /// Always at least one.
+    project_keys: Vec<String>,
+    /// Names of boolean flags' enabled variations, in order of preference.
+    enabled_variation_names: Vec<String>,
+    /// Only flags with these tags are listed, when set.
+    tags: Vec<String>,
+    ignore_tags: Vec<String>,
+    ignore_tags_by_rule: IgnoreTagsByRule,
+    base_url: String,
+    /// `base_url` parsed, for building API URLs and links to pages of the app.
+    app_url: Url,
+    /// How many flags' details are fetched at once.
+    max_concurrent_requests: usize,
+    /// How many of a project's flags are checked each time; the rest wait their turn.
+    max_flags_per_check: usize,
+    /// The rollout pipeline, in order.
+    environments: Vec<LaunchDarklyEnvironment>,
+    targets_count_as_started: bool,
+    /// When set, staleness only counts time on business days.
+    business_days: Option<BusinessDays>,
+    /// How long a temporary flag can be fully rolled out before it's reported for removal.
+    removal_after_ms: i64,
+    /// How long an environment can be off while the one before it is on before it's
+    /// reported as drifting; None to not track which environments flags were on in.
+    drift_after_ms: Option<i64>,
+    /// How long a partial rollout can go before it's due a bump; None to not remind.
+    ramp_after_ms: Option<i64>,
+    /// `ramp_after_ms` for flags with these tags.
+    ramp_after_ms_by_tag: HashMap<String, i64>,
+    /// Whether rollout percentages are tracked across checks.
+    track_rollouts: bool,
+    validate_token: bool,
+    /// How long an experiment can run before it's reported.
+    experiment_after_ms: i64,
+    /// How often flags off everywhere are looked for; None to not.
+    dead_flags_every_ms: Option<i64>,
+    dead_flag_days: i64,
+    segments: bool,
+    segment_stale_ms: i64,
+    approval_requests: bool,
+    scheduled_changes: bool,
+    audit_log: bool,
+    /// Set when the rate limit is about to run out, to hold off requests until it resets.
+    resume_at: Mutex<Option<Instant>>,
+    timeout: Duration,
+    interval: Option<Duration>,
 }
 
 impl LaunchDarklyChecker {
-    pub fn new() -> Result<Self> {
-        let api_token = std::env::var("LAUNCHDARKLY_API_TOKEN")
-            .context("LAUNCHDARKLY_API_TOKEN environment variable not set")?;
-        let maintainer_id = std::env::var("LAUNCHDARKLY_MAINTAINER_ID")
-            .context("LAUNCHDARKLY_MAINTAINER_ID environment variable not set")?;
-        let project_key =
-            std::env::var("LAUNCHDARKLY_PROJECT_KEY").unwrap_or_else(|_| "default".to_string());
+    pub fn new(config: &LaunchDarklyConfig) -> Result<Self> {
+        let api_token = require(
+            &config.api_token,
+            "launchdarkly.api_token",
+            "LAUNCHDARKLY_API_TOKEN",
+        )?;
+        let maintainer_id = require(
+            &config.maintainer_id,
+            "launchdarkly.maintainer_id",
+            "LAUNCHDARKLY_MAINTAINER_ID",
+        )?;
+        let mut project_keys = config.project_keys.clone();
+        if project_keys.is_empty() {
+            project_keys.push("default".to_string());
+        }
+
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("work-driver/", env!("CARGO_PKG_VERSION")))
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .context("Failed to build the LaunchDarkly HTTP client")?;
+        let app_url = Url::parse(&config.base_url)
+            .ok()
+            .filter(|url| !url.cannot_be_a_base())
+            .with_context(|| {
+                format!(
+                    "launchdarkly.base_url '{}' isn't a valid URL",
+                    config.base_url
+                )
+            })?;
 
         Ok(Self {
-            api_token,
-            maintainer_id,
-            project_key,
+            client,
+            api_token: api_token.to_string(),
+            maintainer_id: maintainer_id.to_string(),
+            maintainer_team_keys: config.maintainer_team_keys.clone(),
+            project_keys,
+            enabled_variation_names: config.enabled_variation_names.clone(),
+            tags: config.tags.clone(),
+            ignore_tags: config.ignore_tags.clone(),
+            ignore_tags_by_rule: config.ignore_tags_by_rule.clone(),
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            app_url,
+            max_concurrent_requests: config.max_concurrent_requests.max(1),
+            max_flags_per_check: config.max_flags_per_check.max(2),
+            environments: config.environments.clone(),
+            targets_count_as_started: config.targets_count_as_started,
+            business_days: config.business_days.clone(),
+            removal_after_ms: config.removal_days * 24 * 60 * 60 * 1000,
+            drift_after_ms: config.drift_minutes.map(|minutes| minutes * 60 * 1000),
+            ramp_after_ms: config.ramp_minutes.map(|minutes| minutes * 60 * 1000),
+            ramp_after_ms_by_tag: config
+                .ramp_minutes_by_tag
+                .iter()
+                .map(|(tag, minutes)| (tag.clone(), minutes * 60 * 1000))
+                .collect(),
+            track_rollouts: config.track_rollouts,
+            validate_token: config.validate_token,
+            experiment_after_ms: config.experiment_days * 24 * 60 * 60 * 1000,
+            dead_flags_every_ms: config
+                .dead_flags_report_days
+                .map(|days| days * 24 * 60 * 60 * 1000),
+            dead_flag_days: config.dead_flag_days,
+            segments: config.segments,
+            segment_stale_ms: config.segment_stale_days * 24 * 60 * 60 * 1000,
+            approval_requests: config.approval_requests,
+            scheduled_changes: config.scheduled_changes,
+            audit_log: config.audit_log,
+            resume_at: Mutex::new(None),
+            timeout: Duration::from_secs(config.timeout_seconds),
+            interval: config
+                .interval_minutes
+                .map(|minutes| Duration::from_secs(minutes * 60)),
         })
     }
-}
 
-#[async_trait]
-impl Check for LaunchDarklyChecker {
-    async fn check(&self) -> Result<Vec<String>> {
-        let client = reqwest::Client::new();
+    /// Uses `client` for every request instead of the default one, e.g. with other
+    /// timeouts.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Sends a GET to the API, first waiting out the rate limit if the last response said
+    /// it was nearly used up. 429s are retried once the limit resets.
+    async fn get(&self, url: &str) -> Result<Response> {
+        self.send(self.client.get(url)).await
+    }
+
+    /// Sends `request` like `get`, e.g. with headers of its own.
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let resume_at = *self.resume_at.lock().unwrap();
+        if let Some(at) = resume_at {
+            tokio::time::sleep_until(at).await;
+        }
+        let response = send_with_retry(
+            request.header("Authorization", &self.api_token),
+            "LaunchDarkly",
+        )
+        .await?;
+        if let Some(wait) = quota_wait(response.headers(), self.max_concurrent_requests) {
+            debug!(
+                ?wait,
+                "LaunchDarkly rate limit nearly used up, slowing down"
+            );
+            *self.resume_at.lock().unwrap() = Some(Instant::now() + wait);
+        }
+        Ok(response)
+    }
+
+    /// What the API token can do, from who LaunchDarkly says is calling and whether each
+    /// project can be read. An error means it couldn't be told, e.g. the API is down.
+    async fn token_access(&self) -> Result<TokenAccess> {
+        let rejected = |e: &anyhow::Error| e.downcast_ref::<TokenRejected>().map(|r| r.status);
+        let identity_url = self.api_url(&["caller-identity"], &[]);
+        match self.get(&identity_url).await {
+            Ok(response) => {
+                response
+                    .error_for_status()
+                    .context("Failed to fetch LaunchDarkly caller identity")?;
+            }
+            Err(e) => match rejected(&e) {
+                Some(status) => return Ok(TokenAccess::Rejected(status)),
+                None => return Err(e),
+            },
+        }
+
+        let mut unreadable = Vec::new();
+        for project in &self.project_keys {
+            let project_url = self.api_url(&["projects", project], &[]);
+            let status = match self.get(&project_url).await {
+                Ok(response) => response.status(),
+                Err(e) => rejected(&e).ok_or(e)?,
+            };
+            if matches!(status, StatusCode::FORBIDDEN | StatusCode::NOT_FOUND) {
+                unreadable.push((project.clone(), status));
+            }
+        }
+        Ok(TokenAccess::Usable { unreadable })
+    }
+
+    /// Issues for what the token can't do.
+    fn token_issues(&self, access: &TokenAccess) -> Vec<Issue> {
+        let issue = |id: String, detail: String| Issue {
+            id,
+            source: CheckSource::LaunchDarkly,
+            kind: IssueKind::TokenAccess,
+            severity: Severity::High,
+            title: "LaunchDarkly token".to_string(),
+            detail,
+            url: Some(self.page_url(&["settings", "authorization"], &[])),
+            items: Vec::new(),
+        };
+        match access {
+            TokenAccess::Rejected(StatusCode::UNAUTHORIZED) => vec![issue(
+                "ld:token".to_string(),
+                "is invalid or revoked (401); set a new launchdarkly.api_token".to_string(),
+            )],
+            TokenAccess::Rejected(status) => vec![issue(
+                "ld:token".to_string(),
+                format!(
+                    "was refused ({}); give it a role that can read flags",
+                    status
+                ),
+            )],
+            TokenAccess::Usable { unreadable } => unreadable
+                .iter()
+                .map(|(project, status)| {
+                    let detail = if *status == StatusCode::NOT_FOUND {
+                        format!(
+                            "can't find project '{}'; check launchdarkly.project_keys",
+                            project
+                        )
+                    } else {
+                        format!("lacks reader access to project '{}'", project)
+                    };
+                    issue(format!("ld:token:{}", project), detail)
+                })
+                .collect(),
+        }
+    }
 
-        // First, list all flags for this maintainer
-        let list_url = format!(
-            "https://app.launchdarkly.com/api/v2/flags/{}?filter=maintainerId:{}",
-            self.project_key, self.maintainer_id
+    /// Every flag in `project` matching `filter`, e.g. "maintainerId:abc", and any `tags`,
+    /// following the list's `next` links page by page. Asks for the expanded
+    /// representation with our environments inline.
+    async fn list_flags(&self, project: &str, filter: &str) -> Result<Vec<LaunchDarklyFlag>> {
+        let filter = if self.tags.is_empty() {
+            filter.to_string()
+        } else {
+            // Tags are separated by a `+`, which is how the space is encoded
+            format!("{},tags:{}", filter, self.tags.join(" "))
+        };
+        let mut query = vec![("filter", filter.as_str()), ("summary", "false")];
+        query.extend(
+            self.environments
+                .iter()
+                .map(|env| ("env", env.name.as_str())),
         );
+        let url = self.api_url(&["flags", project], &query);
+        self.list(url, "flags list").await
+    }
 
-        let response = client
-            .get(&list_url)
-            .header("Authorization", &self.api_token)
-            .send()
-            .await
-            .context("Failed to fetch LaunchDarkly flags list")?;
+    /// Every item of the list at `url`, following its `next` links page by page.
+    async fn list<T: DeserializeOwned>(&self, mut url: String, what: &str) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        for _ in 0..MAX_LIST_PAGES {
+            let response = self
+                .get(&url)
+                .await
+                .with_context(|| format!("Failed to fetch LaunchDarkly {}", what))?;
 
-        if !response.status().is_success() {
-            return Err(anyhow::anyhow!(
-                "LaunchDarkly API returned error: {}",
-                response.status()
-            ));
+            if !response.status().is_success() {
+                return Err(anyhow::anyhow!(
+                    "LaunchDarkly API returned error: {}",
+                    response.status()
+                ));
+            }
+
+            let page: LaunchDarklyResponse<T> = response
+                .json()
+                .await
+                .context("Failed to parse LaunchDarkly response")?;
+            let empty = page.items.is_empty();
+            items.extend(page.items);
+            match page.links.next {
+                Some(next) if !empty => {
+                    url = if next.href.starts_with('/') {
+                        format!("{}{}", self.base_url, next.href)
+                    } else {
+                        next.href
+                    };
+                }
+                _ => return Ok(items),
+            }
         }
+        warn!(
+            pages = MAX_LIST_PAGES,
+            "LaunchDarkly {} has too many pages, ignoring the rest", what
+        );
+        Ok(items)
+    }
 
-        let data: LaunchDarklyResponse = response
-            .json()
-            .await
-            .context("Failed to parse LaunchDarkly response")?;
+    /// Approval requests waiting on our review, in any project.
+    async fn approval_issues(&self, now: i64) -> Result<Vec<Issue>> {
+        let filter = format!(
+            "notifyMemberIds anyOf [\"{}\"],reviewStatus anyOf [\"pending\"]",
+            self.maintainer_id
+        );
+        let url = self.api_url(
+            &["approval-requests"],
+            &[("filter", &filter), ("expand", "flag")],
+        );
+        let requests: Vec<ApprovalRequest> = self.list(url, "approval requests").await?;
+        debug!(count = requests.len(), "fetched approval requests");
 
+        let mut requestors: HashMap<String, String> = HashMap::new();
         let mut issues = Vec::new();
-        let now = Utc::now().timestamp_millis();
-        let two_hours_ago = now - (2 * 60 * 60 * 1000);
-        let eighteen_hours_ago = now - (18 * 60 * 60 * 1000);
-
-        // For each flag, fetch detailed info with staging and production environments
-        for flag in data.items {
-            let detail_url = format!(
-                "https://app.launchdarkly.com/api/v2/flags/{}/{}",
-                self.project_key, flag.key
+        for request in requests {
+            // The filter should already leave out the rest, but it's cheap to be sure
+            if request.review_status != "pending"
+                || !request.notify_member_ids.contains(&self.maintainer_id)
+            {
+                continue;
+            }
+            let (Some(project), Some(env), Some(flag_key)) = (
+                resource_key(&request.resource_id, "proj"),
+                resource_key(&request.resource_id, "env"),
+                resource_key(&request.resource_id, "flag"),
+            ) else {
+                debug!(resource = %request.resource_id, "approval request isn't for a flag");
+                continue;
+            };
+            if !requestors.contains_key(&request.requestor_id) {
+                let name = self.member_name(&request.requestor_id).await;
+                requestors.insert(request.requestor_id.clone(), name);
+            }
+            let flag_name = request.flag.as_ref().map_or(flag_key, |flag| &flag.name);
+            issues.push(Issue {
+                id: format!("ld:approval:{}", request.id),
+                source: CheckSource::LaunchDarkly,
+                kind: IssueKind::ApprovalRequested,
+                severity: Severity::Medium,
+                title: format!("Approval request for flag '{}'", flag_name),
+                detail: format!(
+                    "in {} waiting on you (requested by {}, {} ago)",
+                    env,
+                    requestors[&request.requestor_id],
+                    format_age(chrono::Duration::milliseconds(now - request.creation_date))
+                ),
+                url: Some(self.page_url(&["projects", project, "approvals", &request.id], &[])),
+                items: Vec::new(),
+            });
+        }
+        Ok(issues)
+    }
+
+    /// Records changes others made to `flags` in `project` since the audit log was last
+    /// read. The first read only notes where to start from. Failures are logged, and the
+    /// next check reads from the same place.
+    async fn read_audit_log(
+        &self,
+        project: &str,
+        flags: &[LaunchDarklyFlag],
+        now: i64,
+        audit: &mut AuditLog,
+    ) {
+        let Some(&after) = audit.after.get(project) else {
+            audit.after.insert(project.to_string(), now);
+            return;
+        };
+        let url = self.api_url(
+            &["auditlog"],
+            &[
+                ("after", &(after - AUDIT_OVERLAP_MS).to_string()),
+                ("spec", &format!("proj/{}:env/*:flag/*", project)),
+            ],
+        );
+        let entries: Vec<AuditEntry> = match self.list(url, "audit log").await {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!(project, "failed to read LaunchDarkly audit log: {:#}", e);
+                return;
+            }
+        };
+        debug!(project, count = entries.len(), "read audit log");
+
+        for entry in entries {
+            if audit.modifications.contains_key(&entry.id)
+                || entry
+                    .member
+                    .as_ref()
+                    .is_some_and(|member| member.id == self.maintainer_id)
+            {
+                continue;
+            }
+            // Other projects' flags can share our flags' keys
+            let Some((resource, flag)) = entry.target.resources.iter().find_map(|resource| {
+                let key = resource_key(resource, "flag")?;
+                if resource_key(resource, "proj") != Some(project) {
+                    return None;
+                }
+                Some((resource, flags.iter().find(|flag| flag.key == key)?))
+            }) else {
+                continue;
+            };
+            let by = match &entry.member {
+                Some(Member {
+                    first_name: Some(name),
+                    ..
+                }) => name.clone(),
+                Some(member) => member.email.clone(),
+                None => "an API token".to_string(),
+            };
+            let action = entry
+                .title_verb
+                .as_deref()
+                .map(|verb| verb.trim_end_matches(" the flag").to_string());
+            audit.modifications.insert(
+                entry.id.clone(),
+                FlagModification {
+                    project: project.to_string(),
+                    flag_key: flag.key.clone(),
+                    flag_name: flag.name.clone(),
+                    environment: resource_key(resource, "env").map(str::to_string),
+                    by,
+                    action,
+                    date: DateTime::from_timestamp_millis(entry.date).unwrap_or_default(),
+                },
             );
+        }
+        audit.after.insert(project.to_string(), now);
+    }
 
-            let detail_response = client
-                .get(&detail_url)
-                .header("Authorization", &self.api_token)
-                .send()
-                .await
-                .context("Failed to fetch flag details")?;
+    /// An issue for a change someone else made to one of our flags.
+    fn modification_issue(
+        &self,
+        id: &str,
+        modification: &FlagModification,
+        now: DateTime<Utc>,
+    ) -> Issue {
+        let env_name = modification
+            .environment
+            .as_deref()
+            .or(self.environments.last().map(|env| env.name.as_str()))
+            .unwrap_or_default();
+        let mut detail = match &modification.environment {
+            Some(env) => format!("modified in {} by {}", env, modification.by),
+            None => format!("modified by {}", modification.by),
+        };
+        detail.push_str(&format!(" {} ago", format_age(now - modification.date)));
+        if let Some(action) = &modification.action {
+            detail.push_str(&format!(" ({})", action));
+        }
+        Issue {
+            id: format!("ld:audit:{}", id),
+            source: CheckSource::LaunchDarkly,
+            kind: IssueKind::FlagModified,
+            severity: Severity::Medium,
+            title: format!("Flag '{}'", modification.flag_name),
+            detail,
+            url: Some(self.flag_url(&modification.project, &modification.flag_key, env_name)),
+            items: Vec::new(),
+        }
+    }
+
+    /// A member's first name, or their email without one. Falls back to their ID when
+    /// they can't be fetched, which is logged.
+    async fn member_name(&self, id: &str) -> String {
+        let url = self.api_url(&["members", id], &[]);
+        let member: Result<Member> = async {
+            let response = self.get(&url).await?.error_for_status()?;
+            Ok(response.json().await?)
+        }
+        .await;
+        match member {
+            Ok(Member {
+                first_name: Some(name),
+                ..
+            }) => name,
+            Ok(member) => member.email,
+            Err(e) => {
+                warn!(member = id, "failed to fetch LaunchDarkly member: {:#}", e);
+                id.to_string()
+            }
+        }
+    }
+
+    /// Issues for the flags we maintain in `project`.
+    async fn check_project(
+        &self,
+        project: &str,
+        now: i64,
+        tracked: &mut Tracked,
+    ) -> Result<Vec<Issue>> {
+        // First, list all flags for this maintainer, then those of their teams
+        let maintainer_filter = format!("maintainerId:{}", self.maintainer_id);
+        let mut flags = self.list_flags(project, &maintainer_filter).await?;
+        for team in &self.maintainer_team_keys {
+            let team_filter = format!("maintainerTeamKey:{}", team);
+            for mut flag in self.list_flags(project, &team_filter).await? {
+                if !flags.iter().any(|listed| listed.key == flag.key) {
+                    flag.team = Some(team.clone());
+                    flags.push(flag);
+                }
+            }
+        }
+        flags.retain(|flag| !flag.tags.iter().any(|tag| self.ignore_tags.contains(tag)));
+        debug!(project, count = flags.len(), "fetched maintained flags");
+
+        if self.audit_log {
+            self.read_audit_log(project, &flags, now, &mut tracked.audit)
+                .await;
+        }
 
-            if !detail_response.status().is_success() {
-                eprintln!(
-                    "Failed to fetch details for flag '{}': {}",
-                    flag.name,
-                    detail_response.status()
+        // With too many flags to check at once, the rest keep their issues from the last
+        // time they were checked
+        let prefix = format!("{}:", project);
+        let mut carried_over = Vec::new();
+        let mut picked = None;
+        if flags.len() > self.max_flags_per_check {
+            let (keys, cursor) = pick_flags(
+                &flags,
+                self.max_flags_per_check,
+                tracked.cursors.get(project).map(String::as_str),
+                &self.environments,
+            );
+            debug!(
+                project,
+                picked = keys.len(),
+                ?cursor,
+                "too many flags, checking some"
+            );
+            tracked.rotated = true;
+            if let Some(cursor) = cursor {
+                tracked.cursors.insert(project.to_string(), cursor);
+            }
+            tracked.flag_issues.retain(|flag, _| {
+                flag.strip_prefix(&prefix)
+                    .is_none_or(|key| flags.iter().any(|listed| listed.key == key))
+            });
+            for flag in flags.iter().filter(|flag| !keys.contains(&flag.key)) {
+                let flag_id = format!("{}:{}", project, flag.key);
+                carried_over.extend(
+                    tracked
+                        .flag_issues
+                        .get(&flag_id)
+                        .into_iter()
+                        .flatten()
+                        .cloned(),
                 );
-                continue;
+                let on_prefix = format!("{}:", flag_id);
+                let was_on = tracked
+                    .flags_on
+                    .before
+                    .iter()
+                    .filter(|id| id.starts_with(&on_prefix));
+                tracked.flags_on.now.extend(was_on.cloned());
+                let progress = tracked
+                    .rollouts
+                    .before
+                    .iter()
+                    .filter(|(id, _)| id.starts_with(&on_prefix));
+                tracked
+                    .rollouts
+                    .now
+                    .extend(progress.map(|(id, progress)| (id.clone(), progress.clone())));
             }
+            flags.retain(|flag| keys.contains(&flag.key));
+            picked = Some(keys);
+        } else {
+            tracked.cursors.remove(project);
+            tracked
+                .flag_issues
+                .retain(|flag, _| !flag.starts_with(&prefix));
+        }
 
-            let mut flag_detail: LaunchDarklyFlagDetail = detail_response
-                .json()
-                .await
-                .context("Failed to parse flag details")?;
-            flag_detail
-                .environments
-                .retain(|env_name, _env| env_name == "staging" || env_name == "production");
+        let mut details = Vec::new();
+        let mut incomplete = Vec::new();
+        for mut flag in flags {
+            match flag.take_detail() {
+                Some(detail) => details.push(detail),
+                None => incomplete.push(flag),
+            }
+        }
+        debug!(
+            project,
+            inline = details.len(),
+            incomplete = incomplete.len(),
+            "flag details from the list"
+        );
 
-            // Get rollout percentages for both environments
-            let staging_rollout = flag_detail
-                .environments
-                .get("staging")
-                .and_then(|env| get_rollout_percentage(&flag_detail, env));
-            let production_rollout = flag_detail
-                .environments
-                .get("production")
-                .and_then(|env| get_rollout_percentage(&flag_detail, env));
-
-            // Check if staging is finished rolling out, but production isn't started
-            if let (Some(staging), Some(production)) = (staging_rollout, production_rollout) {
-                if staging >= 50.0 && production == 0.0 {
-                    issues.push(format!(
-                        "Flag '{}' [{}:{}:production] rolled out to {:.0}% in staging, but not started in production",
-                        flag_detail.name, self.project_key, flag.key, staging
-                    ));
+        // Fetch detailed info for flags the list left it out of, a few flags at a time
+        let fetches: Vec<_> = incomplete
+            .iter()
+            .map(|flag| self.fetch_flag_detail(project, &flag.key))
+            .collect();
+        let results: Vec<Result<Option<LaunchDarklyFlagDetail>>> = stream::iter(fetches)
+            .buffer_unordered(self.max_concurrent_requests)
+            .collect()
+            .await;
+        for result in results {
+            if let Some(mut detail) = result? {
+                detail.team = incomplete
+                    .iter()
+                    .find(|flag| flag.key == detail.key)
+                    .and_then(|flag| flag.team.clone());
+                details.push(detail);
+            }
+        }
+
+        let mut issues = self.prerequisite_issues(project, &details).await;
+        if self.scheduled_changes {
+            // Environments found not to have scheduled changes, so the rest of the flags
+            // don't ask again
+            let unavailable = Mutex::new(HashSet::new());
+            let fetches: Vec<_> = details
+                .iter()
+                .flat_map(|detail| {
+                    self.environments
+                        .iter()
+                        .filter(|env| detail.environments.contains_key(&env.name))
+                        .map(|env| {
+                            self.overdue_scheduled_changes(
+                                project,
+                                detail,
+                                &env.name,
+                                now,
+                                &unavailable,
+                            )
+                        })
+                })
+                .collect();
+            let overdue: Vec<Vec<Issue>> = stream::iter(fetches)
+                .buffer_unordered(self.max_concurrent_requests)
+                .collect()
+                .await;
+            issues.extend(overdue.into_iter().flatten());
+        }
+        // Experiments holding flags' rollouts, fetched for their names and start dates
+        let unavailable = Mutex::new(HashSet::new());
+        let fetches: Vec<_> = details
+            .iter()
+            .flat_map(|detail| {
+                self.pipeline_envs(detail)
+                    .filter(|(_, env)| env.runs_experiment())
+                    .map(|(env_name, _)| {
+                        self.running_experiment(project, &detail.key, env_name, &unavailable)
+                    })
+            })
+            .collect();
+        let experiments: Vec<Option<(String, String, Experiment)>> = stream::iter(fetches)
+            .buffer_unordered(self.max_concurrent_requests)
+            .collect()
+            .await;
+        for (key, env_name, experiment) in experiments.into_iter().flatten() {
+            if let Some(detail) = details.iter_mut().find(|detail| detail.key == key) {
+                detail.experiments.insert(env_name, experiment);
+            }
+        }
+
+        if self.segments {
+            match self.segment_issues(project, &details, now).await {
+                Ok(segment_issues) => issues.extend(segment_issues),
+                Err(e) => {
+                    warn!(
+                        "Failed to check LaunchDarkly segments in {}: {:#}",
+                        project, e
+                    );
+                    let checker_name = format!("LaunchDarkly segments {}", project);
+                    issues.push(Issue::checker_failed(&checker_name, &e));
                 }
             }
+        }
+        if let Some(dead_flags) = &mut tracked.dead_flags {
+            dead_flags.extend(
+                details
+                    .iter()
+                    .filter_map(|detail| self.dead_flag(project, detail, now)),
+            );
+        }
+        for detail in details {
+            issues.extend(self.flag_issues(project, detail, now, tracked));
+        }
+        for key in picked.iter().flatten() {
+            let flag_id = format!("{}{}", prefix, key);
+            let issue_prefix = format!("ld:{}:", flag_id);
+            let flag_issues = issues
+                .iter()
+                .filter(|issue| issue.id.starts_with(&issue_prefix))
+                .cloned()
+                .collect();
+            tracked.flag_issues.insert(flag_id, flag_issues);
+        }
+        issues.extend(carried_over);
+        Ok(issues)
+    }
 
-            // Check each environment (staging and production) for stale partial rollouts
-            for (env_name, env) in &flag_detail.environments {
-                let Some(last_modified) = env.last_modified else {
-                    continue;
-                };
+    /// A flag's details in every environment, or None when they can't be fetched, which
+    /// is logged.
+    async fn fetch_flag_detail(
+        &self,
+        project: &str,
+        key: &str,
+    ) -> Result<Option<LaunchDarklyFlagDetail>> {
+        debug!(flag = key, "fetching flag details");
+        let detail_url = self.api_url(&["flags", project, key], &[]);
 
-                let (time_threshold, time_str) = if env_name == "staging" {
-                    (two_hours_ago, "2h")
-                } else {
-                    (eighteen_hours_ago, "18h")
-                };
+        let detail_response = self
+            .get(&detail_url)
+            .await
+            .context("Failed to fetch flag details")?;
+
+        if !detail_response.status().is_success() {
+            warn!(
+                flag = key,
+                status = %detail_response.status(),
+                "failed to fetch flag details, skipping"
+            );
+            return Ok(None);
+        }
+
+        let flag_detail = detail_response
+            .json()
+            .await
+            .context("Failed to parse flag details")?;
+        Ok(Some(flag_detail))
+    }
+
+    /// `flag`'s details in each environment of the pipeline it's in, by name.
+    fn pipeline_envs<'a>(
+        &'a self,
+        flag: &'a LaunchDarklyFlagDetail,
+    ) -> impl Iterator<Item = (&'a str, &'a Environment)> {
+        self.environments
+            .iter()
+            .filter_map(|env| Some((env.name.as_str(), flag.environments.get(&env.name)?)))
+    }
 
-                let updated_recently = last_modified > time_threshold;
-                if updated_recently {
+    /// Issues for the segments `flags`' targeting rules use in each pipeline environment:
+    /// ones nobody can be in, so the rules silently match nobody, and ones unchanged for
+    /// `segment_stale_ms` while a flag using them is mid-rollout there. Segments are only
+    /// listed in environments where some flag uses one.
+    async fn segment_issues(
+        &self,
+        project: &str,
+        flags: &[LaunchDarklyFlagDetail],
+        now: i64,
+    ) -> Result<Vec<Issue>> {
+        let mut issues = Vec::new();
+        for env_config in &self.environments {
+            // The flags using each segment here
+            let mut used_by: HashMap<&str, Vec<&LaunchDarklyFlagDetail>> = HashMap::new();
+            for flag in flags {
+                let Some(env) = flag.environments.get(&env_config.name) else {
                     continue;
+                };
+                for key in env.segment_keys() {
+                    let flags = used_by.entry(key).or_default();
+                    if !flags.iter().any(|used| used.key == flag.key) {
+                        flags.push(flag);
+                    }
                 }
+            }
+            for flags in used_by.values_mut() {
+                flags.sort_by(|a, b| a.name.cmp(&b.name));
+            }
+            if used_by.is_empty() {
+                continue;
+            }
 
-                let Some(rollout) = get_rollout_percentage(&flag_detail, env) else {
+            let url = self.api_url(&["segments", project, &env_config.name], &[]);
+            let segments: Vec<Segment> = self.list(url, "segments").await?;
+            for segment in segments {
+                let Some(flags) = used_by.get(segment.key.as_str()) else {
                     continue;
                 };
-                let threshold = if env_name == "staging" { 50.0 } else { 100.0 };
-                if rollout > 0.0 && rollout < threshold {
-                    issues.push(format!(
-                        "Flag '{}' [{}:{}:{}] in {} at partial {:.0}% rollout, not updated in {}",
-                        flag_detail.name,
-                        self.project_key,
-                        flag.key,
-                        env_name,
-                        env_name,
-                        rollout,
-                        time_str
+                let issue = |kind: IssueKind, suffix: &str, detail: String| Issue {
+                    id: format!(
+                        "ld:segment:{}:{}:{}:{}",
+                        project, segment.key, env_config.name, suffix
+                    ),
+                    source: CheckSource::LaunchDarkly,
+                    kind,
+                    severity: Severity::Medium,
+                    title: format!("Segment '{}'", segment.name),
+                    detail,
+                    url: Some(self.page_url(
+                        &["projects", project, "segments", &segment.key, "targeting"],
+                        &[("env", &env_config.name)],
+                    )),
+                    items: Vec::new(),
+                };
+
+                if segment.is_empty() {
+                    issues.push(issue(
+                        IssueKind::EmptySegment,
+                        "empty",
+                        format!(
+                            "in {} has no rules or targets, but {} uses it",
+                            env_config.name,
+                            flag_names(flags)
+                        ),
+                    ));
+                    continue;
+                }
+                let rolling_out: Vec<&LaunchDarklyFlagDetail> = flags
+                    .iter()
+                    .copied()
+                    .filter(|flag| {
+                        let enabled = enabled_variation(flag, &self.enabled_variation_names);
+                        flag.environments
+                            .get(&env_config.name)
+                            .and_then(|env| get_rollout_percentage(flag, enabled, env))
+                            .is_some_and(|rollout| {
+                                rollout > 0.0 && rollout < env_config.done_percent
+                            })
+                    })
+                    .collect();
+                if let Some(since) = segment.last_modified.or(segment.creation_date)
+                    && now - since > self.segment_stale_ms
+                    && !rolling_out.is_empty()
+                {
+                    let days = (now - since) / (24 * 60 * 60 * 1000);
+                    issues.push(issue(
+                        IssueKind::StaleSegment,
+                        "stale",
+                        format!(
+                            "in {} unchanged for {} days while {} is rolling out there",
+                            env_config.name,
+                            days,
+                            flag_names(&rolling_out)
+                        ),
                     ));
                 }
             }
         }
-
         Ok(issues)
     }
-}
 
-fn get_rollout_percentage(flag: &LaunchDarklyFlagDetail, env: &Environment) -> Option<f64> {
-    if !env.on {
-        return Some(0.0);
+    /// A temporary flag that's been off in every pipeline environment for longer than
+    /// `dead_flag_days`, listed in the report of flags to clean up.
+    fn dead_flag(
+        &self,
+        project: &str,
+        flag: &LaunchDarklyFlagDetail,
+        now: i64,
+    ) -> Option<IssueItem> {
+        if !flag.temporary {
+            return None;
+        }
+        let enabled = enabled_variation(flag, &self.enabled_variation_names);
+        let dead_after = self.dead_flag_days * 24 * 60 * 60 * 1000;
+        let envs: Vec<&Environment> = self.pipeline_envs(flag).map(|(_, env)| env).collect();
+        if envs.is_empty()
+            || !envs
+                .iter()
+                .all(|env| off_and_stale(flag, enabled, env, now, dead_after))
+        {
+            return None;
+        }
+        let since = envs
+            .iter()
+            .filter_map(|env| env.last_modified.or(flag.creation_date))
+            .max()?;
+        let days = (now - since) / (24 * 60 * 60 * 1000);
+        let last = self.environments.last()?;
+        Some(IssueItem {
+            text: format!("Flag '{}' untouched for {} days", flag.name, days),
+            url: Some(self.flag_url(project, &flag.key, &last.name)),
+        })
     }
 
-    // Only handle boolean flags
-    if flag.kind != "boolean" {
-        return None;
+    /// The report of flags off everywhere for a long time, found at `at`, or None when
+    /// there weren't any.
+    fn dead_flags_issue(&self, at: DateTime<Utc>, flags: Vec<IssueItem>) -> Option<Issue> {
+        if flags.is_empty() {
+            return None;
+        }
+        Some(Issue {
+            // A new report is a new issue, even once the last one's been marked seen
+            id: format!("ld:dead-flags:{}", at.format("%Y-%m-%d")),
+            source: CheckSource::LaunchDarkly,
+            kind: IssueKind::DeadFlags,
+            severity: Severity::Low,
+            title: format!(
+                "{} flag{}",
+                flags.len(),
+                if flags.len() == 1 { "" } else { "s" }
+            ),
+            detail: format!(
+                "off everywhere and untouched for {}+ days",
+                self.dead_flag_days
+            ),
+            url: None,
+            items: flags,
+        })
     }
 
-    // Find the "enabled" variation index
-    // First, try to find a variation with name "enabled"
-    let enabled_index = flag
-        .variations
-        .iter()
-        .position(|v| {
-            if let Some(name) = &v.name {
-                return name.to_lowercase() == "enabled";
-            }
-            false
-        })
-        // Fallback: find a variation with value true
-        .or_else(|| {
-            flag.variations
-                .iter()
-                .position(|v| v.value.as_bool() == Some(true))
-        })?;
+    /// Issues for `flags` that are on in a pipeline environment where a prerequisite is
+    /// off, so nobody's actually served them. Prerequisites we don't maintain ourselves
+    /// are fetched once each, however many flags depend on them.
+    async fn prerequisite_issues(
+        &self,
+        project: &str,
+        flags: &[LaunchDarklyFlagDetail],
+    ) -> Vec<Issue> {
+        let checked: Vec<&LaunchDarklyFlagDetail> = flags
+            .iter()
+            .filter(|flag| !flag.tagged_any(&self.ignore_tags_by_rule.prerequisites))
+            .collect();
+        let mut known: HashMap<&str, &LaunchDarklyFlagDetail> =
+            flags.iter().map(|flag| (flag.key.as_str(), flag)).collect();
+        let mut unknown: Vec<&str> = checked
+            .iter()
+            .flat_map(|flag| self.pipeline_envs(flag))
+            .flat_map(|(_, env)| &env.prerequisites)
+            .map(|prerequisite| prerequisite.key.as_str())
+            .filter(|key| !known.contains_key(key))
+            .collect();
+        unknown.sort();
+        unknown.dedup();
 
-    env.fallthrough
-        .as_ref()
-        .and_then(|ft| ft.rollout.as_ref())
-        .map(|rollout| {
-            // Calculate percentage from weights
-            let total_weight: i32 = rollout.variations.iter().map(|v| v.weight).sum();
-            if total_weight == 0 {
-                return 0.0;
+        let fetches: Vec<_> = unknown
+            .iter()
+            .map(|key| self.fetch_flag_detail(project, key))
+            .collect();
+        let results: Vec<Result<Option<LaunchDarklyFlagDetail>>> = stream::iter(fetches)
+            .buffer_unordered(self.max_concurrent_requests)
+            .collect()
+            .await;
+        let mut fetched = Vec::new();
+        for result in results {
+            match result {
+                Ok(detail) => fetched.extend(detail),
+                Err(e) => warn!("failed to fetch prerequisite flag, skipping: {:#}", e),
             }
+        }
+        known.extend(fetched.iter().map(|flag| (flag.key.as_str(), flag)));
 
-            // Get the weight for the "enabled" variation
-            let on_weight = rollout
-                .variations
-                .iter()
-                .find(|v| v.variation == enabled_index as i32)
+        let mut issues = Vec::new();
+        for &flag in &checked {
+            for (env_name, env) in self.pipeline_envs(flag) {
+                let enabled = enabled_variation(flag, &self.enabled_variation_names);
+                if !env.on || get_rollout_percentage(flag, enabled, env) == Some(0.0) {
+                    continue;
+                }
+                for prerequisite in &env.prerequisites {
+                    let Some(required) = known.get(prerequisite.key.as_str()) else {
+                        continue;
+                    };
+                    if required
+                        .environments
+                        .get(env_name)
+                        .is_some_and(|env| !env.on)
+                    {
+                        let issue = self.flag_issue(
+                            project,
+                            flag,
+                            env_name,
+                            IssueKind::PrerequisiteOff,
+                            Severity::Medium,
+                            format!(
+                                "in {} depends on prerequisite '{}' which is off",
+                                env_name, required.name
+                            ),
+                        );
+                        issues.push(Issue {
+                            id: format!("{}:prerequisite:{}", issue.id, prerequisite.key),
+                            ..issue
+                        });
+                    }
+                }
+            }
+        }
+        issues
+    }
+
+    /// Issues for `flag`'s changes in `env_name` that were scheduled for before `now` but
+    /// are still waiting to be made, e.g. because they conflicted with another change.
+    /// Failures are logged rather than failing the check.
+    async fn overdue_scheduled_changes(
+        &self,
+        project: &str,
+        flag: &LaunchDarklyFlagDetail,
+        env_name: &str,
+        now: i64,
+        unavailable: &Mutex<HashSet<String>>,
+    ) -> Vec<Issue> {
+        if unavailable.lock().unwrap().contains(env_name) {
+            return Vec::new();
+        }
+        let url = self.api_url(
+            &[
+                "projects",
+                project,
+                "flags",
+                &flag.key,
+                "environments",
+                env_name,
+                "scheduled-changes",
+            ],
+            &[],
+        );
+        let response = match self.get(&url).await {
+            Ok(response) => response,
+            Err(e) => {
+                warn!(flag = %flag.key, env = env_name, "failed to fetch scheduled changes: {:#}", e);
+                return Vec::new();
+            }
+        };
+        // E.g. when the plan doesn't have scheduling, which goes for every flag
+        if matches!(response.status().as_u16(), 403 | 404) {
+            debug!(env = env_name, status = %response.status(), "no scheduled changes");
+            unavailable.lock().unwrap().insert(env_name.to_string());
+            return Vec::new();
+        }
+        let changes: LaunchDarklyResponse<ScheduledChange> = match response.error_for_status() {
+            Ok(response) => match response.json().await {
+                Ok(changes) => changes,
+                Err(e) => {
+                    warn!(flag = %flag.key, env = env_name, "failed to parse scheduled changes: {:#}", e);
+                    return Vec::new();
+                }
+            },
+            Err(e) => {
+                warn!(flag = %flag.key, env = env_name, "failed to fetch scheduled changes: {:#}", e);
+                return Vec::new();
+            }
+        };
+
+        changes
+            .items
+            .iter()
+            .filter(|change| change.execution_date < now)
+            .map(|change| {
+                let scheduled = DateTime::from_timestamp_millis(change.execution_date).map_or_else(
+                    || change.execution_date.to_string(),
+                    |at| at.format("%Y-%m-%d %H:%M UTC").to_string(),
+                );
+                let issue = self.flag_issue(
+                    project,
+                    flag,
+                    env_name,
+                    IssueKind::ScheduledChangeOverdue,
+                    Severity::Medium,
+                    format!(
+                        "change scheduled in {} for {} hasn't been made",
+                        env_name, scheduled
+                    ),
+                );
+                Issue {
+                    id: format!("{}:scheduled:{}", issue.id, change.id),
+                    ..issue
+                }
+            })
+            .collect()
+    }
+
+    /// The experiment running on a flag in `env_name`, as (flag key, environment,
+    /// experiment), or None when there isn't one or it can't be fetched.
+    async fn running_experiment(
+        &self,
+        project: &str,
+        flag_key: &str,
+        env_name: &str,
+        unavailable: &Mutex<HashSet<String>>,
+    ) -> Option<(String, String, Experiment)> {
+        if unavailable.lock().unwrap().contains(env_name) {
+            return None;
+        }
+        let url = self.api_url(
+            &["projects", project, "environments", env_name, "experiments"],
+            &[("filter", &format!("flagKey:{},status:running", flag_key))],
+        );
+        // The experiments API is still in beta
+        let response = match self
+            .send(self.client.get(&url).header("LD-API-Version", "beta"))
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => {
+                warn!(
+                    flag = flag_key,
+                    env = env_name,
+                    "failed to fetch experiments: {:#}",
+                    e
+                );
+                return None;
+            }
+        };
+        // E.g. when the plan doesn't have experimentation, which goes for every flag
+        if matches!(response.status().as_u16(), 403 | 404) {
+            debug!(env = env_name, status = %response.status(), "no experiments");
+            unavailable.lock().unwrap().insert(env_name.to_string());
+            return None;
+        }
+        let experiments: LaunchDarklyResponse<Experiment> = match response.error_for_status() {
+            Ok(response) => match response.json().await {
+                Ok(experiments) => experiments,
+                Err(e) => {
+                    warn!(
+                        flag = flag_key,
+                        env = env_name,
+                        "failed to parse experiments: {:#}",
+                        e
+                    );
+                    return None;
+                }
+            },
+            Err(e) => {
+                warn!(
+                    flag = flag_key,
+                    env = env_name,
+                    "failed to fetch experiments: {:#}",
+                    e
+                );
+                return None;
+            }
+        };
+        let experiment = experiments.items.into_iter().next()?;
+        Some((flag_key.to_string(), env_name.to_string(), experiment))
+    }
+
+    /// Issues for one flag, from its details in each environment of the pipeline.
+    fn flag_issues(
+        &self,
+        project: &str,
+        mut flag_detail: LaunchDarklyFlagDetail,
+        now: i64,
+        tracked: &mut Tracked,
+    ) -> Vec<Issue> {
+        flag_detail
+            .environments
+            .retain(|env_name, _env| self.environments.iter().any(|env| &env.name == env_name));
+
+        // Without knowing which variation turns a boolean flag on, none of its rollout can
+        // be measured
+        let enabled = enabled_variation(&flag_detail, &self.enabled_variation_names);
+        if flag_detail.kind == "boolean" && enabled.is_none() {
+            let env_name = self.environments.last().map_or("", |env| env.name.as_str());
+            let issue = self.flag_issue(
+                project,
+                &flag_detail,
+                env_name,
+                IssueKind::UnknownEnabledVariation,
+                Severity::Low,
+                "skipped: couldn't determine its enabled variation".to_string(),
+            );
+            return vec![Issue {
+                id: format!("ld:{}:{}:enabled-variation", project, flag_detail.key),
+                ..issue
+            }];
+        }
+
+        // Get rollout percentages for each environment in the pipeline
+        let rollouts: Vec<Option<f64>> = self
+            .environments
+            .iter()
+            .map(|env| {
+                flag_detail
+                    .environments
+                    .get(&env.name)
+                    .and_then(|env| get_rollout_percentage(&flag_detail, enabled, env))
+            })
+            .collect();
+        debug!(flag = %flag_detail.key, ?rollouts, "computed rollout percentages");
+        let on_id = |env: &str| format!("{}:{}:{}", project, flag_detail.key, env);
+        for (env, rollout) in self.environments.iter().zip(&rollouts) {
+            if rollout.is_some_and(|rollout| rollout > 0.0) {
+                tracked.flags_on.now.insert(on_id(&env.name));
+            }
+        }
+
+        let mut issues = Vec::new();
+        if self.track_rollouts {
+            for (env_config, rollout) in self.environments.iter().zip(&rollouts) {
+                let Some(rollout) = *rollout else {
+                    continue;
+                };
+                let id = on_id(&env_config.name);
+                let last_modified = flag_detail
+                    .environments
+                    .get(&env_config.name)
+                    .and_then(|env| env.last_modified);
+                let progress = track_rollout(
+                    tracked.rollouts.before.get(&id),
+                    rollout,
+                    last_modified.unwrap_or(now),
+                    now,
+                    env_config.stale_minutes * 60 * 1000,
+                );
+                if let Some(from) = progress.rolled_back_from {
+                    let issue = self.flag_issue(
+                        project,
+                        &flag_detail,
+                        &env_config.name,
+                        IssueKind::RolloutRolledBack,
+                        Severity::Medium,
+                        format!(
+                            "in {} rolled back from {:.0}% to {:.0}%",
+                            env_config.name, from, rollout
+                        ),
+                    );
+                    issues.push(Issue {
+                        id: format!("{}:rolled-back", issue.id),
+                        ..issue
+                    });
+                }
+                tracked.rollouts.now.insert(id, progress);
+            }
+        }
+
+        // Check if an environment is finished rolling out, but the next one isn't started
+        for (i, pair) in self.environments.windows(2).enumerate() {
+            let [done, next] = pair else { continue };
+            // A rule serving the flag to some users means the rollout has started, even
+            // with none of the fallthrough
+            let next_has_rules = flag_detail
+                .environments
+                .get(&next.name)
+                .is_some_and(|env| rules_serve_enabled(enabled, env));
+            // Individual targets are usually the team trying it out before a real rollout
+            let next_targeted = flag_detail
+                .environments
+                .get(&next.name)
+                .is_some_and(|env| targets_enabled(enabled, env));
+            if let (Some(done_rollout), Some(next_rollout)) = (rollouts[i], rollouts[i + 1])
+                && done_rollout >= done.done_percent
+                && next_rollout == 0.0
+                && !next_has_rules
+                && !(next_targeted && self.targets_count_as_started)
+            {
+                // Having been on before, it was turned off rather than never started
+                if tracked.flags_on.before.contains(&on_id(&next.name)) {
+                    tracked.flags_on.now.insert(on_id(&next.name));
+                    let off_since = flag_detail
+                        .environments
+                        .get(&next.name)
+                        .and_then(|env| env.last_modified);
+                    if let (Some(drift_after), Some(off_since)) = (self.drift_after_ms, off_since)
+                        && now - off_since > drift_after
+                        && !flag_detail.tagged_any(&self.ignore_tags_by_rule.drift)
+                    {
+                        issues.push(self.flag_issue(
+                            project,
+                            &flag_detail,
+                            &next.name,
+                            IssueKind::FlagDrift,
+                            Severity::Medium,
+                            format!(
+                                "off in {} but on in {} for {}",
+                                next.name,
+                                done.name,
+                                format_age(chrono::Duration::milliseconds(now - off_since))
+                            ),
+                        ));
+                    }
+                    continue;
+                }
+                if flag_detail.tagged_any(&self.ignore_tags_by_rule.not_started) {
+                    continue;
+                }
+                let started = if next_targeted {
+                    format!("only individual targets in {}", next.name)
+                } else {
+                    format!("not started in {}", next.name)
+                };
+                issues.push(self.flag_issue(
+                    project,
+                    &flag_detail,
+                    &next.name,
+                    IssueKind::RolloutNotStarted,
+                    Severity::Low,
+                    format!(
+                        "rolled out to {:.0}% in {}, but {}",
+                        done_rollout, done.name, started
+                    ),
+                ));
+            }
+        }
+
+        // Temporary flags are meant to be deleted once everyone in the last environment
+        // gets the same variation
+        if flag_detail.temporary
+            && !flag_detail.tagged_any(&self.ignore_tags_by_rule.removal)
+            && let Some(last) = self.environments.last()
+            && let Some(env) = flag_detail.environments.get(&last.name)
+            && fully_rolled_out(&flag_detail, enabled, env)
+            && let Some(since) = env.last_modified.or(flag_detail.creation_date)
+            && now - since > self.removal_after_ms
+        {
+            let days = (now - since) / (24 * 60 * 60 * 1000);
+            issues.push(self.flag_issue(
+                project,
+                &flag_detail,
+                &last.name,
+                IssueKind::ReadyForRemoval,
+                Severity::Low,
+                format!(
+                    "fully rolled out for {} day{} — remove it",
+                    days,
+                    if days == 1 { "" } else { "s" }
+                ),
+            ));
+        }
+
+        // Check each environment for stale partial rollouts
+        if flag_detail.tagged_any(&self.ignore_tags_by_rule.stale) {
+            return issues;
+        }
+        for (env_config, rollout) in self.environments.iter().zip(&rollouts) {
+            let Some(env) = flag_detail.environments.get(&env_config.name) else {
+                continue;
+            };
+            // The rollout is held where it is on purpose while an experiment measures it,
+            // until the experiment's gone on long enough that it may have been forgotten
+            if env.runs_experiment() {
+                issues.extend(self.experiment_issue(project, &flag_detail, &env_config.name, now));
+                continue;
+            }
+            // Edits to anything else don't count as progress when it's tracked
+            let progress = tracked
+                .rollouts
+                .now
+                .get(&on_id(&env_config.name))
+                .map(|progress| progress.since.timestamp_millis());
+            let Some(last_modified) = progress.or(env.last_modified) else {
+                continue;
+            };
+
+            let untouched = match (
+                &self.business_days,
+                DateTime::from_timestamp_millis(last_modified),
+                DateTime::from_timestamp_millis(now),
+            ) {
+                (Some(days), Some(last_modified), Some(now)) => {
+                    business_duration_since(last_modified, now, days).num_milliseconds()
+                }
+                _ => now - last_modified,
+            };
+            let updated_recently = untouched < env_config.stale_minutes * 60 * 1000;
+            if updated_recently {
+                // Until it goes stale, a rollout held too long is only due a nudge
+                if flag_detail.kind == "boolean"
+                    && let Some(rollout) = *rollout
+                    && rollout > 0.0
+                    && rollout < env_config.done_percent
+                    && self
+                        .ramp_after_ms(&flag_detail)
+                        .is_some_and(|ramp_after| untouched >= ramp_after)
+                {
+                    let issue = self.flag_issue(
+                        project,
+                        &flag_detail,
+                        &env_config.name,
+                        IssueKind::RampDue,
+                        Severity::Low,
+                        format!(
+                            "set to {:.0}% in {} {} ago — time to bump it",
+                            rollout,
+                            env_config.name,
+                            format_age(chrono::Duration::milliseconds(now - last_modified))
+                        ),
+                    );
+                    issues.push(Issue {
+                        id: format!("{}:ramp", issue.id),
+                        ..issue
+                    });
+                }
+                continue;
+            }
+
+            // Other kinds have no "enabled" variation, so any split counts as partial
+            if flag_detail.kind != "boolean" {
+                if let Some(split) = variation_split(env) {
+                    let split: Vec<String> = split.iter().map(|p| format!("{:.0}", p)).collect();
+                    issues.push(self.flag_issue(
+                        project,
+                        &flag_detail,
+                        &env_config.name,
+                        IssueKind::StaleRollout,
+                        Severity::Medium,
+                        format!(
+                            "in {} split {} across variations, not updated in {}",
+                            env_config.name,
+                            split.join("/"),
+                            format_minutes(env_config.stale_minutes)
+                        ),
+                    ));
+                }
+                continue;
+            }
+
+            let Some(rollout) = *rollout else {
+                continue;
+            };
+            if rollout > 0.0 && rollout < env_config.done_percent {
+                issues.push(self.flag_issue(
+                    project,
+                    &flag_detail,
+                    &env_config.name,
+                    IssueKind::StaleRollout,
+                    Severity::Medium,
+                    format!(
+                        "in {} at partial {:.0}% rollout, not updated in {}",
+                        env_config.name,
+                        rollout,
+                        format_minutes(env_config.stale_minutes)
+                    ),
+                ));
+            }
+        }
+
+        issues
+    }
+
+    fn flag_issue(
+        &self,
+        project: &str,
+        flag: &LaunchDarklyFlagDetail,
+        env_name: &str,
+        kind: IssueKind,
+        severity: Severity,
+        mut detail: String,
+    ) -> Issue {
+        // Shared flags are someone else's to move along too
+        if let Some(team) = &flag.team {
+            detail.push_str(&format!(" (maintained by team '{}')", team));
+        }
+        Issue {
+            id: format!("ld:{}:{}:{}", project, flag.key, env_name),
+            source: CheckSource::LaunchDarkly,
+            kind,
+            severity,
+            title: format!("Flag '{}'", flag.name),
+            detail,
+            url: Some(self.flag_url(project, &flag.key, env_name)),
+            items: Vec::new(),
+        }
+    }
+
+    /// How long the flag's partial rollouts can go before they're due a bump: the longest
+    /// set for any of its tags, or else `ramp_after_ms`.
+    fn ramp_after_ms(&self, flag_detail: &LaunchDarklyFlagDetail) -> Option<i64> {
+        flag_detail
+            .tags
+            .iter()
+            .filter_map(|tag| self.ramp_after_ms_by_tag.get(tag).copied())
+            .max()
+            .or(self.ramp_after_ms)
+    }
+
+    /// An issue for the experiment holding the flag's rollout in `env_name`, once it's
+    /// been running longer than `experiment_after_ms`.
+    fn experiment_issue(
+        &self,
+        project: &str,
+        flag: &LaunchDarklyFlagDetail,
+        env_name: &str,
+        now: i64,
+    ) -> Option<Issue> {
+        let experiment = flag.experiments.get(env_name);
+        // Without its details, the experiment started no later than the last change
+        let started = experiment
+            .and_then(|experiment| experiment.current_iteration.as_ref()?.started_at)
+            .or(flag.environments.get(env_name)?.last_modified)?;
+        if now - started <= self.experiment_after_ms {
+            return None;
+        }
+        let days = (now - started) / (24 * 60 * 60 * 1000);
+        let name = experiment.map_or(String::new(), |experiment| {
+            format!(" '{}'", experiment.name)
+        });
+        let issue = self.flag_issue(
+            project,
+            flag,
+            env_name,
+            IssueKind::ExperimentRunning,
+            Severity::Low,
+            format!(
+                "in {} running experiment{} for {} day{} — check results",
+                env_name,
+                name,
+                days,
+                if days == 1 { "" } else { "s" }
+            ),
+        );
+        Some(Issue {
+            id: format!("{}:experiment", issue.id),
+            url: experiment
+                .map(|experiment| {
+                    self.page_url(
+                        &[
+                            "projects",
+                            project,
+                            "experiments",
+                            &experiment.key,
+                            "results",
+                        ],
+                        &[("env", env_name)],
+                    )
+                })
+                .or(issue.url.clone()),
+            ..issue
+        })
+    }
+
+    /// The flag's targeting page, showing `env_name`.
+    fn flag_url(&self, project: &str, flag_key: &str, env_name: &str) -> String {
+        // Every environment of the pipeline, latest first like LaunchDarkly's own links
+        let mut query: Vec<(&str, &str)> = self
+            .environments
+            .iter()
+            .rev()
+            .map(|env| ("env", env.name.as_str()))
+            .collect();
+        query.push(("selected-env", env_name));
+        self.page_url(
+            &["projects", project, "flags", flag_key, "targeting"],
+            &query,
+        )
+    }
+
+    /// An endpoint of the v2 API, encoded like `page_url`.
+    fn api_url(&self, path: &[&str], query: &[(&str, &str)]) -> String {
+        let path: Vec<&str> = ["api", "v2"]
+            .into_iter()
+            .chain(path.iter().copied())
+            .collect();
+        self.page_url(&path, query)
+    }
+
+    /// A page of the app at `path` under `base_url`, with each path segment and query
+    /// pair percent-encoded, so keys with slashes or spaces still link to the right page.
+    fn page_url(&self, path: &[&str], query: &[(&str, &str)]) -> String {
+        let mut url = self.app_url.clone();
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments.pop_if_empty().extend(path);
+        }
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        url.to_string()
+    }
+}
+
+#[async_trait]
+impl Check for LaunchDarklyChecker {
+    fn name(&self) -> &str {
+        "LaunchDarkly"
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    async fn check(&self) -> Result<CheckOutput> {
+        let now = Utc::now().timestamp_millis();
+
+        // Only what's enabled is taken from the state file
+        let state = load_state().unwrap_or_default();
+        let had_rotation = !state.ld_flag_cursors.is_empty() || !state.ld_flag_issues.is_empty();
+        let mut tracked = Tracked {
+            cursors: state.ld_flag_cursors,
+            flag_issues: state.ld_flag_issues,
+            ..Tracked::default()
+        };
+        if self.drift_after_ms.is_some() {
+            tracked.flags_on.before = state.ld_flags_on;
+        }
+        if self.track_rollouts {
+            tracked.rollouts.before = state.ld_rollouts;
+        }
+
+        // A token that can't be used gets one clear issue instead of every request failing
+        let mut issues = Vec::new();
+        let mut unreadable = HashSet::new();
+        let mut token_validated = false;
+        let validation_due = self.validate_token
+            && state.ld_token_validated.is_none_or(|at| {
+                now - at.timestamp_millis() > TOKEN_VALIDATION_HOURS * 60 * 60 * 1000
+            });
+        if validation_due {
+            match self.token_access().await {
+                Ok(access) => {
+                    issues.extend(self.token_issues(&access));
+                    match access {
+                        TokenAccess::Rejected(_) => return Ok(issues.into()),
+                        TokenAccess::Usable {
+                            unreadable: projects,
+                        } => {
+                            token_validated = projects.is_empty();
+                            unreadable.extend(projects.into_iter().map(|(project, _)| project));
+                        }
+                    }
+                }
+                // Checked again next time
+                Err(e) => warn!("Failed to validate the LaunchDarkly token: {:#}", e),
+            }
+        }
+        // Flags off everywhere are only looked for every so often
+        let dead_flags_due = self.dead_flags_every_ms.is_some_and(|every| {
+            state
+                .ld_dead_flags_at
+                .is_none_or(|at| now - at.timestamp_millis() >= every)
+        });
+        if dead_flags_due {
+            tracked.dead_flags = Some(Vec::new());
+        }
+        if self.audit_log {
+            tracked.audit = AuditLog {
+                after: state.ld_audit_after,
+                modifications: state.ld_modifications,
+            };
+        }
+
+        let mut failures = Vec::new();
+        for project in self
+            .project_keys
+            .iter()
+            .filter(|project| !unreadable.contains(*project))
+        {
+            match self.check_project(project, now, &mut tracked).await {
+                Ok(project_issues) => issues.extend(project_issues),
+                Err(e) => {
+                    warn!("Failed to check LaunchDarkly project {}: {:#}", project, e);
+                    failures.push((project, e));
+                }
+            }
+        }
+        // Rollouts in projects that couldn't be checked are kept as they were
+        for (project, _) in &failures {
+            let prefix = format!("{}:", project);
+            let progress = tracked
+                .rollouts
+                .before
+                .iter()
+                .filter(|(id, _)| id.starts_with(&prefix));
+            tracked
+                .rollouts
+                .now
+                .extend(progress.map(|(id, progress)| (id.clone(), progress.clone())));
+        }
+        // When no project could be checked, e.g. with a bad token, the checker itself failed
+        if failures.len() == self.project_keys.len() - unreadable.len()
+            && let Some((_, e)) = failures.pop()
+        {
+            return Err(e);
+        }
+        // A report missing a project's flags waits for the next check
+        let dead_flags = tracked.dead_flags.take().filter(|_| failures.is_empty());
+        if self.dead_flags_every_ms.is_some() {
+            let report = match &dead_flags {
+                Some(flags) => DateTime::from_timestamp_millis(now).map(|at| (at, flags.clone())),
+                None => state
+                    .ld_dead_flags_at
+                    .map(|at| (at, state.ld_dead_flags.clone())),
+            };
+            issues.extend(
+                report
+                    .and_then(|(at, flags)| self.dead_flags_issue(at, flags))
+                    .filter(|issue| !state.acknowledged.contains(&issue.id)),
+            );
+        }
+        if self.approval_requests {
+            match self.approval_issues(now).await {
+                Ok(approval_issues) => issues.extend(approval_issues),
+                Err(e) => {
+                    warn!("Failed to check LaunchDarkly approval requests: {:#}", e);
+                    issues.push(Issue::checker_failed("LaunchDarkly approvals", &e));
+                }
+            }
+        }
+        // Changes to our flags are reported until they're marked seen, for a while. Seen
+        // ones are still kept so reading the audit log again doesn't bring them back
+        if self.audit_log {
+            let now = DateTime::from_timestamp_millis(now).unwrap_or_default();
+            tracked.audit.modifications.retain(|_, modification| {
+                now - modification.date < chrono::Duration::days(MODIFICATION_DAYS)
+            });
+            issues.extend(
+                tracked
+                    .audit
+                    .modifications
+                    .iter()
+                    .map(|(id, modification)| self.modification_issue(id, modification, now))
+                    .filter(|issue| !state.acknowledged.contains(&issue.id)),
+            );
+        }
+
+        let save_flags_on = self.drift_after_ms.is_some() && failures.is_empty();
+        let (track_rollouts, audit_log) = (self.track_rollouts, self.audit_log);
+        let changed = self.drift_after_ms.is_some()
+            || track_rollouts
+            || audit_log
+            || tracked.rotated
+            || had_rotation
+            || dead_flags.is_some()
+            || token_validated;
+        let state_update = changed.then(|| {
+            Box::new(move |state: &mut State| {
+                // A project that couldn't be checked would lose what was on in it
+                if save_flags_on {
+                    state.ld_flags_on = tracked.flags_on.now;
+                }
+                if track_rollouts {
+                    state.ld_rollouts = tracked.rollouts.now;
+                }
+                if audit_log {
+                    let modifications = &tracked.audit.modifications;
+                    state
+                        .acknowledged
+                        .retain(|id| match id.strip_prefix("ld:audit:") {
+                            Some(entry) => modifications.contains_key(entry),
+                            None => true,
+                        });
+                    state.ld_audit_after = tracked.audit.after;
+                    state.ld_modifications = tracked.audit.modifications;
+                }
+                if token_validated {
+                    state.ld_token_validated = DateTime::from_timestamp_millis(now);
+                }
+                if let Some(flags) = dead_flags {
+                    state.ld_dead_flags_at = DateTime::from_timestamp_millis(now);
+                    state.ld_dead_flags = flags;
+                    state
+                        .acknowledged
+                        .retain(|id| !id.starts_with("ld:dead-flags:"));
+                }
+                state.ld_flag_cursors = tracked.cursors;
+                state.ld_flag_issues = tracked.flag_issues;
+            }) as StateUpdate
+        });
+        issues.extend(
+            failures
+                .iter()
+                .map(|(project, e)| Issue::checker_failed(&format!("LaunchDarkly {}", project), e)),
+        );
+        // Details arrive in whatever order their requests finish
+        issues.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(CheckOutput {
+            issues,
+            state_update,
+        })
+    }
+}
+
+/// Where a rollout at `percent` has got to since `before`, the last check's progress if
+/// it was tracked then. A rollback is remembered until the rollout is back up to where it
+/// was, or for `report_ms` after it went down.
+fn track_rollout(
+    before: Option<&RolloutProgress>,
+    percent: f64,
+    last_modified: i64,
+    now: i64,
+    report_ms: i64,
+) -> RolloutProgress {
+    let at = |ms: i64| DateTime::from_timestamp_millis(ms).unwrap_or_default();
+    let Some(before) = before else {
+        return RolloutProgress {
+            percent,
+            since: at(last_modified.min(now)),
+            rolled_back_from: None,
+        };
+    };
+    let rolled_back_from = if percent < before.percent {
+        Some(
+            before
+                .rolled_back_from
+                .unwrap_or(before.percent)
+                .max(before.percent),
+        )
+    } else {
+        before.rolled_back_from.filter(|from| percent < *from)
+    };
+    let since = if percent == before.percent {
+        before.since
+    } else {
+        at(now)
+    };
+    RolloutProgress {
+        percent,
+        since,
+        rolled_back_from: rolled_back_from.filter(|_| now - since.timestamp_millis() < report_ms),
+    }
+}
+
+/// Picks which of `flags` to check when there are more than `max`: the most recently
+/// modified, up to half of them, then the rest in turn by key, starting after `cursor`.
+/// Returns their keys and where to start next time.
+fn pick_flags(
+    flags: &[LaunchDarklyFlag],
+    max: usize,
+    cursor: Option<&str>,
+    pipeline: &[LaunchDarklyEnvironment],
+) -> (HashSet<String>, Option<String>) {
+    let mut recent: Vec<(i64, &str)> = flags
+        .iter()
+        .filter_map(|flag| Some((flag.last_modified(pipeline)?, flag.key.as_str())))
+        .collect();
+    recent.sort_by(|a, b| b.cmp(a));
+    let mut picked: HashSet<String> = recent
+        .iter()
+        .take(max / 2)
+        .map(|(_, key)| key.to_string())
+        .collect();
+
+    let mut keys: Vec<&str> = flags.iter().map(|flag| flag.key.as_str()).collect();
+    keys.sort();
+    let start = cursor.map_or(0, |cursor| keys.partition_point(|key| *key <= cursor));
+    let mut next_cursor = cursor.map(str::to_string);
+    for key in keys[start..].iter().chain(&keys[..start]) {
+        if picked.len() >= max {
+            break;
+        }
+        if picked.insert(key.to_string()) {
+            next_cursor = Some(key.to_string());
+        }
+    }
+    (picked, next_cursor)
+}
+
+/// How much of the time from `last_modified` to `now` fell on business days, counting
+/// days in the configured time zone.
+fn business_duration_since(
+    last_modified: DateTime<Utc>,
+    now: DateTime<Utc>,
+    days: &BusinessDays,
+) -> chrono::Duration {
+    match days.utc_offset {
+        Some(offset) => business_duration_in(last_modified, now, days, &offset),
+        None => business_duration_in(last_modified, now, days, &Local),
+    }
+}
+
+fn business_duration_in<Tz: TimeZone>(
+    since: DateTime<Utc>,
+    now: DateTime<Utc>,
+    days: &BusinessDays,
+    tz: &Tz,
+) -> chrono::Duration {
+    let mut total = chrono::Duration::zero();
+    let mut start = since;
+    // A day at a time, up to midnight of the next one
+    while start < now {
+        let date = start.with_timezone(tz).date_naive();
+        let next_day = date
+            .succ_opt()
+            .and_then(|next| {
+                tz.from_local_datetime(&next.and_time(NaiveTime::MIN))
+                    .earliest()
+            })
+            .map(|midnight| midnight.with_timezone(&Utc))
+            .filter(|midnight| *midnight > start);
+        let end = next_day.map_or(now, |midnight| midnight.min(now));
+        if days.is_business_day(date) {
+            total += end - start;
+        }
+        start = end;
+    }
+    total
+}
+
+/// The key of the `kind` resource in an approval request's `resource_id`, e.g. the
+/// environment's ("env") in "proj/default:env/production:flag/new-checkout".
+fn resource_key<'a>(resource_id: &'a str, kind: &str) -> Option<&'a str> {
+    resource_id
+        .split(':')
+        .find_map(|part| part.strip_prefix(kind)?.strip_prefix('/'))
+}
+
+/// How long to hold off further requests when `headers` say no more requests are left in
+/// the rate limit than could be in flight at once.
+fn quota_wait(headers: &HeaderMap, in_flight: usize) -> Option<Duration> {
+    let remaining = [
+        "x-ratelimit-route-remaining",
+        "x-ratelimit-global-remaining",
+    ]
+    .iter()
+    .filter_map(|name| {
+        headers
+            .get(*name)?
+            .to_str()
+            .ok()?
+            .trim()
+            .parse::<usize>()
+            .ok()
+    })
+    .min()?;
+    if remaining > in_flight {
+        return None;
+    }
+    rate_limit_reset(headers)
+}
+
+/// The percentages of traffic an environment's fallthrough rollout serves each variation,
+/// in variation order and leaving out those it doesn't serve at all. None unless it serves
+/// more than one.
+fn variation_split(env: &Environment) -> Option<Vec<f64>> {
+    if !env.on {
+        return None;
+    }
+    let rollout = env.fallthrough.as_ref()?.rollout.as_ref()?;
+    let total_weight: i32 = rollout.variations.iter().map(|v| v.weight).sum();
+    let mut weights: Vec<&WeightedVariation> =
+        rollout.variations.iter().filter(|v| v.weight > 0).collect();
+    if weights.len() < 2 {
+        return None;
+    }
+    weights.sort_by_key(|v| v.variation);
+    Some(
+        weights
+            .iter()
+            .map(|v| v.weight as f64 / total_weight as f64 * 100.0)
+            .collect(),
+    )
+}
+
+/// The index of a boolean flag's "enabled" variation: the first of `names` that a
+/// variation is named, ignoring case, or else the unnamed variation with value `true`. A
+/// `true` variation named something else might be an inverted flag's, e.g. "Disabled",
+/// so it isn't assumed to be enabled.
+fn enabled_variation(flag: &LaunchDarklyFlagDetail, names: &[String]) -> Option<usize> {
+    names
+        .iter()
+        .find_map(|name| {
+            flag.variations.iter().position(|v| {
+                v.name
+                    .as_deref()
+                    .is_some_and(|variation| variation.eq_ignore_ascii_case(name))
+            })
+        })
+        .or_else(|| {
+            flag.variations
+                .iter()
+                .position(|v| v.name.is_none() && v.value.as_bool() == Some(true))
+        })
+}
+
+/// Names `flags` for an issue, e.g. "flag 'New checkout'" or "flag 'New checkout' and 2
+/// others".
+fn flag_names(flags: &[&LaunchDarklyFlagDetail]) -> String {
+    let Some(first) = flags.first() else {
+        return String::new();
+    };
+    match flags.len() {
+        1 => format!("flag '{}'", first.name),
+        2 => format!("flag '{}' and 1 other", first.name),
+        n => format!("flag '{}' and {} others", first.name, n - 1),
+    }
+}
+
+/// Whether `env` serves nobody a flag's enabled variation and hasn't been changed for
+/// `after_ms`. Other kinds of flags are only off when their targeting is.
+fn off_and_stale(
+    flag: &LaunchDarklyFlagDetail,
+    enabled: Option<usize>,
+    env: &Environment,
+    now: i64,
+    after_ms: i64,
+) -> bool {
+    // Everyone falling through to one variation isn't a rollout percentage
+    let fallthrough_off = match env.fallthrough.as_ref().and_then(|f| f.variation) {
+        Some(variation) => enabled.is_some_and(|enabled| variation != enabled as i32),
+        None => get_rollout_percentage(flag, enabled, env) == Some(0.0),
+    };
+    let off = !env.on
+        || (fallthrough_off
+            && !rules_serve_enabled(enabled, env)
+            && !targets_enabled(enabled, env));
+    off && env
+        .last_modified
+        .or(flag.creation_date)
+        .is_some_and(|since| now - since > after_ms)
+}
+
+/// Whether any targeting rule in `env` serves a boolean flag's enabled variation to at
+/// least some of the contexts it matches, so the flag is started there whatever the
+/// fallthrough says.
+fn rules_serve_enabled(enabled: Option<usize>, env: &Environment) -> bool {
+    let Some(enabled_index) = enabled else {
+        return false;
+    };
+    let enabled_index = enabled_index as i32;
+    env.on
+        && env.rules.iter().any(|rule| {
+            rule.variation == Some(enabled_index)
+                || rule.rollout.as_ref().is_some_and(|rollout| {
+                    rollout
+                        .variations
+                        .iter()
+                        .any(|v| v.variation == enabled_index && v.weight > 0)
+                })
+        })
+}
+
+/// Whether `env` serves a boolean flag's enabled variation to any individually targeted
+/// contexts.
+fn targets_enabled(enabled: Option<usize>, env: &Environment) -> bool {
+    let Some(enabled_index) = enabled else {
+        return false;
+    };
+    env.on
+        && env
+            .targets
+            .iter()
+            .chain(&env.context_targets)
+            .any(|target| target.variation == enabled_index as i32 && !target.values.is_empty())
+}
+
+/// Whether `env` serves every context the same variation: the off variation while
+/// targeting is off, or otherwise a single fallthrough variation with no rules or targets
+/// serving anything else. For a boolean flag, it has to be the enabled variation.
+fn fully_rolled_out(
+    flag: &LaunchDarklyFlagDetail,
+    enabled: Option<usize>,
+    env: &Environment,
+) -> bool {
+    let served = if env.on {
+        let fallthrough = env.fallthrough.as_ref().and_then(|fallthrough| {
+            fallthrough.variation.or_else(|| {
+                let weighted: Vec<&WeightedVariation> = fallthrough
+                    .rollout
+                    .as_ref()?
+                    .variations
+                    .iter()
+                    .filter(|v| v.weight > 0)
+                    .collect();
+                match weighted.as_slice() {
+                    [only] => Some(only.variation),
+                    _ => None,
+                }
+            })
+        });
+        let others_targeted = env.rules.iter().any(|rule| rule.variation != fallthrough)
+            || env
+                .targets
+                .iter()
+                .chain(&env.context_targets)
+                .any(|target| Some(target.variation) != fallthrough && !target.values.is_empty());
+        fallthrough.filter(|_| !others_targeted)
+    } else {
+        env.off_variation
+    };
+    match served {
+        Some(variation) if flag.kind == "boolean" => {
+            enabled.is_some_and(|enabled| enabled as i32 == variation)
+        }
+        served => served.is_some(),
+    }
+}
+
+fn get_rollout_percentage(
+    flag: &LaunchDarklyFlagDetail,
+    enabled: Option<usize>,
+    env: &Environment,
+) -> Option<f64> {
+    if !env.on {
+        return Some(0.0);
+    }
+
+    // Only handle boolean flags
+    if flag.kind != "boolean" {
+        return None;
+    }
+
+    let enabled_index = enabled?;
+
+    env.fallthrough
+        .as_ref()
+        .and_then(|ft| ft.rollout.as_ref())
+        .map(|rollout| {
+            // Calculate percentage from weights
+            let total_weight: i32 = rollout.variations.iter().map(|v| v.weight).sum();
+            if total_weight == 0 {
+                return 0.0;
+            }
+
+            // Get the weight for the "enabled" variation
+            let on_weight = rollout
+                .variations
+                .iter()
+                .find(|v| v.variation == enabled_index as i32)
                 .map(|v| v.weight)
                 .unwrap_or(0);
 
             (on_weight as f64 / total_weight as f64) * 100.0
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_server::MockServer;
+    use serde_json::json;
+
+    /// The flags list request for the default project and environments.
+    const LIST: &str =
+        "/api/v2/flags/default?filter=maintainerId%3Ame&summary=false&env=staging&env=production";
+
+    fn checker(server: &MockServer) -> LaunchDarklyChecker {
+        LaunchDarklyChecker::new(&LaunchDarklyConfig {
+            api_token: Some("api-test".to_string()),
+            maintainer_id: Some("me".to_string()),
+            base_url: format!("{}/", server.url),
+            // Checks in tests don't keep rollouts in the state file, or ask who's calling
+            track_rollouts: false,
+            validate_token: false,
+            ..LaunchDarklyConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_launchdarkly_checker() {
+        let server = MockServer::new()
+            .json(
+                "/api/v2/flags/default",
+                include_str!("launchdarkly/flags.json"),
+            )
+            .json(
+                "/api/v2/flags/default/new-checkout",
+                include_str!("launchdarkly/new-checkout.json"),
+            )
+            .json(
+                "/api/v2/flags/default/search-v2",
+                include_str!("launchdarkly/search-v2.json"),
+            )
+            .json(
+                "/api/v2/flags/default/theme",
+                include_str!("launchdarkly/theme.json"),
+            )
+            .respond("/api/v2/flags/default/deleted-flag", 404, "")
+            .start()
+            .await;
+
+        let issues = checker(&server).check().await.unwrap().issues;
+        let summaries: Vec<(String, IssueKind, String)> = issues
+            .iter()
+            .map(|i| (i.id.clone(), i.kind, i.to_string()))
+            .collect();
+        assert_eq!(
+            summaries,
+            [
+                (
+                    "ld:default:new-checkout:production".to_string(),
+                    IssueKind::RolloutNotStarted,
+                    "Flag 'New checkout' rolled out to 100% in staging, but not started in production"
+                        .to_string()
+                ),
+                (
+                    "ld:default:search-v2:production".to_string(),
+                    IssueKind::StaleRollout,
+                    "Flag 'Search v2' in production at partial 25% rollout, not updated in 18h"
+                        .to_string()
+                ),
+                (
+                    "ld:default:theme:production".to_string(),
+                    IssueKind::StaleRollout,
+                    "Flag 'Theme' in production split 50/50 across variations, not updated in 18h"
+                        .to_string()
+                ),
+                (
+                    "ld:default:theme:staging".to_string(),
+                    IssueKind::StaleRollout,
+                    "Flag 'Theme' in staging split 30/30/40 across variations, not updated in 2h"
+                        .to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            issues[0].url.as_deref(),
+            Some(
+                format!(
+                    "{}/projects/default/flags/new-checkout/targeting?env=production&env=staging&selected-env=production",
+                    server.url
+                )
+                .as_str()
+            )
+        );
+        assert_eq!(server.requests()[0], LIST);
+    }
+
+    #[tokio::test]
+    async fn test_launchdarkly_expanded_list() {
+        let server = MockServer::new()
+            .json(LIST, include_str!("launchdarkly/flags-expanded.json"))
+            .json(
+                "/api/v2/flags/default/theme",
+                include_str!("launchdarkly/theme.json"),
+            )
+            .start()
+            .await;
+
+        let issues = checker(&server).check().await.unwrap().issues;
+        let summaries: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            summaries,
+            [
+                "Flag 'New checkout' rolled out to 100% in staging, but not started in production",
+                "Flag 'Search v2' in production at partial 25% rollout, not updated in 18h",
+                "Flag 'Theme' in production split 50/50 across variations, not updated in 18h",
+                "Flag 'Theme' in staging split 30/30/40 across variations, not updated in 2h",
+            ]
+        );
+        // Only the flag listed without its environments needed its details fetched
+        assert_eq!(server.requests(), [LIST, "/api/v2/flags/default/theme"]);
+    }
+
+    #[tokio::test]
+    async fn test_launchdarkly_maintainer_teams() {
+        let server = MockServer::new()
+            .json(
+                LIST,
+                r#"{ "items": [{ "key": "new-checkout", "name": "New checkout" }] }"#,
+            )
+            .json(
+                "/api/v2/flags/default?filter=maintainerTeamKey%3Aplatform&summary=false&env=staging&env=production",
+                r#"{ "items": [
+                    { "key": "new-checkout", "name": "New checkout" },
+                    { "key": "search-v2", "name": "Search v2" }
+                ] }"#,
+            )
+            .json(
+                "/api/v2/flags/default/new-checkout",
+                include_str!("launchdarkly/new-checkout.json"),
+            )
+            .json(
+                "/api/v2/flags/default/search-v2",
+                include_str!("launchdarkly/search-v2.json"),
+            )
+            .start()
+            .await;
+
+        let issues = LaunchDarklyChecker {
+            maintainer_team_keys: vec!["platform".to_string()],
+            ..checker(&server)
+        }
+        .check()
+        .await
+        .unwrap()
+        .issues;
+        // new-checkout is ours as well as the team's, so it's only checked once, as ours
+        let summaries: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            summaries,
+            [
+                "Flag 'New checkout' rolled out to 100% in staging, but not started in production",
+                "Flag 'Search v2' in production at partial 25% rollout, not updated in 18h (maintained by team 'platform')",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_flag_details_fetched_concurrently() {
+        let server = MockServer::new()
+            .json(
+                "/api/v2/flags/default",
+                include_str!("launchdarkly/flags.json"),
+            )
+            .json(
+                "/api/v2/flags/default/new-checkout",
+                include_str!("launchdarkly/new-checkout.json"),
+            )
+            .json(
+                "/api/v2/flags/default/search-v2",
+                include_str!("launchdarkly/search-v2.json"),
+            )
+            .json(
+                "/api/v2/flags/default/theme",
+                include_str!("launchdarkly/theme.json"),
+            )
+            .respond("/api/v2/flags/default/deleted-flag", 404, "")
+            .delay(std::time::Duration::from_millis(50))
+            .start()
+            .await;
+
+        let issues = LaunchDarklyChecker {
+            max_concurrent_requests: 2,
+            ..checker(&server)
+        }
+        .check()
+        .await
+        .unwrap()
+        .issues;
+        // Two of the four detail requests at a time, with the same issues as one at a time
+        assert_eq!(server.most_in_flight(), 2);
+        let ids: Vec<&str> = issues.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "ld:default:new-checkout:production",
+                "ld:default:search-v2:production",
+                "ld:default:theme:production",
+                "ld:default:theme:staging",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_launchdarkly_flags_list_pages() {
+        let server = MockServer::new()
+            .json(
+                LIST,
+                r#"{
+                    "items": [{ "key": "new-checkout", "name": "New checkout" }],
+                    "_links": {
+                        "next": { "href": "/api/v2/flags/default?filter=maintainerId%3Ame&limit=1&offset=1" }
+                    }
+                }"#,
+            )
+            .json(
+                "/api/v2/flags/default?filter=maintainerId%3Ame&limit=1&offset=1",
+                r#"{ "items": [{ "key": "search-v2", "name": "Search v2" }], "_links": {} }"#,
+            )
+            .json(
+                "/api/v2/flags/default/new-checkout",
+                include_str!("launchdarkly/new-checkout.json"),
+            )
+            .json(
+                "/api/v2/flags/default/search-v2",
+                include_str!("launchdarkly/search-v2.json"),
+            )
+            .start()
+            .await;
+
+        let issues = checker(&server).check().await.unwrap().issues;
+        let ids: Vec<&str> = issues.iter().map(|i| i.id.as_str()).collect();
+        // search-v2 is only on the second page
+        assert_eq!(
+            ids,
+            [
+                "ld:default:new-checkout:production",
+                "ld:default:search-v2:production"
+            ]
+        );
+        assert_eq!(
+            server.requests()[..2],
+            [
+                LIST,
+                "/api/v2/flags/default?filter=maintainerId%3Ame&limit=1&offset=1",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_launchdarkly_rate_limits() {
+        let reset_in = |millis: i64| (Utc::now().timestamp_millis() + millis).to_string();
+        let started = Instant::now();
+        let server = MockServer::new()
+            .respond_first(
+                "/api/v2/flags/default",
+                429,
+                &[("x-ratelimit-reset", &reset_in(100))],
+                "",
+            )
+            // Only 2 requests left, fewer than could be in flight
+            .respond_with_headers(
+                "/api/v2/flags/default",
+                200,
+                &[
+                    ("x-ratelimit-route-remaining", "2"),
+                    ("x-ratelimit-reset", &reset_in(400)),
+                ],
+                r#"{ "items": [{ "key": "new-checkout", "name": "New checkout" }] }"#,
+            )
+            .respond_first(
+                "/api/v2/flags/default/new-checkout",
+                429,
+                &[("x-ratelimit-reset", &reset_in(0))],
+                "",
+            )
+            .json(
+                "/api/v2/flags/default/new-checkout",
+                include_str!("launchdarkly/new-checkout.json"),
+            )
+            .start()
+            .await;
+
+        let issues = checker(&server).check().await.unwrap().issues;
+        let ids: Vec<&str> = issues.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, ["ld:default:new-checkout:production"]);
+        assert_eq!(
+            server.requests(),
+            [
+                LIST,
+                LIST,
+                "/api/v2/flags/default/new-checkout",
+                "/api/v2/flags/default/new-checkout",
+            ]
+        );
+        // The detail request waited for the limit to reset
+        assert!(started.elapsed() >= Duration::from_millis(350));
+    }
+
+    #[tokio::test]
+    async fn test_injected_client() {
+        let server = MockServer::new()
+            .json(LIST, r#"{ "items": [] }"#)
+            .delay(Duration::from_millis(500))
+            .start()
+            .await;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(50))
+            .build()
+            .unwrap();
+
+        // Requests that hang time out and are retried, rather than stalling the check
+        let err = checker(&server)
+            .with_client(client)
+            .check()
+            .await
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("timed out"), "{:#}", err);
+        assert!(server.requests().len() > 1);
+    }
+
+    #[tokio::test]
+    async fn test_token_validation() {
+        let revoked = MockServer::new()
+            .respond("/api/v2/caller-identity", 401, "")
+            .start()
+            .await;
+        let rejected = checker(&revoked);
+        let access = rejected.token_access().await.unwrap();
+        assert_eq!(access, TokenAccess::Rejected(StatusCode::UNAUTHORIZED));
+        let issues: Vec<String> = rejected
+            .token_issues(&access)
+            .iter()
+            .map(|i| i.to_string())
+            .collect();
+        assert_eq!(
+            issues,
+            ["LaunchDarkly token is invalid or revoked (401); set a new launchdarkly.api_token"]
+        );
+
+        let server = MockServer::new()
+            .json(
+                "/api/v2/caller-identity",
+                r#"{ "tokenName": "work-driver" }"#,
+            )
+            .json("/api/v2/projects/default", r#"{ "key": "default" }"#)
+            .respond("/api/v2/projects/web", 403, "")
+            .start()
+            .await;
+        let partial = LaunchDarklyChecker {
+            project_keys: vec![
+                "default".to_string(),
+                "web".to_string(),
+                "mobile".to_string(),
+            ],
+            ..checker(&server)
+        };
+        let access = partial.token_access().await.unwrap();
+        let issues: Vec<(String, String)> = partial
+            .token_issues(&access)
+            .iter()
+            .map(|i| (i.id.clone(), i.to_string()))
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                (
+                    "ld:token:web".to_string(),
+                    "LaunchDarkly token lacks reader access to project 'web'".to_string()
+                ),
+                (
+                    "ld:token:mobile".to_string(),
+                    "LaunchDarkly token can't find project 'mobile'; check launchdarkly.project_keys"
+                        .to_string()
+                ),
+            ]
+        );
+
+        // A token that works for every project has nothing to report
+        let valid = LaunchDarklyChecker {
+            project_keys: vec!["default".to_string()],
+            ..partial
+        };
+        let access = valid.token_access().await.unwrap();
+        assert_eq!(access, TokenAccess::Usable { unreadable: vec![] });
+        assert!(valid.token_issues(&access).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_segments() {
+        let server = MockServer::new()
+            .json(LIST, include_str!("launchdarkly/segment-flags.json"))
+            .json(
+                "/api/v2/segments/default/production",
+                include_str!("launchdarkly/segments.json"),
+            )
+            .start()
+            .await;
+
+        let issues = LaunchDarklyChecker {
+            segments: true,
+            ..checker(&server)
+        }
+        .check()
+        .await
+        .unwrap()
+        .issues;
+        let segment_issues: Vec<(String, String)> = issues
+            .iter()
+            .filter(|i| i.id.starts_with("ld:segment:"))
+            .map(|i| (i.id.clone(), i.to_string()))
+            .collect();
+        assert_eq!(
+            segment_issues,
+            [
+                (
+                    "ld:segment:default:beta-testers:production:empty".to_string(),
+                    "Segment 'Beta testers' in production has no rules or targets, but flag 'New checkout' and 1 other uses it".to_string()
+                ),
+                (
+                    "ld:segment:default:internal:production:stale".to_string(),
+                    format!(
+                        "Segment 'Internal' in production unchanged for {} days while flag 'New checkout' is rolling out there",
+                        (Utc::now().timestamp_millis() - 1672531200000) / (24 * 60 * 60 * 1000)
+                    )
+                ),
+            ]
+        );
+        // Staging has no flags using segments, so they aren't listed there
+        let listed: Vec<String> = server
+            .requests()
+            .into_iter()
+            .filter(|request| request.starts_with("/api/v2/segments/"))
+            .collect();
+        assert_eq!(listed, ["/api/v2/segments/default/production"]);
+    }
+
+    #[tokio::test]
+    async fn test_launchdarkly_projects() {
+        let server = MockServer::new()
+            .json(
+                "/api/v2/flags/web",
+                r#"{ "items": [{ "key": "new-checkout", "name": "New checkout" }] }"#,
+            )
+            .json(
+                "/api/v2/flags/web/new-checkout",
+                include_str!("launchdarkly/new-checkout.json"),
+            )
+            .respond("/api/v2/flags/mobile", 400, "unknown project")
+            .start()
+            .await;
+
+        let issues = LaunchDarklyChecker {
+            project_keys: vec!["web".to_string(), "mobile".to_string()],
+            ..checker(&server)
+        }
+        .check()
+        .await
+        .unwrap()
+        .issues;
+        // mobile failing doesn't stop web's flags from being checked
+        let summaries: Vec<(String, String)> = issues
+            .iter()
+            .map(|i| (i.id.clone(), i.to_string()))
+            .collect();
+        assert_eq!(
+            summaries,
+            [
+                (
+                    "checker:LaunchDarkly mobile".to_string(),
+                    "Checker 'LaunchDarkly mobile' failed: LaunchDarkly API returned error: 400 Bad Request".to_string()
+                ),
+                (
+                    "ld:web:new-checkout:production".to_string(),
+                    "Flag 'New checkout' rolled out to 100% in staging, but not started in production".to_string()
+                ),
+            ]
+        );
+        assert!(
+            issues[1]
+                .url
+                .as_deref()
+                .unwrap()
+                .contains("/projects/web/flags/new-checkout/")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_approval_requests() {
+        let requested = Utc::now().timestamp_millis() - (5 * 60 * 60 + 30) * 1000;
+        let request = |id: &str, review_status: &str, notify: &str| {
+            json!({
+                "_id": id,
+                "requestorId": "alice-id",
+                "creationDate": requested,
+                "reviewStatus": review_status,
+                "notifyMemberIds": [notify],
+                "resourceId": "proj/web:env/production:flag/new-checkout",
+                "flag": { "key": "new-checkout", "name": "New checkout" },
+            })
+        };
+        let requests = json!({ "items": [
+            request("req-1", "pending", "me"),
+            request("req-2", "approved", "me"),
+            request("req-3", "pending", "someone-else"),
+        ] });
+        let server = MockServer::new()
+            .json(LIST, r#"{ "items": [] }"#)
+            .json("/api/v2/approval-requests", requests.to_string())
+            .json(
+                "/api/v2/members/alice-id",
+                r#"{ "_id": "alice-id", "firstName": "alice", "email": "alice@example.com" }"#,
+            )
+            .start()
+            .await;
+
+        let issues = LaunchDarklyChecker {
+            approval_requests: true,
+            ..checker(&server)
+        }
+        .check()
+        .await
+        .unwrap()
+        .issues;
+        assert_eq!(
+            issues.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
+            [
+                "Approval request for flag 'New checkout' in production waiting on you (requested by alice, 5h 0m ago)"
+            ]
+        );
+        assert_eq!(issues[0].id, "ld:approval:req-1");
+        assert_eq!(
+            issues[0].url.as_deref(),
+            Some(format!("{}/projects/web/approvals/req-1", server.url).as_str())
+        );
+
+        // When approval requests can't be listed, only that part of the check fails
+        let server = MockServer::new()
+            .json(LIST, r#"{ "items": [] }"#)
+            .respond("/api/v2/approval-requests", 400, "bad filter")
+            .start()
+            .await;
+        let issues = LaunchDarklyChecker {
+            approval_requests: true,
+            ..checker(&server)
+        }
+        .check()
+        .await
+        .unwrap()
+        .issues;
+        assert_eq!(
+            issues.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
+            [
+                "Checker 'LaunchDarkly approvals' failed: LaunchDarkly API returned error: 400 Bad Request"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prerequisites() {
+        let server = MockServer::new()
+            .json(LIST, include_str!("launchdarkly/prerequisites.json"))
+            .json(
+                "/api/v2/flags/default/payments-v2",
+                include_str!("launchdarkly/payments-v2.json"),
+            )
+            .start()
+            .await;
+
+        let issues = checker(&server).check().await.unwrap().issues;
+        let summaries: Vec<(String, String)> = issues
+            .iter()
+            .map(|i| (i.id.clone(), i.to_string()))
+            .collect();
+        // Payments v2 is off in production but on in staging, and Receipts is off in
+        // staging. Search index is on, so Search UI's chain is fine
+        assert_eq!(
+            summaries,
+            [
+                (
+                    "ld:default:checkout-ui:production:prerequisite:payments-v2".to_string(),
+                    "Flag 'Checkout UI' in production depends on prerequisite 'Payments v2' which is off".to_string()
+                ),
+                (
+                    "ld:default:receipts:production:prerequisite:payments-v2".to_string(),
+                    "Flag 'Receipts' in production depends on prerequisite 'Payments v2' which is off".to_string()
+                ),
+            ]
+        );
+        // Payments v2 is fetched once for both flags, and Search index is one of ours
+        assert_eq!(
+            server.requests(),
+            [LIST, "/api/v2/flags/default/payments-v2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_overdue_scheduled_changes() {
+        let scheduled = |flag: &str| {
+            format!(
+                "/api/v2/projects/default/flags/{}/environments/production/scheduled-changes",
+                flag
+            )
+        };
+        let later = Utc::now().timestamp_millis() + 24 * 60 * 60 * 1000;
+        let server = MockServer::new()
+            .json(
+                LIST,
+                r#"{ "items": [
+                    { "key": "new-checkout", "name": "New checkout" },
+                    { "key": "search-v2", "name": "Search v2" }
+                ] }"#,
+            )
+            .json(
+                "/api/v2/flags/default/new-checkout",
+                include_str!("launchdarkly/new-checkout.json"),
+            )
+            .json(
+                "/api/v2/flags/default/search-v2",
+                include_str!("launchdarkly/search-v2.json"),
+            )
+            .json(
+                &scheduled("new-checkout"),
+                json!({ "items": [
+                    { "_id": "overdue", "executionDate": 1717405200000i64 },
+                    { "_id": "upcoming", "executionDate": later },
+                ] })
+                .to_string(),
+            )
+            .json(&scheduled("search-v2"), r#"{ "items": [] }"#)
+            .respond(
+                "/api/v2/projects/default/flags/new-checkout/environments/staging/scheduled-changes",
+                404,
+                "not found",
+            )
+            .start()
+            .await;
+
+        let issues = LaunchDarklyChecker {
+            scheduled_changes: true,
+            max_concurrent_requests: 1,
+            ..checker(&server)
+        }
+        .check()
+        .await
+        .unwrap()
+        .issues;
+        let overdue: Vec<(String, String)> = issues
+            .iter()
+            .filter(|i| i.kind == IssueKind::ScheduledChangeOverdue)
+            .map(|i| (i.id.clone(), i.to_string()))
+            .collect();
+        assert_eq!(
+            overdue,
+            [(
+                "ld:default:new-checkout:production:scheduled:overdue".to_string(),
+                "Flag 'New checkout' change scheduled in production for 2024-06-03 09:00 UTC hasn't been made".to_string()
+            )]
+        );
+        // Staging not having scheduled changes for one flag is remembered for the next
+        let staging_requests = server
+            .requests()
+            .iter()
+            .filter(|request| request.ends_with("/staging/scheduled-changes"))
+            .count();
+        assert_eq!(staging_requests, 1);
+    }
+
+    #[tokio::test]
+    async fn test_experiments() {
+        const DAY: i64 = 24 * 60 * 60 * 1000;
+        let now = Utc::now().timestamp_millis();
+        let flag = |key: &str, name: &str| {
+            json!({
+                "key": key,
+                "name": name,
+                "kind": "boolean",
+                "variations": [
+                    { "_id": "a", "name": "Enabled", "value": true },
+                    { "_id": "b", "name": "Disabled", "value": false },
+                ],
+                "environments": {
+                    "production": {
+                        "lastModified": now - 30 * DAY,
+                        "on": true,
+                        "fallthrough": { "rollout": {
+                            "variations": [
+                                { "variation": 0, "weight": 50000 },
+                                { "variation": 1, "weight": 50000 },
+                            ],
+                            "experimentAllocation": { "defaultVariation": 1, "canReshuffle": false },
+                        } },
+                    },
+                },
+            })
+        };
+        let list = json!({ "items": [flag("search-v2", "Search v2"), flag("theme", "Theme")] });
+        let experiments = |flag: &str| {
+            format!(
+                "/api/v2/projects/default/environments/production/experiments?filter=flagKey%3A{}%2Cstatus%3Arunning",
+                flag
+            )
+        };
+        let experiment = |key: &str, name: &str, started: i64| {
+            json!({ "items": [
+                { "key": key, "name": name, "currentIteration": { "startedAt": started } },
+            ] })
+            .to_string()
+        };
+        let server = MockServer::new()
+            .json(LIST, list.to_string())
+            .json(
+                &experiments("search-v2"),
+                experiment("search-ranking", "Search ranking", now - 20 * DAY),
+            )
+            .json(
+                &experiments("theme"),
+                experiment("dark-mode", "Dark mode", now - 2 * DAY),
+            )
+            .start()
+            .await;
+
+        // Neither rollout is reported as stale while its experiment runs, until the
+        // experiment's been going longer than `experiment_days`
+        let issues = checker(&server).check().await.unwrap().issues;
+        let reported: Vec<(String, String, Option<String>)> = issues
+            .iter()
+            .map(|i| (i.id.clone(), i.to_string(), i.url.clone()))
+            .collect();
+        assert_eq!(
+            reported,
+            [(
+                "ld:default:search-v2:production:experiment".to_string(),
+                "Flag 'Search v2' in production running experiment 'Search ranking' for 20 days — check results".to_string(),
+                Some(format!(
+                    "{}/projects/default/experiments/search-ranking/results?env=production",
+                    server.url
+                )),
+            )]
+        );
+
+        // Without the experiments API, it's been running at least since the last change
+        let server = MockServer::new()
+            .json(LIST, list.to_string())
+            .respond(
+                "/api/v2/projects/default/environments/production/experiments",
+                404,
+                "not found",
+            )
+            .start()
+            .await;
+        let issues = LaunchDarklyChecker {
+            max_concurrent_requests: 1,
+            ..checker(&server)
+        }
+        .check()
+        .await
+        .unwrap()
+        .issues;
+        let reported: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            reported,
+            [
+                "Flag 'Search v2' in production running experiment for 30 days — check results",
+                "Flag 'Theme' in production running experiment for 30 days — check results",
+            ]
+        );
+        let experiment_requests = server
+            .requests()
+            .iter()
+            .filter(|request| request.contains("/experiments"))
+            .count();
+        assert_eq!(experiment_requests, 1);
+    }
+
+    #[test]
+    fn test_off_and_stale() {
+        const DAY: i64 = 24 * 60 * 60 * 1000;
+        let now = 200 * DAY;
+        let flag: LaunchDarklyFlagDetail =
+            serde_json::from_str(include_str!("launchdarkly/rule-targeting.json")).unwrap();
+        let enabled = enabled_variation(
+            &flag,
+            &LaunchDarklyConfig::default().enabled_variation_names,
+        );
+        let off_and_stale = |env: serde_json::Value| {
+            let env = serde_json::from_value(env).unwrap();
+            off_and_stale(&flag, enabled, &env, now, 90 * DAY)
+        };
+
+        assert!(off_and_stale(
+            json!({ "on": false, "lastModified": 10 * DAY })
+        ));
+        // On, but serving everyone the disabled variation
+        assert!(off_and_stale(
+            json!({ "on": true, "lastModified": 10 * DAY, "fallthrough": { "variation": 1 } })
+        ));
+        assert!(!off_and_stale(
+            json!({ "on": false, "lastModified": 150 * DAY })
+        ));
+        assert!(!off_and_stale(json!({
+            "on": true,
+            "lastModified": 10 * DAY,
+            "fallthrough": { "rollout": { "variations": [
+                { "variation": 0, "weight": 25000 },
+                { "variation": 1, "weight": 75000 },
+            ] } },
+        })));
+        // Still served to a beta segment, or to individual users
+        assert!(!off_and_stale(json!({
+            "on": true,
+            "lastModified": 10 * DAY,
+            "fallthrough": { "variation": 1 },
+            "rules": [{ "variation": 0 }],
+        })));
+        assert!(!off_and_stale(json!({
+            "on": true,
+            "lastModified": 10 * DAY,
+            "fallthrough": { "variation": 1 },
+            "targets": [{ "values": ["alice"], "variation": 0 }],
+        })));
+    }
+
+    #[tokio::test]
+    async fn test_dead_flags() {
+        const DAY: i64 = 24 * 60 * 60 * 1000;
+        let now = Utc::now().timestamp_millis();
+        let flag = |key: &str, name: &str, temporary: bool, staging_on: bool, modified: i64| {
+            let env = |on: bool| json!({ "lastModified": now - modified * DAY, "on": on, "offVariation": 1 });
+            json!({
+                "key": key,
+                "name": name,
+                "kind": "boolean",
+                "temporary": temporary,
+                "variations": [
+                    { "_id": "a", "name": "Enabled", "value": true },
+                    { "_id": "b", "name": "Disabled", "value": false },
+                ],
+                "environments": { "staging": env(staging_on), "production": env(false) },
+            })
+        };
+        let list = json!({ "items": [
+            flag("old-checkout", "Old checkout", true, false, 120),
+            flag("kill-switch", "Kill switch", false, false, 120),
+            flag("beta-banner", "Beta banner", true, true, 120),
+            flag("dark-mode", "Dark mode", true, false, 30),
+        ] });
+        let server = MockServer::new().json(LIST, list.to_string()).start().await;
+        let checker = LaunchDarklyChecker {
+            dead_flags_every_ms: Some(30 * DAY),
+            ..checker(&server)
+        };
+        let mut tracked = Tracked {
+            dead_flags: Some(Vec::new()),
+            ..Tracked::default()
+        };
+        checker
+            .check_project("default", now, &mut tracked)
+            .await
+            .unwrap();
+
+        // Only the temporary flag off in every environment for long enough, as one issue
+        let flags = tracked.dead_flags.unwrap();
+        let at = DateTime::parse_from_rfc3339("2024-06-03T09:00:00Z")
+            .unwrap()
+            .to_utc();
+        let issue = checker.dead_flags_issue(at, flags).unwrap();
+        assert_eq!(issue.id, "ld:dead-flags:2024-06-03");
+        assert_eq!(
+            issue.to_string(),
+            "1 flag off everywhere and untouched for 90+ days"
+        );
+        assert_eq!(
+            issue.items,
+            [IssueItem {
+                text: "Flag 'Old checkout' untouched for 120 days".to_string(),
+                url: Some(format!(
+                    "{}/projects/default/flags/old-checkout/targeting?env=production&env=staging&selected-env=production",
+                    server.url
+                )),
+            }]
+        );
+        assert!(checker.dead_flags_issue(at, Vec::new()).is_none());
+    }
+
+    #[test]
+    fn test_business_duration_since() {
+        // 2024-06-07 is a Friday
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let days = |utc_offset: &str, holidays: &[&str]| BusinessDays {
+            holidays: holidays.iter().map(|d| d.parse().unwrap()).collect(),
+            utc_offset: Some(utc_offset.parse().unwrap()),
+            ..BusinessDays::default()
+        };
+        let hours = |since: &str, now: &str, days: &BusinessDays| {
+            business_duration_since(at(since), at(now), days).num_minutes() as f64 / 60.0
+        };
+        let utc = days("+00:00", &[]);
+
+        // Within a weekday, all of it counts
+        assert_eq!(
+            hours("2024-06-05T09:00:00Z", "2024-06-05T12:30:00Z", &utc),
+            3.5
+        );
+        // Friday evening to Monday morning only counts the weekdays' parts
+        let weekend = ("2024-06-07T20:00:00Z", "2024-06-10T02:00:00Z");
+        assert_eq!(hours(weekend.0, weekend.1, &utc), 6.0);
+        // Which depends on where the days start: in Tokyo it's Saturday morning to
+        // Monday 11:00, and in California it's Friday noon to Sunday evening
+        assert_eq!(hours(weekend.0, weekend.1, &days("+09:00", &[])), 11.0);
+        assert_eq!(hours(weekend.0, weekend.1, &days("-08:00", &[])), 12.0);
+        // All on a weekend
+        assert_eq!(
+            hours("2024-06-08T01:00:00Z", "2024-06-09T23:00:00Z", &utc),
+            0.0
+        );
+        // A week is five business days
+        assert_eq!(
+            hours("2024-06-03T00:00:00Z", "2024-06-10T00:00:00Z", &utc),
+            120.0
+        );
+        // Holidays are skipped like weekends
+        let holiday = days("+00:00", &["2024-06-05"]);
+        assert_eq!(
+            hours("2024-06-04T12:00:00Z", "2024-06-06T12:00:00Z", &holiday),
+            24.0
+        );
+        // Nothing when modified after now
+        assert_eq!(
+            hours("2024-06-05T12:00:00Z", "2024-06-05T09:00:00Z", &utc),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_stale_over_business_days() {
+        let issues = |business_days: Option<BusinessDays>| {
+            let checker = LaunchDarklyChecker::new(&LaunchDarklyConfig {
+                api_token: Some("api-test".to_string()),
+                maintainer_id: Some("me".to_string()),
+                business_days,
+                ..LaunchDarklyConfig::default()
+            })
+            .unwrap();
+            let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().timestamp_millis();
+            let flag = serde_json::from_value(json!({
+                "key": "search-v2",
+                "name": "Search v2",
+                "kind": "boolean",
+                "variations": [
+                    { "_id": "a", "name": "Enabled", "value": true },
+                    { "_id": "b", "name": "Disabled", "value": false },
+                ],
+                "environments": {
+                    "production": {
+                        // Friday evening
+                        "lastModified": at("2024-06-07T18:00:00Z"),
+                        "on": true,
+                        "fallthrough": { "rollout": { "variations": [
+                            { "variation": 0, "weight": 25000 },
+                            { "variation": 1, "weight": 75000 },
+                        ] } },
+                    },
+                },
+            }))
+            .unwrap();
+            checker
+                .flag_issues(
+                    "default",
+                    flag,
+                    at("2024-06-10T09:00:00Z"),
+                    &mut Tracked::default(),
+                )
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+        };
+        let report = ["Flag 'Search v2' in production at partial 25% rollout, not updated in 18h"];
+        assert_eq!(issues(None), report);
+        // Only 15 of the 63 hours since were on business days
+        let utc = BusinessDays {
+            utc_offset: "+00:00".parse().ok(),
+            ..BusinessDays::default()
+        };
+        assert!(issues(Some(utc.clone())).is_empty());
+        // In Tokyo it was already Saturday, but it's been Monday for 18 hours
+        let tokyo = BusinessDays {
+            utc_offset: "+09:00".parse().ok(),
+            ..utc
+        };
+        assert_eq!(issues(Some(tokyo)), report);
+    }
+
+    #[test]
+    fn test_ramp_reminders() {
+        const HOUR: i64 = 60 * 60 * 1000;
+        let checker = LaunchDarklyChecker::new(&LaunchDarklyConfig {
+            api_token: Some("api-test".to_string()),
+            maintainer_id: Some("me".to_string()),
+            ramp_minutes: Some(4 * 60),
+            ramp_minutes_by_tag: HashMap::from([("slow-ramp".to_string(), 12 * 60)]),
+            ..LaunchDarklyConfig::default()
+        })
+        .unwrap();
+        let issues = |tags: &[&str], hours_since: i64| {
+            let now = 100 * HOUR;
+            let flag = serde_json::from_value(json!({
+                "key": "search-v2",
+                "name": "Search v2",
+                "kind": "boolean",
+                "tags": tags,
+                "variations": [
+                    { "_id": "a", "name": "Enabled", "value": true },
+                    { "_id": "b", "name": "Disabled", "value": false },
+                ],
+                "environments": {
+                    "production": {
+                        "lastModified": now - hours_since * HOUR,
+                        "on": true,
+                        "fallthrough": { "rollout": { "variations": [
+                            { "variation": 0, "weight": 25000 },
+                            { "variation": 1, "weight": 75000 },
+                        ] } },
+                    },
+                },
+            }))
+            .unwrap();
+            checker
+                .flag_issues("default", flag, now, &mut Tracked::default())
+                .into_iter()
+                .map(|i| (i.id.clone(), i.severity, i.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        assert!(issues(&[], 3).is_empty());
+        assert_eq!(
+            issues(&[], 5),
+            [(
+                "ld:default:search-v2:production:ramp".to_string(),
+                Severity::Low,
+                "Flag 'Search v2' set to 25% in production 5h 0m ago — time to bump it".to_string()
+            )]
+        );
+        // Once stale, it's only the stale rollout
+        assert_eq!(
+            issues(&[], 19),
+            [(
+                "ld:default:search-v2:production".to_string(),
+                Severity::Medium,
+                "Flag 'Search v2' in production at partial 25% rollout, not updated in 18h"
+                    .to_string()
+            )]
+        );
+        // Tagged for a slower ramp, it isn't due yet
+        assert!(issues(&["slow-ramp"], 5).is_empty());
+        assert_eq!(issues(&["slow-ramp", "web"], 13).len(), 1);
+    }
+
+    #[test]
+    fn test_rollout_progress() {
+        const HOUR: i64 = 60 * 60 * 1000;
+        let checker = LaunchDarklyChecker::new(&LaunchDarklyConfig {
+            api_token: Some("api-test".to_string()),
+            maintainer_id: Some("me".to_string()),
+            ..LaunchDarklyConfig::default()
+        })
+        .unwrap();
+        let mut tracked = Tracked::default();
+        // Each check starts from the rollouts the last one left behind
+        let mut check = |percent: i32, last_modified: i64, now: i64| {
+            let flag = serde_json::from_value(json!({
+                "key": "search-v2",
+                "name": "Search v2",
+                "kind": "boolean",
+                "variations": [
+                    { "_id": "a", "name": "Enabled", "value": true },
+                    { "_id": "b", "name": "Disabled", "value": false },
+                ],
+                "environments": {
+                    "production": {
+                        "lastModified": last_modified,
+                        "on": true,
+                        "fallthrough": { "rollout": { "variations": [
+                            { "variation": 0, "weight": percent * 1000 },
+                            { "variation": 1, "weight": (100 - percent) * 1000 },
+                        ] } },
+                    },
+                },
+            }))
+            .unwrap();
+            let issues: Vec<String> = checker
+                .flag_issues("default", flag, now, &mut tracked)
+                .iter()
+                .map(|i| i.to_string())
+                .collect();
+            tracked.rollouts.before = std::mem::take(&mut tracked.rollouts.now);
+            issues
+        };
+
+        // First seen at 25%, then its description is edited without ramping it further
+        assert!(check(25, 0, HOUR).is_empty());
+        assert!(check(25, 11 * HOUR, 12 * HOUR).is_empty());
+        assert_eq!(
+            check(25, 11 * HOUR, 20 * HOUR),
+            ["Flag 'Search v2' in production at partial 25% rollout, not updated in 18h"]
+        );
+        assert!(check(50, 21 * HOUR, 21 * HOUR).is_empty());
+        // Going backwards is reported until it's back up to where it was
+        assert_eq!(
+            check(30, 22 * HOUR, 22 * HOUR),
+            ["Flag 'Search v2' in production rolled back from 50% to 30%"]
+        );
+        assert_eq!(
+            check(40, 23 * HOUR, 23 * HOUR),
+            ["Flag 'Search v2' in production rolled back from 50% to 40%"]
+        );
+        assert!(check(50, 24 * HOUR, 24 * HOUR).is_empty());
+        // Or until it's been left there long enough to be stale instead
+        assert_eq!(
+            check(25, 25 * HOUR, 25 * HOUR),
+            ["Flag 'Search v2' in production rolled back from 50% to 25%"]
+        );
+        assert_eq!(
+            check(25, 25 * HOUR, 44 * HOUR),
+            ["Flag 'Search v2' in production at partial 25% rollout, not updated in 18h"]
+        );
+        assert_eq!(
+            tracked.rollouts.before["default:search-v2:production"],
+            RolloutProgress {
+                percent: 25.0,
+                since: DateTime::from_timestamp_millis(25 * HOUR).unwrap(),
+                rolled_back_from: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        let now = Utc::now().timestamp_millis();
+        let minutes_ago = |minutes: i64| now - minutes * 60 * 1000;
+        let entries = json!({ "items": [
+            {
+                "_id": "bob-off",
+                "date": minutes_ago(12),
+                "titleVerb": "turned off the flag",
+                "member": { "_id": "bob-id", "firstName": "bob", "email": "bob@example.com" },
+                "target": { "resources": ["proj/default:env/production:flag/new-checkout"] },
+            },
+            {
+                "_id": "mine",
+                "date": minutes_ago(10),
+                "titleVerb": "turned on the flag",
+                "member": { "_id": "me", "email": "me@example.com" },
+                "target": { "resources": ["proj/default:env/production:flag/new-checkout"] },
+            },
+            {
+                "_id": "not-ours",
+                "date": minutes_ago(8),
+                "member": { "_id": "bob-id", "email": "bob@example.com" },
+                "target": { "resources": ["proj/default:env/production:flag/someone-elses"] },
+            },
+            {
+                "_id": "other-project",
+                "date": minutes_ago(6),
+                "member": { "_id": "bob-id", "email": "bob@example.com" },
+                "target": { "resources": ["proj/mobile:env/production:flag/new-checkout"] },
+            },
+            {
+                "_id": "token",
+                "date": minutes_ago(5),
+                "titleVerb": "updated the flag",
+                "target": { "resources": ["proj/default:flag/new-checkout"] },
+            },
+        ] });
+        let server = MockServer::new()
+            .json("/api/v2/auditlog", entries.to_string())
+            .start()
+            .await;
+        let checker = LaunchDarklyChecker {
+            audit_log: true,
+            ..checker(&server)
+        };
+        let flags: Vec<LaunchDarklyFlag> =
+            serde_json::from_str(r#"[{ "key": "new-checkout", "name": "New checkout" }]"#).unwrap();
+        let mut audit = AuditLog::default();
+
+        // The first read only notes where to start
+        let started = minutes_ago(30);
+        checker
+            .read_audit_log("default", &flags, started, &mut audit)
+            .await;
+        assert!(server.requests().is_empty());
+        assert_eq!(audit.after["default"], started);
+
+        checker
+            .read_audit_log("default", &flags, now, &mut audit)
+            .await;
+        assert_eq!(
+            server.requests(),
+            [format!(
+                "/api/v2/auditlog?after={}&spec=proj%2Fdefault%3Aenv%2F*%3Aflag%2F*",
+                started - AUDIT_OVERLAP_MS
+            )]
+        );
+        assert_eq!(audit.after["default"], now);
+        let now = DateTime::from_timestamp_millis(now).unwrap();
+        let mut issues: Vec<(String, String)> = audit
+            .modifications
+            .iter()
+            .map(|(id, modification)| {
+                let issue = checker.modification_issue(id, modification, now);
+                (issue.id.clone(), issue.to_string())
+            })
+            .collect();
+        issues.sort();
+        assert_eq!(
+            issues,
+            [
+                (
+                    "ld:audit:bob-off".to_string(),
+                    "Flag 'New checkout' modified in production by bob 12m ago (turned off)"
+                        .to_string()
+                ),
+                (
+                    "ld:audit:token".to_string(),
+                    "Flag 'New checkout' modified by an API token 5m ago (updated)".to_string()
+                ),
+            ]
+        );
+
+        // Entries read again aren't recorded twice
+        audit.modifications.get_mut("bob-off").unwrap().by = "already seen".to_string();
+        checker
+            .read_audit_log("default", &flags, now.timestamp_millis(), &mut audit)
+            .await;
+        assert_eq!(audit.modifications.len(), 2);
+        assert_eq!(audit.modifications["bob-off"].by, "already seen");
+    }
+
+    #[test]
+    fn test_ignore_tags_by_rule() {
+        let checker = LaunchDarklyChecker::new(&LaunchDarklyConfig {
+            api_token: Some("api-test".to_string()),
+            maintainer_id: Some("me".to_string()),
+            ignore_tags_by_rule: IgnoreTagsByRule {
+                stale: vec!["permanent".to_string()],
+                removal: vec!["permanent".to_string()],
+                ..IgnoreTagsByRule::default()
+            },
+            ..LaunchDarklyConfig::default()
+        })
+        .unwrap();
+        let issues = |fixture: &str, tags: &[&str]| {
+            let mut flag: serde_json::Value = serde_json::from_str(fixture).unwrap();
+            flag["tags"] = json!(tags);
+            checker
+                .flag_issues(
+                    "default",
+                    serde_json::from_value(flag).unwrap(),
+                    Utc::now().timestamp_millis(),
+                    &mut Tracked::default(),
+                )
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+        };
+        let search = include_str!("launchdarkly/search-v2.json");
+        let checkout = include_str!("launchdarkly/new-checkout.json");
+
+        assert_eq!(
+            issues(search, &["experiment"]),
+            ["Flag 'Search v2' in production at partial 25% rollout, not updated in 18h"]
+        );
+        // Permanent flags are never stale
+        assert!(issues(search, &["experiment", "permanent"]).is_empty());
+        // But can still be waiting on the next environment
+        assert_eq!(
+            issues(checkout, &["permanent"]),
+            ["Flag 'New checkout' rolled out to 100% in staging, but not started in production"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_launchdarkly_tags() {
+        let server = MockServer::new()
+            .json(
+                "/api/v2/flags/default?filter=maintainerId%3Ame%2Ctags%3Aexperiment+ops&summary=false&env=staging&env=production",
+                r#"{ "items": [
+                    { "key": "new-checkout", "name": "New checkout", "tags": ["experiment"] },
+                    { "key": "search-v2", "name": "Search v2", "tags": ["ops", "sunset"] }
+                ] }"#,
+            )
+            .json(
+                "/api/v2/flags/default/new-checkout",
+                include_str!("launchdarkly/new-checkout.json"),
+            )
+            .start()
+            .await;
+
+        let issues = LaunchDarklyChecker {
+            tags: vec!["experiment".to_string(), "ops".to_string()],
+            ignore_tags: vec!["sunset".to_string()],
+            ..checker(&server)
+        }
+        .check()
+        .await
+        .unwrap()
+        .issues;
+        let summaries: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            summaries,
+            ["Flag 'New checkout' rolled out to 100% in staging, but not started in production"]
+        );
+        // The ignored flag's details aren't even fetched
+        assert_eq!(server.requests().len(), 2);
+        assert!(server.requests()[1].ends_with("/new-checkout"));
+    }
+
+    #[test]
+    fn test_pick_flags() {
+        let pipeline = LaunchDarklyConfig::default().environments;
+        // Two of ten flags were changed recently, in staging
+        let flags: Vec<LaunchDarklyFlag> = (0..10)
+            .map(|i| {
+                let mut flag = json!({ "key": format!("flag-{}", i), "name": "Flag" });
+                if i == 3 || i == 7 {
+                    flag["environments"] = json!({ "staging": { "on": true, "lastModified": i } });
+                }
+                serde_json::from_value(flag).unwrap()
+            })
+            .collect();
+
+        let mut cursor: Option<String> = None;
+        let mut checked = HashSet::new();
+        for run in 0..4 {
+            let (picked, next) = pick_flags(&flags, 4, cursor.as_deref(), &pipeline);
+            assert_eq!(picked.len(), 4, "run {}", run);
+            // The recently changed ones every time
+            assert!(picked.contains("flag-3") && picked.contains("flag-7"));
+            checked.extend(picked);
+            cursor = next;
+        }
+        // The other eight take turns, two at a time, so every flag gets checked
+        assert_eq!(checked.len(), 10);
+        assert_eq!(cursor.as_deref(), Some("flag-9"));
+        // And then it starts over
+        let (picked, next) = pick_flags(&flags, 4, cursor.as_deref(), &pipeline);
+        let mut picked: Vec<String> = picked.into_iter().collect();
+        picked.sort();
+        assert_eq!(picked, ["flag-0", "flag-1", "flag-3", "flag-7"]);
+        assert_eq!(next.as_deref(), Some("flag-1"));
+    }
+
+    #[tokio::test]
+    async fn test_flags_checked_in_turn() {
+        let server = MockServer::new()
+            .json(
+                LIST,
+                r#"{ "items": [
+                    { "key": "theme", "name": "Theme" },
+                    { "key": "search-v2", "name": "Search v2" },
+                    { "key": "new-checkout", "name": "New checkout" }
+                ] }"#,
+            )
+            .json(
+                "/api/v2/flags/default/new-checkout",
+                include_str!("launchdarkly/new-checkout.json"),
+            )
+            .json(
+                "/api/v2/flags/default/search-v2",
+                include_str!("launchdarkly/search-v2.json"),
+            )
+            .json(
+                "/api/v2/flags/default/theme",
+                include_str!("launchdarkly/theme.json"),
+            )
+            .start()
+            .await;
+        let checker = LaunchDarklyChecker {
+            max_flags_per_check: 2,
+            ..checker(&server)
+        };
+        let now = Utc::now().timestamp_millis();
+        let mut tracked = Tracked::default();
+
+        // Only two flags are checked each time, in turn by key, and Search v2's issues are
+        // carried over while it waits
+        let expected: [&[&str]; 3] = [
+            &["new-checkout", "search-v2"],
+            &["new-checkout", "search-v2", "theme"],
+            &["new-checkout", "search-v2", "theme"],
+        ];
+        for flags in expected {
+            let issues = checker
+                .check_project("default", now, &mut tracked)
+                .await
+                .unwrap();
+            let mut issue_flags: Vec<&str> = issues
+                .iter()
+                .map(|issue| issue.id.split(':').nth(2).unwrap())
+                .collect();
+            issue_flags.sort();
+            issue_flags.dedup();
+            assert_eq!(issue_flags, flags);
+        }
+        let fetches = |flag: &str| {
+            let path = format!("/api/v2/flags/default/{}", flag);
+            server.requests().iter().filter(|r| **r == path).count()
+        };
+        assert_eq!(
+            [
+                fetches("new-checkout"),
+                fetches("search-v2"),
+                fetches("theme")
+            ],
+            [2, 2, 2]
+        );
+        assert_eq!(tracked.cursors["default"], "theme");
+    }
+
+    #[test]
+    fn test_flag_urls() {
+        let env = |name: &str| LaunchDarklyEnvironment {
+            name: name.to_string(),
+            done_percent: 100.0,
+            stale_minutes: 120,
+        };
+        let checker = LaunchDarklyChecker::new(&LaunchDarklyConfig {
+            api_token: Some("api-test".to_string()),
+            maintainer_id: Some("me".to_string()),
+            environments: vec![env("dev"), env("prod eu")],
+            ..LaunchDarklyConfig::default()
+        })
+        .unwrap();
+        assert_eq!(
+            checker.flag_url("web", "checkout/v2 beta", "dev"),
+            "https://app.launchdarkly.com/projects/web/flags/checkout%2Fv2%20beta/targeting?env=prod+eu&env=dev&selected-env=dev"
+        );
+
+        // A flag done in one environment but not started in the next links to the next
+        let flag = serde_json::from_value(json!({
+            "key": "new-checkout",
+            "name": "New checkout",
+            "kind": "boolean",
+            "variations": [
+                { "_id": "a", "name": "Enabled", "value": true },
+                { "_id": "b", "name": "Disabled", "value": false },
+            ],
+            "environments": {
+                "dev": {
+                    "lastModified": 0,
+                    "on": true,
+                    "fallthrough": { "rollout": {
+                        "variations": [{ "variation": 0, "weight": 100000 }],
+                    } },
+                },
+                "prod eu": { "lastModified": 0, "on": false, "offVariation": 1 },
+            },
+        }))
+        .unwrap();
+        let issues = checker.flag_issues("web", flag, 0, &mut Tracked::default());
+        let urls: Vec<(IssueKind, Option<&str>)> =
+            issues.iter().map(|i| (i.kind, i.url.as_deref())).collect();
+        assert_eq!(
+            urls,
+            [(
+                IssueKind::RolloutNotStarted,
+                Some(
+                    "https://app.launchdarkly.com/projects/web/flags/new-checkout/targeting?env=prod+eu&env=dev&selected-env=prod+eu"
+                )
+            )]
+        );
+        assert!(
+            LaunchDarklyChecker::new(&LaunchDarklyConfig {
+                api_token: Some("api-test".to_string()),
+                maintainer_id: Some("me".to_string()),
+                base_url: "app.launchdarkly.com".to_string(),
+                ..LaunchDarklyConfig::default()
+            })
+            .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_api_urls_are_encoded() {
+        let server = MockServer::new()
+            .json(
+                "/api/v2/flags/default",
+                r#"{ "items": [{ "key": "checkout/v2", "name": "Checkout v2" }], "_links": {} }"#,
+            )
+            .start()
+            .await;
+        let mut checker = checker(&server);
+        checker.environments[1].name = "prod eu".to_string();
+        checker.check().await.unwrap();
+        assert_eq!(
+            server.requests(),
+            [
+                "/api/v2/flags/default?filter=maintainerId%3Ame&summary=false&env=staging&env=prod+eu",
+                "/api/v2/flags/default/checkout%2Fv2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_eu_instance_urls() {
+        let checker = LaunchDarklyChecker::new(&LaunchDarklyConfig {
+            api_token: Some("api-test".to_string()),
+            maintainer_id: Some("me".to_string()),
+            base_url: "https://app.eu.launchdarkly.com/".to_string(),
+            ..LaunchDarklyConfig::default()
+        })
+        .unwrap();
+        let flag = serde_json::from_str(include_str!("launchdarkly/rule-targeting.json")).unwrap();
+        let issue = checker.flag_issue(
+            "web",
+            &flag,
+            "staging",
+            IssueKind::StaleRollout,
+            Severity::Medium,
+            "in staging at partial 25% rollout".to_string(),
+        );
+        assert_eq!(
+            issue.url.unwrap(),
+            "https://app.eu.launchdarkly.com/projects/web/flags/beta-editor/targeting?env=production&env=staging&selected-env=staging"
+        );
+        assert_eq!(
+            checker.flag_url("web", "beta-editor", "production"),
+            "https://app.eu.launchdarkly.com/projects/web/flags/beta-editor/targeting?env=production&env=staging&selected-env=production"
+        );
+    }
+
+    #[test]
+    fn test_resource_key() {
+        let resource = "proj/default:env/production:flag/new-checkout";
+        assert_eq!(resource_key(resource, "proj"), Some("default"));
+        assert_eq!(resource_key(resource, "env"), Some("production"));
+        assert_eq!(resource_key(resource, "flag"), Some("new-checkout"));
+        assert_eq!(
+            resource_key("proj/default:env/production:segment/beta", "flag"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_custom_environment_pipeline() {
+        let server = MockServer::new()
+            .json(
+                "/api/v2/flags/default",
+                r#"{ "items": [{ "key": "new-onboarding", "name": "New onboarding" }] }"#,
+            )
+            .json(
+                "/api/v2/flags/default/new-onboarding",
+                include_str!("launchdarkly/pipeline.json"),
+            )
+            .start()
+            .await;
+        let env = |name: &str, done_percent, stale_minutes| LaunchDarklyEnvironment {
+            name: name.to_string(),
+            done_percent,
+            stale_minutes,
+        };
+
+        let issues = LaunchDarklyChecker {
+            environments: vec![
+                env("dev", 100.0, 60),
+                env("stage", 50.0, 240),
+                env("prod", 100.0, 1440),
+            ],
+            ..checker(&server)
+        }
+        .check()
+        .await
+        .unwrap()
+        .issues;
+        // The flag's "staging" environment isn't in the pipeline, so its stale 25% is ignored
+        let summaries: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            summaries,
+            [
+                "Flag 'New onboarding' in dev at partial 40% rollout, not updated in 1h",
+                "Flag 'New onboarding' rolled out to 60% in stage, but not started in prod",
+            ]
+        );
+        assert_eq!(
+            issues[1].url.as_deref(),
+            Some(
+                format!(
+                    "{}/projects/default/flags/new-onboarding/targeting?env=prod&env=stage&env=dev&selected-env=prod",
+                    server.url
+                )
+                .as_str()
+            )
+        );
+    }
+
+    #[test]
+    fn test_variation_split() {
+        let flag: LaunchDarklyFlagDetail =
+            serde_json::from_str(include_str!("launchdarkly/theme.json")).unwrap();
+        let split = |env_name: &str| variation_split(&flag.environments[env_name]);
+
+        assert_eq!(split("staging"), Some(vec![30.0, 30.0, 40.0]));
+        // Variations it doesn't serve are left out
+        assert_eq!(split("production"), Some(vec![50.0, 50.0]));
+        // Nothing to report for a string flag serving one variation, or switched off
+        let env = |json| serde_json::from_value::<Environment>(json).unwrap();
+        assert_eq!(
+            variation_split(&env(json!({
+                "on": true,
+                "fallthrough": { "rollout": { "variations": [
+                    { "variation": 0, "weight": 0 },
+                    { "variation": 1, "weight": 100000 },
+                    { "variation": 2, "weight": 0 },
+                ] } },
+            }))),
+            None
+        );
+        assert_eq!(
+            variation_split(&env(json!({
+                "on": false,
+                "fallthrough": { "rollout": { "variations": [
+                    { "variation": 0, "weight": 60000 },
+                    { "variation": 1, "weight": 40000 },
+                ] } },
+            }))),
+            None
+        );
+        // Non-boolean flags have no rollout percentage of their own
+        assert_eq!(
+            get_rollout_percentage(
+                &flag,
+                enabled_variation(
+                    &flag,
+                    &LaunchDarklyConfig::default().enabled_variation_names
+                ),
+                &flag.environments["staging"]
+            ),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rules_count_as_started() {
+        let server = MockServer::new()
+            .json(
+                "/api/v2/flags/default",
+                r#"{ "items": [
+                    { "key": "beta-editor", "name": "Beta editor" },
+                    { "key": "new-checkout", "name": "New checkout" }
+                ] }"#,
+            )
+            .json(
+                "/api/v2/flags/default/beta-editor",
+                include_str!("launchdarkly/rule-targeting.json"),
+            )
+            .json(
+                "/api/v2/flags/default/new-checkout",
+                include_str!("launchdarkly/new-checkout.json"),
+            )
+            .start()
+            .await;
+
+        // Beta editor serves none of production's fallthrough, but a rule serves it to a
+        // segment
+        let issues = checker(&server).check().await.unwrap().issues;
+        let ids: Vec<&str> = issues.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, ["ld:default:new-checkout:production"]);
+    }
+
+    #[tokio::test]
+    async fn test_individual_targets() {
+        let server = MockServer::new()
+            .json(
+                "/api/v2/flags/default",
+                r#"{ "items": [{ "key": "dark-mode", "name": "Dark mode" }] }"#,
+            )
+            .json(
+                "/api/v2/flags/default/dark-mode",
+                include_str!("launchdarkly/targeted.json"),
+            )
+            .start()
+            .await;
+        let issues = |targets_count_as_started| {
+            let checker = LaunchDarklyChecker {
+                targets_count_as_started,
+                ..checker(&server)
+            };
+            async move {
+                let issues = checker.check().await.unwrap().issues;
+                issues.iter().map(|i| i.to_string()).collect::<Vec<_>>()
+            }
+        };
+
+        // Production's fallthrough is at 0%, but alice and bob get the flag
+        assert_eq!(
+            issues(false).await,
+            [
+                "Flag 'Dark mode' rolled out to 100% in staging, but only individual targets in production"
+            ]
+        );
+        assert!(issues(true).await.is_empty());
+    }
+
+    #[test]
+    fn test_flags_ready_for_removal() {
+        let checker = LaunchDarklyChecker::new(&LaunchDarklyConfig {
+            api_token: Some("api-test".to_string()),
+            maintainer_id: Some("me".to_string()),
+            ..LaunchDarklyConfig::default()
+        })
+        .unwrap();
+        const DAY: i64 = 24 * 60 * 60 * 1000;
+        let modified = 1717405200000;
+        let issues = |temporary: bool, production: serde_json::Value, now: i64| {
+            let flag = serde_json::from_value(json!({
+                "key": "new-checkout",
+                "name": "New checkout",
+                "kind": "boolean",
+                "temporary": temporary,
+                "creationDate": modified - 30 * DAY,
+                "variations": [
+                    { "_id": "a", "name": "Enabled", "value": true },
+                    { "_id": "b", "name": "Disabled", "value": false },
+                ],
+                "environments": { "production": production },
+            }))
+            .unwrap();
+            checker
+                .flag_issues("default", flag, now, &mut Tracked::default())
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+        };
+        let serving = |on: bool, fallthrough: serde_json::Value, off_variation: i32| {
+            json!({
+                "lastModified": modified,
+                "on": on,
+                "fallthrough": fallthrough,
+                "offVariation": off_variation,
+            })
+        };
+        let enabled = serving(true, json!({ "variation": 0 }), 1);
+
+        // Reported once it's been fully rolled out for more than 14 days
+        assert!(issues(true, enabled.clone(), modified + 14 * DAY).is_empty());
+        assert_eq!(
+            issues(true, enabled.clone(), modified + 14 * DAY + 1),
+            ["Flag 'New checkout' fully rolled out for 14 days — remove it"]
+        );
+        assert_eq!(
+            issues(true, enabled.clone(), modified + 21 * DAY),
+            ["Flag 'New checkout' fully rolled out for 21 days — remove it"]
+        );
+        // Only temporary flags are meant to be removed
+        assert!(issues(false, enabled, modified + 21 * DAY).is_empty());
+
+        let rollout = |weights: [i32; 2]| {
+            json!({ "rollout": { "variations": [
+                { "variation": 0, "weight": weights[0] },
+                { "variation": 1, "weight": weights[1] },
+            ] } })
+        };
+        let later = modified + 21 * DAY;
+        assert_eq!(
+            issues(true, serving(true, rollout([100000, 0]), 1), later).len(),
+            1
+        );
+        // Targeting off serves everyone the off variation
+        assert_eq!(
+            issues(true, serving(false, json!({ "variation": 1 }), 0), later).len(),
+            1
+        );
+        assert!(issues(true, serving(false, json!({ "variation": 0 }), 1), later).is_empty());
+        // A rule still serving some users the disabled variation
+        let mut with_rule = serving(true, json!({ "variation": 0 }), 1);
+        with_rule["rules"] = json!([{ "variation": 1 }]);
+        assert!(issues(true, with_rule, later).is_empty());
+    }
+
+    #[test]
+    fn test_flag_drift() {
+        let checker = LaunchDarklyChecker::new(&LaunchDarklyConfig {
+            api_token: Some("api-test".to_string()),
+            maintainer_id: Some("me".to_string()),
+            drift_minutes: Some(24 * 60),
+            ..LaunchDarklyConfig::default()
+        })
+        .unwrap();
+        const HOUR: i64 = 60 * 60 * 1000;
+        let modified = 1717405200000;
+        let rollout = json!({ "variations": [
+            { "variation": 0, "weight": 100000 },
+            { "variation": 1, "weight": 0 },
+        ] });
+        let flag = |production_on: bool| {
+            serde_json::from_value(json!({
+                "key": "new-checkout",
+                "name": "New checkout",
+                "kind": "boolean",
+                "variations": [
+                    { "_id": "a", "name": "Enabled", "value": true },
+                    { "_id": "b", "name": "Disabled", "value": false },
+                ],
+                "environments": {
+                    "staging": {
+                        "lastModified": modified,
+                        "on": true,
+                        "fallthrough": { "rollout": rollout },
+                    },
+                    "production": {
+                        "lastModified": modified,
+                        "on": production_on,
+                        "fallthrough": { "rollout": rollout },
+                        "offVariation": 1,
+                    },
+                },
+            }))
+            .unwrap()
+        };
+        let check = |production_on: bool, before: &[&str], now: i64| {
+            let mut tracked = Tracked::default();
+            tracked.flags_on.before = before.iter().map(|id| id.to_string()).collect();
+            let issues: Vec<String> = checker
+                .flag_issues("default", flag(production_on), now, &mut tracked)
+                .iter()
+                .map(|i| i.to_string())
+                .collect();
+            let mut now_on: Vec<String> = tracked.flags_on.now.into_iter().collect();
+            now_on.sort();
+            (issues, now_on)
+        };
+        let staging = "default:new-checkout:staging";
+        let production = "default:new-checkout:production";
+
+        // Never on in production, so it just hasn't started there
+        assert_eq!(
+            check(false, &[staging], modified + 72 * HOUR),
+            (
+                vec![
+                    "Flag 'New checkout' rolled out to 100% in staging, but not started in production"
+                        .to_string()
+                ],
+                vec![staging.to_string()]
+            )
+        );
+        // Turned off in production after being on, reported once it's been off a day
+        assert_eq!(
+            check(false, &[staging, production], modified + 24 * HOUR),
+            (vec![], vec![production.to_string(), staging.to_string()])
+        );
+        assert_eq!(
+            check(false, &[staging, production], modified + 72 * HOUR).0,
+            ["Flag 'New checkout' off in production but on in staging for 3d 0h"]
+        );
+        // Back on in production
+        assert_eq!(
+            check(true, &[staging, production], modified + 72 * HOUR),
+            (vec![], vec![production.to_string(), staging.to_string()])
+        );
+    }
+
+    #[test]
+    fn test_rules_serve_enabled() {
+        let flag: LaunchDarklyFlagDetail =
+            serde_json::from_str(include_str!("launchdarkly/rule-targeting.json")).unwrap();
+        let enabled = enabled_variation(
+            &flag,
+            &LaunchDarklyConfig::default().enabled_variation_names,
+        );
+        let serves = |env| rules_serve_enabled(enabled, &serde_json::from_value(env).unwrap());
+        let rule_rollout = |weights: [i32; 2]| {
+            json!({
+                "on": true,
+                "rules": [{ "rollout": { "variations": [
+                    { "variation": 0, "weight": weights[0] },
+                    { "variation": 1, "weight": weights[1] },
+                ] } }],
+            })
+        };
+
+        assert!(serves(rule_rollout([10000, 90000])));
+        assert!(!serves(rule_rollout([0, 100000])));
+        assert!(!serves(
+            json!({ "on": true, "rules": [{ "variation": 1 }] })
+        ));
+        assert!(!serves(
+            json!({ "on": false, "rules": [{ "variation": 0 }] })
+        ));
+        assert!(!serves(json!({ "on": true })));
+    }
+
+    #[test]
+    fn test_enabled_variation() {
+        let names = LaunchDarklyConfig::default().enabled_variation_names;
+        let enabled = |variations: serde_json::Value, names: &[String]| {
+            let flag = serde_json::from_value(json!({
+                "key": "search-v2",
+                "name": "Search v2",
+                "kind": "boolean",
+                "variations": variations,
+                "environments": {},
+            }))
+            .unwrap();
+            enabled_variation(&flag, names)
+        };
+
+        // Names are matched ignoring case, whatever the order of the variations
+        let on_off = json!([
+            { "_id": "a", "name": "Off", "value": false },
+            { "_id": "b", "name": "On", "value": true },
+        ]);
+        assert_eq!(enabled(on_off.clone(), &names), Some(1));
+        // Earlier names win over later ones
+        let both = json!([
+            { "_id": "a", "name": "true", "value": true },
+            { "_id": "b", "name": "Enabled", "value": true },
+        ]);
+        assert_eq!(enabled(both, &names), Some(1));
+        // An unnamed `true` is the enabled variation when no names match
+        let unnamed = json!([
+            { "_id": "a", "value": false },
+            { "_id": "b", "value": true },
+        ]);
+        assert_eq!(enabled(unnamed, &names), Some(1));
+        // A named `true` is deliberately not assumed to be enabled
+        let inverted = json!([
+            { "_id": "a", "name": "Disabled", "value": true },
+            { "_id": "b", "name": "Legacy", "value": false },
+        ]);
+        assert_eq!(enabled(inverted.clone(), &names), None);
+        assert_eq!(enabled(inverted, &["legacy".to_string()]), Some(1));
+        assert_eq!(enabled(on_off, &["enabled".to_string()]), None);
+    }
+
+    #[test]
+    fn test_unknown_enabled_variation() {
+        let checker = LaunchDarklyChecker::new(&LaunchDarklyConfig {
+            api_token: Some("api-test".to_string()),
+            maintainer_id: Some("me".to_string()),
+            ..LaunchDarklyConfig::default()
+        })
+        .unwrap();
+        let flag = serde_json::from_value(json!({
+            "key": "search-v2",
+            "name": "Search v2",
+            "kind": "boolean",
+            "variations": [
+                { "_id": "a", "name": "Legacy", "value": true },
+                { "_id": "b", "name": "Rewrite", "value": false },
+            ],
+            "environments": {
+                "production": {
+                    "lastModified": 0,
+                    "on": true,
+                    "fallthrough": { "variation": 1 },
+                },
+            },
+        }))
+        .unwrap();
+        let issues = checker.flag_issues(
+            "default",
+            flag,
+            Utc::now().timestamp_millis(),
+            &mut Tracked::default(),
+        );
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].id, "ld:default:search-v2:enabled-variation");
+        assert_eq!(issues[0].severity, Severity::Low);
+        assert_eq!(
+            issues[0].to_string(),
+            "Flag 'Search v2' skipped: couldn't determine its enabled variation"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_launchdarkly_list_error() {
+        let server = MockServer::new()
+            .respond("/api/v2/flags/default", 400, "bad filter")
+            .start()
+            .await;
+
+        let err = checker(&server).check().await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "LaunchDarkly API returned error: 400 Bad Request"
+        );
+    }
+}
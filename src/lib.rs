@@ -1,6 +1,15 @@
 pub mod check;
+pub mod config;
 pub mod github;
+pub mod http;
+pub mod issue;
 pub mod launchdarkly;
+pub mod logging;
+#[cfg(test)]
+mod mock_server;
 pub mod notifier;
+pub mod report;
+pub mod runner;
 pub mod server;
 pub mod state;
+pub mod stats;
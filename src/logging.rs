@@ -0,0 +1,167 @@
+//! A minimal `tracing` subscriber that writes events to stderr, filtered by `RUST_LOG`
+//! directives such as `warn,work_driver=debug` or `work_driver::launchdarkly=trace`.
+
+use chrono::Local;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+const DEFAULT_FILTER: &str = "warn";
+
+#[derive(Debug, PartialEq)]
+pub struct Filter {
+    /// `(target prefix, level)` pairs; a `None` target applies to everything.
+    directives: Vec<(Option<String>, LevelFilter)>,
+}
+
+impl Filter {
+    pub fn parse(spec: &str) -> Self {
+        let directives = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .filter_map(|directive| match directive.split_once('=') {
+                Some((target, level)) => LevelFilter::from_str(level)
+                    .ok()
+                    .map(|level| (Some(target.to_string()), level)),
+                None => match LevelFilter::from_str(directive) {
+                    Ok(level) => Some((None, level)),
+                    // A bare target like `work_driver` enables everything for it
+                    Err(_) => Some((Some(directive.to_string()), LevelFilter::TRACE)),
+                },
+            })
+            .collect();
+        Self { directives }
+    }
+
+    /// The level allowed for `target`, taken from the most specific matching directive.
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.directives
+            .iter()
+            .filter(|(prefix, _)| match prefix {
+                None => true,
+                Some(prefix) => {
+                    target == prefix
+                        || target
+                            .strip_prefix(prefix.as_str())
+                            .is_some_and(|rest| rest.starts_with("::"))
+                }
+            })
+            .max_by_key(|(prefix, _)| prefix.as_ref().map_or(0, |p| p.len() + 1))
+            .map_or(LevelFilter::OFF, |(_, level)| *level)
+    }
+
+    pub fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        *metadata.level() <= self.level_for(metadata.target())
+    }
+}
+
+struct StderrSubscriber {
+    filter: Filter,
+    next_span_id: AtomicU64,
+}
+
+impl Subscriber for StderrSubscriber {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.filter.enabled(metadata)
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        self.filter.directives.iter().map(|(_, level)| *level).max()
+    }
+
+    fn new_span(&self, _span: &Attributes<'_>) -> Id {
+        Id::from_u64(self.next_span_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let metadata = event.metadata();
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let line = format!(
+            "{} {:>5} {}: {}{}\n",
+            Local::now().format("%Y-%m-%dT%H:%M:%S"),
+            metadata.level(),
+            metadata.target(),
+            visitor.message,
+            visitor.fields
+        );
+        let _ = std::io::stderr().write_all(line.as_bytes());
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// Installs the stderr subscriber using `RUST_LOG`, defaulting to warnings and errors only.
+pub fn init() {
+    let spec = std::env::var("RUST_LOG").unwrap_or_else(|_| DEFAULT_FILTER.to_string());
+    let subscriber = StderrSubscriber {
+        filter: Filter::parse(&spec),
+        next_span_id: AtomicU64::new(1),
+    };
+    let _ = tracing::subscriber::set_global_default(subscriber);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_uses_most_specific_directive() {
+        let filter = Filter::parse("warn, work_driver=debug, work_driver::github=off");
+        assert_eq!(filter.level_for("hyper::client"), LevelFilter::WARN);
+        assert_eq!(filter.level_for("work_driver"), LevelFilter::DEBUG);
+        assert_eq!(
+            filter.level_for("work_driver::launchdarkly"),
+            LevelFilter::DEBUG
+        );
+        assert_eq!(filter.level_for("work_driver::github"), LevelFilter::OFF);
+        assert_eq!(filter.level_for("work_driver_extra"), LevelFilter::WARN);
+    }
+
+    #[test]
+    fn test_filter_without_matching_directive_is_off() {
+        let filter = Filter::parse("work_driver");
+        assert_eq!(
+            filter.level_for("work_driver::notifier"),
+            LevelFilter::TRACE
+        );
+        assert_eq!(filter.level_for("reqwest"), LevelFilter::OFF);
+    }
+}
@@ -1,39 +1,196 @@
+mod cli;
+
 use anyhow::Result;
-use work_driver::check::Check;
-use work_driver::github::GitHubChecker;
-use work_driver::launchdarkly::LaunchDarklyChecker;
-use work_driver::notifier::{send_notification, update_html};
+use chrono::Utc;
+use cli::{CheckArgs, Command, OutputFormat};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tracing::error;
+use work_driver::config::{Config, load_config};
+use work_driver::issue::Issue;
+use work_driver::logging;
+use work_driver::notifier::{NotifyDecision, plan_notification};
+use work_driver::report::JsonReport;
+use work_driver::runner::{
+    Availability, CheckReport, Watcher, checker_availability, run_checks_with,
+};
 use work_driver::server::run_server;
+use work_driver::state::{load_state, save_state};
+use work_driver::stats::render_stats;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args: Vec<String> = std::env::args().collect();
-    if args.get(1).is_some_and(|a| a == "serve") {
-        return run_server().await;
-    }
+    logging::init();
 
-    let checkers: Vec<Box<dyn Check>> = vec![
-        Box::new(GitHubChecker::new()),
-        Box::new(LaunchDarklyChecker::new()?),
-    ];
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match cli::parse(&args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{:#}\n\n{}", e, cli::USAGE);
+            std::process::exit(cli::EXIT_USAGE);
+        }
+    };
+
+    match command {
+        Command::Check(check_args) => {
+            let mut config = load_config()?;
+            check_args.apply_to(&mut config);
+            exit_if_no_usable_checkers(&check_args, &config);
+            if check_args.watch {
+                watch(&check_args, &config).await
+            } else if run_once(&check_args, &config).await? {
+                std::process::exit(cli::EXIT_ISSUES_FOUND);
+            } else {
+                Ok(())
+            }
+        }
+        Command::Serve => run_server(load_config()?).await,
+        Command::State => {
+            println!("{}", serde_json::to_string_pretty(&load_state()?)?);
+            Ok(())
+        }
+        Command::Stats => {
+            print!("{}", render_stats(&load_state()?.resolutions));
+            Ok(())
+        }
+        Command::Help => {
+            print!("{}", cli::USAGE);
+            Ok(())
+        }
+    }
+}
 
-    let mut all_issues = Vec::new();
+async fn watch(check_args: &CheckArgs, config: &Config) -> Result<()> {
+    let interval = Duration::from_secs(check_args.interval_minutes * 60);
+    let mut watcher = Watcher::new(config, check_args.run_options(), interval);
+    // Cycle as often as the most frequent checker needs
+    let tick = watcher.tick().unwrap_or(interval);
+    if !check_args.dry_run {
+        let mut state = load_state().unwrap_or_default();
+        state.check_interval_minutes = Some(tick.as_secs() / 60);
+        if let Err(e) = save_state(&state) {
+            error!("Failed to record check interval: {:#}", e);
+        }
+    }
+    loop {
+        tokio::select! {
+            result = watcher.run_cycle(config) => {
+                let result = result.and_then(|report| print_report(check_args, &report, config));
+                if let Err(e) = result {
+                    error!("Check run failed: {:#}", e);
+                    // Keep the dashboard countdown accurate even when the run itself failed
+                    if !check_args.dry_run {
+                        let mut state = load_state().unwrap_or_default();
+                        state.last_check = Some(Utc::now());
+                        if let Err(e) = save_state(&state) {
+                            error!("Failed to record check time: {:#}", e);
+                        }
+                    }
+                }
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
 
-    for checker in checkers {
-        match checker.check().await {
-            Ok(issues) => all_issues.extend(issues),
-            Err(e) => eprintln!("Error running check: {}", e),
+        tokio::select! {
+            _ = tokio::time::sleep(jittered(tick)) => {}
+            _ = tokio::signal::ctrl_c() => break,
         }
     }
 
-    update_html(&all_issues)?;
+    if check_args.format == OutputFormat::Human {
+        println!("Stopping");
+    }
+    Ok(())
+}
 
-    if !all_issues.is_empty() {
-        send_notification(&all_issues)?;
-        println!("{} issues found", all_issues.len());
-    } else {
+/// Adds up to ±10% of random jitter so multiple machines don't hit the APIs in lockstep.
+fn jittered(interval: Duration) -> Duration {
+    let max_jitter_ms = interval.as_millis() as u64 / 10;
+    if max_jitter_ms == 0 {
+        return interval;
+    }
+    let random = RandomState::new().build_hasher().finish();
+    let offset_ms = random % (2 * max_jitter_ms + 1);
+    interval + Duration::from_millis(offset_ms) - Duration::from_millis(max_jitter_ms)
+}
+
+/// Explains why each checker can't run and exits, if none of them can.
+fn exit_if_no_usable_checkers(check_args: &CheckArgs, config: &Config) {
+    let availability = checker_availability(config, &check_args.run_options());
+    if availability
+        .iter()
+        .any(|(_, availability)| *availability == Availability::Ready)
+    {
+        return;
+    }
+
+    eprintln!("No usable checkers:");
+    for (name, availability) in &availability {
+        eprintln!("  {:<14}{}", name, availability);
+    }
+    std::process::exit(cli::EXIT_NO_CHECKERS);
+}
+
+/// Runs every checker once, returning whether any issues were found.
+async fn run_once(check_args: &CheckArgs, config: &Config) -> Result<bool> {
+    let report = run_checks_with(config, &check_args.run_options()).await?;
+    print_report(check_args, &report, config)?;
+    Ok(!report.issues.is_empty())
+}
+
+fn print_report(check_args: &CheckArgs, report: &CheckReport, config: &Config) -> Result<()> {
+    if check_args.format == OutputFormat::Json {
+        let state = load_state().unwrap_or_default();
+        println!(
+            "{}",
+            serde_json::to_string(&JsonReport::new(report, &state))?
+        );
+    } else if check_args.dry_run {
+        print_dry_run(&report.issues, config);
+    } else if report.issues.is_empty() {
         println!("No issues found");
+    } else {
+        println!(
+            "{} issues found ({})",
+            report.issues.len(),
+            report.summary()
+        );
     }
 
     Ok(())
 }
+
+fn print_dry_run(issues: &[Issue], config: &Config) {
+    if issues.is_empty() {
+        println!("No issues found");
+        return;
+    }
+
+    let state = load_state().unwrap_or_default();
+    let plan = plan_notification(issues, &state, config, Utc::now());
+    println!("{:<13} ISSUE", "NOTIFY");
+    for (issue, decision) in &plan {
+        let decision = match decision {
+            NotifyDecision::Notify => "yes",
+            NotifyDecision::Throttled => "no (recent)",
+            NotifyDecision::Seen => "no (seen)",
+            NotifyDecision::Snoozed => "no (snoozed)",
+            NotifyDecision::Deferred => "later (quiet)",
+        };
+        println!("{:<13} {}", decision, issue);
+        if let Some(url) = &issue.url {
+            println!("{:<13} {}", "", url);
+        }
+    }
+
+    let notify_count = plan
+        .iter()
+        .filter(|(_, decision)| *decision == NotifyDecision::Notify)
+        .count();
+    println!(
+        "\n{} issues, {} would trigger a notification (dry run, nothing written)",
+        issues.len(),
+        notify_count
+    );
+}
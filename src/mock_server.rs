@@ -0,0 +1,158 @@
+//! A local HTTP server for testing API-based checkers against canned responses.
+
+use axum::Router;
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, StatusCode};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Status, headers and body.
+type Response = (StatusCode, HeaderMap, String);
+
+#[derive(Clone, Default)]
+struct Routes {
+    /// By path, optionally with a query.
+    responses: Arc<HashMap<String, Response>>,
+    /// Responses served once each, in order, before falling back to `responses`.
+    first_responses: Arc<Mutex<HashMap<String, Vec<Response>>>>,
+    requests: Arc<Mutex<Vec<String>>>,
+    /// How long each response is held back.
+    delay: Duration,
+    in_flight: Arc<AtomicUsize>,
+    most_in_flight: Arc<AtomicUsize>,
+}
+
+pub struct MockServer {
+    /// Base URL without a trailing slash, e.g. "http://127.0.0.1:1234".
+    pub url: String,
+    routes: Routes,
+    responses: HashMap<String, Response>,
+}
+
+impl MockServer {
+    pub fn new() -> Self {
+        Self {
+            url: String::new(),
+            routes: Routes::default(),
+            responses: HashMap::new(),
+        }
+    }
+
+    /// Responds to requests for `path` with a 200 and `body`. A `path` without a query
+    /// matches any query; one with a query only matches that exact query string.
+    pub fn json(self, path: &str, body: impl Into<String>) -> Self {
+        self.respond(path, 200, body)
+    }
+
+    pub fn respond(self, path: &str, status: u16, body: impl Into<String>) -> Self {
+        self.respond_with_headers(path, status, &[], body)
+    }
+
+    pub fn respond_with_headers(
+        mut self,
+        path: &str,
+        status: u16,
+        headers: &[(&'static str, &str)],
+        body: impl Into<String>,
+    ) -> Self {
+        let status = StatusCode::from_u16(status).expect("valid status code");
+        self.responses
+            .insert(path.to_string(), (status, header_map(headers), body.into()));
+        self
+    }
+
+    /// Responds to the next request for `path` with this instead of its usual response,
+    /// e.g. to serve a 429 before the real thing. Can be repeated to queue several.
+    pub fn respond_first(
+        self,
+        path: &str,
+        status: u16,
+        headers: &[(&'static str, &str)],
+        body: impl Into<String>,
+    ) -> Self {
+        let response = (
+            StatusCode::from_u16(status).expect("valid status code"),
+            header_map(headers),
+            body.into(),
+        );
+        self.routes
+            .first_responses
+            .lock()
+            .unwrap()
+            .entry(path.to_string())
+            .or_default()
+            .push(response);
+        self
+    }
+
+    /// Holds back every response by `delay`, so overlapping requests can be counted.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.routes.delay = delay;
+        self
+    }
+
+    /// Binds to a free local port and starts serving. Unknown paths get a 404.
+    pub async fn start(mut self) -> Self {
+        self.routes.responses = Arc::new(std::mem::take(&mut self.responses));
+        let app = Router::new()
+            .fallback(handle)
+            .with_state(self.routes.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock server");
+        self.url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        self
+    }
+
+    /// Each request received so far, as "path?query".
+    pub fn requests(&self) -> Vec<String> {
+        self.routes.requests.lock().unwrap().clone()
+    }
+
+    /// The most requests that have been waiting on a response at once.
+    pub fn most_in_flight(&self) -> usize {
+        self.routes.most_in_flight.load(Ordering::SeqCst)
+    }
+}
+
+fn header_map(headers: &[(&'static str, &str)]) -> HeaderMap {
+    let mut header_map = HeaderMap::new();
+    for (name, value) in headers {
+        header_map.insert(*name, value.parse().expect("valid header value"));
+    }
+    header_map
+}
+
+async fn handle(State(routes): State<Routes>, request: Request) -> Response {
+    let uri = request.uri();
+    let path_and_query = uri.path_and_query().map_or(uri.path(), |pq| pq.as_str());
+    routes
+        .requests
+        .lock()
+        .unwrap()
+        .push(path_and_query.to_string());
+    let in_flight = routes.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+    routes.most_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+    tokio::time::sleep(routes.delay).await;
+    routes.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+    {
+        let mut first_responses = routes.first_responses.lock().unwrap();
+        for key in [path_and_query, uri.path()] {
+            if let Some(queue) = first_responses.get_mut(key)
+                && !queue.is_empty()
+            {
+                return queue.remove(0);
+            }
+        }
+    }
+
+    let exact = routes.responses.get(path_and_query);
+    match exact.or_else(|| routes.responses.get(uri.path())) {
+        Some(response) => response.clone(),
+        None => (StatusCode::NOT_FOUND, HeaderMap::new(), String::new()),
+    }
+}
@@ -1,65 +1,132 @@
-use crate::state::{load_state, save_state};
+use crate::config::{Config, NotifierBackend, Renotify};
+use crate::issue::{Issue, IssueKind, Severity};
+use crate::state::{MutedPr, State, load_state, save_state};
 use anyhow::{Context, Result};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use std::fs;
 use std::process::Command;
+use tracing::{debug, info};
 
-fn format_issue_as_html(issue: &str) -> String {
-    let escaped_issue = html_escape::encode_text(issue);
-    let data_attr = format!(r#" data-issue="{}""#, escaped_issue);
-
-    // Check if it's a PR issue
-    if issue.starts_with("PR #") {
-        if let Some(end_idx) = issue.find(" '") {
-            let number = &issue[4..end_idx];
-            return format!(
-                "<li{}>{}</li>",
-                data_attr,
-                issue.replace(
-                    &format!("PR #{}", number),
-                    &format!(
-                        "<a href=\"https://github.com/figma/figma/pull/{}\" target=\"_blank\">PR #{}</a>",
-                        number, number
-                    )
-                )
-            );
-        }
-    }
+/// Renders an issue as a list item, with `extra` HTML (buttons, labels) after the text.
+fn format_issue_as_html(issue: &Issue, extra: &str) -> String {
+    let text = html_escape::encode_text(&issue.to_string()).into_owned();
+    let data_attr = format!(
+        r#" data-issue="{}""#,
+        html_escape::encode_double_quoted_attribute(&issue.id)
+    );
 
-    // Check if it's a LaunchDarkly flag issue
-    if issue.starts_with("Flag '") && issue.contains(" [") {
-        // Extract the flag metadata: [project:key:env]
-        if let Some(start) = issue.find(" [") {
-            if let Some(end) = issue.find(']') {
-                let metadata = &issue[start + 2..end];
-                let parts: Vec<&str> = metadata.split(':').collect();
-                if parts.len() == 3 {
-                    let project_key = parts[0];
-                    let flag_key = parts[1];
-                    let env = parts[2];
-
-                    let url = format!(
-                        "https://app.launchdarkly.com/projects/{}/flags/{}/targeting?env=production&env=staging&selected-env={}",
-                        project_key, flag_key, env
-                    );
-
-                    // Remove the metadata from the display text
-                    let display_text = issue.replace(&format!(" [{}]", metadata), "");
-                    return format!("<li{}><a href=\"{}\" target=\"_blank\">{}</a></li>", data_attr, url, display_text);
+    let attrs = format!(
+        r#" class="severity-{}"{}"#,
+        issue.severity.css_name(),
+        data_attr
+    );
+    // What a rolled-up issue covers goes underneath it
+    let extra = if issue.items.is_empty() {
+        extra.to_string()
+    } else {
+        let items: Vec<String> = issue
+            .items
+            .iter()
+            .map(|item| {
+                let text = html_escape::encode_text(&item.text).into_owned();
+                match &item.url {
+                    Some(url) => format!(
+                        "<li><a href=\"{}\" target=\"_blank\">{}</a></li>",
+                        html_escape::encode_double_quoted_attribute(url),
+                        text
+                    ),
+                    None => format!("<li>{}</li>", text),
                 }
-            }
-        }
-    }
+            })
+            .collect();
+        format!(
+            r#"{}<ul class="issue-items">{}</ul>"#,
+            extra,
+            items.join("")
+        )
+    };
 
-    // Default: no link
-    format!("<li{}>{}</li>", data_attr, issue)
+    match &issue.url {
+        Some(url) => format!(
+            "<li{}><a href=\"{}\" target=\"_blank\">{}</a>{}</li>",
+            attrs,
+            html_escape::encode_double_quoted_attribute(url),
+            text,
+            extra
+        ),
+        None => format!("<li{}>{}{}</li>", attrs, text, extra),
+    }
 }
 
-fn generate_html(unseen: &[String], seen: &[String]) -> String {
-    let unseen_items: Vec<String> = unseen.iter().map(|i| format_issue_as_html(i)).collect();
-    let seen_items: Vec<String> = seen.iter().map(|i| format_issue_as_html(i)).collect();
+fn generate_html(
+    unseen: &[&Issue],
+    seen: &[&Issue],
+    snoozed: &[(&Issue, DateTime<Utc>)],
+    muted: &[(&String, &MutedPr)],
+    first_seen: &HashMap<String, DateTime<Utc>>,
+    now: DateTime<Utc>,
+    config: &Config,
+) -> String {
+    let age_label = |issue: &Issue| match first_seen.get(&issue.id) {
+        Some(ts) => format!(
+            r#" <span class="age">open for {}</span>"#,
+            format_age(now.signed_duration_since(*ts))
+        ),
+        None => String::new(),
+    };
+    let review_group = review_group(unseen, first_seen, now, config);
+    let grouped: Vec<&String> = review_group
+        .iter()
+        .flat_map(|(_, reviews)| reviews.iter().map(|i| &i.id))
+        .collect();
+    let snooze_button = format!(
+        r#" <button class="snooze-btn" data-minutes="{}">Snooze {}</button>"#,
+        config.notifier.snooze_minutes,
+        format_minutes(config.notifier.snooze_minutes)
+    );
+    let mut unseen_items: Vec<String> = unseen
+        .iter()
+        .filter(|i| !grouped.contains(&&i.id))
+        .map(|i| format_issue_as_html(i, &(age_label(i) + &snooze_button)))
+        .collect();
+    if let Some((group, reviews)) = &review_group {
+        let items: Vec<String> = reviews
+            .iter()
+            .map(|i| format_issue_as_html(i, &(age_label(i) + &snooze_button)))
+            .collect();
+        // Where the oldest of them would have been listed
+        let position = unseen
+            .iter()
+            .take_while(|i| !grouped.contains(&&i.id))
+            .count();
+        unseen_items.insert(
+            position,
+            format!(
+                r#"<li class="review-group"><details><summary>{}</summary><ul>
+            {}
+        </ul></details></li>"#,
+                html_escape::encode_text(&group.to_string()),
+                items.join("\n            ")
+            ),
+        );
+    }
+    let seen_items: Vec<String> = seen
+        .iter()
+        .map(|i| format_issue_as_html(i, &age_label(i)))
+        .collect();
+    let snoozed_items: Vec<String> = snoozed
+        .iter()
+        .map(|(i, until)| {
+            let label = format!(
+                r#" <span class="snoozed-until">(snoozed until {})</span>"#,
+                until.with_timezone(&chrono::Local).format("%a %H:%M")
+            );
+            format_issue_as_html(i, &(age_label(i) + &label))
+        })
+        .collect();
 
-    let unseen_content = if unseen_items.is_empty() {
+    let unseen_content = if unseen.is_empty() {
         r#"<p class="empty" id="empty-msg">All caught up!</p>"#.to_string()
     } else {
         unseen_items.join("\n        ")
@@ -70,7 +137,7 @@ fn generate_html(unseen: &[String], seen: &[String]) -> String {
     <ul class="unseen" id="unseen-list">
         {}
     </ul>"#,
-        unseen_items.len(),
+        unseen.len(),
         unseen_content
     );
 
@@ -81,11 +148,64 @@ fn generate_html(unseen: &[String], seen: &[String]) -> String {
         {}
     </ul>
     </div>"#,
-        if seen_items.is_empty() { r#" style="display:none""# } else { "" },
+        if seen_items.is_empty() {
+            r#" style="display:none""#
+        } else {
+            ""
+        },
         seen_items.len(),
         seen_items.join("\n        ")
     );
 
+    let snoozed_section = format!(
+        r#"<div id="snoozed-section"{}>
+    <h2 class="seen-header" id="snoozed-header">Snoozed ({})</h2>
+    <ul class="snoozed" id="snoozed-list">
+        {}
+    </ul>
+    </div>"#,
+        if snoozed_items.is_empty() {
+            r#" style="display:none""#
+        } else {
+            ""
+        },
+        snoozed_items.len(),
+        snoozed_items.join("\n        ")
+    );
+
+    let muted_items: Vec<String> = muted
+        .iter()
+        .map(|(pr_ref, pr)| {
+            let text = html_escape::encode_text(&format!("{} '{}'", pr_ref, pr.title)).into_owned();
+            match &pr.url {
+                Some(url) => format!(
+                    "<li><a href=\"{}\" target=\"_blank\">{}</a></li>",
+                    html_escape::encode_double_quoted_attribute(url),
+                    text
+                ),
+                None => format!("<li>{}</li>", text),
+            }
+        })
+        .collect();
+    // Collapsed, since muted PRs are only listed so they're not forgotten entirely
+    let muted_section = format!(
+        r#"<div id="muted-section"{}>
+    <details>
+    <summary><h2 class="seen-header">Muted ({})</h2></summary>
+    <ul class="snoozed" id="muted-list">
+        {}
+    </ul>
+    </details>
+    </div>"#,
+        if muted_items.is_empty() {
+            r#" style="display:none""#
+        } else {
+            ""
+        },
+        muted_items.len(),
+        muted_items.join("\n        ")
+    );
+
     format!(
         r#"<!DOCTYPE html>
 <html>
@@ -129,6 +249,15 @@ fn generate_html(unseen: &[String], seen: &[String]) -> String {
             border-left: 4px solid #0969da;
             transition: opacity 0.3s, background 0.3s;
         }}
+        .unseen li.severity-high {{
+            border-left-color: #cf222e;
+        }}
+        .unseen li.severity-medium {{
+            border-left-color: #bf8700;
+        }}
+        .unseen li.severity-low {{
+            border-left-color: #0969da;
+        }}
         .seen li {{
             padding: 10px 10px 10px 32px;
             margin: 8px 0;
@@ -163,6 +292,46 @@ fn generate_html(unseen: &[String], seen: &[String]) -> String {
         li.marking-seen {{
             opacity: 0.3;
         }}
+        .snoozed li {{
+            padding: 10px;
+            margin: 8px 0;
+            background: #f9f9f9;
+            border-radius: 6px;
+            border-left: 4px solid #d1d5da;
+            color: #8b949e;
+            opacity: 0.7;
+        }}
+        .snoozed a, .snoozed-until {{
+            color: #8b949e;
+        }}
+        .age {{
+            font-size: 12px;
+            color: #8b949e;
+            margin-left: 6px;
+        }}
+        .unseen li.review-group {{
+            background: none;
+            border-left-color: #d1d5da;
+        }}
+        .issue-items {{
+            margin: 6px 0 0;
+            font-size: 13px;
+        }}
+        .review-group summary, #muted-section summary {{
+            cursor: pointer;
+        }}
+        #muted-section h2 {{
+            display: inline;
+        }}
+        .snooze-btn {{
+            float: right;
+            font-size: 12px;
+            color: #666;
+            background: none;
+            border: 1px solid #d1d5da;
+            border-radius: 4px;
+            cursor: pointer;
+        }}
     </style>
 </head>
 <body>
@@ -170,6 +339,8 @@ fn generate_html(unseen: &[String], seen: &[String]) -> String {
     <div class="timer" id="timer"></div>
     {}
     {}
+    {}
+    {}
     <script>
     (function() {{
         // Countdown timer
@@ -179,7 +350,8 @@ fn generate_html(unseen: &[String], seen: &[String]) -> String {
                 .then(state => {{
                     if (state.last_check) {{
                         const lastCheck = new Date(state.last_check);
-                        const nextCheck = new Date(lastCheck.getTime() + 5 * 60 * 1000);
+                        const interval = (state.check_interval_minutes || 5) * 60 * 1000;
+                        const nextCheck = new Date(lastCheck.getTime() + interval);
                         const now = new Date();
                         const remaining = Math.max(0, nextCheck - now);
                         const minutes = Math.floor(remaining / 60000);
@@ -197,10 +369,16 @@ fn generate_html(unseen: &[String], seen: &[String]) -> String {
         setInterval(updateTimer, 5000);
 
         function updateCounts() {{
-            const unseenCount = document.getElementById('unseen-list').querySelectorAll('li').length;
-            const seenCount = document.getElementById('seen-list').querySelectorAll('li').length;
+            // Grouped review requests are counted individually, and the group goes once it's empty
+            document.querySelectorAll('.review-group').forEach(group => {{
+                if (!group.querySelector('li[data-issue]')) group.remove();
+            }});
+            const unseenCount = document.getElementById('unseen-list').querySelectorAll('li[data-issue]').length;
+            const seenCount = document.getElementById('seen-list').querySelectorAll('li[data-issue]').length;
+            const snoozedCount = document.getElementById('snoozed-list').querySelectorAll('li[data-issue]').length;
             document.getElementById('unseen-header').textContent = 'Needs Attention (' + unseenCount + ')';
             document.getElementById('seen-header').textContent = 'Recently Reviewed (' + seenCount + ')';
+            document.getElementById('snoozed-header').textContent = 'Snoozed (' + snoozedCount + ')';
             const emptyMsg = document.getElementById('empty-msg');
             if (unseenCount === 0 && !emptyMsg) {{
                 const p = document.createElement('p');
@@ -213,6 +391,24 @@ fn generate_html(unseen: &[String], seen: &[String]) -> String {
             }}
         }}
 
+        // Snooze buttons stop notifications for an issue for a while
+        document.addEventListener('click', function(e) {{
+            const button = e.target.closest('.snooze-btn');
+            if (!button) return;
+
+            const li = button.closest('li');
+            fetch('/snooze', {{
+                method: 'POST',
+                headers: {{ 'Content-Type': 'application/json' }},
+                body: JSON.stringify({{ issue: li.dataset.issue, minutes: Number(button.dataset.minutes) }})
+            }}).catch(() => {{}});
+
+            button.remove();
+            document.getElementById('snoozed-list').appendChild(li);
+            document.getElementById('snoozed-section').style.display = '';
+            updateCounts();
+        }});
+
         // Intercept link clicks to mark as seen
         document.addEventListener('click', function(e) {{
             const link = e.target.closest('a');
@@ -223,8 +419,14 @@ fn generate_html(unseen: &[String], seen: &[String]) -> String {
 
             e.preventDefault();
 
-            // Use the raw issue key embedded in data-issue
-            const issueText = li.dataset.issue;
+            // Use the stable issue ID embedded in data-issue
+            const issueId = li.dataset.issue;
+
+            // Snoozed issues and muted PRs stay where they are
+            if (li.closest('#snoozed-list, #muted-list')) {{
+                window.open(link.href, '_blank');
+                return;
+            }}
 
             // Visual feedback
             li.classList.add('marking-seen');
@@ -233,7 +435,7 @@ fn generate_html(unseen: &[String], seen: &[String]) -> String {
             fetch('/seen', {{
                 method: 'POST',
                 headers: {{ 'Content-Type': 'application/json' }},
-                body: JSON.stringify({{ issue: issueText }})
+                body: JSON.stringify({{ issue: issueId }})
             }}).catch(() => {{}});
 
             // Open the link
@@ -251,47 +453,80 @@ fn generate_html(unseen: &[String], seen: &[String]) -> String {
     </script>
 </body>
 </html>"#,
-        unseen_section, seen_section
+        unseen_section, seen_section, snoozed_section, muted_section
     )
 }
 
-pub fn update_html(issues: &[String]) -> Result<()> {
-    let output_path = shellexpand::tilde("~/Desktop/work-driver-issues.html");
+pub fn update_html(issues: &[Issue], config: &Config) -> Result<()> {
+    let output_path = config.notifier.html_path();
 
     let mut state = load_state().unwrap_or_default();
     let now = Utc::now();
-    let seen_threshold = chrono::Duration::minutes(30);
+    let seen_threshold = chrono::Duration::minutes(config.notifier.seen_minutes);
 
-    // Classify issues as seen or unseen
+    for issue in issues {
+        state.first_seen.entry(issue.id.clone()).or_insert(now);
+        state
+            .issue_kinds
+            .insert(issue.id.clone(), (issue.source, issue.kind));
+    }
+    let mut issues: Vec<&Issue> = issues.iter().collect();
+    sort_oldest_first(&mut issues, &state.first_seen);
+
+    // Classify issues as snoozed, seen or unseen
     let mut unseen_issues = Vec::new();
     let mut seen_issues = Vec::new();
+    let mut snoozed_issues = Vec::new();
+
+    for issue in issues.iter().copied() {
+        if let Some(until) = state.snoozed.get(&issue.id).filter(|until| **until > now) {
+            snoozed_issues.push((issue, *until));
+            continue;
+        }
 
-    for issue in issues {
         let is_seen = state
             .seen
-            .get(issue)
+            .get(&issue.id)
             .is_some_and(|ts| now.signed_duration_since(*ts) < seen_threshold);
 
         if is_seen {
-            seen_issues.push(issue.clone());
+            seen_issues.push(issue);
         } else {
-            unseen_issues.push(issue.clone());
+            unseen_issues.push(issue);
         }
     }
 
     // Clean up stale entries from state
-    let current_issues: std::collections::HashSet<&String> = issues.iter().collect();
+    let current_issues: std::collections::HashSet<&String> = issues.iter().map(|i| &i.id).collect();
+    state.record_resolutions(&current_issues, now);
     state
-        .issue_timestamps
+        .last_notified
         .retain(|k, _| current_issues.contains(k));
     state.seen.retain(|k, _| current_issues.contains(k));
+    state.first_seen.retain(|k, _| current_issues.contains(k));
+    state.issue_kinds.retain(|k, _| current_issues.contains(k));
+    state.deferred.retain(|k, _| current_issues.contains(k));
+    state
+        .snoozed
+        .retain(|k, until| current_issues.contains(k) && *until > now);
 
     // Update last_check
     state.last_check = Some(now);
 
+    let mut muted: Vec<(&String, &MutedPr)> = state.muted_open.iter().collect();
+    muted.sort_by_key(|(pr_ref, _)| *pr_ref);
+
     // Write HTML
-    let html_content = generate_html(&unseen_issues, &seen_issues);
-    fs::write(output_path.as_ref(), html_content).context("Failed to write issues to file")?;
+    let html_content = generate_html(
+        &unseen_issues,
+        &seen_issues,
+        &snoozed_issues,
+        &muted,
+        &state.first_seen,
+        now,
+        config,
+    );
+    fs::write(&output_path, html_content).context("Failed to write issues to file")?;
 
     // Save state
     save_state(&state).context("Failed to save state")?;
@@ -299,97 +534,765 @@ pub fn update_html(issues: &[String]) -> Result<()> {
     Ok(())
 }
 
-pub fn send_notification(detailed_issues: &[String]) -> Result<()> {
-    let mut state = load_state().unwrap_or_default();
-    let now = Utc::now();
-    let seen_threshold = chrono::Duration::minutes(30);
-    let notify_threshold = chrono::Duration::minutes(19);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyDecision {
+    /// Clicked in the dashboard recently, so it's not notified about.
+    Seen,
+    /// Snoozed from the dashboard until a later time.
+    Snoozed,
+    /// Notified about within the re-notify threshold.
+    Throttled,
+    /// Would notify, but it's quiet hours; held until they end.
+    Deferred,
+    Notify,
+}
 
-    // Filter to unseen issues and check throttle
-    let mut needs_notification = false;
-    let mut unseen_issues = Vec::new();
-    for issue in detailed_issues {
-        let is_seen = state
-            .seen
-            .get(issue)
-            .is_some_and(|ts| now.signed_duration_since(*ts) < seen_threshold);
-        if is_seen {
-            continue;
+/// Decides, without side effects, which issues a notification sent at `now` would cover.
+pub fn plan_notification<'a>(
+    issues: &'a [Issue],
+    state: &State,
+    config: &Config,
+    now: DateTime<Utc>,
+) -> Vec<(&'a Issue, NotifyDecision)> {
+    let seen_threshold = chrono::Duration::minutes(config.notifier.seen_minutes);
+    let quiet = config.notifier.is_quiet(now);
+
+    issues
+        .iter()
+        .map(|issue| {
+            let key = &issue.id;
+            let renotify = if issue.kind.notifies_once() {
+                Renotify::Never
+            } else {
+                config.notifier.renotify_minutes
+            };
+            let is_seen = state
+                .seen
+                .get(key)
+                .is_some_and(|ts| now.signed_duration_since(*ts) < seen_threshold);
+            let is_snoozed = state.snoozed.get(key).is_some_and(|until| *until > now);
+            let decision = if is_snoozed {
+                debug!(issue = %key, "snoozed, not notifying");
+                NotifyDecision::Snoozed
+            } else if is_seen {
+                debug!(issue = %key, "seen recently, not notifying");
+                NotifyDecision::Seen
+            } else if state.deferred.contains_key(key) {
+                debug!(issue = %key, "held during quiet hours, notifying");
+                NotifyDecision::Notify
+            } else {
+                match (state.last_notified.get(key), renotify) {
+                    (None, _) => {
+                        debug!(issue = %key, "new issue, notifying");
+                        NotifyDecision::Notify
+                    }
+                    (Some(_), Renotify::Never) => {
+                        debug!(issue = %key, "already notified once, not re-notifying");
+                        NotifyDecision::Throttled
+                    }
+                    (Some(last_notified), Renotify::Minutes(minutes))
+                        if now.signed_duration_since(*last_notified)
+                            <= chrono::Duration::minutes(minutes) =>
+                    {
+                        debug!(issue = %key, "notified recently, within threshold");
+                        NotifyDecision::Throttled
+                    }
+                    (Some(_), Renotify::Minutes(_)) => {
+                        debug!(issue = %key, "re-notifying, threshold passed");
+                        NotifyDecision::Notify
+                    }
+                }
+            };
+            // Anything due during quiet hours is held rather than dropped
+            let decision = if quiet && decision == NotifyDecision::Notify {
+                debug!(issue = %key, "quiet hours, deferring");
+                NotifyDecision::Deferred
+            } else {
+                decision
+            };
+            (issue, decision)
+        })
+        .collect()
+}
+
+/// What a notification is about, as handed to a [`Notifier`].
+#[derive(Debug)]
+pub struct Notification<'a> {
+    pub summary: String,
+    /// Every outstanding issue that isn't seen or snoozed, not just the new ones.
+    pub issues: Vec<&'a Issue>,
+    /// Whether an issue has been open past the escalation threshold.
+    pub escalated: bool,
+}
+
+/// Delivers notifications somewhere the user will see them.
+pub trait Notifier: Send + Sync {
+    fn notify(&self, notification: &Notification) -> Result<()>;
+}
+
+/// Desktop notifications through `terminal-notifier`, opening the dashboard when clicked.
+pub struct MacosNotifier {
+    dashboard_url: String,
+    escalation_sound: String,
+    escalation_alert: bool,
+}
+
+impl MacosNotifier {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            dashboard_url: config.server.url(),
+            escalation_sound: config.notifier.escalation.sound.clone(),
+            escalation_alert: config.notifier.escalation.alert,
         }
+    }
+}
+
+impl Notifier for MacosNotifier {
+    fn notify(&self, notification: &Notification) -> Result<()> {
+        let sound = if notification.escalated {
+            self.escalation_sound.as_str()
+        } else {
+            "Blow"
+        };
 
-        unseen_issues.push(issue.as_str());
+        Command::new("terminal-notifier")
+            .args([
+                "-title",
+                "Work Driver",
+                "-message",
+                &notification.summary,
+                "-sound",
+                sound,
+                "-open",
+                &self.dashboard_url,
+            ])
+            .output()
+            .context("Failed to send notification")?;
 
-        match state.issue_timestamps.get(issue) {
-            Some(last_notified) => {
-                if now.signed_duration_since(*last_notified) > notify_threshold {
-                    needs_notification = true;
-                    state.issue_timestamps.insert(issue.clone(), now);
-                }
-            }
-            None => {
-                needs_notification = true;
-                state.issue_timestamps.insert(issue.clone(), now);
+        if notification.escalated && self.escalation_alert {
+            // Not waited on: the alert stays up until it's dismissed
+            let script = format!(
+                "display alert \"Work Driver\" message {:?} as critical",
+                notification.summary
+            );
+            Command::new("osascript")
+                .args(["-e", &script])
+                .spawn()
+                .context("Failed to show escalation alert")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Only logs what would have been sent, for machines without a notification backend.
+pub struct LogNotifier;
+
+impl Notifier for LogNotifier {
+    fn notify(&self, notification: &Notification) -> Result<()> {
+        info!(
+            escalated = notification.escalated,
+            "{}", notification.summary
+        );
+        Ok(())
+    }
+}
+
+/// The notifier selected by `notifier.backend`.
+pub fn notifier_from_config(config: &Config) -> Box<dyn Notifier> {
+    match config.notifier.backend {
+        NotifierBackend::Macos => Box::new(MacosNotifier::new(config)),
+        NotifierBackend::Log => Box::new(LogNotifier),
+    }
+}
+
+pub fn send_notification(
+    detailed_issues: &[Issue],
+    config: &Config,
+    notifier: &dyn Notifier,
+) -> Result<()> {
+    let mut state = load_state().unwrap_or_default();
+    let result = notify_with_state(detailed_issues, &mut state, config, Utc::now(), notifier);
+    // Saved even if delivery failed, so a broken notifier doesn't re-fire every run
+    save_state(&state).context("Failed to save state")?;
+    result
+}
+
+/// Decides what to notify about at `now`, records it in `state` and hands it to `notifier`.
+fn notify_with_state(
+    detailed_issues: &[Issue],
+    state: &mut State,
+    config: &Config,
+    now: DateTime<Utc>,
+    notifier: &dyn Notifier,
+) -> Result<()> {
+    let plan = plan_notification(detailed_issues, state, config, now);
+    let unseen_issues: Vec<&Issue> = plan
+        .iter()
+        .filter(|(_, decision)| !matches!(decision, NotifyDecision::Seen | NotifyDecision::Snoozed))
+        .map(|(issue, _)| *issue)
+        .collect();
+    // During quiet hours nothing is sent; remember what would have been so it's
+    // notified when the window ends even if its throttle hasn't expired
+    if plan
+        .iter()
+        .any(|(_, decision)| *decision == NotifyDecision::Deferred)
+    {
+        for (issue, decision) in &plan {
+            if *decision == NotifyDecision::Deferred {
+                state.deferred.entry(issue.id.clone()).or_insert(now);
             }
         }
+        debug!("quiet hours, holding notification");
+        return Ok(());
+    }
+
+    let mut needs_notification = false;
+    for (issue, decision) in &plan {
+        if *decision == NotifyDecision::Notify {
+            needs_notification = true;
+            state.last_notified.insert(issue.id.clone(), now);
+        }
     }
+    state.deferred.clear();
 
     if !needs_notification || unseen_issues.is_empty() {
+        debug!("nothing new to notify about");
         return Ok(());
     }
 
-    save_state(&state).context("Failed to save state")?;
+    let (summary, escalated) = notification_message(&unseen_issues, &state.first_seen, now, config);
+    // The group stands in for its PRs, whose own state still decides when it notifies
+    let review_group = review_group(&unseen_issues, &state.first_seen, now, config);
+    let mut issues: Vec<&Issue> = unseen_issues;
+    if let Some((group, reviews)) = &review_group {
+        let is_grouped = |issue: &Issue| reviews.iter().any(|review| review.id == issue.id);
+        let position = issues.iter().take_while(|i| !is_grouped(i)).count();
+        issues.retain(|i| !is_grouped(i));
+        issues.insert(position, group);
+    }
+    notifier.notify(&Notification {
+        summary,
+        issues,
+        escalated,
+    })
+}
 
-    // Build summary grouped by type
-    let mut failing = 0;
-    let mut needs_review = 0;
-    let mut draft_ready = 0;
-    let mut needs_label = 0;
-    let mut flags = 0;
-    for issue in &unseen_issues {
-        if issue.contains("has failing checks") {
-            failing += 1;
-        } else if issue.contains("awaiting your review") {
-            needs_review += 1;
-        } else if issue.contains("is draft with all checks passing") {
-            draft_ready += 1;
-        } else if issue.contains("missing ready-to-merge label") {
-            needs_label += 1;
-        } else if issue.starts_with("Flag ") {
-            flags += 1;
-        }
+/// When more than `github.review_group_threshold` of `issues` are requests to review a
+/// PR, a single issue summarizing them, e.g. "14 PRs awaiting your review (oldest: #123,
+/// 6d 2h)", along with the requests it stands for, oldest first.
+fn review_group<'a>(
+    issues: &[&'a Issue],
+    first_seen: &HashMap<String, DateTime<Utc>>,
+    now: DateTime<Utc>,
+    config: &Config,
+) -> Option<(Issue, Vec<&'a Issue>)> {
+    let mut reviews: Vec<&Issue> = issues
+        .iter()
+        .copied()
+        .filter(|i| i.kind == IssueKind::AwaitingReview && i.id.contains(":pr:"))
+        .collect();
+    if reviews.len() <= config.github.review_group_threshold {
+        return None;
     }
-    let mut parts = Vec::new();
-    if failing > 0 {
-        parts.push(format!("{} failing check{}", failing, if failing == 1 { "" } else { "s" }));
+    sort_oldest_first(&mut reviews, first_seen);
+
+    let oldest = reviews[0];
+    // PR titles start with the reference, e.g. "PR #123 'Fix the thing'"
+    let reference = oldest
+        .title
+        .strip_prefix("PR ")
+        .and_then(|title| title.split(' ').next())
+        .unwrap_or(&oldest.title);
+    let oldest_label = match first_seen.get(&oldest.id) {
+        Some(ts) => format!(
+            "{}, {}",
+            reference,
+            format_age(now.signed_duration_since(*ts))
+        ),
+        None => reference.to_string(),
+    };
+    let group = Issue {
+        id: "github:review-requests".to_string(),
+        source: oldest.source,
+        kind: IssueKind::AwaitingReview,
+        severity: reviews.iter().map(|i| i.severity).max()?,
+        title: format!("{} PRs", reviews.len()),
+        detail: format!("awaiting your review (oldest: {})", oldest_label),
+        url: None,
+        items: Vec::new(),
+    };
+    Some((group, reviews))
+}
+
+/// The notification text for `issues`, and whether it's escalated because the oldest
+/// of them has been open longer than the escalation threshold.
+fn notification_message(
+    issues: &[&Issue],
+    first_seen: &HashMap<String, DateTime<Utc>>,
+    now: DateTime<Utc>,
+    config: &Config,
+) -> (String, bool) {
+    let escalation = &config.notifier.escalation;
+    let mut summary = summarize(issues);
+    let Some(age) = oldest_age(issues, first_seen, now) else {
+        return (summary, false);
+    };
+    summary.push_str(&format!(" (oldest open for {})", format_age(age)));
+
+    let escalated = escalation.enabled && age > chrono::Duration::minutes(escalation.after_minutes);
+    if escalated {
+        debug!(age = %format_age(age), "escalating notification");
+        summary.insert_str(0, "STILL OPEN: ");
     }
-    if needs_review > 0 {
-        parts.push(format!("{} review{} waiting", needs_review, if needs_review == 1 { "" } else { "s" }));
+    (summary, escalated)
+}
+
+/// Sorts issues by when they were first reported, oldest first. Issues without a
+/// first-seen time are treated as brand new; ties keep their checker order.
+fn sort_oldest_first(issues: &mut [&Issue], first_seen: &HashMap<String, DateTime<Utc>>) {
+    issues.sort_by_key(|issue| {
+        first_seen
+            .get(&issue.id)
+            .copied()
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    });
+}
+
+/// How long the longest-outstanding of `issues` has been open.
+fn oldest_age(
+    issues: &[&Issue],
+    first_seen: &HashMap<String, DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Option<chrono::Duration> {
+    issues
+        .iter()
+        .filter_map(|issue| first_seen.get(&issue.id))
+        .min()
+        .map(|ts| now.signed_duration_since(*ts))
+}
+
+/// Formats an age with its two largest units, e.g. "2d 4h", "3h 12m" or "5m".
+pub fn format_age(age: chrono::Duration) -> String {
+    let minutes = age.num_minutes().max(0);
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
     }
-    if draft_ready > 0 {
-        parts.push(format!("{} draft{} ready", draft_ready, if draft_ready == 1 { "" } else { "s" }));
+}
+
+/// Formats a whole number of minutes compactly, e.g. "45m", "4h" or "1h30m".
+pub fn format_minutes(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h{}m", h, m),
     }
-    if needs_label > 0 {
-        parts.push(format!("{} PR{} ready to merge", needs_label, if needs_label == 1 { "" } else { "s" }));
+}
+
+/// Noun phrase used to count issues of a kind in the summary, as (singular, plural).
+fn summary_phrase(kind: IssueKind) -> (&'static str, &'static str) {
+    match kind {
+        IssueKind::FailingChecks => ("failing check", "failing checks"),
+        IssueKind::PendingChecks => ("stuck check", "stuck checks"),
+        IssueKind::AwaitingReview => ("review waiting", "reviews waiting"),
+        IssueKind::AwaitingTeamReview => ("team review waiting", "team reviews waiting"),
+        IssueKind::DraftReady => ("draft ready", "drafts ready"),
+        IssueKind::MissingReadyLabel | IssueKind::ReadyToMerge => {
+            ("PR ready to merge", "PRs ready to merge")
+        }
+        IssueKind::ChangesRequested => ("PR with changes requested", "PRs with changes requested"),
+        IssueKind::MergeConflicts => ("PR with conflicts", "PRs with conflicts"),
+        IssueKind::UnresolvedThreads => ("PR with open threads", "PRs with open threads"),
+        IssueKind::AwaitingYourReply => {
+            ("thread awaiting your reply", "threads awaiting your reply")
+        }
+        IssueKind::AwaitingTheirReply => ("unanswered comment", "unanswered comments"),
+        IssueKind::MissingRequiredCheck => ("missing required check", "missing required checks"),
+        IssueKind::NoChecks => ("PR without checks", "PRs without checks"),
+        IssueKind::BehindBase => ("PR behind its base", "PRs behind their base"),
+        IssueKind::AutoMergeStuck => ("stuck auto-merge", "stuck auto-merges"),
+        IssueKind::Unreviewed => ("PR without review", "PRs without review"),
+        IssueKind::AwaitingApprovals => ("PR awaiting approvals", "PRs awaiting approvals"),
+        IssueKind::BaseMerged => ("PR on a merged branch", "PRs on merged branches"),
+        IssueKind::NoCodeOwner => ("PR without code owners", "PRs without code owners"),
+        IssueKind::ClosedUnmerged => ("PR closed unmerged", "PRs closed unmerged"),
+        IssueKind::AutoCloseSoon => ("PR about to go stale", "PRs about to go stale"),
+        IssueKind::PendingReview => ("unsubmitted review", "unsubmitted reviews"),
+        IssueKind::SecurityAlert => ("security alert", "security alerts"),
+        IssueKind::WorkflowFailing => ("failing workflow", "failing workflows"),
+        IssueKind::DeploymentWaiting => ("deployment to approve", "deployments to approve"),
+        IssueKind::AssignedIssue => ("issue assigned", "issues assigned"),
+        IssueKind::MilestoneDue => ("milestone due", "milestones due"),
+        IssueKind::FollowUp => ("follow-up", "follow-ups"),
+        IssueKind::Mentioned => ("mention", "mentions"),
+        IssueKind::RolloutNotStarted | IssueKind::StaleRollout => ("flag stale", "flags stale"),
+        IssueKind::RampDue => ("flag to ramp", "flags to ramp"),
+        IssueKind::ReadyForRemoval => ("flag to remove", "flags to remove"),
+        IssueKind::FlagDrift => ("flag drifted", "flags drifted"),
+        IssueKind::ApprovalRequested => ("flag approval waiting", "flag approvals waiting"),
+        IssueKind::ScheduledChangeOverdue => ("flag change overdue", "flag changes overdue"),
+        IssueKind::PrerequisiteOff => ("prerequisite off", "prerequisites off"),
+        IssueKind::FlagModified => ("flag changed", "flags changed"),
+        IssueKind::RolloutRolledBack => ("rollout rolled back", "rollouts rolled back"),
+        IssueKind::ExperimentRunning => ("experiment running", "experiments running"),
+        IssueKind::DeadFlags => ("dead flags report", "dead flags reports"),
+        IssueKind::TokenAccess => ("token problem", "token problems"),
+        IssueKind::EmptySegment => ("empty segment", "empty segments"),
+        IssueKind::StaleSegment => ("stale segment", "stale segments"),
+        IssueKind::UnknownEnabledVariation => ("flag not understood", "flags not understood"),
+        IssueKind::CheckerFailed => ("checker failed", "checkers failed"),
     }
-    if flags > 0 {
-        parts.push(format!("{} flag{} stale", flags, if flags == 1 { "" } else { "s" }));
+}
+
+/// Builds the notification text, leading with severity counts,
+/// e.g. "1 urgent, 2 normal: 1 failing check, 2 reviews waiting".
+pub fn summarize(issues: &[&Issue]) -> String {
+    let mut severity_parts = Vec::new();
+    for (severity, label) in [
+        (Severity::High, "urgent"),
+        (Severity::Medium, "normal"),
+        (Severity::Low, "low priority"),
+    ] {
+        let count = issues.iter().filter(|i| i.severity == severity).count();
+        if count > 0 {
+            severity_parts.push(format!("{} {}", count, label));
+        }
     }
-    let summary = parts.join(", ");
 
-    Command::new("terminal-notifier")
-        .args([
-            "-title",
-            "Work Driver",
-            "-message",
-            &summary,
-            "-sound",
-            "Blow",
-            "-open",
-            "http://localhost:9845/",
-        ])
-        .output()
-        .context("Failed to send notification")?;
+    // Group by phrase in order of first appearance
+    let mut phrase_counts: Vec<((&str, &str), usize)> = Vec::new();
+    for issue in issues {
+        let phrase = summary_phrase(issue.kind);
+        match phrase_counts.iter_mut().find(|(p, _)| *p == phrase) {
+            Some((_, count)) => *count += 1,
+            None => phrase_counts.push((phrase, 1)),
+        }
+    }
+    let kind_parts: Vec<String> = phrase_counts
+        .iter()
+        .map(|((singular, plural), count)| {
+            format!("{} {}", count, if *count == 1 { singular } else { plural })
+        })
+        .collect();
 
-    Ok(())
+    format!("{}: {}", severity_parts.join(", "), kind_parts.join(", "))
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::issue::{CheckSource, IssueItem};
+
+    fn issue(id: &str, kind: IssueKind, severity: Severity) -> Issue {
+        Issue {
+            id: id.to_string(),
+            source: CheckSource::GitHub,
+            kind,
+            severity,
+            title: format!("PR #{}", id),
+            detail: "needs attention".to_string(),
+            url: None,
+            items: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_summarize_leads_with_severity() {
+        let issues = [
+            issue("1", IssueKind::FailingChecks, Severity::High),
+            issue("2", IssueKind::AwaitingReview, Severity::Medium),
+            issue("3", IssueKind::AwaitingReview, Severity::Medium),
+            issue("4", IssueKind::RolloutNotStarted, Severity::Low),
+        ];
+        let refs: Vec<&Issue> = issues.iter().collect();
+        assert_eq!(
+            summarize(&refs),
+            "1 urgent, 2 normal, 1 low priority: 1 failing check, 2 reviews waiting, 1 flag stale"
+        );
+    }
+
+    #[test]
+    fn test_plan_notifies_issues_held_during_quiet_hours() {
+        let now = Utc::now();
+        let issues = [
+            issue("held", IssueKind::FailingChecks, Severity::High),
+            issue("recent", IssueKind::AwaitingReview, Severity::Medium),
+        ];
+        let mut state = State::default();
+        for id in ["held", "recent"] {
+            state
+                .last_notified
+                .insert(id.to_string(), now - chrono::Duration::minutes(1));
+        }
+        state.deferred.insert("held".to_string(), now);
+
+        assert_eq!(
+            decisions(&issues, &state, &Config::default(), now),
+            [NotifyDecision::Notify, NotifyDecision::Throttled]
+        );
+    }
+
+    #[test]
+    fn test_notification_escalates_for_old_issues() {
+        let now = Utc::now();
+        let issues = [issue("1", IssueKind::FailingChecks, Severity::High)];
+        let refs: Vec<&Issue> = issues.iter().collect();
+        let config = Config::default();
+
+        let first_seen = HashMap::from([("1".to_string(), now - chrono::Duration::minutes(90))]);
+        assert_eq!(
+            notification_message(&refs, &first_seen, now, &config),
+            (
+                "1 urgent: 1 failing check (oldest open for 1h 30m)".to_string(),
+                false
+            )
+        );
+
+        let first_seen = HashMap::from([("1".to_string(), now - chrono::Duration::hours(5))]);
+        assert_eq!(
+            notification_message(&refs, &first_seen, now, &config),
+            (
+                "STILL OPEN: 1 urgent: 1 failing check (oldest open for 5h 0m)".to_string(),
+                true
+            )
+        );
+    }
+
+    /// Records notifications instead of sending them.
+    #[derive(Default)]
+    struct RecordingNotifier {
+        sent: std::sync::Mutex<Vec<(String, Vec<String>)>>,
+    }
+
+    impl Notifier for RecordingNotifier {
+        fn notify(&self, notification: &Notification) -> Result<()> {
+            let ids = notification.issues.iter().map(|i| i.id.clone()).collect();
+            self.sent
+                .lock()
+                .unwrap()
+                .push((notification.summary.clone(), ids));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_notify_with_state_sends_once_then_throttles() {
+        let now = Utc::now();
+        let issues = [
+            issue("1", IssueKind::FailingChecks, Severity::High),
+            issue("2", IssueKind::AwaitingReview, Severity::Medium),
+        ];
+        let mut state = State::default();
+        state.seen.insert("2".to_string(), now);
+        let config = Config::default();
+        let notifier = RecordingNotifier::default();
+
+        notify_with_state(&issues, &mut state, &config, now, &notifier).unwrap();
+        notify_with_state(&issues, &mut state, &config, now, &notifier).unwrap();
+
+        let sent = notifier.sent.into_inner().unwrap();
+        assert_eq!(
+            sent,
+            [(
+                "1 urgent: 1 failing check".to_string(),
+                vec!["1".to_string()]
+            )]
+        );
+        assert_eq!(state.last_notified.get("1"), Some(&now));
+        assert!(!state.last_notified.contains_key("2"));
+    }
+
+    fn review_request(number: u32) -> Issue {
+        Issue {
+            title: format!("PR #{} 'Change {}'", number, number),
+            ..issue(
+                &format!("github:org/repo:pr:{}", number),
+                IssueKind::AwaitingReview,
+                Severity::Medium,
+            )
+        }
+    }
+
+    #[test]
+    fn test_review_requests_past_threshold_notify_as_group() {
+        let now = "2024-06-03T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let mut issues = vec![issue("1", IssueKind::FailingChecks, Severity::High)];
+        issues.extend((101..=107).map(review_request));
+        let mut state = State::default();
+        state.first_seen.insert(
+            "github:org/repo:pr:104".to_string(),
+            now - chrono::Duration::hours(50),
+        );
+        let config = Config::default();
+        let unseen: Vec<&Issue> = issues.iter().collect();
+
+        let (group, reviews) = review_group(&unseen, &state.first_seen, now, &config).unwrap();
+        assert_eq!(
+            group.to_string(),
+            "7 PRs awaiting your review (oldest: #104, 2d 2h)"
+        );
+        assert_eq!(reviews.len(), 7);
+        assert!(review_group(&unseen[..6], &state.first_seen, now, &config).is_none());
+
+        let notifier = RecordingNotifier::default();
+        notify_with_state(&issues, &mut state, &config, now, &notifier).unwrap();
+        // Resolving some of the reviews keeps the rest throttled
+        let later = now + chrono::Duration::minutes(5);
+        notify_with_state(&issues[..6], &mut state, &config, later, &notifier).unwrap();
+
+        let sent = notifier.sent.into_inner().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].1, ["1", "github:review-requests"]);
+        assert_eq!(
+            state.last_notified.get("github:org/repo:pr:101"),
+            Some(&now)
+        );
+    }
+
+    #[test]
+    fn test_rolled_up_issue_lists_items() {
+        let rolled_up = Issue {
+            items: vec![
+                IssueItem {
+                    text: "Flag 'Old <checkout>'".to_string(),
+                    url: Some("https://app.launchdarkly.com/old-checkout".to_string()),
+                },
+                IssueItem {
+                    text: "Flag 'Beta banner'".to_string(),
+                    url: None,
+                },
+            ],
+            ..issue(
+                "ld:dead-flags:2024-06-03",
+                IssueKind::DeadFlags,
+                Severity::Low,
+            )
+        };
+        assert_eq!(
+            format_issue_as_html(&rolled_up, ""),
+            concat!(
+                r#"<li class="severity-low" data-issue="ld:dead-flags:2024-06-03">PR #ld:dead-flags:2024-06-03 needs attention"#,
+                r#"<ul class="issue-items"><li><a href="https://app.launchdarkly.com/old-checkout" target="_blank">Flag 'Old &lt;checkout&gt;'</a></li><li>Flag 'Beta banner'</li></ul></li>"#
+            )
+        );
+        // Notifications only count the issue itself
+        assert_eq!(
+            summarize(&[&rolled_up]),
+            "1 low priority: 1 dead flags report"
+        );
+    }
+
+    #[test]
+    fn test_muted_prs_listed_collapsed() {
+        let now = "2024-06-03T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let config = Config::default();
+        let html = |muted: &[(&String, &MutedPr)]| {
+            generate_html(&[], &[], &[], muted, &HashMap::new(), now, &config)
+        };
+        assert!(html(&[]).contains(r#"<div id="muted-section" style="display:none">"#));
+
+        let pr_ref = "org/repo#12".to_string();
+        let muted_pr = MutedPr {
+            title: "Sweeping <refactor>".to_string(),
+            url: Some("https://github.com/org/repo/pull/12".to_string()),
+        };
+        let html = html(&[(&pr_ref, &muted_pr)]);
+        assert!(html.contains("Muted (1)"));
+        assert!(html.contains(
+            r#"<li><a href="https://github.com/org/repo/pull/12" target="_blank">org/repo#12 'Sweeping &lt;refactor&gt;'</a></li>"#
+        ));
+    }
+
+    fn decisions(
+        issues: &[Issue],
+        state: &State,
+        config: &Config,
+        now: DateTime<Utc>,
+    ) -> Vec<NotifyDecision> {
+        plan_notification(issues, state, config, now)
+            .into_iter()
+            .map(|(_, decision)| decision)
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_renotifies_after_threshold() {
+        let start = "2024-06-03T09:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let issues = [issue("1", IssueKind::FailingChecks, Severity::High)];
+        let mut state = State::default();
+        let mut config = Config::default();
+        config.notifier.renotify_minutes = Renotify::Minutes(19);
+
+        assert_eq!(
+            decisions(&issues, &state, &config, start),
+            [NotifyDecision::Notify]
+        );
+
+        state.last_notified.insert("1".to_string(), start);
+        let at = |minutes| start + chrono::Duration::minutes(minutes);
+        assert_eq!(
+            decisions(&issues, &state, &config, at(5)),
+            [NotifyDecision::Throttled]
+        );
+        assert_eq!(
+            decisions(&issues, &state, &config, at(19)),
+            [NotifyDecision::Throttled]
+        );
+        assert_eq!(
+            decisions(&issues, &state, &config, at(20)),
+            [NotifyDecision::Notify]
+        );
+
+        config.notifier.renotify_minutes = Renotify::Never;
+        assert_eq!(
+            decisions(&issues, &state, &config, at(60 * 24)),
+            [NotifyDecision::Throttled]
+        );
+    }
+
+    #[test]
+    fn test_plan_notifies_once_per_workflow_failure() {
+        let start = "2024-06-03T09:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let issues = [issue("1", IssueKind::WorkflowFailing, Severity::High)];
+        let mut state = State::default();
+        let config = Config::default();
+
+        state.last_notified.insert("1".to_string(), start);
+        assert_eq!(
+            decisions(&issues, &state, &config, start + chrono::Duration::hours(4)),
+            [NotifyDecision::Throttled]
+        );
+    }
+
+    #[test]
+    fn test_sort_oldest_first_and_format_age() {
+        let now = Utc::now();
+        let issues = [
+            issue("new", IssueKind::AwaitingReview, Severity::Medium),
+            issue("old", IssueKind::FailingChecks, Severity::High),
+            issue("untracked", IssueKind::DraftReady, Severity::Low),
+        ];
+        let first_seen = HashMap::from([
+            ("new".to_string(), now - chrono::Duration::minutes(5)),
+            (
+                "old".to_string(),
+                now - chrono::Duration::minutes(52 * 60 + 7),
+            ),
+        ]);
+
+        let mut refs: Vec<&Issue> = issues.iter().collect();
+        sort_oldest_first(&mut refs, &first_seen);
+        let ids: Vec<&str> = refs.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, ["old", "new", "untracked"]);
+
+        let age = oldest_age(&refs, &first_seen, now).unwrap();
+        assert_eq!(format_age(age), "2d 4h");
+        assert_eq!(format_age(chrono::Duration::minutes(192)), "3h 12m");
+        assert_eq!(format_age(chrono::Duration::seconds(30)), "0m");
+    }
+}
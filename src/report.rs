@@ -0,0 +1,167 @@
+//! The stable JSON document printed by `work-driver check --format json`. Fields may be
+//! added, but existing ones keep their names and meaning; bump `SCHEMA_VERSION` otherwise.
+
+use crate::issue::{CheckSource, Issue, IssueKind, Severity};
+use crate::runner::CheckReport;
+use crate::state::State;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct JsonReport {
+    pub version: u32,
+    pub checked_at: DateTime<Utc>,
+    /// The same text a notification would use, e.g. "1 urgent: 1 failing check".
+    pub summary: String,
+    pub issues: Vec<JsonIssue>,
+    pub checkers: Vec<JsonChecker>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct JsonIssue {
+    pub id: String,
+    pub source: CheckSource,
+    pub kind: IssueKind,
+    pub severity: Severity,
+    /// The issue as one line of text, as shown in the dashboard.
+    pub text: String,
+    pub url: Option<String>,
+    /// When the issue was first reported; absent for issues a dry run found first.
+    pub first_seen: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckerStatus {
+    Ok,
+    Error,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct JsonChecker {
+    pub name: String,
+    pub status: CheckerStatus,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+impl JsonReport {
+    pub fn new(report: &CheckReport, state: &State) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            checked_at: report.started_at,
+            summary: if report.issues.is_empty() {
+                String::new()
+            } else {
+                report.summary()
+            },
+            issues: report
+                .issues
+                .iter()
+                .map(|issue| JsonIssue::new(issue, state))
+                .collect(),
+            checkers: report
+                .checkers
+                .iter()
+                .map(|run| JsonChecker {
+                    name: run.name.clone(),
+                    status: match run.error {
+                        Some(_) => CheckerStatus::Error,
+                        None => CheckerStatus::Ok,
+                    },
+                    error: run.error.clone(),
+                    duration_ms: run.duration_ms,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl JsonIssue {
+    fn new(issue: &Issue, state: &State) -> Self {
+        Self {
+            id: issue.id.clone(),
+            source: issue.source,
+            kind: issue.kind,
+            severity: issue.severity,
+            text: issue.to_string(),
+            url: issue.url.clone(),
+            first_seen: state.first_seen.get(&issue.id).copied(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::CheckerRun;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_report_schema_roundtrips() {
+        let checked_at = "2024-06-03T09:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let report = CheckReport {
+            started_at: checked_at,
+            duration_ms: 1200,
+            issues: vec![Issue {
+                id: "github:figma/figma:pr:42".to_string(),
+                source: CheckSource::GitHub,
+                kind: IssueKind::FailingChecks,
+                severity: Severity::High,
+                title: "PR #42".to_string(),
+                detail: "has failing checks".to_string(),
+                url: Some("https://github.com/figma/figma/pull/42".to_string()),
+                items: Vec::new(),
+            }],
+            checkers: vec![
+                CheckerRun {
+                    name: "GitHub".to_string(),
+                    duration_ms: 800,
+                    error: None,
+                },
+                CheckerRun {
+                    name: "LaunchDarkly".to_string(),
+                    duration_ms: 0,
+                    error: Some("launchdarkly.api_token is not set".to_string()),
+                },
+            ],
+        };
+        let mut state = State::default();
+        state
+            .first_seen
+            .insert("github:figma/figma:pr:42".to_string(), checked_at);
+
+        let json_report = JsonReport::new(&report, &state);
+        let value = serde_json::to_value(&json_report).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "version": 1,
+                "checked_at": "2024-06-03T09:00:00Z",
+                "summary": "1 urgent: 1 failing check",
+                "issues": [{
+                    "id": "github:figma/figma:pr:42",
+                    "source": "GitHub",
+                    "kind": "FailingChecks",
+                    "severity": "High",
+                    "text": "PR #42 has failing checks",
+                    "url": "https://github.com/figma/figma/pull/42",
+                    "first_seen": "2024-06-03T09:00:00Z",
+                }],
+                "checkers": [
+                    { "name": "GitHub", "status": "ok", "error": null, "duration_ms": 800 },
+                    {
+                        "name": "LaunchDarkly",
+                        "status": "error",
+                        "error": "launchdarkly.api_token is not set",
+                        "duration_ms": 0,
+                    },
+                ],
+            })
+        );
+        let parsed: JsonReport = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed, json_report);
+    }
+}
@@ -0,0 +1,476 @@
+use crate::check::{Check, check_with_timeout};
+use crate::config::CHECKER_NAMES;
+use crate::config::Config;
+use crate::github::{GH_MISSING, GitHubChecker, gh_installed, gh_required};
+use crate::issue::{Issue, dedup_by_id};
+use crate::launchdarkly::LaunchDarklyChecker;
+use crate::notifier::{notifier_from_config, send_notification, summarize, update_html};
+use crate::state::{StateUpdate, load_state, save_state};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures_util::future::join_all;
+use serde::Serialize;
+use std::fmt;
+use std::time::{Duration, Instant};
+use tracing::error;
+
+/// What a check run covers and which side effects it has.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunOptions {
+    /// Lowercased checker names to run; empty means all enabled ones.
+    pub only: Vec<String>,
+    pub notify: bool,
+    /// Run the checkers without writing the dashboard, state or notifying.
+    pub dry_run: bool,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            only: Vec::new(),
+            notify: true,
+            dry_run: false,
+        }
+    }
+}
+
+impl RunOptions {
+    pub fn includes(&self, checker_name: &str) -> bool {
+        self.only.is_empty()
+            || self
+                .only
+                .iter()
+                .any(|o| o.eq_ignore_ascii_case(checker_name))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckerRun {
+    pub name: String,
+    pub duration_ms: u64,
+    /// Why the checker failed or couldn't be constructed.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckReport {
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    /// Every issue found, including ones reporting failed checkers.
+    pub issues: Vec<Issue>,
+    pub checkers: Vec<CheckerRun>,
+}
+
+impl CheckReport {
+    pub fn errors(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.checkers
+            .iter()
+            .filter_map(|run| Some((run.name.as_str(), run.error.as_deref()?)))
+    }
+
+    /// Severity and kind counts, as used in notifications.
+    pub fn summary(&self) -> String {
+        let refs: Vec<&Issue> = self.issues.iter().collect();
+        summarize(&refs)
+    }
+}
+
+/// Runs every enabled checker, then updates the dashboard and sends a notification.
+pub async fn run_checks(config: &Config) -> Result<CheckReport> {
+    run_checks_with(config, &RunOptions::default()).await
+}
+
+pub async fn run_checks_with(config: &Config, options: &RunOptions) -> Result<CheckReport> {
+    // Every checker is due on the first cycle, so the interval doesn't matter here
+    Watcher::new(config, options.clone(), Duration::ZERO)
+        .run_cycle(config)
+        .await
+}
+
+/// Whether a known checker will run, and if not, why.
+#[derive(Debug, PartialEq)]
+pub enum Availability {
+    Ready,
+    /// Turned off by its `enabled` setting or `WORK_DRIVER_CHECKS`.
+    Disabled,
+    /// Left out by `--only`.
+    NotSelected,
+    /// Enabled but can't run, e.g. a missing binary or setting.
+    Unavailable(String),
+}
+
+impl fmt::Display for Availability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Availability::Ready => write!(f, "ready"),
+            Availability::Disabled => {
+                write!(f, "disabled in config.toml or by WORK_DRIVER_CHECKS")
+            }
+            Availability::NotSelected => write!(f, "not selected by --only"),
+            Availability::Unavailable(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// Whether the GitHub checker can run: with an API token, or through `gh`.
+fn github_usable(config: &Config) -> bool {
+    !gh_required(&config.github) || gh_installed()
+}
+
+/// The availability of every known checker, in `CHECKER_NAMES` order.
+pub fn checker_availability(
+    config: &Config,
+    options: &RunOptions,
+) -> Vec<(&'static str, Availability)> {
+    CHECKER_NAMES
+        .iter()
+        .map(|&name| {
+            let (enabled, unavailable) = match name {
+                "github" => (
+                    config.github.enabled,
+                    (!github_usable(config)).then(|| GH_MISSING.to_string()),
+                ),
+                "launchdarkly" => (
+                    config.launchdarkly.enabled,
+                    LaunchDarklyChecker::new(&config.launchdarkly)
+                        .err()
+                        .map(|e| format!("{:#}", e)),
+                ),
+                _ => unreachable!("unknown checker {}", name),
+            };
+            let availability = if !enabled {
+                Availability::Disabled
+            } else if !options.includes(name) {
+                Availability::NotSelected
+            } else if let Some(reason) = unavailable {
+                Availability::Unavailable(reason)
+            } else {
+                Availability::Ready
+            };
+            (name, availability)
+        })
+        .collect()
+}
+
+/// An enabled checker that couldn't be constructed, reported in place of its results.
+type Misconfigured = (Issue, CheckerRun);
+
+/// Builds the enabled checkers. Checkers that are enabled but can't be constructed are
+/// returned as issues with their failed runs instead.
+fn build_checkers(
+    config: &Config,
+    options: &RunOptions,
+) -> (Vec<Box<dyn Check>>, Vec<Misconfigured>) {
+    let mut checkers: Vec<Box<dyn Check>> = Vec::new();
+    let mut misconfigured = Vec::new();
+    if config.github.enabled && options.includes("github") {
+        let checker = GitHubChecker::new(&config.github);
+        match checker.preflight() {
+            Ok(()) => checkers.push(Box::new(checker)),
+            Err(e) => misconfigured.push((
+                Issue::checker_misconfigured("GitHub", &e),
+                CheckerRun {
+                    name: "GitHub".to_string(),
+                    duration_ms: 0,
+                    error: Some(format!("{:#}", e)),
+                },
+            )),
+        }
+    }
+    if config.launchdarkly.enabled && options.includes("launchdarkly") {
+        match LaunchDarklyChecker::new(&config.launchdarkly) {
+            Ok(checker) => checkers.push(Box::new(checker)),
+            Err(e) => misconfigured.push((
+                Issue::checker_misconfigured("LaunchDarkly", &e),
+                CheckerRun {
+                    name: "LaunchDarkly".to_string(),
+                    duration_ms: 0,
+                    error: Some(format!("{:#}", e)),
+                },
+            )),
+        }
+    }
+    (checkers, misconfigured)
+}
+
+struct ScheduledChecker {
+    checker: Box<dyn Check>,
+    interval: Duration,
+    last_run: Option<Instant>,
+    /// Issues and run details from the most recent run.
+    latest: Option<(Vec<Issue>, CheckerRun)>,
+}
+
+impl ScheduledChecker {
+    /// Whether the checker should run at `now`. Allows some slack so jittered watch
+    /// cycles slightly shorter than the interval don't skip a run.
+    fn is_due(&self, now: Instant) -> bool {
+        self.last_run
+            .is_none_or(|last_run| now.duration_since(last_run) >= self.interval.mul_f64(0.85))
+    }
+}
+
+/// Runs checkers on their own intervals across watch cycles, merging each checker's most
+/// recent results so a cycle where only some checkers ran still reports everything.
+pub struct Watcher {
+    checkers: Vec<ScheduledChecker>,
+    misconfigured: Vec<Misconfigured>,
+    options: RunOptions,
+}
+
+impl Watcher {
+    /// `default_interval` applies to checkers that don't set their own.
+    pub fn new(config: &Config, options: RunOptions, default_interval: Duration) -> Self {
+        let (checkers, misconfigured) = build_checkers(config, &options);
+        Self::from_checkers(checkers, misconfigured, options, default_interval)
+    }
+
+    fn from_checkers(
+        checkers: Vec<Box<dyn Check>>,
+        misconfigured: Vec<Misconfigured>,
+        options: RunOptions,
+        default_interval: Duration,
+    ) -> Self {
+        let checkers = checkers
+            .into_iter()
+            .map(|checker| ScheduledChecker {
+                interval: checker.interval().unwrap_or(default_interval),
+                checker,
+                last_run: None,
+                latest: None,
+            })
+            .collect();
+        Self {
+            checkers,
+            misconfigured,
+            options,
+        }
+    }
+
+    /// How long to wait between cycles: the shortest checker interval.
+    pub fn tick(&self) -> Option<Duration> {
+        self.checkers.iter().map(|c| c.interval).min()
+    }
+
+    /// Runs the checkers that are due, then updates the dashboard and notifies using the
+    /// latest results from every checker.
+    pub async fn run_cycle(&mut self, config: &Config) -> Result<CheckReport> {
+        let started_at = Utc::now();
+        let start = Instant::now();
+
+        // Run due checkers concurrently
+        let results = join_all(
+            self.checkers
+                .iter_mut()
+                .filter(|scheduled| scheduled.is_due(start))
+                .map(|scheduled| async move {
+                    let checker_start = Instant::now();
+                    let result = check_with_timeout(scheduled.checker.as_ref()).await;
+                    (scheduled, checker_start, result)
+                }),
+        )
+        .await;
+
+        let mut state_updates: Vec<StateUpdate> = Vec::new();
+        for (scheduled, checker_start, result) in results {
+            let name = scheduled.checker.name();
+            let (issues, error) = match result {
+                Ok(output) => {
+                    state_updates.extend(output.state_update);
+                    (output.issues, None)
+                }
+                Err(e) => {
+                    error!("Error running {} check: {:#}", name, e);
+                    (
+                        vec![Issue::checker_failed(name, &e)],
+                        Some(format!("{:#}", e)),
+                    )
+                }
+            };
+            let run = CheckerRun {
+                name: name.to_string(),
+                duration_ms: checker_start.elapsed().as_millis() as u64,
+                error,
+            };
+            scheduled.last_run = Some(checker_start);
+            scheduled.latest = Some((issues, run));
+        }
+
+        // Merge in name order so the issue list is stable from run to run regardless of
+        // which checker finished first.
+        let mut merged: Vec<(&[Issue], &CheckerRun)> = self
+            .checkers
+            .iter()
+            .filter_map(|scheduled| scheduled.latest.as_ref())
+            .map(|(issues, run)| (issues.as_slice(), run))
+            .chain(
+                self.misconfigured
+                    .iter()
+                    .map(|(issue, run)| (std::slice::from_ref(issue), run)),
+            )
+            .collect();
+        merged.sort_by(|(_, a), (_, b)| a.name.cmp(&b.name));
+
+        let mut all_issues: Vec<Issue> = merged
+            .iter()
+            .flat_map(|(issues, _)| issues.iter().cloned())
+            .collect();
+        let runs: Vec<CheckerRun> = merged.iter().map(|(_, run)| (*run).clone()).collect();
+
+        dedup_by_id(&mut all_issues);
+
+        if !self.options.dry_run {
+            if !state_updates.is_empty() {
+                let mut state = load_state().unwrap_or_default();
+                for update in state_updates {
+                    update(&mut state);
+                }
+                save_state(&state).context("Failed to save checkers' state")?;
+            }
+            update_html(&all_issues, config)?;
+            if self.options.notify && !all_issues.is_empty() {
+                send_notification(&all_issues, config, notifier_from_config(config).as_ref())?;
+            }
+        }
+
+        Ok(CheckReport {
+            started_at,
+            duration_ms: start.elapsed().as_millis() as u64,
+            issues: all_issues,
+            checkers: runs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check::CheckOutput;
+    use crate::issue::{CheckSource, IssueKind, Severity};
+    use async_trait::async_trait;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Reports one issue named after itself and counts its runs, and the times its state
+    /// update is applied.
+    struct CountingChecker {
+        name: &'static str,
+        interval: Duration,
+        runs: Arc<AtomicU32>,
+        updates: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl Check for CountingChecker {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn interval(&self) -> Option<Duration> {
+            Some(self.interval)
+        }
+
+        async fn check(&self) -> Result<CheckOutput> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            let updates = self.updates.clone();
+            let issues = vec![Issue {
+                id: self.name.to_string(),
+                source: CheckSource::GitHub,
+                kind: IssueKind::AwaitingReview,
+                severity: Severity::Medium,
+                title: self.name.to_string(),
+                detail: "needs review".to_string(),
+                url: None,
+                items: Vec::new(),
+            }];
+            Ok(CheckOutput {
+                issues,
+                state_update: Some(Box::new(move |_| {
+                    updates.fetch_add(1, Ordering::SeqCst);
+                })),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watcher_runs_checkers_on_their_intervals() {
+        let fast_runs = Arc::new(AtomicU32::new(0));
+        let slow_runs = Arc::new(AtomicU32::new(0));
+        let updates = Arc::new(AtomicU32::new(0));
+        let checkers: Vec<Box<dyn Check>> = vec![
+            Box::new(CountingChecker {
+                name: "slow",
+                interval: Duration::from_secs(3600),
+                runs: slow_runs.clone(),
+                updates: updates.clone(),
+            }),
+            Box::new(CountingChecker {
+                name: "fast",
+                interval: Duration::ZERO,
+                runs: fast_runs.clone(),
+                updates: updates.clone(),
+            }),
+        ];
+        let options = RunOptions {
+            dry_run: true,
+            ..RunOptions::default()
+        };
+        let mut watcher =
+            Watcher::from_checkers(checkers, Vec::new(), options, Duration::from_secs(60));
+        assert_eq!(watcher.tick(), Some(Duration::ZERO));
+
+        let config = Config::default();
+        watcher.run_cycle(&config).await.unwrap();
+        let report = watcher.run_cycle(&config).await.unwrap();
+
+        assert_eq!(fast_runs.load(Ordering::SeqCst), 2);
+        assert_eq!(slow_runs.load(Ordering::SeqCst), 1);
+        // The slow checker's earlier results are still reported
+        let ids: Vec<&str> = report.issues.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, ["fast", "slow"]);
+        // A dry run leaves the state file alone
+        assert_eq!(updates.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_checker_availability_explains_why() {
+        let mut config = Config::default();
+        config.github.enabled = false;
+        let options = RunOptions {
+            only: vec!["github".to_string()],
+            ..RunOptions::default()
+        };
+        assert_eq!(
+            checker_availability(&config, &options),
+            [
+                ("github", Availability::Disabled),
+                ("launchdarkly", Availability::NotSelected),
+            ]
+        );
+
+        let availability = checker_availability(&config, &RunOptions::default());
+        assert!(matches!(
+            &availability[1],
+            ("launchdarkly", Availability::Unavailable(reason))
+                if reason.starts_with("launchdarkly.api_token is not set")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_misconfigured_checker_is_reported() {
+        let mut config = Config::default();
+        config.github.enabled = false;
+        let options = RunOptions {
+            dry_run: true,
+            ..RunOptions::default()
+        };
+
+        let report = run_checks_with(&config, &options).await.unwrap();
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].id, "checker:LaunchDarkly");
+        let errors: Vec<(&str, &str)> = report.errors().collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "LaunchDarkly");
+        assert!(errors[0].1.starts_with("launchdarkly.api_token is not set"));
+    }
+}
@@ -1,5 +1,9 @@
+use crate::config::Config;
+use crate::github::is_pr_ref;
+use crate::runner::{CheckReport, run_checks};
 use crate::state::{load_state, save_state};
 use anyhow::Result;
+use axum::extract::State;
 use axum::http::StatusCode;
 use axum::response::Html;
 use axum::routing::{get, post};
@@ -7,41 +11,112 @@ use axum::{Json, Router};
 use chrono::Utc;
 use serde::Deserialize;
 use std::fs;
+use std::sync::Arc;
 use tower_http::cors::CorsLayer;
 
 #[derive(Deserialize)]
 struct SeenRequest {
+    /// The issue's stable ID
     issue: String,
 }
 
-async fn index() -> Result<Html<String>, StatusCode> {
-    let path = shellexpand::tilde("~/Desktop/work-driver-issues.html");
-    let content = fs::read_to_string(path.as_ref()).map_err(|_| StatusCode::NOT_FOUND)?;
+async fn index(State(config): State<Arc<Config>>) -> Result<Html<String>, StatusCode> {
+    let content =
+        fs::read_to_string(config.notifier.html_path()).map_err(|_| StatusCode::NOT_FOUND)?;
     Ok(Html(content))
 }
 
 async fn mark_seen(Json(body): Json<SeenRequest>) -> Result<StatusCode, StatusCode> {
     let mut state = load_state().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if state
+        .issue_kinds
+        .get(&body.issue)
+        .is_some_and(|(_, kind)| kind.acknowledged_when_seen())
+    {
+        state.acknowledged.insert(body.issue.clone());
+    }
     state.seen.insert(body.issue, Utc::now());
     save_state(&state).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     Ok(StatusCode::OK)
 }
 
+#[derive(Deserialize)]
+struct SnoozeRequest {
+    /// The issue's stable ID
+    issue: String,
+    minutes: i64,
+}
+
+async fn snooze(Json(body): Json<SnoozeRequest>) -> Result<StatusCode, StatusCode> {
+    if body.minutes <= 0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let mut state = load_state().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    state.snoozed.insert(
+        body.issue,
+        Utc::now() + chrono::Duration::minutes(body.minutes),
+    );
+    save_state(&state).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+struct MuteRequest {
+    /// e.g. "figma/figma#123"
+    pr: String,
+}
+
+/// Stops reporting a PR until it's unmuted.
+async fn mute(Json(body): Json<MuteRequest>) -> Result<StatusCode, StatusCode> {
+    if !is_pr_ref(&body.pr) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let mut state = load_state().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    state.muted.insert(body.pr.to_lowercase());
+    save_state(&state).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(StatusCode::OK)
+}
+
+async fn unmute(Json(body): Json<MuteRequest>) -> Result<StatusCode, StatusCode> {
+    let mut state = load_state().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let pr = body.pr.to_lowercase();
+    state.muted.remove(&pr);
+    state.muted_open.remove(&pr);
+    save_state(&state).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(StatusCode::OK)
+}
+
+/// Runs a full check cycle now and returns its report.
+async fn check_now(
+    State(config): State<Arc<Config>>,
+) -> Result<Json<CheckReport>, (StatusCode, String)> {
+    let report = run_checks(&config)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)))?;
+    Ok(Json(report))
+}
+
 async fn get_state() -> Result<Json<serde_json::Value>, StatusCode> {
     let state = load_state().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     let value = serde_json::to_value(state).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     Ok(Json(value))
 }
 
-pub async fn run_server() -> Result<()> {
+pub async fn run_server(config: Config) -> Result<()> {
+    let address = format!("127.0.0.1:{}", config.server.port);
     let app = Router::new()
         .route("/", get(index))
         .route("/seen", post(mark_seen))
+        .route("/snooze", post(snooze))
+        .route("/mute", post(mute))
+        .route("/unmute", post(unmute))
         .route("/state", get(get_state))
-        .layer(CorsLayer::permissive());
+        .route("/check", post(check_now))
+        .layer(CorsLayer::permissive())
+        .with_state(Arc::new(config));
 
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:9845").await?;
-    println!("Server listening on http://127.0.0.1:9845");
+    let listener = tokio::net::TcpListener::bind(&address).await?;
+    println!("Server listening on http://{}", address);
     axum::serve(listener, app).await?;
     Ok(())
 }
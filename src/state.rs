@@ -1,25 +1,233 @@
+use crate::issue::{CheckSource, Issue, IssueItem, IssueKind};
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
 
+/// A change a checker wants made to the state file, applied and saved by the runner once
+/// the run is allowed side effects, i.e. not during a dry run.
+pub type StateUpdate = Box<dyn FnOnce(&mut State) + Send>;
+
+/// How many resolved issues are kept for `work-driver stats`.
+pub const MAX_RESOLUTIONS: usize = 500;
+
+/// How many commits' results are kept for each check.
+pub const MAX_CHECK_RESULTS: usize = 20;
+
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct State {
+    // Issue maps are keyed by `Issue::id`
     #[serde(default)]
     pub seen: HashMap<String, DateTime<Utc>>,
+    /// When each issue last triggered a notification, for re-notify throttling.
+    #[serde(default, alias = "issue_timestamps")]
+    pub last_notified: HashMap<String, DateTime<Utc>>,
+    /// When each current issue was first reported, for ordering and ages.
+    #[serde(default)]
+    pub first_seen: HashMap<String, DateTime<Utc>>,
+    /// The latest source and kind of each current issue, recorded when it resolves.
+    #[serde(default)]
+    pub issue_kinds: HashMap<String, (CheckSource, IssueKind)>,
+    /// Issues that went away, oldest first, capped at `MAX_RESOLUTIONS`.
+    #[serde(default)]
+    pub resolutions: Vec<Resolution>,
+    /// Issues held back during quiet hours, notified as soon as the window ends.
+    #[serde(default)]
+    pub deferred: HashMap<String, DateTime<Utc>>,
+    /// When each snoozed issue should start notifying again.
+    #[serde(default)]
+    pub snoozed: HashMap<String, DateTime<Utc>>,
+    /// When auto-merge was first seen enabled on each PR, for APIs that don't report it.
+    #[serde(default)]
+    pub auto_merge_since: HashMap<String, DateTime<Utc>>,
+    /// When each of our PRs without reviews was first seen, so one we've only just
+    /// started watching isn't reported as unreviewed straight away.
+    #[serde(default)]
+    pub unreviewed_since: HashMap<String, DateTime<Utc>>,
+    /// Recent results of each check, keyed by "{repo}:{check name}".
+    #[serde(default)]
+    pub check_history: HashMap<String, CheckHistory>,
+    /// Our open PRs on the last GitHub check, as `owner/repo#number`, for noticing ones
+    /// closed without merging.
+    #[serde(default)]
+    pub open_prs: HashSet<String>,
+    /// LaunchDarkly pipeline environments flags were on in on the last check, as
+    /// `project:flag:env`, for telling a flag turned off from one not started yet.
+    #[serde(default)]
+    pub ld_flags_on: HashSet<String>,
+    /// How far each LaunchDarkly pipeline environment's rollout had got on the last check,
+    /// by `project:flag:env`.
+    #[serde(default)]
+    pub ld_rollouts: HashMap<String, RolloutProgress>,
+    /// When each LaunchDarkly project's audit log was last read up to, in milliseconds.
+    #[serde(default)]
+    pub ld_audit_after: HashMap<String, i64>,
+    /// Changes others made to our LaunchDarkly flags, by audit log entry ID, reported
+    /// until they're marked seen.
+    #[serde(default)]
+    pub ld_modifications: HashMap<String, FlagModification>,
+    /// Per LaunchDarkly project with too many flags to check at once, the key of the last
+    /// one checked in turn.
     #[serde(default)]
-    pub issue_timestamps: HashMap<String, DateTime<Utc>>,
+    pub ld_flag_cursors: HashMap<String, String>,
+    /// Issues for those projects' flags as of the last time each was checked, by
+    /// `project:flag`.
+    #[serde(default)]
+    pub ld_flag_issues: HashMap<String, Vec<Issue>>,
+    /// When the LaunchDarkly API token was last found to work for every project.
+    #[serde(default)]
+    pub ld_token_validated: Option<DateTime<Utc>>,
+    /// When LaunchDarkly flags off everywhere for a long time were last looked for.
+    #[serde(default)]
+    pub ld_dead_flags_at: Option<DateTime<Utc>>,
+    /// The flags found then, reported until the next time they're looked for.
+    #[serde(default)]
+    pub ld_dead_flags: Vec<IssueItem>,
+    /// Issues marked seen that stay silenced for good, e.g. follow-ups on merged PRs.
+    #[serde(default)]
+    pub acknowledged: HashSet<String>,
+    /// PRs muted from the dashboard, as `owner/repo#number`.
+    #[serde(default)]
+    pub muted: HashSet<String>,
+    /// Muted PRs that were still open on the last GitHub check, by `owner/repo#number`.
+    #[serde(default)]
+    pub muted_open: HashMap<String, MutedPr>,
     #[serde(default)]
     pub last_check: Option<DateTime<Utc>>,
+    /// Minutes between watch mode's check cycles, for the dashboard's countdown to the
+    /// next one. Defaults to 5 until watch mode has run.
+    #[serde(default)]
+    pub check_interval_minutes: Option<u64>,
 }
 
-pub fn state_path() -> Result<PathBuf> {
+/// A muted PR, listed on the dashboard so it isn't forgotten entirely.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MutedPr {
+    pub title: String,
+    pub url: Option<String>,
+}
+
+/// A change someone else made to one of our LaunchDarkly flags.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlagModification {
+    pub project: String,
+    pub flag_key: String,
+    pub flag_name: String,
+    /// None for changes to the flag itself rather than its targeting in an environment.
+    pub environment: Option<String>,
+    /// Who made it: a member's first name or email.
+    pub by: String,
+    /// What they did, e.g. "turned off".
+    pub action: Option<String>,
+    pub date: DateTime<Utc>,
+}
+
+/// A LaunchDarkly flag's rollout percentage in one environment, as tracked across checks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RolloutProgress {
+    pub percent: f64,
+    /// When it last changed, or the environment's last change when first seen.
+    pub since: DateTime<Utc>,
+    /// What it had got up to before going down, until it's back up there.
+    #[serde(default)]
+    pub rolled_back_from: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Resolution {
+    pub id: String,
+    /// Unknown for issues first seen before sources were recorded.
+    pub source: Option<CheckSource>,
+    pub kind: Option<IssueKind>,
+    pub first_seen: DateTime<Utc>,
+    pub resolved_at: DateTime<Utc>,
+}
+
+/// Recent results of one check on our PRs, for spotting flaky checks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CheckHistory {
+    /// Whether the check last passed on each recent commit, oldest first, capped at
+    /// `MAX_CHECK_RESULTS`.
+    pub results: Vec<(String, bool)>,
+    /// When the check's result changed on a commit that didn't, e.g. a rerun passing.
+    pub flips: Vec<DateTime<Utc>>,
+}
+
+impl CheckHistory {
+    /// Records the check's result on commit `sha`, dropping flips from before
+    /// `forget_before`. Returns whether anything changed.
+    pub fn record(
+        &mut self,
+        sha: &str,
+        passed: bool,
+        now: DateTime<Utc>,
+        forget_before: DateTime<Utc>,
+    ) -> bool {
+        let flips = self.flips.len();
+        self.flips.retain(|flip| *flip >= forget_before);
+        let mut changed = self.flips.len() != flips;
+
+        match self.results.iter_mut().find(|(commit, _)| commit == sha) {
+            Some((_, last)) if *last == passed => {}
+            Some((_, last)) => {
+                *last = passed;
+                self.flips.push(now);
+                changed = true;
+            }
+            None => {
+                self.results.push((sha.to_string(), passed));
+                let excess = self.results.len().saturating_sub(MAX_CHECK_RESULTS);
+                self.results.drain(..excess);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Whether the check has flipped on an unchanged commit since `since`.
+    pub fn flipped_since(&self, since: DateTime<Utc>) -> bool {
+        self.flips.iter().any(|flip| *flip >= since)
+    }
+}
+
+impl State {
+    /// Records resolutions for every tracked issue not in `current_ids`.
+    pub fn record_resolutions(&mut self, current_ids: &HashSet<&String>, now: DateTime<Utc>) {
+        let mut resolved: Vec<Resolution> = self
+            .first_seen
+            .iter()
+            .filter(|(id, _)| !current_ids.contains(id))
+            .map(|(id, first_seen)| {
+                let kind = self.issue_kinds.get(id);
+                Resolution {
+                    id: id.clone(),
+                    source: kind.map(|(source, _)| *source),
+                    kind: kind.map(|(_, kind)| *kind),
+                    first_seen: *first_seen,
+                    resolved_at: now,
+                }
+            })
+            .collect();
+        resolved.sort_by_key(|r| r.first_seen);
+        self.resolutions.extend(resolved);
+
+        let excess = self.resolutions.len().saturating_sub(MAX_RESOLUTIONS);
+        self.resolutions.drain(..excess);
+    }
+}
+
+/// Where state and caches are kept, created if missing.
+pub fn state_dir() -> Result<PathBuf> {
     let home = std::env::var("HOME").context("HOME not set")?;
     let dir = PathBuf::from(home).join(".local/share/work-driver");
     fs::create_dir_all(&dir).context("Failed to create state directory")?;
-    Ok(dir.join("state.json"))
+    Ok(dir)
+}
+
+pub fn state_path() -> Result<PathBuf> {
+    Ok(state_dir()?.join("state.json"))
 }
 
 pub fn load_state() -> Result<State> {
@@ -39,3 +247,70 @@ pub fn save_state(state: &State) -> Result<()> {
     fs::rename(&tmp_path, &path).context("Failed to rename temp state file")?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_old_state_files_still_load() {
+        let state: State = serde_json::from_str(
+            r#"{"seen": {}, "issue_timestamps": {"github:pr:1": "2024-06-03T09:00:00Z"}}"#,
+        )
+        .unwrap();
+        assert!(state.last_notified.contains_key("github:pr:1"));
+        assert!(state.resolutions.is_empty());
+    }
+
+    #[test]
+    fn test_check_history_records_flips() {
+        let start: DateTime<Utc> = "2024-06-03T09:00:00Z".parse().unwrap();
+        let at = |hours| start + chrono::Duration::hours(hours);
+        let mut history = CheckHistory::default();
+
+        // A failure and then the same failure on the next run isn't a flip
+        assert!(history.record("a", false, at(0), start));
+        assert!(!history.record("a", false, at(1), start));
+        assert!(!history.flipped_since(start));
+
+        // A rerun passing on the same commit is
+        assert!(history.record("a", true, at(2), start));
+        assert!(history.flipped_since(at(1)));
+        assert!(!history.flipped_since(at(3)));
+
+        // Failing on a new commit is just a new result
+        assert!(history.record("b", false, at(3), start));
+        assert_eq!(history.flips, [at(2)]);
+        assert_eq!(
+            history.results,
+            [("a".to_string(), true), ("b".to_string(), false)]
+        );
+
+        // Old flips are forgotten, and only the latest commits are kept
+        for n in 0..MAX_CHECK_RESULTS {
+            history.record(&n.to_string(), true, at(4), at(4));
+        }
+        assert!(history.flips.is_empty());
+        assert_eq!(history.results.len(), MAX_CHECK_RESULTS);
+        assert_eq!(history.results[0].0, "0");
+    }
+
+    #[test]
+    fn test_record_resolutions_is_bounded() {
+        let now = Utc::now();
+        let mut state = State::default();
+        for n in 0..MAX_RESOLUTIONS + 10 {
+            state.first_seen.insert(n.to_string(), now);
+        }
+        state.first_seen.insert("open".to_string(), now);
+        state.issue_kinds.insert(
+            "open".to_string(),
+            (CheckSource::GitHub, IssueKind::AwaitingReview),
+        );
+
+        let open = "open".to_string();
+        state.record_resolutions(&HashSet::from([&open]), now);
+        assert_eq!(state.resolutions.len(), MAX_RESOLUTIONS);
+        assert!(state.resolutions.iter().all(|r| r.id != "open"));
+    }
+}
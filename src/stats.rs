@@ -0,0 +1,160 @@
+//! Time-to-resolution statistics over the resolution history kept in state.
+
+use crate::notifier::format_age;
+use crate::state::Resolution;
+use chrono::Duration;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+#[derive(Debug, PartialEq)]
+pub struct ResolutionStats {
+    pub count: usize,
+    pub median: Duration,
+    pub p95: Duration,
+}
+
+impl ResolutionStats {
+    pub fn new(mut durations: Vec<Duration>) -> Option<Self> {
+        if durations.is_empty() {
+            return None;
+        }
+        durations.sort();
+        Some(Self {
+            count: durations.len(),
+            median: percentile(&durations, 50.0),
+            p95: percentile(&durations, 95.0),
+        })
+    }
+}
+
+/// Nearest-rank percentile of a sorted, non-empty slice.
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Renders resolution counts and times grouped by source, then by kind.
+pub fn render_stats(resolutions: &[Resolution]) -> String {
+    if resolutions.is_empty() {
+        return "No resolved issues recorded yet\n".to_string();
+    }
+
+    let mut out = String::new();
+    let by_source = group(resolutions, |r| match r.source {
+        Some(source) => format!("{:?}", source),
+        None => "unknown".to_string(),
+    });
+    let by_kind = group(resolutions, |r| match r.kind {
+        Some(kind) => format!("{:?}", kind),
+        None => "unknown".to_string(),
+    });
+
+    for (heading, groups) in [("SOURCE", by_source), ("KIND", by_kind)] {
+        let _ = writeln!(
+            out,
+            "{:<20} {:>8} {:>8} {:>8}",
+            heading, "RESOLVED", "MEDIAN", "P95"
+        );
+        for (label, stats) in groups {
+            let _ = writeln!(
+                out,
+                "{:<20} {:>8} {:>8} {:>8}",
+                label,
+                stats.count,
+                format_age(stats.median),
+                format_age(stats.p95)
+            );
+        }
+        out.push('\n');
+    }
+
+    let first = resolutions.iter().map(|r| r.resolved_at).min();
+    if let Some(first) = first {
+        let _ = writeln!(
+            out,
+            "{} resolutions since {}",
+            resolutions.len(),
+            first.format("%Y-%m-%d")
+        );
+    }
+    out
+}
+
+fn group(
+    resolutions: &[Resolution],
+    label: impl Fn(&Resolution) -> String,
+) -> BTreeMap<String, ResolutionStats> {
+    let mut durations: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
+    for resolution in resolutions {
+        durations
+            .entry(label(resolution))
+            .or_default()
+            .push(resolution.resolved_at - resolution.first_seen);
+    }
+    durations
+        .into_iter()
+        .filter_map(|(label, durations)| Some((label, ResolutionStats::new(durations)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::issue::{CheckSource, IssueKind};
+    use chrono::{DateTime, Utc};
+
+    #[test]
+    fn test_resolution_stats_percentiles() {
+        let durations = (1..=20).map(Duration::minutes).collect();
+        assert_eq!(
+            ResolutionStats::new(durations),
+            Some(ResolutionStats {
+                count: 20,
+                median: Duration::minutes(10),
+                p95: Duration::minutes(19),
+            })
+        );
+        assert_eq!(ResolutionStats::new(Vec::new()), None);
+    }
+
+    #[test]
+    fn test_render_stats_groups_by_source_and_kind() {
+        let resolved_at: DateTime<Utc> = "2024-06-03T12:00:00Z".parse().unwrap();
+        let resolution = |source, kind, minutes| Resolution {
+            id: format!("{}", minutes),
+            source,
+            kind,
+            first_seen: resolved_at - Duration::minutes(minutes),
+            resolved_at,
+        };
+        let resolutions = [
+            resolution(
+                Some(CheckSource::GitHub),
+                Some(IssueKind::AwaitingReview),
+                90,
+            ),
+            resolution(
+                Some(CheckSource::GitHub),
+                Some(IssueKind::FailingChecks),
+                30,
+            ),
+            resolution(None, None, 5),
+        ];
+
+        assert_eq!(
+            render_stats(&resolutions),
+            "\
+SOURCE               RESOLVED   MEDIAN      P95
+GitHub                      2      30m   1h 30m
+unknown                     1       5m       5m
+
+KIND                 RESOLVED   MEDIAN      P95
+AwaitingReview              1   1h 30m   1h 30m
+FailingChecks               1      30m      30m
+unknown                     1       5m       5m
+
+3 resolutions since 2024-06-03
+"
+        );
+    }
+}